@@ -0,0 +1,5 @@
+pub mod inspect;
+pub mod query;
+pub mod serve;
+pub mod stats;
+pub mod verify;
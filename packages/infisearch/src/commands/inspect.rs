@@ -0,0 +1,269 @@
+use std::io::{self, Error, ErrorKind};
+use std::path::Path;
+
+use infisearch_search::native::{NativeSearcher, NativeSearcherOptions};
+use serde_json::{json, Map, Value};
+
+/// What to inspect in the binary index files
+pub enum Inspection {
+    /// Dictionary terms starting with the prefix, up to the limit
+    Terms { prefix: String, limit: usize },
+    /// The decoded postings list of a dictionary term
+    Postings { term: String },
+    /// A document's stored fields and document infos
+    Doc { doc_id: u32 },
+    /// Document ids deleted or updated by incremental indexing runs
+    Invalidated,
+}
+
+fn get_terms(searcher: &NativeSearcher, prefix: &str, limit: usize) -> Value {
+    let mut num_terms = 0;
+    let mut terms = Vec::new();
+    for (term, term_info) in searcher.get_term_infos(prefix) {
+        num_terms += 1;
+        if terms.len() < limit {
+            terms.push(json!({
+                "term": term,
+                "docFreq": term_info.doc_freq,
+                "postingsFileName": term_info.postings_file_name,
+                "postingsFileOffset": term_info.postings_file_offset,
+            }));
+        }
+    }
+
+    json!({ "numTerms": num_terms, "terms": terms })
+}
+
+fn get_postings(searcher: &NativeSearcher, term: &str) -> io::Result<Value> {
+    let postings = searcher.get_postings(term)?.ok_or_else(|| Error::new(
+        ErrorKind::NotFound,
+        format!("\"{}\" is not in the dictionary, the terms subcommand lists the indexed terms", term),
+    ))?;
+
+    let postings: Vec<Value> = postings
+        .into_iter()
+        .map(|posting| {
+            let fields: Vec<Value> = posting.fields
+                .into_iter()
+                .map(|(field_name, term_freq, positions)| json!({
+                    "field": field_name,
+                    "termFreq": term_freq,
+                    "positions": positions,
+                }))
+                .collect();
+            json!({ "docId": posting.doc_id, "isInvalidated": posting.is_invalidated, "fields": fields })
+        })
+        .collect();
+
+    Ok(json!({ "term": term, "postings": postings }))
+}
+
+fn get_doc(searcher: &NativeSearcher, doc_id: u32) -> io::Result<Value> {
+    let output_config = &searcher.output_config;
+    if doc_id >= output_config.last_doc_id {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            format!("Document {} does not exist, the last document id is {}", doc_id, output_config.last_doc_id),
+        ));
+    }
+
+    let fields: Vec<Value> = searcher.load_fields(doc_id)?
+        .into_iter()
+        .map(|(field_name, field_text)| json!([field_name, field_text]))
+        .collect();
+
+    let mut enums = Map::new();
+    let mut numbers = Map::new();
+    for field_info in output_config.field_infos.iter() {
+        if field_info.enum_info.is_some() {
+            enums.insert(field_info.name.clone(), json!(searcher.get_enum_value(doc_id, &field_info.name)));
+        }
+        if field_info.i64_info.is_some() {
+            numbers.insert(field_info.name.clone(), json!(searcher.get_i64_value(doc_id, &field_info.name)));
+        }
+    }
+
+    let length_factors: Map<String, Value> = searcher.get_doc_length_factors(doc_id)
+        .into_iter()
+        .map(|(field_name, length_factor)| (field_name.to_owned(), json!(length_factor)))
+        .collect();
+
+    Ok(json!({
+        "docId": doc_id,
+        "isInvalidated": searcher.is_invalidated(doc_id),
+        "fields": fields,
+        "enums": enums,
+        "numbers": numbers,
+        "lengthFactors": length_factors,
+    }))
+}
+
+fn get_invalidated(searcher: &NativeSearcher) -> Value {
+    let last_doc_id = searcher.output_config.last_doc_id;
+    let invalidated_doc_ids: Vec<u32> = (0..last_doc_id)
+        .filter(|&doc_id| searcher.is_invalidated(doc_id))
+        .collect();
+
+    json!({ "numDocIds": last_doc_id, "invalidatedDocIds": invalidated_doc_ids })
+}
+
+/// Decodes the requested part of the index in the output folder.
+pub fn inspect(output_folder_path: &Path, inspection: &Inspection) -> io::Result<Value> {
+    let searcher = NativeSearcher::new(output_folder_path, NativeSearcherOptions::default())?;
+
+    match inspection {
+        Inspection::Terms { prefix, limit } => Ok(get_terms(&searcher, prefix, *limit)),
+        Inspection::Postings { term } => get_postings(&searcher, term),
+        Inspection::Doc { doc_id } => get_doc(&searcher, *doc_id),
+        Inspection::Invalidated => Ok(get_invalidated(&searcher)),
+    }
+}
+
+fn get_invalidated_label(value: &Value) -> &'static str {
+    if value["isInvalidated"] == true { " (invalidated)" } else { "" }
+}
+
+pub fn print_inspection(inspection: &Inspection, inspected: &Value) {
+    match inspection {
+        Inspection::Terms { .. } => {
+            println!("{} terms", inspected["numTerms"]);
+            for term in inspected["terms"].as_array().into_iter().flatten() {
+                println!(
+                    "  {}: doc freq {}, postings list {} at offset {}",
+                    term["term"].as_str().unwrap_or_default(),
+                    term["docFreq"],
+                    term["postingsFileName"],
+                    term["postingsFileOffset"],
+                );
+            }
+        }
+        Inspection::Postings { .. } => {
+            for posting in inspected["postings"].as_array().into_iter().flatten() {
+                println!("Document {}{}", posting["docId"], get_invalidated_label(posting));
+                for field in posting["fields"].as_array().into_iter().flatten() {
+                    println!(
+                        "  {}: term freq {}, positions {}",
+                        field["field"].as_str().unwrap_or_default(),
+                        field["termFreq"],
+                        field["positions"],
+                    );
+                }
+            }
+        }
+        Inspection::Doc { .. } => {
+            println!("Document {}{}", inspected["docId"], get_invalidated_label(inspected));
+
+            println!("Fields:");
+            for field in inspected["fields"].as_array().into_iter().flatten() {
+                if let (Some(field_name), Some(field_text)) = (field[0].as_str(), field[1].as_str()) {
+                    println!("  {}: {}", field_name, field_text);
+                }
+            }
+
+            for (label, key) in [("Enum values", "enums"), ("I64 values", "numbers"), ("Length factors", "lengthFactors")] {
+                if let Some(values) = inspected[key].as_object().filter(|values| !values.is_empty()) {
+                    println!("{}:", label);
+                    for (field_name, value) in values {
+                        println!("  {}: {}", field_name, value);
+                    }
+                }
+            }
+        }
+        Inspection::Invalidated => {
+            let invalidated_doc_ids: Vec<String> = inspected["invalidatedDocIds"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|doc_id| doc_id.to_string())
+                .collect();
+            if invalidated_doc_ids.is_empty() {
+                println!("None of {} document ids are invalidated", inspected["numDocIds"]);
+            } else {
+                println!(
+                    "{} of {} document ids invalidated: {}",
+                    invalidated_doc_ids.len(),
+                    inspected["numDocIds"],
+                    invalidated_doc_ids.join(", "),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use crate::indexer::input_config::InfiConfig;
+    use crate::indexer::Indexer;
+
+    use super::{inspect, Inspection};
+
+    #[test]
+    fn test_inspect() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let input_folder_path = temp_dir.path().join("input");
+        let output_folder_path = temp_dir.path().join("output");
+        fs::create_dir_all(&input_folder_path).unwrap();
+        fs::create_dir_all(&output_folder_path).unwrap();
+
+        let index = |is_incremental: bool, docs: &[(&str, &str)]| {
+            let mut indexer = Indexer::new(
+                &input_folder_path,
+                &output_folder_path,
+                InfiConfig::default(),
+                is_incremental,
+                true,
+                false,
+                false,
+            ).unwrap();
+            for (file_name, text) in docs {
+                fs::write(
+                    input_folder_path.join(file_name),
+                    format!("<html><head><title>{}</title></head><body><p>{}</p></body></html>", file_name, text),
+                ).unwrap();
+                indexer.index_file(&input_folder_path.join(file_name), file_name.as_ref()).unwrap();
+            }
+            indexer.finish_writing_docs().unwrap();
+        };
+
+        index(false, &[("a.html", "lorem ipsum lorem"), ("b.html", "lorem dolor")]);
+
+        let terms = inspect(&output_folder_path, &Inspection::Terms { prefix: "lo".to_owned(), limit: 10 }).unwrap();
+        assert_eq!(terms["numTerms"], 1);
+        assert_eq!(terms["terms"][0]["term"], "lorem");
+        assert_eq!(terms["terms"][0]["docFreq"], 2);
+
+        let postings = inspect(&output_folder_path, &Inspection::Postings { term: "lorem".to_owned() }).unwrap();
+        assert_eq!(postings["postings"].as_array().unwrap().len(), 2);
+        assert_eq!(postings["postings"][0]["isInvalidated"], false);
+        let body = postings["postings"][0]["fields"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|field| field["field"] == "body")
+            .unwrap();
+        assert_eq!(body["termFreq"], 2);
+        assert_eq!(body["positions"].as_array().unwrap().len(), 2);
+
+        assert!(inspect(&output_folder_path, &Inspection::Postings { term: "lorme".to_owned() }).is_err());
+
+        let doc = inspect(&output_folder_path, &Inspection::Doc { doc_id: 1 }).unwrap();
+        assert!(doc["fields"].as_array().unwrap().contains(&json!(["_relative_fp", "b.html"])));
+        assert!(doc["lengthFactors"]["body"].as_f64().unwrap() > 0.0);
+        assert!(inspect(&output_folder_path, &Inspection::Doc { doc_id: 2 }).is_err());
+
+        // b is deleted
+        index(true, &[("a.html", "lorem ipsum lorem")]);
+
+        let invalidated = inspect(&output_folder_path, &Inspection::Invalidated).unwrap();
+        assert_eq!(invalidated["invalidatedDocIds"], json!([1]));
+
+        let postings = inspect(&output_folder_path, &Inspection::Postings { term: "dolor".to_owned() }).unwrap();
+        assert_eq!(postings["postings"][0]["docId"], 1);
+        assert_eq!(postings["postings"][0]["isInvalidated"], true);
+    }
+}
@@ -0,0 +1,329 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use infisearch_search::federated::FederatedSearcher;
+use infisearch_search::native::{NativeSearcher, NativeSearcherOptions, QueryOpts};
+use infisearch_search::{DocResult, Query};
+use serde_json::{json, Value};
+
+/// Runs the query against the index in the output folder,
+/// returning the top `num_results` results and the parsed query.
+///
+/// Stored field texts are truncated to `snippet_len` characters, if it is non-zero.
+/// Each result also has the best passage of its scored fields with the searched terms highlighted,
+/// `snippet_len` characters long (or the whole field text if zero).
+pub fn get_query_results(
+    output_folder_path: &Path,
+    query: &str,
+    num_results: usize,
+    snippet_len: usize,
+) -> io::Result<Value> {
+    let mut searcher = NativeSearcher::new(output_folder_path, NativeSearcherOptions::default())?;
+    let mut query = searcher.run_query(query, &QueryOpts::default())?;
+
+    let mut results = Vec::with_capacity(num_results.min(query.results_total));
+    for doc_result in query.get_next_n_results(num_results) {
+        results.push(get_result_json(&searcher, &query, &doc_result, snippet_len, snippet_len)?);
+    }
+
+    let query_parts: Value = serde_json::from_str(&query.get_query_parts())?;
+    let suggestion: Value = serde_json::from_str(&query.get_suggestion())?;
+
+    Ok(json!({
+        "queryParts": query_parts,
+        "suggestion": suggestion,
+        "resultsTotal": query.results_total,
+        "results": results,
+    }))
+}
+
+/// Runs the query against the indexes in all output folders as one, see `get_query_results`.
+///
+/// Each result also has the position of its index in `output_folder_paths`,
+/// and the parsed query is that of the first index.
+pub fn get_federated_query_results(
+    output_folder_paths: &[PathBuf],
+    query: &str,
+    num_results: usize,
+    snippet_len: usize,
+) -> io::Result<Value> {
+    let mut searcher = FederatedSearcher::new(output_folder_paths, NativeSearcherOptions::default())?;
+    let mut query = searcher.run_query(query, &QueryOpts::default())?;
+
+    let mut results = Vec::with_capacity(num_results.min(query.results_total));
+    for federated_result in query.get_next_n_results(num_results) {
+        let doc_result = DocResult { doc_id: federated_result.doc_id, score: federated_result.score };
+        let mut result = get_result_json(
+            searcher.searcher(federated_result.index),
+            query.query(federated_result.index),
+            &doc_result,
+            snippet_len,
+            snippet_len,
+        )?;
+        result["index"] = json!(federated_result.index);
+        results.push(result);
+    }
+
+    let query_parts: Value = serde_json::from_str(&query.query(0).get_query_parts())?;
+    let suggestion: Value = serde_json::from_str(&query.get_suggestion())?;
+
+    Ok(json!({
+        "queryParts": query_parts,
+        "suggestion": suggestion,
+        "resultsTotal": query.results_total,
+        "results": results,
+    }))
+}
+
+/// Loads the result's stored fields, truncated to `field_len` characters if it is non-zero,
+/// and its best snippet, `snippet_len` characters long (or the whole field text if zero).
+pub fn get_result_json(
+    searcher: &NativeSearcher,
+    query: &Query,
+    doc_result: &DocResult,
+    field_len: usize,
+    snippet_len: usize,
+) -> io::Result<Value> {
+    let doc_fields = searcher.load_fields(doc_result.doc_id)?;
+
+    let snippet = searcher.get_snippet(query, &doc_fields, snippet_len).map(|snippet| {
+        let highlights: Vec<Value> = snippet.highlights.iter().map(|range| json!([range.start, range.end])).collect();
+        json!({
+            "field": snippet.field_name,
+            "text": snippet.text,
+            "highlights": highlights,
+            "isStartTruncated": snippet.is_start_truncated,
+            "isEndTruncated": snippet.is_end_truncated,
+        })
+    });
+
+    let fields: Vec<Value> = doc_fields
+        .into_iter()
+        .map(|(field_name, field_text)| json!([field_name, truncate(&field_text, field_len)]))
+        .collect();
+
+    Ok(json!({
+        "docId": doc_result.doc_id,
+        "score": doc_result.score,
+        "fields": fields,
+        "snippet": snippet,
+    }))
+}
+
+/// Collapses runs of whitespace, which are common in html sourced texts, before truncating.
+fn truncate(text: &str, snippet_len: usize) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if snippet_len == 0 {
+        return text;
+    }
+
+    match text.char_indices().nth(snippet_len) {
+        Some((idx, _c)) => format!("{}...", &text[..idx]),
+        None => text,
+    }
+}
+
+/// Wraps the highlighted ranges of a snippet in `**`, with ellipses for truncated ends.
+fn highlight_snippet(snippet: &Value) -> Option<String> {
+    let text = snippet["text"].as_str()?;
+
+    let mut highlighted = String::with_capacity(text.len());
+    if snippet["isStartTruncated"] == true {
+        highlighted.push_str("...");
+    }
+
+    let mut last_end = 0;
+    for range in snippet["highlights"].as_array()? {
+        let start = range[0].as_u64()? as usize;
+        let end = range[1].as_u64()? as usize;
+        highlighted.push_str(&text[last_end..start]);
+        highlighted.push_str("**");
+        highlighted.push_str(&text[start..end]);
+        highlighted.push_str("**");
+        last_end = end;
+    }
+    highlighted.push_str(&text[last_end..]);
+
+    if snippet["isEndTruncated"] == true {
+        highlighted.push_str("...");
+    }
+
+    Some(highlighted)
+}
+
+pub fn print_query_results(query_results: &Value) {
+    println!("Query parts: {}", query_results["queryParts"]);
+    if let Some(suggested_query) = query_results["suggestion"]["query"].as_str() {
+        println!("Did you mean: {}", suggested_query);
+    }
+    println!("{} results", query_results["resultsTotal"]);
+
+    if let Some(results) = query_results["results"].as_array() {
+        for (rank, result) in results.iter().enumerate() {
+            println!();
+            if result["index"].is_null() {
+                println!("{}. Document {} (score {})", rank + 1, result["docId"], result["score"]);
+            } else {
+                println!("{}. Document {} of index {} (score {})", rank + 1, result["docId"], result["index"], result["score"]);
+            }
+
+            // Only the first text of each field, the rest are usually repeated body / heading texts
+            let mut printed_fields: Vec<&str> = Vec::new();
+            for field in result["fields"].as_array().into_iter().flatten() {
+                if let (Some(field_name), Some(field_text)) = (field[0].as_str(), field[1].as_str()) {
+                    if !printed_fields.contains(&field_name) {
+                        println!("   {}: {}", field_name, field_text);
+                        printed_fields.push(field_name);
+                    }
+                }
+            }
+
+            if let (Some(field_name), Some(highlighted)) = (result["snippet"]["field"].as_str(), highlight_snippet(&result["snippet"])) {
+                println!("   > {}: {}", field_name, highlighted);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::path::Path;
+
+    use pretty_assertions::assert_eq;
+
+    use crate::indexer::input_config::InfiConfig;
+    use crate::indexer::Indexer;
+
+    use serde_json::json;
+
+    use super::{highlight_snippet, truncate};
+
+    fn index(input_folder_path: &Path, output_folder_path: &Path, docs: &[(&str, &str)]) {
+        fs::create_dir_all(input_folder_path).unwrap();
+        let mut indexer = Indexer::new(
+            input_folder_path,
+            output_folder_path,
+            InfiConfig::default(),
+            false,
+            false,
+            false,
+            false,
+        ).unwrap();
+        for (file_name, text) in docs {
+            fs::write(
+                input_folder_path.join(file_name),
+                format!("<html><body><p>{}</p></body></html>", text),
+            ).unwrap();
+            indexer.index_file(&input_folder_path.join(file_name), file_name.as_ref()).unwrap();
+        }
+        indexer.finish_writing_docs().unwrap();
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("lorem ipsum", 0), "lorem ipsum");
+        assert_eq!(truncate("lorem ipsum", 5), "lorem...");
+        assert_eq!(truncate("lorem ipsum", 11), "lorem ipsum");
+        assert_eq!(truncate("我他我他", 2), "我他...");
+        assert_eq!(truncate("\n  lorem \n\n ipsum  ", 0), "lorem ipsum");
+    }
+
+    #[test]
+    fn test_highlight_snippet() {
+        let snippet = json!({
+            "text": "lorem ipsum dolor",
+            "highlights": [[0, 5], [12, 17]],
+            "isStartTruncated": false,
+            "isEndTruncated": true,
+        });
+        assert_eq!(highlight_snippet(&snippet).unwrap(), "**lorem** ipsum **dolor**...");
+    }
+
+    #[test]
+    fn test_get_query_results() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let input_folder_path = temp_dir.path().join("input");
+        let output_folder_path = temp_dir.path().join("output");
+        fs::create_dir_all(&input_folder_path).unwrap();
+        fs::create_dir_all(&output_folder_path).unwrap();
+
+        fs::write(
+            input_folder_path.join("lorem.html"),
+            "<html><head><title>Lorem</title></head><body><p>lorem ipsum dolor sit amet</p></body></html>",
+        ).unwrap();
+        fs::write(
+            input_folder_path.join("ipsum.html"),
+            "<html><head><title>Ipsum</title></head><body><p>ipsum dolor</p></body></html>",
+        ).unwrap();
+
+        let mut indexer = Indexer::new(
+            &input_folder_path,
+            &output_folder_path,
+            InfiConfig::default(),
+            false,
+            false,
+            false,
+            false,
+        ).unwrap();
+        indexer.index_file(&input_folder_path.join("lorem.html"), "lorem.html".as_ref()).unwrap();
+        indexer.index_file(&input_folder_path.join("ipsum.html"), "ipsum.html".as_ref()).unwrap();
+        indexer.finish_writing_docs().unwrap();
+
+        let results = super::get_query_results(&output_folder_path, "lorem", 10, 0).unwrap();
+        assert_eq!(results["resultsTotal"], 1);
+        // The top level query parts are wrapped in a bracket
+        assert_eq!(results["queryParts"][0]["children"][0]["term"], "lorem");
+        assert_eq!(results["suggestion"], json!(null));
+
+        let fields = results["results"][0]["fields"].as_array().unwrap();
+        assert!(fields.iter().any(|field| field[0] == "title" && field[1] == "Lorem"));
+        assert!(fields.iter().any(|field| field[0] == "_relative_fp" && field[1] == "lorem.html"));
+        // The title is the closest match, and the whole text is returned with a snippet_len of 0
+        assert_eq!(results["results"][0]["snippet"]["field"], "title");
+        assert_eq!(results["results"][0]["snippet"]["highlights"], json!([[0, 5]]));
+
+        let results = super::get_query_results(&output_folder_path, "dolor amet", 10, 0).unwrap();
+        assert_eq!(results["results"][0]["snippet"]["field"], "body");
+        assert_eq!(results["results"][0]["snippet"]["text"], "lorem ipsum dolor sit amet");
+        assert_eq!(results["results"][0]["snippet"]["highlights"], json!([[12, 17], [22, 26]]));
+
+        let results = super::get_query_results(&output_folder_path, "lorme ", 10, 0).unwrap();
+        assert_eq!(results["resultsTotal"], 1);
+        assert_eq!(results["suggestion"]["query"], "lorem ");
+        assert_eq!(results["suggestion"]["corrections"][0]["docFreq"], 1);
+
+        let results = super::get_query_results(&output_folder_path, "ipsum", 10, 0).unwrap();
+        assert_eq!(results["resultsTotal"], 2);
+        // Shorter document (and title match) ranks first
+        assert!(results["results"][0]["fields"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|field| field[0] == "title" && field[1] == "Ipsum"));
+    }
+
+    #[test]
+    fn test_get_federated_query_results() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let output_folder_paths = vec![temp_dir.path().join("output1"), temp_dir.path().join("output2")];
+        index(&temp_dir.path().join("input1"), &output_folder_paths[0], &[("a.html", "lorem ipsum")]);
+        index(&temp_dir.path().join("input2"), &output_folder_paths[1], &[("b.html", "lorem dolor"), ("c.html", "dolor")]);
+
+        let results = super::get_federated_query_results(&output_folder_paths, "lorem", 10, 0).unwrap();
+        assert_eq!(results["resultsTotal"], 2);
+        assert_eq!(results["queryParts"][0]["children"][0]["term"], "lorem");
+        assert_eq!(results["suggestion"], json!(null));
+
+        // Results have the same format as get_query_results, with the position of their index
+        let result = &results["results"][1];
+        assert_eq!(result["index"], 1);
+        assert_eq!(result["docId"], 0);
+        assert!(result["fields"].as_array().unwrap().iter().any(|field| field[0] == "_relative_fp" && field[1] == "b.html"));
+        assert_eq!(result["snippet"]["field"], "body");
+        assert_eq!(result["snippet"]["highlights"], json!([[0, 5]]));
+
+        let results = super::get_federated_query_results(&output_folder_paths, "lorme ", 10, 0).unwrap();
+        assert_eq!(results["suggestion"]["query"], "lorem ");
+    }
+}
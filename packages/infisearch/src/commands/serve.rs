@@ -0,0 +1,380 @@
+use std::fs;
+use std::io::{self, Error, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use infisearch_search::native::{NativeSearcher, NativeSearcherOptions, QueryOpts};
+use log::{error, info};
+use percent_encoding::percent_decode_str;
+use serde_json::{json, Value};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::commands::query;
+
+const DEFAULT_LIMIT: usize = 10;
+const DEFAULT_SNIPPET_LEN: usize = 160;
+const DEFAULT_COMPLETIONS_LIMIT: usize = 5;
+
+pub struct ServeResponse {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+impl ServeResponse {
+    fn json(status: u16, body: &Value) -> Self {
+        ServeResponse {
+            status,
+            content_type: "application/json",
+            body: body.to_string().into_bytes(),
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        ServeResponse::json(status, &json!({ "error": message }))
+    }
+}
+
+/// Parameters of the `/search` endpoint, the query string equivalents of what `get_query` decodes.
+struct SearchParams {
+    query: String,
+    opts: QueryOpts,
+    offset: usize,
+    limit: usize,
+    snippet_len: usize,
+}
+
+/// Parses the `/search` query string, which supports:
+/// - `q=<query>`
+/// - `enum.<field>=<value>`, repeatable, keeping documents with any of the values.
+///   An empty value keeps documents without one.
+/// - `i64.<field>=<gte>..<lte>`, where either bound may be omitted
+/// - `sort=<field>[:asc|:desc]`, repeatable in order of precedence, descending by default
+/// - `offset=<n>` and `limit=<n>` for paging
+/// - `snippet_len=<n>`
+fn parse_search_params(query_string: &str) -> Result<SearchParams, String> {
+    let mut params = SearchParams {
+        query: String::new(),
+        opts: QueryOpts::default(),
+        offset: 0,
+        limit: DEFAULT_LIMIT,
+        snippet_len: DEFAULT_SNIPPET_LEN,
+    };
+
+    let parse_usize = |key: &str, value: &str| {
+        value.parse::<usize>().map_err(|_e| format!("Invalid {} \"{}\"", key, value))
+    };
+
+    for (key, value) in form_urlencoded::parse(query_string.as_bytes()) {
+        if let Some(field_name) = key.strip_prefix("enum.") {
+            let enum_value = if value.is_empty() { None } else { Some(value.into_owned()) };
+            match params.opts.enum_filters.iter_mut().find(|(name, _values)| name == field_name) {
+                Some((_name, values)) => values.push(enum_value),
+                None => params.opts.enum_filters.push((field_name.to_owned(), vec![enum_value])),
+            }
+        } else if let Some(field_name) = key.strip_prefix("i64.") {
+            let (gte, lte) = value
+                .split_once("..")
+                .ok_or_else(|| format!("Invalid range \"{}\" for {}, expected <gte>..<lte>", value, key))?;
+            let parse_bound = |bound: &str| -> Result<Option<i64>, String> {
+                if bound.is_empty() {
+                    Ok(None)
+                } else {
+                    bound.parse().map(Some).map_err(|_e| format!("Invalid bound \"{}\" for {}", bound, key))
+                }
+            };
+            params.opts.i64_filters.push((field_name.to_owned(), parse_bound(gte)?, parse_bound(lte)?));
+        } else {
+            match key.as_ref() {
+                "q" => params.query = value.into_owned(),
+                "sort" => {
+                    let (field_name, ascending) = match value.rsplit_once(':') {
+                        Some((field_name, "asc")) => (field_name, true),
+                        Some((field_name, "desc")) => (field_name, false),
+                        _ => (value.as_ref(), false),
+                    };
+                    params.opts.sort_by.push((field_name.to_owned(), ascending));
+                }
+                "offset" => params.offset = parse_usize(&key, &value)?,
+                "limit" => params.limit = parse_usize(&key, &value)?,
+                "snippet_len" => params.snippet_len = parse_usize(&key, &value)?,
+                _ => return Err(format!("Unknown parameter \"{}\"", key)),
+            }
+        }
+    }
+
+    Ok(params)
+}
+
+fn search(searcher: &mut NativeSearcher, params: &SearchParams) -> io::Result<Value> {
+    let mut query = searcher.run_query(&params.query, &params.opts)?;
+
+    // Results are ranked lazily, skip the previous pages
+    query.get_next_n_results(params.offset);
+
+    let mut results = Vec::with_capacity(params.limit.min(query.results_total));
+    for doc_result in query.get_next_n_results(params.limit) {
+        results.push(query::get_result_json(searcher, &query, &doc_result, 0, params.snippet_len)?);
+    }
+
+    let query_parts: Value = serde_json::from_str(&query.get_query_parts())?;
+    let suggestion: Value = serde_json::from_str(&query.get_suggestion())?;
+
+    Ok(json!({
+        "queryParts": query_parts,
+        "suggestion": suggestion,
+        "resultsTotal": query.results_total,
+        "results": results,
+    }))
+}
+
+/// Parses the `/complete` query string, which supports `q=<partial query>` and `limit=<n>`.
+fn parse_complete_params(query_string: &str) -> Result<(String, usize), String> {
+    let mut query = String::new();
+    let mut limit = DEFAULT_COMPLETIONS_LIMIT;
+
+    for (key, value) in form_urlencoded::parse(query_string.as_bytes()) {
+        match key.as_ref() {
+            "q" => query = value.into_owned(),
+            "limit" => limit = value.parse().map_err(|_e| format!("Invalid limit \"{}\"", value))?,
+            _ => return Err(format!("Unknown parameter \"{}\"", key)),
+        }
+    }
+
+    Ok((query, limit))
+}
+
+fn complete(searcher: &mut NativeSearcher, query: &str, limit: usize) -> io::Result<Value> {
+    let completions = searcher.complete(query, limit)?;
+
+    let terms: Vec<Value> = completions.terms
+        .iter()
+        .map(|completion| json!({
+            "query": completion.query,
+            "term": completion.term,
+            "docFreq": completion.doc_freq,
+        }))
+        .collect();
+    let phrases: Vec<Value> = completions.phrases
+        .iter()
+        .map(|completion| json!({
+            "phrase": completion.phrase,
+            "fieldName": completion.field_name,
+            "numDocs": completion.num_docs,
+        }))
+        .collect();
+
+    Ok(json!({ "terms": terms, "phrases": phrases }))
+}
+
+fn get_content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("html") => "text/html; charset=utf-8",
+        Some("js") => "text/javascript; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        Some("json") => "application/json",
+        Some("wasm") => "application/wasm",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("woff2") => "font/woff2",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Resolves the url path under the output folder, rejecting any that escape it.
+fn resolve_file_path(output_folder_path: &Path, url_path: &str) -> Option<PathBuf> {
+    let decoded = percent_decode_str(url_path).decode_utf8().ok()?;
+
+    let mut file_path = output_folder_path.to_path_buf();
+    for segment in decoded.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => return None,
+            _ if segment.contains('\\') || Path::new(segment).is_absolute() => return None,
+            _ => file_path.push(segment),
+        }
+    }
+
+    if file_path.is_dir() {
+        file_path.push("index.html");
+    }
+
+    Some(file_path)
+}
+
+fn serve_file(output_folder_path: &Path, url_path: &str) -> ServeResponse {
+    let file_path = match resolve_file_path(output_folder_path, url_path) {
+        Some(file_path) => file_path,
+        None => return ServeResponse::error(400, "Invalid path"),
+    };
+
+    match fs::read(&file_path) {
+        Ok(body) => ServeResponse {
+            status: 200,
+            content_type: get_content_type(&file_path),
+            body,
+        },
+        Err(e) if e.kind() == ErrorKind::NotFound => ServeResponse::error(404, "Not found"),
+        Err(e) => ServeResponse::error(500, &e.to_string()),
+    }
+}
+
+/// Responds to a GET request for the given url, either a search, a query completion,
+/// or a static file in the output folder.
+pub fn handle_request(searcher: &mut NativeSearcher, output_folder_path: &Path, url: &str) -> ServeResponse {
+    let (url_path, query_string) = url.split_once('?').unwrap_or((url, ""));
+
+    if url_path == "/search" {
+        match parse_search_params(query_string) {
+            Ok(params) => match search(searcher, &params) {
+                Ok(results) => ServeResponse::json(200, &results),
+                Err(e) => ServeResponse::error(500, &e.to_string()),
+            },
+            Err(message) => ServeResponse::error(400, &message),
+        }
+    } else if url_path == "/complete" {
+        match parse_complete_params(query_string) {
+            Ok((query, limit)) => match complete(searcher, &query, limit) {
+                Ok(completions) => ServeResponse::json(200, &completions),
+                Err(e) => ServeResponse::error(500, &e.to_string()),
+            },
+            Err(message) => ServeResponse::error(400, &message),
+        }
+    } else {
+        serve_file(output_folder_path, url_path)
+    }
+}
+
+fn respond(request: Request, serve_response: ServeResponse) {
+    let content_type = Header::from_bytes("Content-Type", serve_response.content_type)
+        .expect("content types should be valid header values");
+    let response = Response::from_data(serve_response.body)
+        .with_status_code(serve_response.status)
+        .with_header(content_type);
+
+    if let Err(e) = request.respond(response) {
+        error!("Failed to send response. {}", e);
+    }
+}
+
+/// Serves the output folder's files, searches over the index at `/search`,
+/// and completes queries at `/complete`, until the process is killed.
+pub fn serve(output_folder_path: &Path, address: &str) -> io::Result<()> {
+    let mut searcher = NativeSearcher::new(output_folder_path, NativeSearcherOptions::default())?;
+
+    let server = Server::http(address).map_err(|e| Error::other(e.to_string()))?;
+
+    info!("Serving {} at http://{}", output_folder_path.to_string_lossy(), address);
+
+    for request in server.incoming_requests() {
+        let serve_response = match request.method() {
+            Method::Get | Method::Head => handle_request(&mut searcher, output_folder_path, request.url()),
+            _ => ServeResponse::error(405, "Only GET requests are supported"),
+        };
+        respond(request, serve_response);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use infisearch_search::native::{NativeSearcher, NativeSearcherOptions};
+    use pretty_assertions::assert_eq;
+    use serde_json::Value;
+
+    use crate::indexer::input_config::InfiConfig;
+    use crate::indexer::Indexer;
+
+    use super::{handle_request, parse_search_params};
+
+    #[test]
+    fn test_parse_search_params() {
+        let params = parse_search_params(
+            "q=lorem+ipsum&enum.weather=sunny&enum.weather=&i64.price=10..&i64.date=..20&sort=date:asc&sort=_score&limit=5",
+        ).unwrap();
+        assert_eq!(params.query, "lorem ipsum");
+        assert_eq!(
+            params.opts.enum_filters,
+            vec![("weather".to_owned(), vec![Some("sunny".to_owned()), None])],
+        );
+        assert_eq!(
+            params.opts.i64_filters,
+            vec![("price".to_owned(), Some(10), None), ("date".to_owned(), None, Some(20))],
+        );
+        assert_eq!(params.opts.sort_by, vec![("date".to_owned(), true), ("_score".to_owned(), false)]);
+        assert_eq!((params.offset, params.limit), (0, 5));
+
+        assert!(parse_search_params("i64.price=10").is_err());
+        assert!(parse_search_params("limit=-1").is_err());
+        assert!(parse_search_params("lorem=ipsum").is_err());
+    }
+
+    #[test]
+    fn test_handle_request() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let input_folder_path = temp_dir.path().join("input");
+        let output_folder_path = temp_dir.path().join("output");
+        fs::create_dir_all(&input_folder_path).unwrap();
+        fs::create_dir_all(&output_folder_path).unwrap();
+
+        for (file_name, text) in [("a.html", "lorem ipsum"), ("b.html", "lorem dolor"), ("c.html", "lorem lorem")] {
+            fs::write(input_folder_path.join(file_name), format!("<html><body><p>{}</p></body></html>", text)).unwrap();
+        }
+
+        let mut indexer = Indexer::new(
+            &input_folder_path,
+            &output_folder_path,
+            InfiConfig::default(),
+            false,
+            false,
+            false,
+            false,
+        ).unwrap();
+        for file_name in ["a.html", "b.html", "c.html"] {
+            indexer.index_file(&input_folder_path.join(file_name), file_name.as_ref()).unwrap();
+        }
+        indexer.finish_writing_docs().unwrap();
+
+        let mut searcher = NativeSearcher::new(&output_folder_path, NativeSearcherOptions::default()).unwrap();
+
+        let response = handle_request(&mut searcher, &output_folder_path, "/search?q=lorem&limit=2");
+        assert_eq!(response.status, 200);
+        let results: Value = serde_json::from_slice(&response.body).unwrap();
+        assert_eq!(results["resultsTotal"], 3);
+        assert_eq!(results["results"].as_array().unwrap().len(), 2);
+        let first_page_doc_ids = [results["results"][0]["docId"].clone(), results["results"][1]["docId"].clone()];
+
+        let response = handle_request(&mut searcher, &output_folder_path, "/search?q=lorem&offset=2&limit=2");
+        let results: Value = serde_json::from_slice(&response.body).unwrap();
+        assert_eq!(results["results"].as_array().unwrap().len(), 1);
+        assert!(!first_page_doc_ids.contains(&results["results"][0]["docId"]));
+        assert_eq!(results["results"][0]["snippet"]["highlights"][0][0], 0);
+
+        let response = handle_request(&mut searcher, &output_folder_path, "/search?q=lorem&limit=x");
+        assert_eq!(response.status, 400);
+
+        let response = handle_request(&mut searcher, &output_folder_path, "/search?q=lorem&limit=18446744073709551615");
+        assert_eq!(response.status, 200);
+        let results: Value = serde_json::from_slice(&response.body).unwrap();
+        assert_eq!(results["results"].as_array().unwrap().len(), 3);
+
+        let response = handle_request(&mut searcher, &output_folder_path, "/complete?q=lor&limit=18446744073709551615");
+        assert_eq!(response.status, 200);
+
+        let response = handle_request(&mut searcher, &output_folder_path, "/complete?q=lor");
+        assert_eq!(response.status, 200);
+        let completions: Value = serde_json::from_slice(&response.body).unwrap();
+        assert_eq!(completions["terms"][0]["query"], "lorem");
+        assert_eq!(completions["terms"][0]["docFreq"], 3);
+
+        let response = handle_request(&mut searcher, &output_folder_path, "/output_config.json");
+        assert_eq!(response.status, 200);
+        assert_eq!(response.content_type, "application/json");
+
+        assert_eq!(handle_request(&mut searcher, &output_folder_path, "/missing.json").status, 404);
+        assert_eq!(handle_request(&mut searcher, &output_folder_path, "/../input/a.html").status, 400);
+        assert_eq!(handle_request(&mut searcher, &output_folder_path, "/%2E%2E/input/a.html").status, 400);
+    }
+}
@@ -0,0 +1,290 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use infisearch_common::metadata::MetadataReader;
+use infisearch_common::{FILE_EXT, METADATA_FILE};
+use infisearch_search::native::OutputConfig;
+use serde_json::{json, Value};
+use walkdir::WalkDir;
+
+use crate::OUTPUT_CONFIG_FILE;
+
+// Upper bounds of the postings list file size distribution's buckets, larger files go into a last bucket
+const PL_SIZE_BUCKETS: [u64; 6] = [1 << 10, 4 << 10, 16 << 10, 64 << 10, 256 << 10, 1 << 20];
+
+/// Count, total, and distribution of the given file sizes
+fn get_size_summary(mut sizes: Vec<u64>) -> Value {
+    sizes.sort_unstable();
+
+    let total_size: u64 = sizes.iter().sum();
+    let percentile = |percent: usize| {
+        if sizes.is_empty() { 0 } else { sizes[((sizes.len() - 1) * percent) / 100] }
+    };
+
+    json!({
+        "numFiles": sizes.len(),
+        "totalSize": total_size,
+        "averageSize": if sizes.is_empty() { 0 } else { total_size / sizes.len() as u64 },
+        "medianSize": percentile(50),
+        "p90Size": percentile(90),
+        "maxSize": percentile(100),
+    })
+}
+
+fn get_file_sizes(folder_path: &Path, extension: &str) -> Vec<u64> {
+    WalkDir::new(folder_path)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file()
+            && entry.path().extension().is_some_and(|ext| ext == extension))
+        .filter_map(|entry| entry.metadata().ok())
+        .map(|metadata| metadata.len())
+        .collect()
+}
+
+/// Reports the statistics of the index in the output folder useful for tuning the indexer's
+/// scaling options, including the `top_terms` terms with the highest document frequencies.
+pub fn get_index_stats(output_folder_path: &Path, top_terms: usize) -> io::Result<Value> {
+    let output_config: OutputConfig = serde_json::from_slice(&fs::read(output_folder_path.join(OUTPUT_CONFIG_FILE))?)?;
+    let index_folder_path = output_folder_path.join(&output_config.index_ver);
+
+    let mut metadata_rdr = MetadataReader::new(fs::read(index_folder_path.join(METADATA_FILE))?);
+
+    // ------------------------------------------------------------
+    // Documents and fields
+
+    let mut num_docs = 0;
+    let mut doc_id_counter = 0;
+    let mut average_lengths = Vec::with_capacity(output_config.num_scored_fields);
+    let mut num_enum_fields = 0;
+    let mut num_i64_fields = 0;
+    metadata_rdr.read_docinfo_inital_metadata(
+        &mut num_docs,
+        &mut doc_id_counter,
+        &mut average_lengths,
+        &mut num_enum_fields,
+        &mut num_i64_fields,
+        output_config.num_scored_fields,
+    );
+
+    let average_field_lengths: Vec<Value> = average_lengths
+        .iter()
+        .enumerate()
+        .filter_map(|(field_id, average_length)| {
+            output_config.field_infos
+                .iter()
+                .find(|field_info| field_info.id as usize == field_id)
+                .map(|field_info| json!({ "field": field_info.name, "averageLength": average_length }))
+        })
+        .collect();
+    // ------------------------------------------------------------
+
+    // ------------------------------------------------------------
+    // Dictionary
+
+    let dictionary = metadata_rdr.setup_dictionary();
+    let mut terms: Vec<(&str, u32)> = dictionary.term_infos
+        .iter()
+        .map(|(term, term_info)| (term.as_str(), term_info.doc_freq))
+        .collect();
+    // Stable, so that terms with equal document frequencies stay in alphabetical order
+    terms.sort_by(|(_term_a, doc_freq_a), (_term_b, doc_freq_b)| doc_freq_b.cmp(doc_freq_a));
+    let top_terms: Vec<Value> = terms
+        .iter()
+        .take(top_terms)
+        .map(|(term, doc_freq)| json!({ "term": term, "docFreq": doc_freq }))
+        .collect();
+
+    let section_lengths = metadata_rdr.get_section_lengths();
+    // ------------------------------------------------------------
+
+    // ------------------------------------------------------------
+    // Files
+
+    let pl_sizes = get_file_sizes(&index_folder_path, FILE_EXT);
+    let mut pl_size_distribution: Vec<Value> = PL_SIZE_BUCKETS
+        .iter()
+        .enumerate()
+        .map(|(idx, &max_size)| {
+            let min_size = if idx == 0 { 0 } else { PL_SIZE_BUCKETS[idx - 1] };
+            let num_files = pl_sizes.iter().filter(|&&size| size >= min_size && size < max_size).count();
+            json!({ "maxSize": max_size, "numFiles": num_files })
+        })
+        .collect();
+    let last_bucket_min_size = PL_SIZE_BUCKETS[PL_SIZE_BUCKETS.len() - 1];
+    pl_size_distribution.push(json!({
+        "maxSize": null,
+        "numFiles": pl_sizes.iter().filter(|&&size| size >= last_bucket_min_size).count(),
+    }));
+
+    let mut postings_lists = get_size_summary(pl_sizes);
+    postings_lists["distribution"] = Value::from(pl_size_distribution);
+
+    let field_stores = get_size_summary(get_file_sizes(&index_folder_path.join("field_store"), "json"));
+    // ------------------------------------------------------------
+
+    Ok(json!({
+        "numDocs": num_docs,
+        "numDeletedDocs": doc_id_counter - num_docs,
+        "numTerms": dictionary.term_infos.len(),
+        "metadataLengths": {
+            "dictionaryString": section_lengths.dict_string,
+            "dictionaryTable": section_lengths.dict_table,
+            "invalidationVec": section_lengths.invalidation_vec,
+            "docInfos": section_lengths.doc_infos,
+            "spellingIndex": section_lengths.spelling_index,
+        },
+        "postingsLists": postings_lists,
+        "fieldStores": field_stores,
+        "averageFieldLengths": average_field_lengths,
+        "topTerms": top_terms,
+    }))
+}
+
+fn format_size(size: &Value) -> String {
+    let size = size.as_u64().unwrap_or(0);
+    if size < 1 << 10 {
+        format!("{} B", size)
+    } else if size < 1 << 20 {
+        format!("{:.1} KiB", size as f64 / (1 << 10) as f64)
+    } else {
+        format!("{:.1} MiB", size as f64 / (1 << 20) as f64)
+    }
+}
+
+fn print_size_summary(name: &str, summary: &Value) {
+    println!(
+        "{}: {} files, {}",
+        name, summary["numFiles"], format_size(&summary["totalSize"]),
+    );
+    println!(
+        "  Average {}, median {}, 90th percentile {}, max {}",
+        format_size(&summary["averageSize"]),
+        format_size(&summary["medianSize"]),
+        format_size(&summary["p90Size"]),
+        format_size(&summary["maxSize"]),
+    );
+}
+
+pub fn print_index_stats(stats: &Value) {
+    println!("Documents: {} ({} deleted)", stats["numDocs"], stats["numDeletedDocs"]);
+    println!("Dictionary terms: {}", stats["numTerms"]);
+
+    let metadata_lengths = &stats["metadataLengths"];
+    println!("Metadata lengths:");
+    println!("  Dictionary string: {}", format_size(&metadata_lengths["dictionaryString"]));
+    println!("  Dictionary table: {}", format_size(&metadata_lengths["dictionaryTable"]));
+    println!("  Invalidation Vec: {}", format_size(&metadata_lengths["invalidationVec"]));
+    println!("  Doc Infos: {}", format_size(&metadata_lengths["docInfos"]));
+    println!("  Spelling Index: {}", format_size(&metadata_lengths["spellingIndex"]));
+
+    print_size_summary("Postings lists", &stats["postingsLists"]);
+    for bucket in stats["postingsLists"]["distribution"].as_array().into_iter().flatten() {
+        if bucket["maxSize"].is_null() {
+            println!("    >= {}: {}", format_size(&json!(PL_SIZE_BUCKETS[PL_SIZE_BUCKETS.len() - 1])), bucket["numFiles"]);
+        } else {
+            println!("    < {}: {}", format_size(&bucket["maxSize"]), bucket["numFiles"]);
+        }
+    }
+
+    print_size_summary("Field stores", &stats["fieldStores"]);
+
+    println!("Average field lengths:");
+    for field in stats["averageFieldLengths"].as_array().into_iter().flatten() {
+        if let (Some(field_name), Some(average_length)) = (field["field"].as_str(), field["averageLength"].as_f64()) {
+            println!("  {}: {:.2}", field_name, average_length);
+        }
+    }
+
+    println!("Top terms by document frequency:");
+    for term in stats["topTerms"].as_array().into_iter().flatten() {
+        if let Some(term_str) = term["term"].as_str() {
+            println!("  {}: {}", term_str, term["docFreq"]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use crate::indexer::input_config::InfiConfig;
+    use crate::indexer::Indexer;
+
+    use super::{format_size, get_size_summary};
+
+    #[test]
+    fn test_get_size_summary() {
+        let summary = get_size_summary(vec![30, 10, 20, 40]);
+        assert_eq!(summary["numFiles"], 4);
+        assert_eq!(summary["totalSize"], 100);
+        assert_eq!(summary["averageSize"], 25);
+        assert_eq!(summary["medianSize"], 20);
+        assert_eq!(summary["maxSize"], 40);
+
+        assert_eq!(get_size_summary(Vec::new())["maxSize"], 0);
+
+        assert_eq!(format_size(&json!(512)), "512 B");
+        assert_eq!(format_size(&json!(1536)), "1.5 KiB");
+    }
+
+    #[test]
+    fn test_get_index_stats() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let input_folder_path = temp_dir.path().join("input");
+        let output_folder_path = temp_dir.path().join("output");
+        fs::create_dir_all(&input_folder_path).unwrap();
+        fs::create_dir_all(&output_folder_path).unwrap();
+
+        let index = |is_incremental: bool, docs: &[(&str, &str)]| {
+            let mut indexer = Indexer::new(
+                &input_folder_path,
+                &output_folder_path,
+                InfiConfig::default(),
+                is_incremental,
+                true,
+                false,
+                false,
+            ).unwrap();
+            for (file_name, text) in docs {
+                fs::write(
+                    input_folder_path.join(file_name),
+                    format!("<html><body><p>{}</p></body></html>", text),
+                ).unwrap();
+                indexer.index_file(&input_folder_path.join(file_name), file_name.as_ref()).unwrap();
+            }
+            indexer.finish_writing_docs().unwrap();
+        };
+
+        index(false, &[("a.html", "lorem ipsum"), ("b.html", "lorem dolor"), ("c.html", "lorem lorem")]);
+
+        let stats = super::get_index_stats(&output_folder_path, 2).unwrap();
+        assert_eq!(stats["numDocs"], 3);
+        assert_eq!(stats["numDeletedDocs"], 0);
+        assert_eq!(stats["topTerms"], json!([{ "term": "lorem", "docFreq": 3 }, { "term": "dolor", "docFreq": 1 }]));
+        assert!(stats["postingsLists"]["numFiles"].as_u64().unwrap() > 0);
+        assert_eq!(stats["postingsLists"]["distribution"][0]["numFiles"], stats["postingsLists"]["numFiles"]);
+        assert_eq!(stats["fieldStores"]["numFiles"], 1);
+
+        let body_length = stats["averageFieldLengths"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|field| field["field"] == "body")
+            .unwrap()["averageLength"]
+            .as_f64()
+            .unwrap();
+        assert!((body_length - 2.0).abs() < f64::EPSILON);
+
+        // c is deleted
+        index(true, &[("a.html", "lorem ipsum"), ("b.html", "lorem dolor")]);
+
+        let stats = super::get_index_stats(&output_folder_path, 2).unwrap();
+        assert_eq!(stats["numDocs"], 2);
+        assert_eq!(stats["numDeletedDocs"], 1);
+    }
+}
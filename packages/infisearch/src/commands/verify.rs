@@ -0,0 +1,640 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use byteorder::{ByteOrder, LittleEndian};
+use infisearch_common::bitmap;
+use infisearch_common::dictionary::{TermInfo, DICT_MAX_BIT_LENS, DICT_MAX_VALUES_U8};
+use infisearch_common::metadata::HEADER_LEN;
+use infisearch_common::packed_var_int::read_bits_from;
+use infisearch_common::postings_list::{CHUNK_SIZE, LAST_FIELD_MASK, MIN_CHUNK_SIZE, SHORT_FORM_MASK};
+use infisearch_common::{FILE_EXT, METADATA_FILE};
+use infisearch_search::native::OutputConfig;
+use walkdir::WalkDir;
+
+use crate::OUTPUT_CONFIG_FILE;
+
+pub struct VerifyReport {
+    pub num_terms: usize,
+    pub num_pl_files: usize,
+    pub num_field_stores: usize,
+    /// Descriptions of each inconsistency found, empty if the index is intact
+    pub problems: Vec<String>,
+}
+
+/// Bounds checked reads of the index's binary formats, which the searcher reads without checks
+struct CheckedReader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> CheckedReader<'a> {
+    fn new(buf: &'a [u8], pos: usize) -> Self {
+        CheckedReader { buf, pos }
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), String> {
+        if self.pos + len > self.buf.len() {
+            return Err(format!("unexpected end of {} bytes at byte {}", self.buf.len(), self.pos));
+        }
+        self.pos += len;
+        Ok(())
+    }
+
+    fn read_u8(&mut self) -> Result<u8, String> {
+        self.skip(1)?;
+        Ok(self.buf[self.pos - 1])
+    }
+
+    fn read_u32(&mut self) -> Result<u32, String> {
+        self.skip(4)?;
+        Ok(LittleEndian::read_u32(&self.buf[self.pos - 4..]))
+    }
+
+    /// See `infisearch_common::utils::varint`, where the last byte has the continuation bit set instead
+    fn read_var_int(&mut self) -> Result<u64, String> {
+        let start = self.pos;
+        let mut value: u64 = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.read_u8()?;
+            if shift >= 64 {
+                return Err(format!("overlong varint at byte {}", start));
+            }
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 != 0 {
+                return Ok(value);
+            }
+            shift += 7;
+        }
+    }
+
+    /// Reads bits starting from the current byte, see `read_bits_from`
+    fn read_bits(&self, bit_pos: &mut usize, bit_len: usize) -> Result<u32, String> {
+        if bit_len == 0 {
+            return Ok(0);
+        } else if bit_len > 32 {
+            return Err(format!("invalid bit length {} at byte {}", bit_len, self.pos));
+        }
+
+        let slice = &self.buf[self.pos..];
+        if *bit_pos + bit_len > slice.len() * 8 {
+            return Err(format!("unexpected end of {} bytes in packed bits at byte {}", self.buf.len(), self.pos));
+        }
+        Ok(read_bits_from(bit_pos, bit_len, slice))
+    }
+}
+
+// ------------------------------------------------------------
+// Metadata
+
+/// Checks the metadata header's section offsets, returning them if they are valid
+fn verify_header(buf: &[u8], problems: &mut Vec<String>) -> Option<[usize; 4]> {
+    if buf.len() < HEADER_LEN {
+        problems.push(format!("Metadata: {} bytes is shorter than its {} byte header", buf.len(), HEADER_LEN));
+        return None;
+    }
+
+    let offsets = [0, 4, 8, 12].map(|pos| LittleEndian::read_u32(&buf[pos..]) as usize);
+    let mut prev_offset = HEADER_LEN;
+    for &offset in offsets.iter() {
+        if offset < prev_offset || offset > buf.len() {
+            problems.push(format!(
+                "Metadata: header offsets {:?} are not ascending within the {} byte file",
+                offsets, buf.len(),
+            ));
+            return None;
+        }
+        prev_offset = offset;
+    }
+
+    Some(offsets)
+}
+
+fn verify_invalidation_vec(invalidation_vec: &[u8], last_doc_id: u32, problems: &mut Vec<String>) -> bool {
+    let expected_len = (last_doc_id as usize).div_ceil(8);
+    if invalidation_vec.len() != expected_len {
+        problems.push(format!(
+            "Invalidation vector: {} bytes, but {} are needed for {} document ids",
+            invalidation_vec.len(), expected_len, last_doc_id,
+        ));
+        return false;
+    }
+
+    let num_padding_bits = expected_len * 8 - last_doc_id as usize;
+    if (0..num_padding_bits).any(|bit| bitmap::check(invalidation_vec, last_doc_id as usize + bit)) {
+        problems.push(format!("Invalidation vector: document ids after the last id {} are invalidated", last_doc_id));
+    }
+
+    true
+}
+
+/// Decodes every section of the doc infos, see `DocInfos::finalize_and_flush`
+fn verify_doc_infos(doc_infos: &[u8], output_config: &OutputConfig, num_valid_docs: Option<u32>) -> Result<(), String> {
+    let mut rdr = CheckedReader::new(doc_infos, 0);
+
+    let num_docs = rdr.read_u32()?;
+    let doc_id_counter = rdr.read_u32()?;
+    if doc_id_counter != output_config.last_doc_id {
+        return Err(format!(
+            "{} document ids, but the output config's last document id is {}",
+            doc_id_counter, output_config.last_doc_id,
+        ));
+    }
+    if let Some(num_valid_docs) = num_valid_docs.filter(|&num_valid_docs| num_valid_docs != num_docs) {
+        return Err(format!("{} documents, but {} are not invalidated", num_docs, num_valid_docs));
+    }
+
+    let num_fields = output_config.num_scored_fields;
+    let num_doc_ids = doc_id_counter as usize;
+
+    // Average field lengths
+    rdr.skip(num_fields * 8)?;
+
+    let field_lengths_start = rdr.pos;
+    let field_lengths_end = field_lengths_start + rdr.read_u32()? as usize;
+    for _i in 0..num_doc_ids * num_fields {
+        rdr.read_var_int()?;
+    }
+    if rdr.pos != field_lengths_end {
+        return Err(format!(
+            "field lengths of {} documents end at byte {}, instead of byte {}",
+            num_doc_ids, rdr.pos, field_lengths_end,
+        ));
+    }
+
+    // Enum values, bit packed
+    let mut enum_value_counts: Vec<usize> = Vec::new();
+    for field_info in output_config.field_infos.iter() {
+        if let Some(enum_info) = &field_info.enum_info {
+            if enum_info.enum_id >= enum_value_counts.len() {
+                enum_value_counts.resize(enum_info.enum_id + 1, 0);
+            }
+            enum_value_counts[enum_info.enum_id] = enum_info.enum_values.len();
+        }
+    }
+    let num_enum_fields = rdr.read_u32()? as usize;
+    if num_enum_fields != enum_value_counts.len() {
+        return Err(format!("{} enum fields, but the output config has {}", num_enum_fields, enum_value_counts.len()));
+    }
+    let mut bit_lens = Vec::with_capacity(num_enum_fields);
+    for _enum_id in 0..num_enum_fields {
+        bit_lens.push(rdr.read_u8()? as usize);
+    }
+    let mut bit_pos = 0;
+    for doc_id in 0..num_doc_ids {
+        for (enum_id, &bit_len) in bit_lens.iter().enumerate() {
+            let ev_id = rdr.read_bits(&mut bit_pos, bit_len)? as usize;
+            if ev_id > enum_value_counts[enum_id] {
+                return Err(format!(
+                    "document {} has enum value id {} for enum {}, which only has {} values",
+                    doc_id, ev_id, enum_id, enum_value_counts[enum_id],
+                ));
+            }
+        }
+    }
+    rdr.skip(bit_pos.div_ceil(8))?;
+
+    // I64 values, as varint deltas from each field's minimum
+    let num_i64_fields = rdr.read_u32()? as usize;
+    let expected_num_i64_fields = output_config.field_infos.iter().filter(|fi| fi.i64_info.is_some()).count();
+    if num_i64_fields != expected_num_i64_fields {
+        return Err(format!("{} i64 fields, but the output config has {}", num_i64_fields, expected_num_i64_fields));
+    }
+    rdr.skip(num_i64_fields * 8)?;
+    for _i in 0..num_doc_ids * num_i64_fields {
+        rdr.read_var_int()?;
+    }
+
+    if rdr.pos != doc_infos.len() {
+        return Err(format!("decoding ends at byte {} of {}", rdr.pos, doc_infos.len()));
+    }
+
+    Ok(())
+}
+
+/// Decodes the front coded dictionary, see `DictionaryConstructor`, which reads it without checks
+fn verify_dictionary(dict_string: &[u8], dict_table: &[u8]) -> Result<BTreeMap<String, TermInfo>, String> {
+    let rdr = CheckedReader::new(dict_table, 0);
+    let mut bit_pos = 0;
+    let mut chunk_lens = [0; 4];
+    let mut chunk_idxes = [0_u8; 4];
+    // See `PackedVarIntReader::read_type`
+    let mut read_type = |t: usize| -> Result<u32, String> {
+        if chunk_idxes[t] == 0 {
+            chunk_lens[t] = rdr.read_bits(&mut bit_pos, DICT_MAX_BIT_LENS[t])? as usize + 1;
+        }
+        chunk_idxes[t] = (chunk_idxes[t] + 1) % DICT_MAX_VALUES_U8[t];
+        rdr.read_bits(&mut bit_pos, chunk_lens[t])
+    };
+
+    let mut term_infos = BTreeMap::new();
+    let mut term = String::new();
+    let mut dict_string_pos = 0;
+    let mut postings_file_name = 0;
+    let mut postings_file_offset: u32 = 0;
+    while dict_string_pos < dict_string.len() {
+        let mut doc_freq = read_type(0)?;
+
+        // new postings list delimiter
+        if doc_freq == 0 {
+            postings_file_name += 1;
+            postings_file_offset = 0;
+            doc_freq = read_type(0)?;
+        }
+
+        postings_file_offset = postings_file_offset.checked_add(read_type(1)?)
+            .ok_or_else(|| format!("postings list offset of term {} overflows", term_infos.len()))?;
+
+        let prefix_len = read_type(2)? as usize;
+        let remaining_len = read_type(3)? as usize;
+        if prefix_len > term.len() || !term.is_char_boundary(prefix_len) {
+            return Err(format!(
+                "term {} shares {} bytes with the previous term \"{}\", which is not a prefix of it",
+                term_infos.len(), prefix_len, term,
+            ));
+        }
+
+        let suffix = dict_string.get(dict_string_pos..dict_string_pos + remaining_len).ok_or_else(|| format!(
+            "term {} of {} bytes is out of range of the {} byte dictionary string",
+            term_infos.len(), remaining_len, dict_string.len(),
+        ))?;
+        let suffix = std::str::from_utf8(suffix)
+            .map_err(|e| format!("term {} at byte {} is not valid UTF-8. {}", term_infos.len(), dict_string_pos, e))?;
+        dict_string_pos += remaining_len;
+
+        term.truncate(prefix_len);
+        term.push_str(suffix);
+
+        let term_info = TermInfo { doc_freq, postings_file_name, postings_file_offset };
+        if term_infos.insert(term.clone(), term_info).is_some() {
+            return Err(format!("term \"{}\" is repeated", term));
+        }
+    }
+
+    Ok(term_infos)
+}
+// ------------------------------------------------------------
+
+// ------------------------------------------------------------
+// Postings lists
+
+/// Decodes a postings list, see `PostingsList::parse_pl`, returning where it ends.
+fn verify_postings_list(
+    pl: &[u8],
+    term_info: &TermInfo,
+    num_scored_fields: usize,
+    with_positions: bool,
+    last_doc_id: u32,
+) -> Result<usize, String> {
+    if term_info.postings_file_offset as usize >= pl.len() {
+        return Err(format!("offset {} is out of range of the {} byte file", term_info.postings_file_offset, pl.len()));
+    }
+
+    let mut rdr = CheckedReader::new(pl, term_info.postings_file_offset as usize);
+    let mut prev_doc_id: Option<u64> = None;
+    for _i in 0..term_info.doc_freq {
+        let doc_id_gap = rdr.read_var_int()?;
+        let doc_id = prev_doc_id.map_or(doc_id_gap, |prev_doc_id| prev_doc_id + doc_id_gap);
+        if prev_doc_id.is_some() && doc_id_gap == 0 {
+            return Err(format!("document {} is repeated", doc_id));
+        }
+        if doc_id >= last_doc_id as u64 {
+            return Err(format!("document {} is not below the last document id {}", doc_id, last_doc_id));
+        }
+        prev_doc_id = Some(doc_id);
+
+        let mut prev_field_id: Option<u8> = None;
+        loop {
+            let field_byte = rdr.read_u8()?;
+            let (field_id, field_tf) = if (field_byte & SHORT_FORM_MASK) != 0 {
+                ((field_byte & 0b00111000) >> 3, (field_byte & 0b00000111) as u64)
+            } else {
+                (field_byte & 0b00111111, rdr.read_var_int()?)
+            };
+
+            if field_id as usize >= num_scored_fields {
+                return Err(format!("document {} has field id {}, but there are {} scored fields", doc_id, field_id, num_scored_fields));
+            }
+            if prev_field_id.is_some_and(|prev_field_id| prev_field_id >= field_id) {
+                return Err(format!("document {} has field ids out of order", doc_id));
+            }
+            if field_tf == 0 {
+                return Err(format!("document {} has a term frequency of 0 for field {}", doc_id, field_id));
+            }
+            prev_field_id = Some(field_id);
+
+            if with_positions {
+                if field_tf >= MIN_CHUNK_SIZE as u64 {
+                    let mut bit_pos = 0;
+                    let mut num_read = 0;
+                    while num_read < field_tf {
+                        let chunk_len = rdr.read_bits(&mut bit_pos, 5)? as usize;
+                        for _i in 0..CHUNK_SIZE {
+                            rdr.read_bits(&mut bit_pos, chunk_len)?;
+                            num_read += 1;
+                            if num_read == field_tf {
+                                break;
+                            }
+                        }
+                    }
+                    rdr.skip(bit_pos.div_ceil(8))?;
+                } else {
+                    for _i in 0..field_tf {
+                        rdr.read_var_int()?;
+                    }
+                }
+            }
+
+            if (field_byte & LAST_FIELD_MASK) != 0 {
+                break;
+            }
+        }
+    }
+
+    Ok(rdr.pos)
+}
+
+fn verify_pl_files(
+    index_folder_path: &Path,
+    output_config: &OutputConfig,
+    term_infos: &BTreeMap<String, TermInfo>,
+    problems: &mut Vec<String>,
+) -> usize {
+    // Terms in each postings list file, in the order they are stored
+    let mut pl_terms: BTreeMap<u32, Vec<(&str, &TermInfo)>> = BTreeMap::new();
+    for (term, term_info) in term_infos.iter() {
+        pl_terms.entry(term_info.postings_file_name).or_default().push((term.as_str(), term_info));
+    }
+
+    let num_pls_per_dir = output_config.indexing_config.num_pls_per_dir;
+    for (pl_num, terms) in pl_terms.iter_mut() {
+        let relative_path = PathBuf::from(format!("pl_{}", pl_num / num_pls_per_dir))
+            .join(format!("pl_{}.{}", pl_num, FILE_EXT));
+        let pl = match fs::read(index_folder_path.join(&relative_path)) {
+            Ok(pl) => pl,
+            Err(e) => {
+                problems.push(format!("{}: unreadable, needed by {} terms. {}", relative_path.display(), terms.len(), e));
+                continue;
+            }
+        };
+
+        terms.sort_by_key(|(_term, term_info)| term_info.postings_file_offset);
+        for (idx, (term, term_info)) in terms.iter().enumerate() {
+            let next_offset = terms.get(idx + 1)
+                .map_or(pl.len(), |(_next_term, next_term_info)| next_term_info.postings_file_offset as usize);
+
+            let result = verify_postings_list(
+                &pl,
+                term_info,
+                output_config.num_scored_fields,
+                output_config.indexing_config.with_positions,
+                output_config.last_doc_id,
+            );
+            match result {
+                Ok(end) if end != next_offset => problems.push(format!(
+                    "{}: postings of \"{}\" with a document frequency of {} end at byte {}, but the next postings start at byte {}",
+                    relative_path.display(), term, term_info.doc_freq, end, next_offset,
+                )),
+                Ok(_end) => {}
+                Err(message) => problems.push(format!("{}: postings of \"{}\": {}", relative_path.display(), term, message)),
+            }
+        }
+    }
+
+    pl_terms.len()
+}
+// ------------------------------------------------------------
+
+// ------------------------------------------------------------
+// Field stores
+
+fn verify_field_stores(
+    index_folder_path: &Path,
+    output_config: &OutputConfig,
+    invalidation_vec: Option<&[u8]>,
+    problems: &mut Vec<String>,
+) -> usize {
+    let mut num_docs_by_path: HashMap<PathBuf, usize> = HashMap::new();
+    for entry in WalkDir::new(index_folder_path.join("field_store")).into_iter().filter_map(|entry| entry.ok()) {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+
+        let relative_path = entry.path().strip_prefix(index_folder_path).unwrap_or_else(|_e| entry.path());
+        let parsed = fs::read(entry.path())
+            .map_err(|e| e.to_string())
+            .and_then(|buf| serde_json::from_slice::<Vec<Vec<(String, String)>>>(&buf).map_err(|e| e.to_string()));
+        match parsed {
+            Ok(docs) => {
+                num_docs_by_path.insert(entry.path().to_path_buf(), docs.len());
+            }
+            Err(message) => problems.push(format!("{}: invalid field store. {}", relative_path.display(), message)),
+        }
+    }
+
+    // Valid documents missing from each field store, which may be missing itself
+    let mut missing_docs: BTreeMap<PathBuf, Vec<u32>> = BTreeMap::new();
+    for doc_id in 0..output_config.last_doc_id {
+        if invalidation_vec.is_some_and(|invalidation_vec| bitmap::check(invalidation_vec, doc_id as usize)) {
+            continue;
+        }
+
+        let (path, idx) = output_config.get_field_store_location(index_folder_path, doc_id);
+        if num_docs_by_path.get(&path).is_none_or(|&num_docs| idx >= num_docs) {
+            missing_docs.entry(path).or_default().push(doc_id);
+        }
+    }
+
+    for (path, doc_ids) in missing_docs {
+        let relative_path = path.strip_prefix(index_folder_path).unwrap_or(&path);
+        problems.push(format!(
+            "{}: missing {} documents, from document {} to {}",
+            relative_path.display(), doc_ids.len(), doc_ids[0], doc_ids[doc_ids.len() - 1],
+        ));
+    }
+
+    num_docs_by_path.len()
+}
+// ------------------------------------------------------------
+
+/// Cross-checks the metadata, postings lists, and field stores of the index in the output folder.
+///
+/// Errors are only returned if the output config or metadata cannot be read at all.
+pub fn verify_index(output_folder_path: &Path) -> io::Result<VerifyReport> {
+    let output_config: OutputConfig = serde_json::from_slice(&fs::read(output_folder_path.join(OUTPUT_CONFIG_FILE))?)?;
+    let index_folder_path = output_folder_path.join(&output_config.index_ver);
+    let metadata = fs::read(index_folder_path.join(METADATA_FILE))?;
+
+    let mut report = VerifyReport { num_terms: 0, num_pl_files: 0, num_field_stores: 0, problems: Vec::new() };
+    let problems = &mut report.problems;
+
+    let offsets = verify_header(&metadata, problems);
+
+    let invalidation_vec = offsets.and_then(|[_dict_table, invalidation_vec, doc_infos, _spelling_index]| {
+        let invalidation_vec = &metadata[invalidation_vec..doc_infos];
+        if verify_invalidation_vec(invalidation_vec, output_config.last_doc_id, problems) {
+            Some(invalidation_vec)
+        } else {
+            None
+        }
+    });
+
+    if let Some([_dict_table, _invalidation_vec, doc_infos, spelling_index]) = offsets {
+        let num_valid_docs = invalidation_vec.map(|invalidation_vec| {
+            (0..output_config.last_doc_id)
+                .filter(|&doc_id| !bitmap::check(invalidation_vec, doc_id as usize))
+                .count() as u32
+        });
+        if let Err(message) = verify_doc_infos(&metadata[doc_infos..spelling_index], &output_config, num_valid_docs) {
+            problems.push(format!("Doc infos: {}", message));
+        }
+    }
+
+    report.num_field_stores = verify_field_stores(&index_folder_path, &output_config, invalidation_vec, problems);
+
+    if let Some([dict_table, invalidation_vec, _doc_infos, _spelling_index]) = offsets {
+        match verify_dictionary(&metadata[HEADER_LEN..dict_table], &metadata[dict_table..invalidation_vec]) {
+            Ok(term_infos) => {
+                report.num_terms = term_infos.len();
+                report.num_pl_files = verify_pl_files(&index_folder_path, &output_config, &term_infos, problems);
+            }
+            Err(message) => problems.push(format!("Dictionary: {}", message)),
+        }
+    }
+
+    Ok(report)
+}
+
+pub fn print_verify_report(report: &VerifyReport) {
+    println!(
+        "Checked {} terms in {} postings list files, and {} field stores",
+        report.num_terms, report.num_pl_files, report.num_field_stores,
+    );
+
+    if report.problems.is_empty() {
+        println!("No problems found");
+    } else {
+        for problem in report.problems.iter() {
+            println!("  {}", problem);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::path::Path;
+
+    use byteorder::{ByteOrder, LittleEndian};
+    use pretty_assertions::assert_eq;
+
+    use infisearch_common::METADATA_FILE;
+    use infisearch_common::metadata::HEADER_LEN;
+
+    use crate::indexer::input_config::InfiConfig;
+    use crate::indexer::Indexer;
+
+    use super::verify_index;
+
+    fn index(input_folder_path: &Path, output_folder_path: &Path, is_incremental: bool, docs: &[(&str, &str)]) {
+        let mut indexer = Indexer::new(
+            input_folder_path,
+            output_folder_path,
+            InfiConfig::default(),
+            is_incremental,
+            true,
+            false,
+            false,
+        ).unwrap();
+        for (file_name, text) in docs {
+            fs::write(
+                input_folder_path.join(file_name),
+                format!("<html><body><p>{}</p></body></html>", text),
+            ).unwrap();
+            indexer.index_file(&input_folder_path.join(file_name), file_name.as_ref()).unwrap();
+        }
+        indexer.finish_writing_docs().unwrap();
+    }
+
+    #[test]
+    fn test_verify_index() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let input_folder_path = temp_dir.path().join("input");
+        let output_folder_path = temp_dir.path().join("output");
+        fs::create_dir_all(&input_folder_path).unwrap();
+        fs::create_dir_all(&output_folder_path).unwrap();
+
+        index(&input_folder_path, &output_folder_path, false, &[
+            ("a.html", "lorem ipsum lorem ipsum lorem ipsum dolor"), ("b.html", "lorem dolor"), ("c.html", "sit amet"),
+        ]);
+        let report = verify_index(&output_folder_path).unwrap();
+        assert_eq!(report.problems, Vec::<String>::new());
+        assert_eq!(report.num_pl_files, 1);
+        assert_eq!(report.num_field_stores, 1);
+
+        // b is updated, c is deleted, d is added
+        index(&input_folder_path, &output_folder_path, true, &[
+            ("a.html", "lorem ipsum lorem ipsum lorem ipsum dolor"), ("b.html", "sit dolor"), ("d.html", "amet"),
+        ]);
+        assert_eq!(verify_index(&output_folder_path).unwrap().problems, Vec::<String>::new());
+
+        // Corrupt the postings lists and a field store
+        let index_folder_path = fs::read_dir(&output_folder_path)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .find(|path| path.is_dir())
+            .unwrap();
+        let pl_path = index_folder_path.join("pl_0/pl_0.mls");
+        let pl = fs::read(&pl_path).unwrap();
+        fs::write(&pl_path, &pl[..pl.len() - 1]).unwrap();
+        let field_store_path = index_folder_path.join("field_store/0/0/0.json");
+        let field_store = fs::read(&field_store_path).unwrap();
+        fs::write(&field_store_path, &field_store[..field_store.len() - 1]).unwrap();
+
+        let problems = verify_index(&output_folder_path).unwrap().problems;
+        assert!(problems.iter().any(|problem| problem.starts_with("field_store/0/0/0.json: invalid field store")));
+        assert!(problems.iter().any(|problem| problem.starts_with("field_store/0/0/0.json: missing")));
+        assert!(problems.iter().any(|problem| problem.starts_with("pl_0/pl_0.mls: postings of")));
+    }
+
+    #[test]
+    fn test_verify_corrupt_dictionary() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let input_folder_path = temp_dir.path().join("input");
+        let output_folder_path = temp_dir.path().join("output");
+        fs::create_dir_all(&input_folder_path).unwrap();
+        fs::create_dir_all(&output_folder_path).unwrap();
+
+        index(&input_folder_path, &output_folder_path, false, &[
+            ("a.html", "lorem ipsum dolor"), ("b.html", "sit amet"),
+        ]);
+        assert_eq!(verify_index(&output_folder_path).unwrap().problems, Vec::<String>::new());
+
+        let index_folder_path = fs::read_dir(&output_folder_path)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .find(|path| path.is_dir())
+            .unwrap();
+        let metadata_path = index_folder_path.join(METADATA_FILE);
+        let metadata = fs::read(&metadata_path).unwrap();
+        let dict_table_offset = LittleEndian::read_u32(&metadata) as usize;
+
+        // Invalid UTF-8 in the first term
+        let mut corrupted = metadata.clone();
+        corrupted[HEADER_LEN] = 0xff;
+        fs::write(&metadata_path, &corrupted).unwrap();
+        let problems = verify_index(&output_folder_path).unwrap().problems;
+        assert!(problems.iter().any(|problem| problem.starts_with("Dictionary: term 0 at byte 0 is not valid UTF-8")));
+
+        // Lengths past the end of the dictionary string
+        let mut corrupted = metadata;
+        corrupted[dict_table_offset] = 0xff;
+        corrupted[dict_table_offset + 1] = 0xff;
+        corrupted[dict_table_offset + 2] = 0xff;
+        fs::write(&metadata_path, &corrupted).unwrap();
+        let problems = verify_index(&output_folder_path).unwrap().problems;
+        assert!(problems.iter().any(|problem| problem.starts_with("Dictionary: ")));
+    }
+}
@@ -0,0 +1,301 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use log::{info, warn};
+
+use crate::completions_writer;
+use crate::error::IndexerError;
+use crate::incremental_info::{IncrementalIndexInfo, INCREMENTAL_INFO_FILE_NAME};
+use crate::indexer;
+use crate::merger::{self, SourceIndex};
+use crate::OUTPUT_CONFIG_FILE;
+
+/// Folder in the output folder the new incremental indexing info and output config are written to,
+/// before being moved into place.
+static STAGING_FOLDER: &str = "_compact_staging";
+
+/// Rewrites the index in the output folder without the documents that incremental indexing runs
+/// deleted or updated, which are otherwise only invalidated.
+///
+/// Doc ids are renumbered densely, with the incremental indexing info updated accordingly,
+/// and the index is written to a new inner folder which replaces the old one only once complete.
+/// If anything fails before then, the new folder is removed and the old index is left as it was.
+///
+/// Returns the number of documents purged.
+pub fn compact(output_folder_path: &Path) -> Result<u32, IndexerError> {
+    let (source, incremental_info) = SourceIndex::read(output_folder_path, 0)?;
+    let num_docs = source.num_docs;
+
+    let num_purged_docs = source.output_config.last_doc_id - num_docs;
+    if num_purged_docs == 0 {
+        info!("No deleted documents to purge, the index is already compact.");
+        return Ok(0);
+    }
+
+    // The new index_ver has to differ for cache-busting, and so as not to overwrite the old index
+    let mut index_ver = indexer::get_index_ver();
+    while index_ver == source.output_config.index_ver {
+        std::thread::sleep(Duration::from_millis(10));
+        index_ver = indexer::get_index_ver();
+    }
+    let output_folder_path_inner = output_folder_path.join(&index_ver);
+    fs::create_dir(&output_folder_path_inner).map_err(|e| IndexerError::io(&output_folder_path_inner, e))?;
+
+    let staging_folder_path = output_folder_path.join(STAGING_FOLDER);
+    let old_output_folder_path_inner = match write_compacted(
+        source,
+        incremental_info,
+        output_folder_path,
+        &output_folder_path_inner,
+        &staging_folder_path,
+        index_ver,
+    ) {
+        Ok(old_output_folder_path_inner) => old_output_folder_path_inner,
+        Err(e) => {
+            for folder_path in [&output_folder_path_inner, &staging_folder_path] {
+                if let Err(e) = fs::remove_dir_all(folder_path) {
+                    if e.kind() != io::ErrorKind::NotFound {
+                        warn!("Failed to remove the folder {}. {}", folder_path.to_string_lossy(), e);
+                    }
+                }
+            }
+            return Err(e);
+        }
+    };
+
+    if let Err(e) = fs::remove_dir_all(&old_output_folder_path_inner) {
+        warn!("Failed to remove the old index folder {}. {}", old_output_folder_path_inner.to_string_lossy(), e);
+    }
+
+    info!("{} deleted documents purged, {} documents remain.", num_purged_docs, num_docs);
+
+    Ok(num_purged_docs)
+}
+
+/// Writes the compacted index to `output_folder_path_inner`, then switches over to it.
+///
+/// Returns the old index's inner folder.
+fn write_compacted(
+    mut source: SourceIndex,
+    mut incremental_info: IncrementalIndexInfo,
+    output_folder_path: &Path,
+    output_folder_path_inner: &Path,
+    staging_folder_path: &Path,
+    index_ver: String,
+) -> Result<PathBuf, IndexerError> {
+    let num_docs = source.num_docs;
+    let field_infos = source.config.fields_config.get_field_infos(output_folder_path_inner, Some(&source.output_config))?;
+
+    // ------------------------------------------------------------
+    // Metadata
+
+    let mut doc_infos = source.read_doc_infos(&field_infos);
+    incremental_info.compact(&source.new_doc_ids);
+
+    let invalidation_vec_ser = incremental_info.write_invalidation_vec(num_docs);
+    let (doc_infos_ser, _enums_ev_strs) = doc_infos.finalize_and_flush(num_docs, &field_infos, &mut incremental_info);
+
+    let dict_writer = merger::write_pl_files(
+        std::slice::from_ref(&source),
+        output_folder_path_inner,
+        &source.config.indexing_config,
+        &mut incremental_info,
+    )?;
+
+    indexer::write_metadata(
+        output_folder_path_inner,
+        source.config.indexing_config.spelling_index,
+        invalidation_vec_ser,
+        doc_infos_ser,
+        dict_writer,
+        false,
+    )?;
+    // ------------------------------------------------------------
+
+    completions_writer::copy_completions(
+        &[(&source.output_folder_path_inner, &source.new_doc_ids)],
+        output_folder_path_inner,
+    );
+
+    merger::write_field_stores(
+        std::slice::from_ref(&source),
+        &field_infos,
+        source.config.indexing_config.num_docs_per_block,
+    )?;
+
+    // ------------------------------------------------------------
+    // Switch over to the compacted index
+    //
+    // Both files are written in full first, then renamed over the old ones.
+    // The output config goes last, as searchers only see the new index once it points to it.
+
+    let old_output_folder_path_inner = source.output_folder_path_inner;
+    let mut output_config = source.output_config;
+    output_config.index_ver = index_ver;
+    output_config.last_doc_id = num_docs;
+    output_config.indexing_config.pl_names_to_cache = incremental_info.pl_names_to_cache.clone();
+
+    if staging_folder_path.exists() {
+        fs::remove_dir_all(staging_folder_path).map_err(|e| IndexerError::io(staging_folder_path, e))?;
+    }
+    fs::create_dir(staging_folder_path).map_err(|e| IndexerError::io(staging_folder_path, e))?;
+    incremental_info.write_info_file(staging_folder_path)?;
+    output_config.write(staging_folder_path)?;
+
+    for file_name in [INCREMENTAL_INFO_FILE_NAME, OUTPUT_CONFIG_FILE] {
+        let staged_file_path = staging_folder_path.join(file_name);
+        fs::rename(&staged_file_path, output_folder_path.join(file_name))
+            .map_err(|e| IndexerError::io(&staged_file_path, e))?;
+    }
+    fs::remove_dir(staging_folder_path).map_err(|e| IndexerError::io(staging_folder_path, e))?;
+    // ------------------------------------------------------------
+
+    Ok(old_output_folder_path_inner)
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::path::Path;
+
+    use infisearch_search::native::{NativeSearcher, NativeSearcherOptions, QueryOpts};
+    use pretty_assertions::assert_eq;
+
+    use crate::commands::verify::verify_index;
+    use crate::indexer::input_config::InfiConfig;
+    use crate::indexer::Indexer;
+
+    use super::compact;
+
+    fn index(input_folder_path: &Path, output_folder_path: &Path, is_incremental: bool, docs: &[(&str, &str)]) {
+        // Small blocks, so that the field stores of the compacted index span several blocks
+        let config = InfiConfig::new(r#"{ "indexing_config": { "num_docs_per_block": 2 } }"#.to_owned()).unwrap();
+        let mut indexer = Indexer::new(
+            input_folder_path,
+            output_folder_path,
+            config,
+            is_incremental,
+            true,
+            false,
+            false,
+        ).unwrap();
+        for (file_name, text) in docs {
+            fs::write(
+                input_folder_path.join(file_name),
+                format!("<html><body><p>{}</p></body></html>", text),
+            ).unwrap();
+            indexer.index_file(&input_folder_path.join(file_name), file_name.as_ref()).unwrap();
+        }
+        indexer.finish_writing_docs().unwrap();
+    }
+
+    fn search(output_folder_path: &Path, query: &str) -> Vec<String> {
+        let mut searcher = NativeSearcher::new(output_folder_path, NativeSearcherOptions::default()).unwrap();
+        let mut query = searcher.run_query(query, &QueryOpts::default()).unwrap();
+        let mut relative_fps: Vec<String> = query.get_next_n_results(10)
+            .into_iter()
+            .map(|doc_result| {
+                let fields = searcher.load_fields(doc_result.doc_id).unwrap();
+                fields.into_iter().find(|(field_name, _text)| field_name == "_relative_fp").unwrap().1
+            })
+            .collect();
+        relative_fps.sort();
+        relative_fps
+    }
+
+    fn get_last_doc_id(output_folder_path: &Path) -> u32 {
+        NativeSearcher::new(output_folder_path, NativeSearcherOptions::default()).unwrap().output_config.last_doc_id
+    }
+
+    #[test]
+    fn test_compact() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let input_folder_path = temp_dir.path().join("input");
+        let output_folder_path = temp_dir.path().join("output");
+        fs::create_dir_all(&input_folder_path).unwrap();
+
+        index(&input_folder_path, &output_folder_path, false, &[
+            ("a.html", "lorem ipsum"),
+            ("b.html", "lorem dolor"),
+            ("c.html", "sit amet"),
+            ("d.html", "lorem sit"),
+            ("e.html", "amet dolor"),
+        ]);
+
+        // b is deleted, c is updated
+        fs::remove_file(input_folder_path.join("b.html")).unwrap();
+        index(&input_folder_path, &output_folder_path, true, &[
+            ("a.html", "lorem ipsum"),
+            ("c.html", "consectetur amet"),
+            ("d.html", "lorem sit"),
+            ("e.html", "amet dolor"),
+        ]);
+        assert_eq!(get_last_doc_id(&output_folder_path), 6);
+
+        assert_eq!(compact(&output_folder_path).unwrap(), 2);
+        assert_eq!(get_last_doc_id(&output_folder_path), 4);
+        assert_eq!(verify_index(&output_folder_path).unwrap().problems, Vec::<String>::new());
+        assert_eq!(fs::read_dir(&output_folder_path).unwrap().filter(|entry| entry.as_ref().unwrap().path().is_dir()).count(), 1);
+
+        assert_eq!(search(&output_folder_path, "lorem"), vec!["a.html", "d.html"]);
+        assert_eq!(search(&output_folder_path, "sit"), vec!["d.html"]);
+        assert_eq!(search(&output_folder_path, "amet"), vec!["c.html", "e.html"]);
+        assert_eq!(search(&output_folder_path, "consectetur"), vec!["c.html"]);
+
+        assert_eq!(compact(&output_folder_path).unwrap(), 0);
+
+        // Incremental indexing continues from the renumbered doc ids
+        fs::remove_file(input_folder_path.join("a.html")).unwrap();
+        index(&input_folder_path, &output_folder_path, true, &[
+            ("c.html", "consectetur amet"),
+            ("d.html", "lorem sit"),
+            ("e.html", "amet dolor"),
+            ("f.html", "lorem amet"),
+        ]);
+        assert_eq!(get_last_doc_id(&output_folder_path), 5);
+        assert_eq!(verify_index(&output_folder_path).unwrap().problems, Vec::<String>::new());
+        assert_eq!(search(&output_folder_path, "lorem"), vec!["d.html", "f.html"]);
+        assert_eq!(search(&output_folder_path, "amet"), vec!["c.html", "e.html", "f.html"]);
+    }
+
+    #[test]
+    fn test_compact_failure() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let input_folder_path = temp_dir.path().join("input");
+        let output_folder_path = temp_dir.path().join("output");
+        fs::create_dir_all(&input_folder_path).unwrap();
+
+        index(&input_folder_path, &output_folder_path, false, &[("a.html", "lorem ipsum"), ("b.html", "lorem dolor")]);
+        fs::remove_file(input_folder_path.join("b.html")).unwrap();
+        index(&input_folder_path, &output_folder_path, true, &[("a.html", "lorem ipsum")]);
+
+        let read_output_files = || -> Vec<(String, Vec<u8>)> {
+            let mut files: Vec<(String, Vec<u8>)> = fs::read_dir(&output_folder_path)
+                .unwrap()
+                .map(|entry| entry.unwrap().path())
+                .map(|path| {
+                    let contents = if path.is_dir() { Vec::new() } else { fs::read(&path).unwrap() };
+                    (path.file_name().unwrap().to_string_lossy().into_owned(), contents)
+                })
+                .collect();
+            files.sort();
+            files
+        };
+
+        // Corrupt the field store of the remaining document, which compaction copies last
+        let searcher = NativeSearcher::new(&output_folder_path, NativeSearcherOptions::default()).unwrap();
+        let (field_store_path, _idx) = searcher.output_config.get_field_store_location(
+            &output_folder_path.join(&searcher.output_config.index_ver), 0,
+        );
+        fs::write(&field_store_path, "invalid").unwrap();
+
+        let output_files = read_output_files();
+        assert!(compact(&output_folder_path).is_err());
+
+        // The new index folder is removed, and the output config and incremental info are left as they were
+        assert_eq!(read_output_files(), output_files);
+        assert_eq!(get_last_doc_id(&output_folder_path), 2);
+    }
+}
@@ -0,0 +1,61 @@
+use std::fs;
+use std::path::Path;
+
+use infisearch_common::bitmap;
+use infisearch_common::completions;
+use infisearch_common::COMPLETIONS_FILE;
+
+use crate::doc_info::DocInfos;
+use crate::utils::varint;
+
+fn write_phrase(doc_id: u32, field_id: u8, phrase: &str, output: &mut Vec<u8>) {
+    varint::get_var_int_vec(doc_id, output);
+    output.push(field_id);
+    varint::get_var_int_vec(phrase.len() as u32, output);
+    output.extend_from_slice(phrase.as_bytes());
+}
+
+/// Writes the completion phrases of all documents,
+/// see `infisearch_common::completions::decode_completion_phrases` for the format.
+///
+/// For incremental indexing, phrases of documents that are still valid
+/// are carried over from the previous completions file.
+pub fn write_completions(output_folder_path_inner: &Path, doc_infos: &DocInfos, invalidation_vector: &[u8]) {
+    let completions_path = output_folder_path_inner.join(COMPLETIONS_FILE);
+
+    let mut output = Vec::new();
+
+    if let Ok(old_completions) = fs::read(&completions_path) {
+        for phrase in completions::decode_completion_phrases(&old_completions) {
+            if !bitmap::check(invalidation_vector, phrase.doc_id as usize) {
+                write_phrase(phrase.doc_id, phrase.field_id, &phrase.phrase, &mut output);
+            }
+        }
+    }
+
+    for doc_info in doc_infos.doc_infos.iter() {
+        for (field_id, phrase) in doc_info.completions.iter() {
+            write_phrase(doc_info.doc_id, *field_id, phrase, &mut output);
+        }
+    }
+
+    fs::write(&completions_path, output).expect("Failed to write completions file");
+}
+
+/// Copies the completion phrases of documents that are still valid from previous indexes
+/// to a compacted or merged index, where each index's `new_doc_ids` maps its doc ids to the new ones.
+pub fn copy_completions(old_indexes: &[(&Path, &[Option<u32>])], output_folder_path_inner: &Path) {
+    let mut output = Vec::new();
+
+    for (old_output_folder_path_inner, new_doc_ids) in old_indexes {
+        if let Ok(old_completions) = fs::read(old_output_folder_path_inner.join(COMPLETIONS_FILE)) {
+            for phrase in completions::decode_completion_phrases(&old_completions) {
+                if let Some(Some(new_doc_id)) = new_doc_ids.get(phrase.doc_id as usize) {
+                    write_phrase(*new_doc_id, phrase.field_id, &phrase.phrase, &mut output);
+                }
+            }
+        }
+    }
+
+    fs::write(output_folder_path_inner.join(COMPLETIONS_FILE), output).expect("Failed to write completions file");
+}
@@ -87,6 +87,7 @@ impl DocInfos {
                 doc_nums: Vec::new(),
                 field_lengths: Vec::with_capacity(num_scored_fields),
                 field_texts: Vec::new(),
+                completions: Vec::new(),
             };
 
             for _i in 0..num_scored_fields {
@@ -106,6 +107,57 @@ impl DocInfos {
         }
     }
 
+    /// Removes the previous index's documents that were invalidated, for compacting the index,
+    /// where `new_doc_ids` maps each old doc id to its new one.
+    pub fn compact(&mut self, new_doc_ids: &[Option<u32>], field_infos: &Arc<FieldInfos>) {
+        let num_enum_fields = field_infos.num_enum_fields;
+        let num_i64_fields = field_infos.num_i64_fields;
+
+        let mut docs_enum_values = Vec::with_capacity(self.docs_enum_values.len());
+        let mut docs_i64_values = Vec::with_capacity(self.docs_i64_values.len());
+        let mut doc_infos = std::mem::take(&mut self.doc_infos);
+        doc_infos.retain_mut(|doc_info| {
+            let old_doc_id = doc_info.doc_id as usize;
+            if let Some(new_doc_id) = new_doc_ids[old_doc_id] {
+                docs_enum_values.extend_from_slice(
+                    &self.docs_enum_values[old_doc_id * num_enum_fields..(old_doc_id + 1) * num_enum_fields],
+                );
+                docs_i64_values.extend_from_slice(
+                    &self.docs_i64_values[old_doc_id * num_i64_fields..(old_doc_id + 1) * num_i64_fields],
+                );
+                doc_info.doc_id = new_doc_id;
+                true
+            } else {
+                false
+            }
+        });
+
+        self.original_doc_id_counter = doc_infos.len() as u32;
+        self.doc_infos = doc_infos;
+        self.docs_enum_values = docs_enum_values;
+        self.docs_i64_values = docs_i64_values;
+    }
+
+    /// Appends the document infos of another index for merging indexes,
+    /// whose doc ids should already be renumbered to follow this one's.
+    ///
+    /// `enum_ev_id_maps` maps the other index's enum value ids of each enum field to this one's.
+    pub fn merge(&mut self, other: DocInfos, enum_ev_id_maps: &[Vec<EnumMax>]) {
+        let num_enum_fields = enum_ev_id_maps.len();
+
+        self.doc_infos.extend(other.doc_infos);
+        self.docs_enum_values.extend(
+            other.docs_enum_values
+                .into_iter()
+                .enumerate()
+                .map(|(idx, ev_id)| {
+                    enum_ev_id_maps[idx % num_enum_fields].get(ev_id as usize).copied().unwrap_or(0)
+                }),
+        );
+        self.docs_i64_values.extend(other.docs_i64_values);
+        self.original_doc_id_counter += other.original_doc_id_counter;
+    }
+
     fn sort_and_merge_block_doclengths(&mut self) {
         self.all_block_doc_lengths.sort();
 
@@ -0,0 +1,64 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug)]
+pub enum IndexerError {
+    /// The indexer configuration does not match the schema, or contains invalid values
+    Config(String),
+    /// A loader's options do not match its schema
+    LoaderConfig { loader: String, message: String },
+    /// A file could not be parsed by its loader
+    Loader { path: PathBuf, message: String },
+    Io { path: PathBuf, source: io::Error },
+    /// A document to add directly, without a file, is invalid
+    Document(String),
+    /// The output folder contains a previous index that cannot be incrementally updated
+    IncompatibleIncremental(String),
+    /// The indexes to merge cannot be merged into one
+    Merge(String),
+}
+
+impl IndexerError {
+    pub fn io(path: &Path, source: io::Error) -> Self {
+        IndexerError::Io { path: path.to_path_buf(), source }
+    }
+
+    pub fn loader_config(loader: &str, message: impl ToString) -> Self {
+        IndexerError::LoaderConfig { loader: loader.to_owned(), message: message.to_string() }
+    }
+
+    pub fn loader(path: &Path, message: impl ToString) -> Self {
+        IndexerError::Loader { path: path.to_path_buf(), message: message.to_string() }
+    }
+}
+
+impl fmt::Display for IndexerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IndexerError::Config(message) => write!(f, "Invalid configuration. {}", message),
+            IndexerError::LoaderConfig { loader, message } => {
+                write!(f, "{} options did not match schema. {}", loader, message)
+            }
+            IndexerError::Loader { path, message } => {
+                write!(f, "Failed to load {}. {}", path.to_string_lossy(), message)
+            }
+            IndexerError::Io { path, source } => write!(f, "I/O error for {}. {}", path.to_string_lossy(), source),
+            IndexerError::Document(message) => write!(f, "Invalid document. {}", message),
+            IndexerError::IncompatibleIncremental(message) => {
+                write!(f, "Previous index cannot be incrementally updated, try a full reindex. {}", message)
+            }
+            IndexerError::Merge(message) => write!(f, "Indexes cannot be merged. {}", message),
+        }
+    }
+}
+
+impl Error for IndexerError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            IndexerError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -3,10 +3,12 @@ use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
 
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
 use rustc_hash::FxHashMap;
 
 use serde::{Deserialize, Serialize};
 
+use crate::error::IndexerError;
 use crate::indexer::output_config::InfiOutputConfig;
 use crate::utils::escape_json;
 
@@ -35,31 +37,38 @@ fn get_default_fields() -> FxHashMap<String, Option<FieldConfig>> {
     FxHashMap::from_iter(vec![
         ("title".to_owned(), Some(FieldConfig {
             storage: get_default_storage(),
-            weight: 2.0, k: 1.2, b: 0.15
+            weight: 2.0, k: 1.2, b: 0.15,
+            completions: true,
         })),
         ("h1".to_owned(), Some(FieldConfig {
             storage: get_default_storage(),
-            weight: 2.0, k: 1.2, b: 0.15
+            weight: 2.0, k: 1.2, b: 0.15,
+            completions: true,
         })),
         ("heading".to_owned(), Some(FieldConfig {
             storage: get_default_storage(),
-            weight: 1.5, k: 1.2, b: 0.25
+            weight: 1.5, k: 1.2, b: 0.25,
+            completions: true,
         })),
         ("body".to_owned(), Some(FieldConfig {
             storage: get_default_storage(),
-            weight: 1.0, k: 1.2, b: 0.75
+            weight: 1.0, k: 1.2, b: 0.75,
+            completions: false,
         })),
         ("headingLink".to_owned(), Some(FieldConfig {
             storage: get_default_storage(),
-            weight: 0.0, k: 1.2, b: 0.75
+            weight: 0.0, k: 1.2, b: 0.75,
+            completions: false,
         })),
         (RELATIVE_FP_FIELD.to_owned(), Some(FieldConfig {
             storage: get_default_storage(),
-            weight: 0.0, k: 1.2, b: 0.75
+            weight: 0.0, k: 1.2, b: 0.75,
+            completions: false,
         })),
         ("link".to_owned(), Some(FieldConfig {
             storage: get_default_storage(),
-            weight: 0.0, k: 1.2, b: 0.75
+            weight: 0.0, k: 1.2, b: 0.75,
+            completions: false,
         })),
     ])
 }
@@ -105,7 +114,11 @@ impl FieldsConfig {
         &self,
         output_folder_path_inner: &Path,
         incremental_output_config: Option<&InfiOutputConfig>,
-    ) -> Arc<FieldInfos> {
+    ) -> Result<Arc<FieldInfos>, IndexerError> {
+        let missing_old_field = |field_name: &str| IndexerError::IncompatibleIncremental(
+            format!("Field {} is missing from the previous output config", field_name),
+        );
+
         let mut field_infos_by_name: FxHashMap<String, FieldInfo> = FxHashMap::default();
         let mut field_infos_by_id: Vec<FieldInfo> = Vec::with_capacity(self.fields.len());
 
@@ -122,20 +135,26 @@ impl FieldsConfig {
                 num_scored_fields += 1;
             }
 
+            let old_field_info = if let Some(incremental_output_config) = incremental_output_config {
+                Some(
+                    incremental_output_config.field_infos
+                        .iter()
+                        .find(|fi| fi.name.as_str() == field_name)
+                        .ok_or_else(|| missing_old_field(field_name))?
+                )
+            } else {
+                None
+            };
+
             field_infos_by_id.push(FieldInfo {
                 name: field_name.to_owned(),
                 escaped_name: escape_json::escape(field_name).into_owned(),
                 id: 0,
                 enum_info: if field_config.storage.iter().any(|s| matches!(s, StorageType::Enum)) {
-                    let (enum_id, enum_values) = if let Some(incremental_output_config) = incremental_output_config {
-                        let old_enum_info = incremental_output_config.field_infos
-                            .iter()
-                            .find_map(|fi| if fi.name.as_str() == field_name {
-                                fi.enum_info.as_ref()
-                            } else {
-                                None
-                            })
-                            .unwrap();
+                    let (enum_id, enum_values) = if let Some(old_field_info) = old_field_info {
+                        let old_enum_info = old_field_info.enum_info
+                            .as_ref()
+                            .ok_or_else(|| missing_old_field(field_name))?;
                         (old_enum_info.enum_id, old_enum_info.enum_values.clone())
                     } else {
                         (num_enum_fields, Vec::new())
@@ -151,35 +170,34 @@ impl FieldsConfig {
                     None
                 },
                 store_text: field_config.storage.iter().any(|s| matches!(s, StorageType::Text)),
-                i64_info: field_config.storage
+                i64_info: if let Some(num_info) = field_config.storage
                     .iter()
-                    .find_map(|s| if let StorageType::I64(num_info) = s {
-                        let info = if let Some(incremental_output_config) = incremental_output_config {
-                            let old_num_info = incremental_output_config.field_infos
-                                .iter()
-                                .find_map(|fi| if fi.name.as_str() == field_name {
-                                    fi.i64_info.as_ref()
-                                } else {
-                                    None
-                                })
-                                .unwrap();
-                            old_num_info.clone()
-                        } else {
-                            I64Info {
-                                id: num_i64_fields,
-                                ..num_info.clone()
-                            }
-                        };
-
-                        num_i64_fields += 1;
-
-                        Some(info)
+                    .find_map(|s| if let StorageType::I64(num_info) = s { Some(num_info) } else { None })
+                {
+                    num_info.parse.validate().map_err(|e| IndexerError::Config(format!("Field {}. {}", field_name, e)))?;
+
+                    let info = if let Some(old_field_info) = old_field_info {
+                        old_field_info.i64_info
+                            .as_ref()
+                            .ok_or_else(|| missing_old_field(field_name))?
+                            .clone()
                     } else {
-                        None
-                    }),
+                        I64Info {
+                            id: num_i64_fields,
+                            ..num_info.clone()
+                        }
+                    };
+
+                    num_i64_fields += 1;
+
+                    Some(info)
+                } else {
+                    None
+                },
                 weight: field_config.weight,
                 k: field_config.k,
                 b: field_config.b,
+                completions: field_config.completions,
             });
         }
 
@@ -202,9 +220,9 @@ impl FieldsConfig {
         let field_output_folder_path = output_folder_path_inner.join("field_store");
 
         std::fs::create_dir_all(&field_output_folder_path)
-            .expect("Failed to create field store output folder in output directory");
+            .map_err(|e| IndexerError::io(&field_output_folder_path, e))?;
 
-        Arc::new(FieldInfos {
+        Ok(Arc::new(FieldInfos {
             field_infos_by_name,
             field_infos_by_id,
             num_scored_fields,
@@ -213,7 +231,7 @@ impl FieldsConfig {
             num_docs_per_store: self.num_docs_per_store,
             num_stores_per_dir: self.num_stores_per_dir,
             field_output_folder_path,
-        })
+        }))
     }
 }
 
@@ -254,6 +272,60 @@ pub enum I64ParseStrategy {
     },
 }
 
+impl I64ParseStrategy {
+    /// Checks the default time and timezone of datetimes, which are the same for every document
+    fn validate(&self) -> Result<(), String> {
+        if let I64ParseStrategy::Datetime { datetime_fmt: _, timezone, time } = self {
+            if timezone.is_some_and(|timezone| FixedOffset::east_opt(timezone).is_none()) {
+                return Err("Invalid default timezone".to_owned());
+            }
+
+            if let Some(time) = time {
+                if NaiveTime::from_num_seconds_from_midnight_opt(*time, 0).is_none() {
+                    return Err("Invalid default time".to_owned());
+                }
+
+                if timezone.is_none() {
+                    return Err(
+                        "Default time without timezone specified, a timezone is required to calculate the UNIX timestamp"
+                            .to_owned(),
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn parse(&self, field_text: &str) -> Result<i64, String> {
+        match self {
+            I64ParseStrategy::Integer => field_text.parse::<i64>().map_err(|e| e.to_string()),
+            I64ParseStrategy::Round => {
+                field_text.parse::<f64>().map(|value| value.round() as i64).map_err(|e| e.to_string())
+            }
+            I64ParseStrategy::Datetime { datetime_fmt: format, time, timezone } => {
+                if let Some(timezone) = timezone.and_then(FixedOffset::east_opt) {
+                    let naive_date_time = if let Some(time) = time.and_then(|time| {
+                        NaiveTime::from_num_seconds_from_midnight_opt(time, 0)
+                    }) {
+                        NaiveDate::parse_from_str(field_text, format).map(|date| date.and_time(time))
+                    } else {
+                        NaiveDateTime::parse_from_str(field_text, format)
+                    };
+
+                    naive_date_time
+                        .map(|naive_date_time| timezone.from_utc_datetime(&naive_date_time).timestamp())
+                        .map_err(|e| e.to_string())
+                } else {
+                    DateTime::parse_from_str(field_text, format)
+                        .map(|date_time| date_time.timestamp())
+                        .map_err(|e| e.to_string())
+                }
+            }
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct I64Info {
     #[serde(default)]
@@ -285,6 +357,9 @@ pub struct FieldConfig {
     pub k: f32,
     #[serde(default = "get_default_b")]
     pub b: f32,
+    /// Whether the field's texts are offered as whole-phrase query completions
+    #[serde(default)]
+    pub completions: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -298,6 +373,7 @@ pub struct FieldInfo {
     pub enum_info: Option<EnumInfo>,
     pub store_text: bool,
     pub i64_info: Option<I64Info>,
+    pub completions: bool,
 }
 
 // Initialised json field configuration
@@ -1,6 +1,7 @@
 use std::collections::BTreeMap;
+use std::collections::hash_map::Entry;
 use std::fs::File;
-use std::io::{BufReader, Read, Write};
+use std::io::{self, BufReader, Read, Write};
 use std::path::Path;
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -11,23 +12,25 @@ use serde_json::Value;
 
 use infisearch_common::dictionary::Dictionary;
 use infisearch_common::{bitmap, METADATA_FILE};
-use infisearch_common::metadata::MetadataReader;
+use infisearch_common::metadata::{MetadataReader, FORMAT_VER};
 
+use crate::error::IndexerError;
 use crate::indexer::output_config::InfiOutputConfig;
 use crate::utils::fs_utils;
+use crate::worker::miner::Zone;
 use crate::{INFISEARCH_VER, i_debug, OLD_SOURCE_CONFIG, OUTPUT_CONFIG_FILE};
 
 lazy_static! {
     static ref CURRENT_MILLIS: u128 = SystemTime::now().duration_since(UNIX_EPOCH)
-        .expect("Failed to obtain current system time. Consider using the --incremental-content-hash option.")
+        .unwrap_or_default()
         .as_millis();
 }
 
 // Not used for search
-static INCREMENTAL_INFO_FILE_NAME: &str = "_incremental_info.json";
+pub(crate) static INCREMENTAL_INFO_FILE_NAME: &str = "_incremental_info.json";
 
 fn get_default_dictionary() -> Dictionary {
-    Dictionary { term_infos: BTreeMap::default() }
+    Dictionary { term_infos: BTreeMap::default(), spelling_index: Vec::new() }
 }
 
 // TODO write a custom serialize-deserialize with a named struct for readability
@@ -37,6 +40,7 @@ struct DocIdsAndFileHash(
     u32,                 // hash
     #[serde(skip)] bool, // false by default, detect if files were encountered in the current run (delete if not)
     Vec<String>,         // secondary files that were _add_files linked to
+    #[serde(default)] bool, // added with Indexer::add_document, hashed by its fields instead of a file
 );
 
 // Special hash to indicate a file does not exist
@@ -69,6 +73,14 @@ pub struct IncrementalIndexInfo {
 
     #[serde(skip, default = "get_default_dictionary")]
     pub dictionary: Dictionary,
+
+    // Number of files added / updated / deleted in the current run, for logging
+    #[serde(skip)]
+    pub num_added_files: u32,
+    #[serde(skip)]
+    pub num_updated_files: u32,
+    #[serde(skip)]
+    pub num_deleted_files: u32,
 }
 
 impl IncrementalIndexInfo {
@@ -90,6 +102,9 @@ impl IncrementalIndexInfo {
                 pl_names_to_cache: Vec::new(),
                 invalidation_vector: Vec::new(),
                 dictionary: get_default_dictionary(),
+                num_added_files: 0,
+                num_updated_files: 0,
+                num_deleted_files: 0,
             },
         )
     }
@@ -100,11 +115,11 @@ impl IncrementalIndexInfo {
         json_config: &Value,
         is_incremental: bool,
         use_content_hash: bool,
-    ) -> (Option<InfiOutputConfig>, Option<MetadataReader>, IncrementalIndexInfo) {
+    ) -> Result<(Option<InfiOutputConfig>, Option<MetadataReader>, IncrementalIndexInfo), IndexerError> {
         // --------------------------------------------------------
         // Full index
         if !is_incremental {
-            return IncrementalIndexInfo::empty(use_content_hash);
+            return Ok(IncrementalIndexInfo::empty(use_content_hash));
         }
         // --------------------------------------------------------
 
@@ -114,11 +129,11 @@ impl IncrementalIndexInfo {
         if let Ok(meta) = std::fs::metadata(output_folder_path.join(INCREMENTAL_INFO_FILE_NAME)) {
             if !meta.is_file() {
                 info!("Old incremental index info missing. Running a full reindex.");
-                return IncrementalIndexInfo::empty(use_content_hash);
+                return Ok(IncrementalIndexInfo::empty(use_content_hash));
             }
         } else {
             info!("Old incremental index info missing. Running a full reindex.");
-            return IncrementalIndexInfo::empty(use_content_hash);
+            return Ok(IncrementalIndexInfo::empty(use_content_hash));
         }
         // --------------------------------------------------------
 
@@ -127,54 +142,58 @@ impl IncrementalIndexInfo {
         // The InfiSearch version used might however be different.
         let old_output_config = output_folder_path.join(OUTPUT_CONFIG_FILE);
         let old_output_config = if old_output_config.exists() {
-            let old_output_conf_str = std::fs::read_to_string(&old_output_config).unwrap();
+            let old_output_conf_str = std::fs::read_to_string(&old_output_config)
+                .map_err(|e| IndexerError::io(&old_output_config, e))?;
             let deserialized: Result<InfiOutputConfig, _> = serde_json::from_str(&old_output_conf_str);
 
             if let Ok(old_output_conf) = deserialized {
                 if old_output_conf.ver != INFISEARCH_VER {
                     info!("InfiSearch version changed. Running a full reindex.");
-                    return IncrementalIndexInfo::empty(use_content_hash);
+                    return Ok(IncrementalIndexInfo::empty(use_content_hash));
+                }
+
+                if old_output_conf.format_ver != FORMAT_VER {
+                    info!("Index format changed. Running a full reindex.");
+                    return Ok(IncrementalIndexInfo::empty(use_content_hash));
                 }
 
                 old_output_conf
             } else {
                 info!("Old output config invalid. Running a full reindex.");
-                return IncrementalIndexInfo::empty(use_content_hash);
+                return Ok(IncrementalIndexInfo::empty(use_content_hash));
             }
         } else {
             warn!("Old output config missing. Running a full reindex.");
-            return IncrementalIndexInfo::empty(use_content_hash);
+            return Ok(IncrementalIndexInfo::empty(use_content_hash));
         };
         // --------------------------------------------------------
 
         // --------------------------------------------------------
         // Next, verify the user config hasn't changed.
-        if let Ok(mut file) = File::open(output_folder_path.join(OLD_SOURCE_CONFIG)) {
+        let old_config_path = output_folder_path.join(OLD_SOURCE_CONFIG);
+        if let Ok(mut file) = File::open(&old_config_path) {
             let mut old_config = "".to_owned();
-            file.read_to_string(&mut old_config).expect("Unable to read old config file");
-            let old_json_config: Value = serde_json::from_str(&old_config)
-                .expect(&(OLD_SOURCE_CONFIG.to_owned() + " does not match schema!"));
+            file.read_to_string(&mut old_config).map_err(|e| IndexerError::io(&old_config_path, e))?;
+            let old_json_config: Value = serde_json::from_str(&old_config).map_err(|e| {
+                IndexerError::IncompatibleIncremental(format!("{} does not match schema! {}", OLD_SOURCE_CONFIG, e))
+            })?;
             if *json_config != old_json_config {
                 info!("Configuration file changed. Running a full reindex.");
-                return IncrementalIndexInfo::empty(use_content_hash);
+                return Ok(IncrementalIndexInfo::empty(use_content_hash));
             }
         } else {
             warn!("Old configuration file missing. Running a full reindex.");
-            return IncrementalIndexInfo::empty(use_content_hash);
+            return Ok(IncrementalIndexInfo::empty(use_content_hash));
         }
         // --------------------------------------------------------
 
         // --------------------------------------------------------
         // Check the type of content hash used is the same
-        let info_file = File::open(output_folder_path.join(INCREMENTAL_INFO_FILE_NAME))
-            .expect("Failed to obtain incremental index info file handle.");
-
-        let mut info: IncrementalIndexInfo = serde_json::from_reader(BufReader::new(info_file))
-            .expect("incremental index info deserialization failed!");
+        let mut info = IncrementalIndexInfo::read_from_output_folder(output_folder_path)?;
 
         if info.use_content_hash != use_content_hash {
             info!("Content hash option changed. Running a full reindex.");
-            return IncrementalIndexInfo::empty(use_content_hash);
+            return Ok(IncrementalIndexInfo::empty(use_content_hash));
         }
         // --------------------------------------------------------
 
@@ -194,7 +213,7 @@ impl IncrementalIndexInfo {
                     output_folder_path_inner.to_string_lossy(),
                     e,
                 );
-                return IncrementalIndexInfo::empty(use_content_hash);
+                return Ok(IncrementalIndexInfo::empty(use_content_hash));
             }
         }
         // --------------------------------------------------------
@@ -202,20 +221,41 @@ impl IncrementalIndexInfo {
         // --------------------------------------------------------
         // Initialise metadata, read in the invalidation vector
 
-        let metadata_rdr = if let Ok(mut file) = File::open(output_folder_path_inner.join(METADATA_FILE)) {
+        let metadata_path = output_folder_path_inner.join(METADATA_FILE);
+        let metadata_rdr = if let Ok(mut file) = File::open(&metadata_path) {
             let mut buf = Vec::new();
-            file.read_to_end(&mut buf).unwrap();
+            file.read_to_end(&mut buf).map_err(|e| IndexerError::io(&metadata_path, e))?;
             MetadataReader::new(buf)
         } else {
             warn!("metadata file missing. Running a full reindex.");
-            return IncrementalIndexInfo::empty(use_content_hash);
+            return Ok(IncrementalIndexInfo::empty(use_content_hash));
         };
 
         metadata_rdr.get_invalidation_vec(&mut info.invalidation_vector);
 
         // --------------------------------------------------------
 
-        (Some(old_output_config), Some(metadata_rdr), info)
+        Ok((Some(old_output_config), Some(metadata_rdr), info))
+    }
+
+    pub fn read_from_output_folder(output_folder_path: &Path) -> Result<IncrementalIndexInfo, IndexerError> {
+        let info_file_path = output_folder_path.join(INCREMENTAL_INFO_FILE_NAME);
+        let info_file = File::open(&info_file_path)
+            .map_err(|e| IndexerError::io(&info_file_path, e))?;
+
+        serde_json::from_reader(BufReader::new(info_file))
+            .map_err(|e| IndexerError::IncompatibleIncremental(
+                format!("Incremental index info deserialization failed! {}", e),
+            ))
+    }
+
+    /// Whether any file indexed from the input folder is at or under this relative path,
+    /// such as the files of a deleted folder.
+    pub fn has_files_under(&self, relative_path: &Path) -> bool {
+        self.mappings.iter().any(|(external_id, DocIdsAndFileHash(_doc_ids, _hash, _encountered, secondary_files, is_document))| {
+            (!is_document && Path::new(external_id).starts_with(relative_path))
+                || secondary_files.iter().any(|secondary_file| Path::new(secondary_file).starts_with(relative_path))
+        })
     }
 
     pub fn setup_dictionary(&mut self, metadata_rdr: &MetadataReader) {
@@ -234,21 +274,40 @@ impl IncrementalIndexInfo {
 
     /// Returns whether file was not modified or not for incremental indexing.
     /// A new file is counted as "modified"
-    pub fn set_file(&mut self, external_id: &str, path: &Path, input_folder_path: &Path) -> bool {
+    pub fn set_file(
+        &mut self,
+        external_id: &str,
+        path: &Path,
+        input_folder_path: &Path,
+    ) -> Result<bool, IndexerError> {
+        let use_content_hash = self.use_content_hash;
+        self.set_external_id(external_id, false, |secondary_paths| {
+            Self::get_file_hash(use_content_hash, path, input_folder_path, secondary_paths)
+        })
+    }
+
+    /// `set_file` for documents added without a file, which are hashed by their zones instead.
+    pub fn set_document(&mut self, external_id: &str, zones: &[Zone]) -> Result<bool, IndexerError> {
+        let new_hash = Self::get_document_hash(zones);
+        self.set_external_id(external_id, true, |_secondary_paths| Ok(new_hash))
+    }
+
+    fn set_external_id(
+        &mut self,
+        external_id: &str,
+        is_document: bool,
+        get_hash: impl FnOnce(&Vec<String>) -> Result<u32, IndexerError>,
+    ) -> Result<bool, IndexerError> {
         if let Some(old_hash) = self.mappings.get_mut(external_id) {
             // Old file
-            let new_hash = Self::get_file_hash(
-                self.use_content_hash,
-                path,
-                input_folder_path,
-                &old_hash.3,
-            );
+            let new_hash = get_hash(&old_hash.3)?;
 
             // Set encountered flag to know which files were deleted later on
             old_hash.2 = true;
 
             if old_hash.1 != new_hash {
                 i_debug!("{} was updated", external_id);
+                self.num_updated_files += 1;
 
                 self.num_deleted_docs += old_hash.0.len() as u32;
                 for doc_id in old_hash.0.drain(..) {
@@ -256,15 +315,27 @@ impl IncrementalIndexInfo {
                 }
                 old_hash.3.clear();
 
-                return false;
+                // File hashes are only updated after indexing, in update_file_hashes
+                if is_document {
+                    old_hash.1 = new_hash;
+                }
+                old_hash.4 = is_document;
+
+                return Ok(false);
             }
 
-            true
+            Ok(true)
         } else {
             // New file
-            self.mappings.insert(external_id.to_owned(), DocIdsAndFileHash(Vec::new(), 0, true, Vec::new()));
+            i_debug!("{} was added", external_id);
+            self.num_added_files += 1;
+            let hash = if is_document { get_hash(&Vec::new())? } else { 0 };
+            self.mappings.insert(
+                external_id.to_owned(),
+                DocIdsAndFileHash(Vec::new(), hash, true, Vec::new(), is_document),
+            );
 
-            false
+            Ok(false)
         }
     }
 
@@ -279,9 +350,8 @@ impl IncrementalIndexInfo {
 
         if let Ok(metadata) = std::fs::metadata(path) {
             if let Ok(modified) = metadata.modified() {
-                modified.duration_since(UNIX_EPOCH)
-                    .expect("Failed to calculate timestamp. Consider using the --incremental-content-hash option")
-                    .as_millis()
+                // Modification times before the epoch are unlikely, and are treated as the epoch
+                modified.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis()
             } else {
                 i_debug!("Obtaining modified timestamp failed for {}", path.to_string_lossy());
 
@@ -298,17 +368,26 @@ impl IncrementalIndexInfo {
         }
     }
 
+    fn get_document_hash(zones: &[Zone]) -> u32 {
+        let mut hasher = crc32fast::Hasher::new();
+        for zone in zones {
+            hasher.update(zone.field_name.as_bytes());
+            hasher.update(&[0]);
+            hasher.update(zone.field_text.as_bytes());
+            hasher.update(&zone.separation.to_le_bytes());
+        }
+        hasher.finalize()
+    }
+
     fn get_file_hash(
         use_content_hash: bool,
         path: &Path,
         input_folder_path: &Path,
         secondary_paths: &Vec<String>,
-    ) -> u32 {
+    ) -> Result<u32, IndexerError> {
         if use_content_hash {
-            static ERR: &str = "Failed to read file for calculating content hash!";
-
             let mut buf = if path.is_file() {
-                std::fs::read(path).expect(ERR)
+                std::fs::read(path).map_err(|e| IndexerError::io(path, e))?
             } else {
                 ABSENT_HASH.to_le_bytes().to_vec()
             };
@@ -316,16 +395,15 @@ impl IncrementalIndexInfo {
             for secondary_path in secondary_paths {
                 let secondary_path = input_folder_path.join(secondary_path);
                 if secondary_path.is_file() {
-                    File::open(secondary_path)
-                        .expect(ERR)
-                        .read_to_end(&mut buf)
-                        .expect(ERR);
+                    File::open(&secondary_path)
+                        .and_then(|mut file| file.read_to_end(&mut buf))
+                        .map_err(|e| IndexerError::io(&secondary_path, e))?;
                 } else {
                     buf.extend_from_slice(&ABSENT_HASH.to_le_bytes());
                 }
             }
 
-            crc32fast::hash(&buf)
+            Ok(crc32fast::hash(&buf))
         } else {
             // Use last modified timestamp otherwise
             let mut timestamps = Vec::with_capacity(1 + secondary_paths.len());
@@ -335,9 +413,9 @@ impl IncrementalIndexInfo {
                 timestamps.push(Self::get_timestamp(&input_folder_path.join(secondary_path)));
             }
 
-            crc32fast::hash(unsafe {
+            Ok(crc32fast::hash(unsafe {
                 std::slice::from_raw_parts(timestamps.as_ptr() as *const u8, timestamps.len() * 16)
-            })
+            }))
         }
     }
 
@@ -348,6 +426,7 @@ impl IncrementalIndexInfo {
             .filter(|(_path, docids_and_filehash)| {
                 if !docids_and_filehash.2 {
                     i_debug!("{} was deleted", _path);
+                    self.num_deleted_files += 1;
 
                     for &doc_id in docids_and_filehash.0.iter() {
                         bitmap::set(&mut self.invalidation_vector, doc_id as usize);
@@ -369,7 +448,7 @@ impl IncrementalIndexInfo {
         self.invalidation_vector.clone()
     }
 
-    fn update_file_hashes(&mut self, input_folder_path: &Path) {
+    fn update_file_hashes(&mut self, input_folder_path: &Path) -> Result<(), IndexerError> {
         for map in std::mem::take(&mut self.inv_mappings_secondary) {
             for (doc_id, secondary_ids) in map {
                 let main_id = self.inv_mappings.get(&doc_id)
@@ -382,23 +461,64 @@ impl IncrementalIndexInfo {
         }
 
         for (main_id, doc_id_and_filehash) in self.mappings.iter_mut() {
+            if doc_id_and_filehash.4 {
+                continue;
+            }
+
             doc_id_and_filehash.1 = Self::get_file_hash(
                 self.use_content_hash,
                 &input_folder_path.join(main_id),
                 input_folder_path,
                 &doc_id_and_filehash.3,
-            );
+            )?;
         }
+
+        Ok(())
     }
 
-    pub fn write_info(&mut self, input_folder_path: &Path, output_folder_path: &Path) {
-        self.update_file_hashes(input_folder_path);
+    /// Renumbers the doc ids of the mappings for compaction, where `new_doc_ids` maps old doc ids to new ones,
+    /// and clears the invalidation vector as no invalidated doc ids remain.
+    pub fn compact(&mut self, new_doc_ids: &[Option<u32>]) {
+        for doc_ids_and_file_hash in self.mappings.values_mut() {
+            doc_ids_and_file_hash.0 = doc_ids_and_file_hash.0
+                .iter()
+                .filter_map(|&doc_id| new_doc_ids[doc_id as usize])
+                .collect();
+        }
+
+        self.num_deleted_docs = 0;
+        self.invalidation_vector.clear();
+    }
+
+    /// Adds the mappings of another index for merging indexes,
+    /// whose doc ids should already be renumbered to the merged index's with `compact`.
+    pub fn merge(&mut self, other: IncrementalIndexInfo) {
+        for (external_id, other_doc_ids_and_file_hash) in other.mappings {
+            match self.mappings.entry(external_id) {
+                // Keep the documents of both indexes
+                Entry::Occupied(mut entry) => {
+                    let doc_ids_and_file_hash = entry.get_mut();
+                    doc_ids_and_file_hash.0.extend(other_doc_ids_and_file_hash.0);
+                    doc_ids_and_file_hash.3.extend(other_doc_ids_and_file_hash.3);
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(other_doc_ids_and_file_hash);
+                }
+            }
+        }
+    }
 
-        let serialized = serde_json::to_string(self).unwrap();
+    pub fn write_info(&mut self, input_folder_path: &Path, output_folder_path: &Path) -> Result<(), IndexerError> {
+        self.update_file_hashes(input_folder_path)?;
+        self.write_info_file(output_folder_path)
+    }
 
-        File::create(output_folder_path.join(INCREMENTAL_INFO_FILE_NAME))
-            .unwrap()
-            .write_all(serialized.as_bytes())
-            .unwrap();
+    /// Writes the info as is, without updating the file hashes from the source folder
+    pub fn write_info_file(&self, output_folder_path: &Path) -> Result<(), IndexerError> {
+        let info_file_path = output_folder_path.join(INCREMENTAL_INFO_FILE_NAME);
+        serde_json::to_vec(self)
+            .map_err(io::Error::from)
+            .and_then(|serialized| File::create(&info_file_path)?.write_all(&serialized))
+            .map_err(|e| IndexerError::io(&info_file_path, e))
     }
 }
@@ -3,34 +3,46 @@ pub mod output_config;
 mod spimi_writer;
 mod worker;
 
+use std::collections::BTreeMap;
 use std::fs::{self, File};
-use std::io::{Write, BufWriter};
+use std::io::{self, Write, BufWriter};
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::time::{Instant, UNIX_EPOCH, SystemTime};
 
-use infisearch_common::language::InfiLanguageConfig;
+use log::info;
+
+use infisearch_common::language::{InfiLanguageConfig, InfiSynonymsConfig};
+use infisearch_common::dictionary;
+use infisearch_common::metadata::HEADER_LEN;
 use infisearch_common::METADATA_FILE;
+use infisearch_common::synonyms::Synonyms;
 use infisearch_common::tokenize::IndexerTokenizer;
 use infisearch_lang_ascii::ascii;
 use infisearch_lang_ascii_stemmer::ascii_stemmer;
 use infisearch_lang_chinese::chinese;
 
+use crate::completions_writer;
 use crate::dictionary_writer::DictWriter;
+use crate::spelling_index_writer;
 use crate::doc_info::DocInfos;
+use crate::error::IndexerError;
 use crate::utils::{fs_utils, time};
 use crate::{i_debug, spimi_reader, OLD_SOURCE_CONFIG};
 use crate::incremental_info::IncrementalIndexInfo;
-use crate::field_info::FieldInfos;
+use crate::field_info::{FieldInfos, RELATIVE_FP_FIELD};
 use crate::indexer::input_config::{InfiConfig, InfiIndexingConfig};
-use crate::loader::LoaderBoxed;
+use crate::loader::{BasicLoaderResult, LoaderBoxed, LoaderResult};
 use crate::worker::miner::WorkerMiner;
 use crate::worker::{create_worker, MainToWorkerMessage, Worker, WorkerToMainMessage};
 
 use crossbeam::channel::{self, Receiver, Sender};
 
+pub use crate::loader::json::parse_json_document;
+pub use crate::loader::{Zone, DEFAULT_ZONE_SEPARATION};
+
 pub struct Indexer {
     indexing_config: Arc<InfiIndexingConfig>,
     doc_id_counter: u32,
@@ -67,12 +79,12 @@ impl Indexer {
         use_content_hash: bool,
         preserve_output_folder: bool,
         log_perf: bool,
-    ) -> Indexer {
+    ) -> Result<Indexer, IndexerError> {
         // -----------------------------------------------------------
 
-        fs::create_dir_all(&output_folder_path).expect("could not create output directory!");
+        fs::create_dir_all(output_folder_path).map_err(|e| IndexerError::io(output_folder_path, e))?;
 
-        let index_ver = (SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() / 100).to_string();
+        let index_ver = get_index_ver();
         let output_folder_path_inner = output_folder_path.join(&index_ver); // For cache-busting
 
         // -----------------------------------------------------------
@@ -90,11 +102,11 @@ impl Indexer {
             &config.json_config,
             is_incremental,
             use_content_hash,
-        );
+        )?;
         let is_incremental = incremental_output_config.is_some();
 
         if !output_folder_path_inner.exists() {
-            fs::create_dir(&output_folder_path_inner).expect("could not create inner output directory!");
+            fs::create_dir(&output_folder_path_inner).map_err(|e| IndexerError::io(&output_folder_path_inner, e))?;
         }
         
         // -----------------------------------------------------------
@@ -109,23 +121,24 @@ impl Indexer {
         // -----------------------------------------------------------
         // Store the current raw json configuration file, for checking if it changed in the next run
 
+        let old_config_path = output_folder_path.join(OLD_SOURCE_CONFIG);
         fs::write(
-            output_folder_path.join(OLD_SOURCE_CONFIG),
+            &old_config_path,
             serde_json::to_string_pretty(&config.json_config)
                 .expect("Failed to serialize current configuration file"),
         )
-        .expect(&("Failed to write ".to_owned() + OLD_SOURCE_CONFIG));
+        .map_err(|e| IndexerError::io(&old_config_path, e))?;
 
         // -----------------------------------------------------------
 
         // -----------------------------------------------------------
         // Misc
 
-        let loaders: Arc<Vec<LoaderBoxed>> = Arc::new(config.indexing_config.get_loaders_from_config());
+        let loaders: Arc<Vec<LoaderBoxed>> = Arc::new(config.indexing_config.get_loaders_from_config()?);
 
         let field_infos = config.fields_config.get_field_infos(
             &output_folder_path_inner, incremental_output_config.as_ref(),
-        );
+        )?;
 
         // ------------------------------
         // Previous index info
@@ -149,7 +162,18 @@ impl Indexer {
 
         let spimi_counter = doc_id_counter % config.indexing_config.num_docs_per_block;
 
-        let tokenizer = Indexer::resolve_tokenizer(&config.lang_config);
+        let tokenizer = match config.get_custom_tokenizer() {
+            Some(factory) => factory(&config.lang_config)?,
+            None => Indexer::resolve_tokenizer(&config.lang_config)?,
+        };
+
+        let mut lang_config = config.lang_config;
+        Indexer::normalize_synonyms(&mut lang_config.synonyms, &*tokenizer);
+        let synonyms = if lang_config.synonyms.expand_at_index_time && !lang_config.synonyms.is_empty() {
+            Some(Arc::new(Synonyms::for_index(&lang_config.synonyms)))
+        } else {
+            None
+        };
 
         let indexing_config = Arc::from(config.indexing_config);
 
@@ -174,6 +198,7 @@ impl Indexer {
             let tx_worker_clone = tx_worker.clone();
             let rx_worker_clone = rx_worker.clone();
             let tokenize_clone = Arc::clone(&tokenizer);
+            let synonyms_clone = synonyms.clone();
             let field_info_clone = Arc::clone(&field_infos);
             let indexing_config_clone = Arc::clone(&indexing_config);
             let num_workers_writing_blocks_clone = Arc::clone(&num_workers_writing_blocks);
@@ -189,6 +214,7 @@ impl Indexer {
                         tx_worker_clone,
                         rx_worker_clone,
                         tokenize_clone,
+                        synonyms_clone,
                         field_info_clone,
                         indexing_config_clone,
                         expected_num_docs_per_thread,
@@ -208,6 +234,7 @@ impl Indexer {
             indexing_config.with_positions,
             expected_num_docs_per_thread,
             &tokenizer,
+            synonyms,
             input_folder_path.to_path_buf(),
             &loaders,
             #[cfg(debug_assertions)]
@@ -232,7 +259,7 @@ impl Indexer {
             rx_main,
             rx_worker,
             num_workers_writing_blocks,
-            lang_config: config.lang_config,
+            lang_config,
             is_incremental,
             start_doc_id: doc_id_counter,
             start_block_number: 0,
@@ -241,15 +268,52 @@ impl Indexer {
         };
         indexer.start_block_number = indexer.block_number();
 
-        indexer
+        Ok(indexer)
+    }
+
+    fn resolve_tokenizer(
+        lang_config: &InfiLanguageConfig,
+    ) -> Result<Arc<dyn IndexerTokenizer + Send + Sync>, IndexerError> {
+        if !lang_config.analyzers.is_empty() {
+            return Indexer::resolve_analyzer_tokenizer(lang_config).map_err(IndexerError::Config);
+        }
+
+        match lang_config.lang.as_str() {
+            "ascii" => Ok(Arc::new(ascii::new_with_options(lang_config))),
+            "ascii_stemmer" => Ok(Arc::new(ascii_stemmer::new_with_options(lang_config))),
+            "chinese" => Ok(Arc::new(chinese::new_with_options(lang_config))),
+            _ => Err(IndexerError::Config(format!("Unsupported language {}", lang_config.lang))),
+        }
+    }
+
+    /// Normalizes the synonyms with the tokenizer, so that the searcher can use them as is
+    fn normalize_synonyms(synonyms: &mut InfiSynonymsConfig, tokenizer: &dyn IndexerTokenizer) {
+        let normalize = |synonym: &str| {
+            let mut text = synonym.to_owned();
+            let terms: Vec<String> = tokenizer.tokenize(&mut text).flatten().map(|term| term.into_owned()).collect();
+            terms.join(" ")
+        };
+
+        for set in synonyms.equivalent.iter_mut() {
+            for synonym in set.iter_mut() {
+                *synonym = normalize(synonym);
+            }
+        }
+
+        let mut mappings: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for (from, to) in std::mem::take(&mut synonyms.mappings) {
+            mappings.entry(normalize(&from)).or_default().extend(to.iter().map(|synonym| normalize(synonym)));
+        }
+        synonyms.mappings = mappings;
     }
 
-    fn resolve_tokenizer(lang_config: &InfiLanguageConfig) -> Arc<dyn IndexerTokenizer + Send + Sync> {
+    fn resolve_analyzer_tokenizer(
+        lang_config: &InfiLanguageConfig,
+    ) -> Result<Arc<dyn IndexerTokenizer + Send + Sync>, String> {
         match lang_config.lang.as_str() {
-            "ascii" => Arc::new(ascii::new_with_options(lang_config)),
-            "ascii_stemmer" => Arc::new(ascii_stemmer::new_with_options(lang_config)),
-            "chinese" => Arc::new(chinese::new_with_options(lang_config)),
-            _ => panic!("Unsupported language {}", lang_config.lang),
+            "ascii" => Ok(Arc::new(ascii::new_with_analyzers(lang_config)?)),
+            "ascii_stemmer" => Ok(Arc::new(ascii_stemmer::new_with_analyzers(lang_config)?)),
+            _ => Err(format!("Analyzers are not supported for the {} language", lang_config.lang)),
         }
     }
 
@@ -257,9 +321,12 @@ impl Indexer {
         ((self.doc_id_counter as f64) / (self.indexing_config.num_docs_per_block as f64)).floor() as u32
     }
 
-    pub fn index_file(&mut self, absolute_path: &Path, relative_path: &Path) {
+    /// Indexes the file with the first loader that accepts it.
+    ///
+    /// Errors only affect this file, which is skipped; indexing can continue with other files.
+    pub fn index_file(&mut self, absolute_path: &Path, relative_path: &Path) -> Result<(), IndexerError> {
         if self.indexing_config.is_excluded(relative_path) {
-            return;
+            return Ok(());
         }
 
         let relative_path_lossy;
@@ -270,45 +337,81 @@ impl Indexer {
             &relative_path_lossy
         };
 
-        for loader in self.loaders.iter() {
-            if let Some(loader_results) = loader.try_index_file(absolute_path, relative_path)
-            {
-                let is_not_modified = self.incremental_info.set_file(external_id, absolute_path, &self.input_folder_path);
+        let loaders = Arc::clone(&self.loaders);
+        for loader in loaders.iter() {
+            if let Some(loader_results) = loader.try_index_file(absolute_path, relative_path)? {
+                let is_not_modified = self.incremental_info.set_file(external_id, absolute_path, &self.input_folder_path)?;
                 if is_not_modified && self.is_incremental {
-                    return;
+                    return Ok(());
                 }
 
                 for loader_result in loader_results {
-                    self.tx_main.send(MainToWorkerMessage::Index {
-                        doc_id: self.doc_id_counter,
-                        loader_result,
-                    }).expect("Failed to send index msg to worker!");
+                    self.index_loader_result(external_id, loader_result);
+                }
 
+                break;
+            }
+        }
 
-                    Self::try_index_doc(&mut self.doc_miner, &self.rx_worker, 30); // TODO 30 a little arbitrary?
+        Ok(())
+    }
 
-                    self.incremental_info.add_doc_to_file(external_id, self.doc_id_counter);
+    /// Indexes a document from its fields directly, without going through the loaders,
+    /// as if it were a file at `external_id` relative to the source folder.
+    ///
+    /// Zones are indexed in order, the same way a loader's are. A `_relative_fp` field
+    /// with the `external_id` is prepended, unless one is already present.
+    ///
+    /// For incremental indexing, the document is compared against the previous run's using a hash of its fields.
+    /// As with files, documents not added again in an incremental run are deleted.
+    ///
+    /// Values that cannot be parsed for i64 fields are skipped with a warning, taking the field's default.
+    pub fn add_document(&mut self, external_id: &str, mut zones: Vec<Zone>) -> Result<(), IndexerError> {
+        if !zones.iter().any(|zone| zone.field_name == RELATIVE_FP_FIELD) {
+            zones.insert(0, Zone {
+                field_name: RELATIVE_FP_FIELD.to_owned(),
+                field_text: external_id.to_owned(),
+                separation: DEFAULT_ZONE_SEPARATION,
+            });
+        }
 
-                    self.doc_id_counter += 1;
-                    self.spimi_counter += 1;
+        let is_not_modified = self.incremental_info.set_document(external_id, &zones)?;
+        if is_not_modified && self.is_incremental {
+            return Ok(());
+        }
 
-                    if self.spimi_counter == self.indexing_config.num_docs_per_block {
-                        Self::try_index_doc(&mut self.doc_miner, &self.rx_worker, 0);
+        let absolute_path = self.input_folder_path.join(external_id);
+        self.index_loader_result(external_id, Box::new(BasicLoaderResult::new(zones, absolute_path)));
 
-                        let main_thread_block_index_results = self.doc_miner.get_results();
-                        let block_number = self.block_number() - 1;
+        Ok(())
+    }
 
-                        let secondary_inv_mappings = self.merge_block(
-                            main_thread_block_index_results, block_number, false,
-                        );
-                        self.incremental_info.extend_secondary_inv_mappings(secondary_inv_mappings);
+    fn index_loader_result(&mut self, external_id: &str, loader_result: Box<dyn LoaderResult + Send>) {
+        self.tx_main.send(MainToWorkerMessage::Index {
+            doc_id: self.doc_id_counter,
+            loader_result,
+        }).expect("Failed to send index msg to worker!");
 
-                        self.spimi_counter = 0;
-                    }
-                }
 
-                break;
-            }
+        Self::try_index_doc(&mut self.doc_miner, &self.rx_worker, 30); // TODO 30 a little arbitrary?
+
+        self.incremental_info.add_doc_to_file(external_id, self.doc_id_counter);
+
+        self.doc_id_counter += 1;
+        self.spimi_counter += 1;
+
+        if self.spimi_counter == self.indexing_config.num_docs_per_block {
+            Self::try_index_doc(&mut self.doc_miner, &self.rx_worker, 0);
+
+            let main_thread_block_index_results = self.doc_miner.get_results();
+            let block_number = self.block_number() - 1;
+
+            let secondary_inv_mappings = self.merge_block(
+                main_thread_block_index_results, block_number, false,
+            );
+            self.incremental_info.extend_secondary_inv_mappings(secondary_inv_mappings);
+
+            self.spimi_counter = 0;
         }
     }
 
@@ -325,7 +428,8 @@ impl Indexer {
         }
     }
 
-    pub fn finish_writing_docs(mut self) -> u32 {
+    /// Merges the indexed documents into the output folder, returning the number of documents in the index.
+    pub fn finish_writing_docs(mut self) -> Result<u32, IndexerError> {
         let first_block = self.start_block_number;
         let mut last_block = self.block_number();
 
@@ -354,9 +458,18 @@ impl Indexer {
         time::print_time_elapsed(&self.start_instant, "Block indexing done!");
 
         // N-way merge of spimi blocks
-        let enums_ev_strs = self.merge_blocks(first_block, last_block, self.start_instant.is_some());
+        let enums_ev_strs = self.merge_blocks(first_block, last_block, self.start_instant.is_some())?;
 
-        self.incremental_info.write_info(&self.input_folder_path, &self.output_folder_path);
+        if self.is_incremental {
+            info!(
+                "{} files added, {} updated, {} deleted.",
+                self.incremental_info.num_added_files,
+                self.incremental_info.num_updated_files,
+                self.incremental_info.num_deleted_files,
+            );
+        }
+
+        self.incremental_info.write_info(&self.input_folder_path, &self.output_folder_path)?;
 
         spimi_reader::common::cleanup_blocks(first_block, last_block, &self.output_folder_path_inner);
 
@@ -371,9 +484,9 @@ impl Indexer {
         let total_docs = self.doc_id_counter - self.incremental_info.num_deleted_docs;
 
         // Config needs to be written after workers are joined, as it calls Arc::try_unwrap.
-        output_config::write_output_config(self, enums_ev_strs);
+        output_config::write_output_config(self, enums_ev_strs)?;
 
-        total_docs
+        Ok(total_docs)
     }
 
     fn has_docs_added(&self) -> bool {
@@ -386,11 +499,19 @@ impl Indexer {
             .into_inner()
             .expect("No thread should be holding doc infos mutex when merging blocks");
 
-        doc_infos_unwrapped_inner.finalize_and_flush(
+        let doc_infos_ser = doc_infos_unwrapped_inner.finalize_and_flush(
             num_docs as u32,
             &self.field_infos,
             &mut self.incremental_info,
-        )
+        );
+
+        completions_writer::write_completions(
+            &self.output_folder_path_inner,
+            &doc_infos_unwrapped_inner,
+            &self.incremental_info.invalidation_vector,
+        );
+
+        doc_infos_ser
     }
 
     pub fn flush_metadata(
@@ -399,51 +520,23 @@ impl Indexer {
         doc_infos_ser: Vec<u8>,
         dict_writer: DictWriter,
         log_sizes: bool,
-    ) {
-        let metadata_file = self.output_folder_path_inner.join(METADATA_FILE);
-        let mut metadata_writer = BufWriter::new(
-            File::create(metadata_file).unwrap()
-        );
-
-        let (dict_table_ser, dict_string_ser) = dict_writer.flush();
-        let dict_table_ser: &[u8] = dict_table_ser.as_raw_slice();
-    
-        /*
-         Store the dictionary string first for better gzip compression,
-         followed by the dict table for locality.
-
-         The invalidation vec and docinfo needs to be read first however,
-         so store 3 u32 offsets totalling 12 bytes:
-         - dictionary table
-         - invalidation vec
-         - docinfo
-         */
-
-        if log_sizes {
-            println!("Metadata lengths:");
-            println!("  Dictionary string: {}", dict_string_ser.len());
-            println!("  Dictionary table: {}", dict_table_ser.len());
-            println!("  Invalidation Vec: {}", invalidation_vec_ser.len());
-            println!("  Doc Infos: {}", doc_infos_ser.len());
-        }
-
-        let dict_table_offset = 12 + dict_string_ser.len() as u32;
-        let invalidation_vec_offset = dict_table_offset + dict_table_ser.len() as u32;
-        let doc_infos_offset = invalidation_vec_offset + invalidation_vec_ser.len() as u32;
-
-        metadata_writer.write_all(&dict_table_offset.to_le_bytes()).unwrap();
-        metadata_writer.write_all(&invalidation_vec_offset.to_le_bytes()).unwrap();
-        metadata_writer.write_all(&doc_infos_offset.to_le_bytes()).unwrap();
-
-        metadata_writer.write_all(&dict_string_ser).unwrap();
-        metadata_writer.write_all(&dict_table_ser).unwrap();
-        metadata_writer.write_all(&invalidation_vec_ser).unwrap();
-        metadata_writer.write_all(&doc_infos_ser).unwrap();
-
-        metadata_writer.flush().expect("Failed to flush metadata.json");
+    ) -> Result<(), IndexerError> {
+        write_metadata(
+            &self.output_folder_path_inner,
+            self.indexing_config.spelling_index,
+            invalidation_vec_ser,
+            doc_infos_ser,
+            dict_writer,
+            log_sizes,
+        )
     }
 
-    fn merge_blocks(&mut self, first_block: u32, last_block: u32, log_metadata_sizes: bool) -> Vec<Vec<String>> {
+    fn merge_blocks(
+        &mut self,
+        first_block: u32,
+        last_block: u32,
+        log_metadata_sizes: bool,
+    ) -> Result<Vec<Vec<String>>, IndexerError> {
         let num_blocks = last_block - first_block + 1;
 
         if self.is_incremental {
@@ -470,9 +563,9 @@ impl Indexer {
                 doc_infos_ser,
                 dict_writer,
                 log_metadata_sizes,
-            );
+            )?;
 
-            enums_ev_strs
+            Ok(enums_ev_strs)
         } else {
             let invalidation_vec_ser = self.incremental_info.write_invalidation_vec(self.doc_id_counter);
             let (doc_infos_ser, enums_ev_strs) = self.flush_doc_infos(self.doc_id_counter as f64);
@@ -494,9 +587,552 @@ impl Indexer {
                 doc_infos_ser,
                 dict_writer,
                 log_metadata_sizes,
-            );
+            )?;
+
+            Ok(enums_ev_strs)
+        }
+    }
+}
+
+/// A new version for the index's inner output folder, for cache-busting
+pub(crate) fn get_index_ver() -> String {
+    (SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() / 100).to_string()
+}
+
+/// Writes the metadata file, which the searcher loads upfront, to the inner output folder
+pub(crate) fn write_metadata(
+    output_folder_path_inner: &Path,
+    spelling_index: bool,
+    invalidation_vec_ser: Vec<u8>,
+    doc_infos_ser: Vec<u8>,
+    dict_writer: DictWriter,
+    log_sizes: bool,
+) -> Result<(), IndexerError> {
+    let (dict_table_ser, dict_string_ser) = dict_writer.flush();
+    let dict_table_ser: &[u8] = dict_table_ser.as_raw_slice();
+
+    let spelling_index_ser = if spelling_index {
+        spelling_index_writer::write_spelling_index(
+            &dictionary::setup_dictionary(dict_table_ser, &dict_string_ser),
+        )
+    } else {
+        Vec::new()
+    };
+
+    /*
+     Store the dictionary string first for better gzip compression,
+     followed by the dict table for locality.
+
+     The invalidation vec and docinfo needs to be read first however,
+     so store 4 u32 offsets totalling 16 bytes:
+     - dictionary table
+     - invalidation vec
+     - docinfo
+     - spelling index, which is last as it is only needed for spelling correction
+     */
+
+    if log_sizes {
+        println!("Metadata lengths:");
+        println!("  Dictionary string: {}", dict_string_ser.len());
+        println!("  Dictionary table: {}", dict_table_ser.len());
+        println!("  Invalidation Vec: {}", invalidation_vec_ser.len());
+        println!("  Doc Infos: {}", doc_infos_ser.len());
+        println!("  Spelling Index: {}", spelling_index_ser.len());
+    }
 
-            enums_ev_strs
+    let dict_table_offset = (HEADER_LEN + dict_string_ser.len()) as u32;
+    let invalidation_vec_offset = dict_table_offset + dict_table_ser.len() as u32;
+    let doc_infos_offset = invalidation_vec_offset + invalidation_vec_ser.len() as u32;
+    let spelling_index_offset = doc_infos_offset + doc_infos_ser.len() as u32;
+
+    let metadata_file = output_folder_path_inner.join(METADATA_FILE);
+    let write_metadata_file = || -> io::Result<()> {
+        let mut metadata_writer = BufWriter::new(File::create(&metadata_file)?);
+
+        metadata_writer.write_all(&dict_table_offset.to_le_bytes())?;
+        metadata_writer.write_all(&invalidation_vec_offset.to_le_bytes())?;
+        metadata_writer.write_all(&doc_infos_offset.to_le_bytes())?;
+        metadata_writer.write_all(&spelling_index_offset.to_le_bytes())?;
+
+        metadata_writer.write_all(&dict_string_ser)?;
+        metadata_writer.write_all(dict_table_ser)?;
+        metadata_writer.write_all(&invalidation_vec_ser)?;
+        metadata_writer.write_all(&doc_infos_ser)?;
+        metadata_writer.write_all(&spelling_index_ser)?;
+
+        metadata_writer.flush()
+    };
+    write_metadata_file().map_err(|e| IndexerError::io(&metadata_file, e))
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::path::Path;
+
+    use infisearch_search::native::{NativeSearcher, NativeSearcherOptions, QueryOpts};
+    use pretty_assertions::assert_eq;
+
+    use super::{ascii, Arc, Indexer, Zone, DEFAULT_ZONE_SEPARATION};
+    use crate::error::IndexerError;
+    use crate::indexer::input_config::InfiConfig;
+    use crate::loader::{BasicLoaderResult, Loader, LoaderResultIterator, RELATIVE_FP_FIELD};
+    use crate::OUTPUT_CONFIG_FILE;
+
+    fn zone(field_name: &str, field_text: &str) -> Zone {
+        Zone { field_name: field_name.to_owned(), field_text: field_text.to_owned(), separation: DEFAULT_ZONE_SEPARATION }
+    }
+
+    fn body(text: &str) -> Vec<Zone> {
+        vec![zone("body", text)]
+    }
+
+    fn index_documents(
+        output_folder_path: &Path,
+        config: InfiConfig,
+        is_incremental: bool,
+        docs: Vec<(&str, Vec<Zone>)>,
+    ) {
+        // Documents are added directly, so the input folder is unused
+        let mut indexer = Indexer::new(
+            output_folder_path.parent().unwrap(),
+            output_folder_path,
+            config,
+            is_incremental,
+            false,
+            false,
+            false,
+        ).unwrap();
+        for (external_id, zones) in docs {
+            indexer.add_document(external_id, zones).unwrap();
+        }
+        indexer.finish_writing_docs().unwrap();
+    }
+
+    /// Sorted relative file paths, or external ids, of the top 10 results.
+    ///
+    /// The last term of the query is prefix searched unless it has a trailing space.
+    fn search(output_folder_path: &Path, query: &str) -> Vec<String> {
+        search_with_options(output_folder_path, query, &QueryOpts::default(), NativeSearcherOptions::default())
+    }
+
+    fn search_with_options(
+        output_folder_path: &Path,
+        query: &str,
+        opts: &QueryOpts,
+        options: NativeSearcherOptions,
+    ) -> Vec<String> {
+        let mut searcher = NativeSearcher::new(output_folder_path, options).unwrap();
+        let mut query = searcher.run_query(query, opts).unwrap();
+        let mut relative_fps: Vec<String> = query.get_next_n_results(10)
+            .into_iter()
+            .map(|doc_result| {
+                let fields = searcher.load_fields(doc_result.doc_id).unwrap();
+                fields.into_iter().find(|(field_name, _text)| field_name == "_relative_fp").unwrap().1
+            })
+            .collect();
+        relative_fps.sort();
+        relative_fps
+    }
+
+    fn index_bodies(output_folder_path: &Path, is_incremental: bool, docs: &[(&str, &str)]) {
+        let docs = docs.iter().map(|(external_id, text)| (*external_id, body(text))).collect();
+        index_documents(output_folder_path, InfiConfig::default(), is_incremental, docs);
+    }
+
+    /// Indexes each line of .lines files as a separate document
+    struct LinesLoader {
+        field_name: String,
+    }
+
+    impl Loader for LinesLoader {
+        fn try_index_file(
+            &self,
+            absolute_path: &Path,
+            relative_path: &Path,
+        ) -> Result<Option<LoaderResultIterator<'_>>, IndexerError> {
+            if relative_path.extension().is_none_or(|extension| extension != "lines") {
+                return Ok(None);
+            }
+
+            let text = fs::read_to_string(absolute_path).map_err(|e| IndexerError::io(absolute_path, e))?;
+            let link = relative_path.to_string_lossy().into_owned();
+            let absolute_path = absolute_path.to_path_buf();
+            let field_name = self.field_name.clone();
+
+            let docs: Vec<_> = text
+                .lines()
+                .enumerate()
+                .map(|(idx, line)| {
+                    let zones = vec![zone(&field_name, line), zone(RELATIVE_FP_FIELD, &format!("{}#{}", link, idx))];
+                    Box::new(BasicLoaderResult::new(zones, absolute_path.clone())) as _
+                })
+                .collect();
+            Ok(Some(Box::new(docs.into_iter())))
         }
+
+        fn get_name(&self) -> String {
+            "LinesLoader".to_owned()
+        }
+    }
+
+    #[test]
+    fn test_custom_loader() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let input_folder_path = temp_dir.path().join("input");
+        let output_folder_path = temp_dir.path().join("output");
+        fs::create_dir_all(&input_folder_path).unwrap();
+        fs::write(input_folder_path.join("a.lines"), "lorem ipsum\nlorem dolor").unwrap();
+
+        let mut config = InfiConfig::new(
+            "{ \"indexing_config\": { \"loaders\": { \"LinesLoader\": { \"field\": \"title\" } } } }".to_owned(),
+        ).unwrap();
+        config.indexing_config.register_loader("LinesLoader", |options| {
+            let field_name = options["field"]
+                .as_str()
+                .ok_or_else(|| IndexerError::loader_config("LinesLoader", "Missing field"))?
+                .to_owned();
+            Ok(Box::new(LinesLoader { field_name }))
+        });
+
+        let mut indexer = Indexer::new(&input_folder_path, &output_folder_path, config, false, false, false, false)
+            .unwrap();
+        indexer.index_file(&input_folder_path.join("a.lines"), "a.lines".as_ref()).unwrap();
+        indexer.finish_writing_docs().unwrap();
+
+        assert_eq!(search(&output_folder_path, "lorem"), vec!["a.lines#0", "a.lines#1"]);
+        assert_eq!(search(&output_folder_path, "title:dolor"), vec!["a.lines#1"]);
+    }
+
+    #[test]
+    fn test_custom_tokenizer() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let input_folder_path = temp_dir.path().join("input");
+        let output_folder_path = temp_dir.path().join("output");
+        fs::create_dir_all(&input_folder_path).unwrap();
+
+        let get_config = || InfiConfig::new("{ \"lang_config\": { \"lang\": \"custom_ascii\" } }".to_owned()).unwrap();
+        assert!(Indexer::new(&input_folder_path, &output_folder_path, get_config(), false, false, false, false).is_err());
+
+        let mut config = get_config();
+        config.register_tokenizer("custom_ascii", |lang_config| Ok(Arc::new(ascii::new_with_options(lang_config))));
+        index_documents(&output_folder_path, config, false, vec![("a", body("lorem ipsum"))]);
+
+        assert!(NativeSearcher::new(&output_folder_path, NativeSearcherOptions::default()).is_err());
+
+        let mut options = NativeSearcherOptions::default();
+        options.register_tokenizer("custom_ascii", |lang_config| Box::new(ascii::new_with_options(lang_config)));
+        assert_eq!(search_with_options(&output_folder_path, "lorem", &QueryOpts::default(), options), vec!["a"]);
+
+        // Other searchers are unaffected
+        assert!(NativeSearcher::new(&output_folder_path, NativeSearcherOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_field_analyzers() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let input_folder_path = temp_dir.path().join("input");
+        let output_folder_path = temp_dir.path().join("output");
+        fs::create_dir_all(&input_folder_path).unwrap();
+
+        // Titles are not stemmed
+        let config = InfiConfig::new(r#"{
+            "lang_config": {
+                "lang": "ascii_stemmer",
+                "analyzers": {
+                    "exact": {
+                        "tokenizer": "whitespace",
+                        "token_filters": [{ "type": "lowercase" }],
+                        "fields": ["title"]
+                    },
+                    "code": { "tokenizer": "whitespace", "fields": ["heading"] }
+                }
+            }
+        }"#.to_owned()).unwrap();
+        index_documents(&output_folder_path, config, false, vec![
+            ("a", body("running quickly")),
+            ("b", vec![zone("title", "Runners")]),
+            ("c", vec![zone("heading", "Foo bar.baz")]),
+            ("d", body("foo running")),
+        ]);
+
+        assert_eq!(search(&output_folder_path, "running "), vec!["a", "d"]);
+        // Not in the dictionary once stemmed, but is with the title's analyzer, in the title only
+        assert_eq!(search(&output_folder_path, "runners "), vec!["b"]);
+        // Split and case preserved as the heading's analyzer does, even when the default analyzer's term exists
+        assert_eq!(search(&output_folder_path, "bar.baz "), vec!["c"]);
+        assert_eq!(search(&output_folder_path, "Foo "), vec!["c", "d"]);
+        assert_eq!(search(&output_folder_path, "+Foo +running "), vec!["d"]);
+
+        let invalid_config = InfiConfig::new(r#"{
+            "lang_config": {
+                "lang": "ascii",
+                "analyzers": { "default": { "token_filters": [{ "type": "stemmer", "language": "english" }] } }
+            }
+        }"#.to_owned()).unwrap();
+        assert!(Indexer::new(&input_folder_path, &output_folder_path, invalid_config, false, false, false, false).is_err());
+    }
+
+    #[test]
+    fn test_synonyms() {
+        for expand_at_index_time in [false, true] {
+            let temp_dir = tempfile::tempdir().unwrap();
+            let output_folder_path = temp_dir.path().join("output");
+
+            let config = InfiConfig::new(format!(r#"{{
+                "lang_config": {{
+                    "lang": "ascii_stemmer",
+                    "synonyms": {{
+                        "equivalent": [["Cars", "motor vehicles"]],
+                        "mappings": {{ "TV": ["television"] }},
+                        "expand_at_index_time": {}
+                    }}
+                }}
+            }}"#, expand_at_index_time)).unwrap();
+            index_documents(&output_folder_path, config, false, vec![
+                ("a", body("cars for sale")),
+                ("b", body("used motor vehicles")),
+                ("c", body("television reviews")),
+                ("d", body("tv repairs")),
+                ("e", body("vehicles with a motor")),
+            ]);
+
+            let searcher = NativeSearcher::new(&output_folder_path, NativeSearcherOptions::default()).unwrap();
+            let synonyms = &searcher.output_config.lang_config.synonyms;
+            assert_eq!(synonyms.equivalent, vec![vec!["car".to_owned(), "motor vehicl".to_owned()]]);
+            assert_eq!(synonyms.mappings["tv"], vec!["televis".to_owned()]);
+
+            assert_eq!(search(&output_folder_path, "cars "), vec!["a", "b"]);
+            assert_eq!(search(&output_folder_path, "motor vehicles "), vec!["a", "b", "e"]);
+            assert_eq!(search(&output_folder_path, "tv "), vec!["c", "d"]);
+            // One-way mappings are not expanded in reverse
+            assert_eq!(search(&output_folder_path, "television "), vec!["c"]);
+        }
+    }
+
+    #[test]
+    fn test_spelling_suggestions() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let output_folder_path = temp_dir.path().join("output");
+
+        index_bodies(&output_folder_path, false, &[
+            ("a", "lorem ipsum"), ("b", "lorem dolor"), ("c", "lorme"),
+        ]);
+
+        for spelling_suggestions_only in [false, true] {
+            let mut searcher = NativeSearcher::new(&output_folder_path, NativeSearcherOptions {
+                spelling_suggestions_only,
+                ..NativeSearcherOptions::default()
+            }).unwrap();
+
+            let mut query = searcher.run_query("Ipsun dolr ", &QueryOpts::default()).unwrap();
+            let suggestion = query.suggestion().unwrap();
+            assert_eq!(suggestion.query, "ipsum dolor ");
+            assert_eq!(suggestion.corrections.len(), 2);
+            assert_eq!(suggestion.corrections[0].original_term, "ipsun");
+            assert_eq!(suggestion.corrections[0].corrected_term, "ipsum");
+            assert_eq!(suggestion.corrections[0].doc_freq, 1);
+
+            let num_results = query.get_next_n_results(10).len();
+            assert_eq!(num_results, if spelling_suggestions_only { 0 } else { 2 });
+
+            // Correctly spelled terms are searched as is
+            let query = searcher.run_query("lorme ", &QueryOpts::default()).unwrap();
+            assert!(query.suggestion().is_none());
+        }
+    }
+
+    #[test]
+    fn test_spelling_suggestions_stemmed() {
+        let analyzers = r#", "analyzers": { "exact": { "tokenizer": "whitespace", "fields": ["title"] } }"#;
+        for analyzers in ["", analyzers] {
+            let temp_dir = tempfile::tempdir().unwrap();
+            let output_folder_path = temp_dir.path().join("output");
+
+            let config = InfiConfig::new(format!(r#"{{
+                "lang_config": {{ "lang": "ascii_stemmer"{} }}
+            }}"#, analyzers)).unwrap();
+            index_documents(&output_folder_path, config, false, vec![
+                ("a", body("running shoes")), ("b", body("walking")),
+            ]);
+
+            let mut searcher = NativeSearcher::new(&output_folder_path, NativeSearcherOptions::default()).unwrap();
+            let query = searcher.run_query("+Runnning shoez ", &QueryOpts::default()).unwrap();
+            let suggestion = query.suggestion().unwrap();
+            // The query's suffix is kept where it stems to the correction, not the stem itself
+            assert_eq!(suggestion.query, "+running shoe ");
+            assert_eq!(suggestion.corrections[0].original_term, "runn");
+            assert_eq!(suggestion.corrections[0].corrected_term, "run");
+        }
+    }
+
+    #[test]
+    fn test_completions() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let output_folder_path = temp_dir.path().join("output");
+
+        let index_titles = |is_incremental: bool, docs: &[(&str, &str, &str)]| {
+            let docs = docs
+                .iter()
+                .map(|(external_id, title, text)| (*external_id, vec![zone("body", text), zone("title", title)]))
+                .collect();
+            index_documents(&output_folder_path, InfiConfig::default(), is_incremental, docs);
+        };
+
+        index_titles(false, &[
+            ("a", "Getting  Started", "installation"),
+            ("b", "getting started", "install"),
+            ("c", "Configuring the Indexer", "install"),
+        ]);
+
+        let complete = |query: &str| {
+            let mut searcher = NativeSearcher::new(&output_folder_path, NativeSearcherOptions::default()).unwrap();
+            searcher.complete(query, 5).unwrap()
+        };
+
+        let completions = complete("Configuring inst");
+        let terms: Vec<(&str, &str, u32)> = completions.terms
+            .iter()
+            .map(|completion| (completion.query.as_str(), completion.term.as_str(), completion.doc_freq))
+            .collect();
+        assert_eq!(terms, vec![
+            ("Configuring install", "install", 2),
+            ("Configuring installation", "installation", 1),
+        ]);
+
+        // No term is being typed
+        assert!(complete("inst ").terms.is_empty());
+
+        // Texts are matched at word boundaries, ignoring case and whitespace
+        let completions = complete("getting started");
+        assert_eq!(completions.phrases.len(), 1);
+        assert_eq!(completions.phrases[0].phrase, "Getting Started");
+        assert_eq!(completions.phrases[0].field_name, "title");
+        assert_eq!(completions.phrases[0].num_docs, 2);
+        assert_eq!(complete("the  ind").phrases[0].phrase, "Configuring the Indexer");
+        assert!(complete("tarted").phrases.is_empty());
+
+        // Body texts are not completed by default
+        assert!(complete("installation").phrases.is_empty());
+
+        // a is deleted, c is updated
+        index_titles(true, &[
+            ("b", "getting started", "install"),
+            ("c", "Configuring the Searcher", "install"),
+        ]);
+
+        let completions = complete("getting");
+        assert_eq!(completions.phrases.len(), 1);
+        assert_eq!(completions.phrases[0].phrase, "getting started");
+        assert_eq!(completions.phrases[0].num_docs, 1);
+
+        let phrases: Vec<String> = complete("configuring").phrases
+            .into_iter()
+            .map(|completion| completion.phrase)
+            .collect();
+        assert_eq!(phrases, vec!["Configuring the Searcher".to_owned()]);
+    }
+
+    #[test]
+    fn test_completions_folded() {
+        for lang in ["ascii", "ascii_stemmer"] {
+            let temp_dir = tempfile::tempdir().unwrap();
+            let output_folder_path = temp_dir.path().join("output");
+
+            let config = InfiConfig::new(format!(r#"{{ "lang_config": {{ "lang": "{}" }} }}"#, lang)).unwrap();
+            index_documents(&output_folder_path, config, false, vec![
+                ("a", vec![zone("title", "Café Society"), zone("h1", "Café Society"), zone("body", "cafeteria")]),
+                ("b", body("cafe racers")),
+            ]);
+
+            let mut searcher = NativeSearcher::new(&output_folder_path, NativeSearcherOptions::default()).unwrap();
+            let completions = searcher.complete("the Caf", 5).unwrap();
+            let terms: Vec<(&str, &str, u32)> = completions.terms
+                .iter()
+                .map(|completion| (completion.query.as_str(), completion.term.as_str(), completion.doc_freq))
+                .collect();
+            if lang == "ascii" {
+                // Completed to the word as written in the title
+                assert_eq!(terms, vec![("the café", "cafe", 2), ("the cafeteria", "cafeteria", 1)]);
+            } else {
+                // Stems are not completed
+                assert_eq!(terms, vec![]);
+            }
+
+            // The title and h1 are of the same document
+            let completions = searcher.complete("café soc", 5).unwrap();
+            assert_eq!(completions.phrases.len(), 1);
+            assert_eq!(completions.phrases[0].num_docs, 1);
+        }
+    }
+
+    #[test]
+    fn test_add_document() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let output_folder_path = temp_dir.path().join("output");
+
+        index_bodies(&output_folder_path, false, &[("a", "lorem ipsum"), ("b", "lorem dolor")]);
+        assert_eq!(search(&output_folder_path, "lorem"), vec!["a", "b"]);
+
+        // b is updated, a is unchanged, c is added
+        index_bodies(&output_folder_path, true, &[("a", "lorem ipsum"), ("b", "sit amet"), ("c", "lorem amet")]);
+        assert_eq!(search(&output_folder_path, "lorem"), vec!["a", "c"]);
+        assert_eq!(search(&output_folder_path, "amet"), vec!["b", "c"]);
+
+        // a and c are deleted, as they were not added again
+        index_bodies(&output_folder_path, true, &[("b", "sit amet")]);
+        assert_eq!(search(&output_folder_path, "lorem"), Vec::<String>::new());
+        assert_eq!(search(&output_folder_path, "amet"), vec!["b"]);
+    }
+
+    #[test]
+    fn test_invalid_i64_values() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let input_folder_path = temp_dir.path().join("input");
+        let output_folder_path = temp_dir.path().join("output");
+        fs::create_dir_all(&input_folder_path).unwrap();
+
+        let config = InfiConfig::new(r#"{
+            "fields_config": { "fields": { "price": { "storage": [{ "type": "i64", "default": -1 }] } } }
+        }"#.to_owned()).unwrap();
+        index_documents(&output_folder_path, config, false, vec![
+            ("a", vec![zone("body", "lorem"), zone("price", "10")]),
+            ("b", vec![zone("body", "lorem"), zone("price", "ten")]),
+        ]);
+
+        // Only the invalid value is skipped, taking the default
+        let searcher = NativeSearcher::new(&output_folder_path, NativeSearcherOptions::default()).unwrap();
+        assert_eq!(searcher.get_i64_value(0, "price"), Some(10));
+        assert_eq!(searcher.get_i64_value(1, "price"), Some(-1));
+
+        let time_without_timezone = InfiConfig::new(r#"{
+            "fields_config": { "fields": { "date": { "storage": [{
+                "type": "i64", "default": 0, "parse": { "method": "datetime", "datetime_fmt": "%Y-%m-%d", "time": 0 }
+            }] } } }
+        }"#.to_owned()).unwrap();
+        assert!(matches!(
+            Indexer::new(&input_folder_path, &output_folder_path, time_without_timezone, false, false, false, false),
+            Err(IndexerError::Config(_)),
+        ));
+    }
+
+    #[test]
+    fn test_format_ver_change() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let output_folder_path = temp_dir.path().join("output");
+        index_bodies(&output_folder_path, false, &[("a", "lorem"), ("b", "lorem")]);
+
+        // As written before the index format was versioned
+        let output_config_path = output_folder_path.join(OUTPUT_CONFIG_FILE);
+        let mut output_config: serde_json::Value = serde_json::from_slice(&fs::read(&output_config_path).unwrap()).unwrap();
+        output_config.as_object_mut().unwrap().remove("formatVer");
+        fs::write(&output_config_path, output_config.to_string()).unwrap();
+        assert!(NativeSearcher::new(&output_folder_path, NativeSearcherOptions::default()).is_err());
+
+        // Fully reindexed, instead of deleting a from the old index
+        index_bodies(&output_folder_path, true, &[("b", "lorem")]);
+        let searcher = NativeSearcher::new(&output_folder_path, NativeSearcherOptions::default()).unwrap();
+        assert_eq!(searcher.output_config.last_doc_id, 1);
+        assert_eq!(search(&output_folder_path, "lorem"), vec!["b"]);
     }
 }
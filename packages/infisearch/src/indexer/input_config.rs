@@ -4,14 +4,18 @@ mod preset_medium;
 mod preset_large;
 
 use std::path::Path;
+use std::sync::Arc;
 
 use infisearch_common::language::InfiLanguageConfig;
+use infisearch_common::tokenize::IndexerTokenizer;
 
 use crate::{field_info::FieldsConfig, SOURCE_CONFIG_FILE};
-use crate::loader::LoaderBoxed;
+use crate::error::IndexerError;
+use crate::loader::{LoaderBoxed, LoaderFactory};
 use crate::loader::csv::CsvLoader;
 use crate::loader::html::HtmlLoader;
 use crate::loader::json::JsonLoader;
+use crate::loader::markdown::MarkdownLoader;
 use crate::loader::txt::TxtLoader;
 use crate::loader::pdf::PdfLoader;
 
@@ -60,6 +64,10 @@ fn get_default_with_positions() -> bool {
     true
 }
 
+fn get_default_spelling_index() -> bool {
+    true
+}
+
 #[derive(Deserialize)]
 pub struct InfiIndexingConfig {
     #[serde(default = "get_default_num_threads", skip_serializing)]
@@ -89,11 +97,17 @@ pub struct InfiIndexingConfig {
     #[serde(default = "get_default_loaders")]
     pub loaders: FxHashMap<String, serde_json::Value>,
 
+    #[serde(skip, default = "FxHashMap::default")]
+    custom_loaders: FxHashMap<String, LoaderFactory>,
+
     #[serde(default = "get_default_num_pls_per_dir")]
     pub num_pls_per_dir: u32,
 
     #[serde(default = "get_default_with_positions")]
     pub with_positions: bool,
+
+    #[serde(default = "get_default_spelling_index")]
+    pub spelling_index: bool,
 }
 
 impl Default for InfiIndexingConfig {
@@ -108,31 +122,52 @@ impl Default for InfiIndexingConfig {
             exclude_patterns: Vec::new(),
             include_patterns: Vec::new(),
             loaders: get_default_loaders(),
+            custom_loaders: FxHashMap::default(),
             num_pls_per_dir: get_default_num_pls_per_dir(),
             with_positions: get_default_with_positions(),
+            spelling_index: get_default_spelling_index(),
         };
 
-        indexing_config.init_patterns();
+        indexing_config.init_patterns().expect("Default glob patterns should be valid");
         indexing_config
     }
 }
 
 impl InfiIndexingConfig {
-    pub fn get_loaders_from_config(&self) -> Vec<LoaderBoxed> {
+    /// Registers a custom loader, created by `factory` when `name` is a key of `loaders`
+    /// with the key's value as its options, as the built-in loaders are.
+    ///
+    /// Registering a built-in loader's name replaces it.
+    /// This must be done before passing the configuration to `Indexer::new`.
+    pub fn register_loader(
+        &mut self,
+        name: &str,
+        factory: impl Fn(Value) -> Result<LoaderBoxed, IndexerError> + Send + Sync + 'static,
+    ) {
+        self.custom_loaders.insert(name.to_owned(), Box::new(factory));
+    }
+
+    pub fn get_loaders_from_config(&self) -> Result<Vec<LoaderBoxed>, IndexerError> {
         let mut loaders: Vec<LoaderBoxed> = Vec::new();
 
         for (key, value) in self.loaders.clone() {
+            if let Some(factory) = self.custom_loaders.get(&key) {
+                loaders.push(factory(value)?);
+                continue;
+            }
+
             match key.as_str() {
-                "HtmlLoader" => loaders.push(HtmlLoader::get_new_html_loader(value)),
-                "CsvLoader" => loaders.push(CsvLoader::get_new_csv_loader(value)),
-                "JsonLoader" => loaders.push(JsonLoader::get_new_json_loader(value)),
-                "TxtLoader" => loaders.push(TxtLoader::get_new_txt_loader(value)),
-                "PdfLoader" => loaders.push(PdfLoader::get_new_pdf_loader(value)),
-                _ => panic!("Unknown loader type encountered in config"),
+                "HtmlLoader" => loaders.push(HtmlLoader::get_new_html_loader(value)?),
+                "CsvLoader" => loaders.push(CsvLoader::get_new_csv_loader(value)?),
+                "JsonLoader" => loaders.push(JsonLoader::get_new_json_loader(value)?),
+                "TxtLoader" => loaders.push(TxtLoader::get_new_txt_loader(value)?),
+                "MarkdownLoader" => loaders.push(MarkdownLoader::get_new_markdown_loader(value)?),
+                "PdfLoader" => loaders.push(PdfLoader::get_new_pdf_loader(value)?),
+                _ => return Err(IndexerError::Config(format!("Unknown loader type {} encountered", key))),
             }
         }
 
-        loaders
+        Ok(loaders)
     }
 
     pub fn is_excluded(&self, relative_path: &Path) -> bool {
@@ -144,19 +179,30 @@ impl InfiIndexingConfig {
         )
     }
 
-    fn init_patterns(&mut self) {
+    fn init_patterns(&mut self) -> Result<(), IndexerError> {
         self.exclude_patterns = self.exclude
             .iter()
-            .map(|pat_str| Pattern::new(pat_str).expect("Invalid exclude glob pattern!"))
-            .collect();
+            .map(|pat_str| Pattern::new(pat_str).map_err(|e| {
+                IndexerError::Config(format!("Invalid exclude glob pattern {}. {}", pat_str, e))
+            }))
+            .collect::<Result<_, _>>()?;
 
         self.include_patterns = self.include
             .iter()
-            .map(|pat_str| Pattern::new(pat_str).expect("Invalid include glob pattern!"))
-            .collect();
+            .map(|pat_str| Pattern::new(pat_str).map_err(|e| {
+                IndexerError::Config(format!("Invalid include glob pattern {}. {}", pat_str, e))
+            }))
+            .collect::<Result<_, _>>()?;
+
+        Ok(())
     }
 }
 
+/// Creates the tokenizer for a custom `lang_config.lang`. Registered with `InfiConfig::register_tokenizer`.
+pub type TokenizerFactory = Box<
+    dyn Fn(&InfiLanguageConfig) -> Result<Arc<dyn IndexerTokenizer + Send + Sync>, IndexerError> + Send + Sync
+>;
+
 #[derive(Deserialize)]
 pub struct InfiConfig {
     #[serde(default = "get_default_preset")]
@@ -169,14 +215,34 @@ pub struct InfiConfig {
     pub indexing_config: InfiIndexingConfig,
     #[serde(skip)]
     pub json_config: Value,
+    #[serde(skip)]
+    custom_tokenizers: FxHashMap<String, TokenizerFactory>,
 }
 
 impl InfiConfig {
-    pub fn new(raw_config: String) -> Self {
+    /// Registers a custom tokenizer, used when `lang_config.lang` is `lang`.
+    ///
+    /// Registering a built-in language's name replaces it.
+    /// The searcher needs a matching `SearchTokenizer` registered under the same name,
+    /// see `NativeSearcherOptions::register_tokenizer`.
+    pub fn register_tokenizer(
+        &mut self,
+        lang: &str,
+        factory: impl Fn(&InfiLanguageConfig) -> Result<Arc<dyn IndexerTokenizer + Send + Sync>, IndexerError>
+            + Send + Sync + 'static,
+    ) {
+        self.custom_tokenizers.insert(lang.to_owned(), Box::new(factory));
+    }
+
+    pub(crate) fn get_custom_tokenizer(&self) -> Option<&TokenizerFactory> {
+        self.custom_tokenizers.get(&self.lang_config.lang)
+    }
+
+    pub fn new(raw_config: String) -> Result<Self, IndexerError> {
         let mut config: InfiConfig = serde_json::from_str(&raw_config)
-            .expect("infi_search.json does not match schema!");
+            .map_err(|e| IndexerError::Config(format!("infi_search.json does not match schema! {}", e)))?;
         let json_config: Value = serde_json::from_str(&raw_config)
-            .expect("infi_search.json does not match schema!");
+            .map_err(|e| IndexerError::Config(format!("infi_search.json does not match schema! {}", e)))?;
 
         config.fields_config.merge_default_fields();
 
@@ -196,14 +262,51 @@ impl InfiConfig {
         }
 
         config.json_config = json_config;
-        config.indexing_config.init_patterns();
+        config.indexing_config.init_patterns()?;
 
-        config
+        Ok(config)
     }
 }
 
 impl Default for InfiConfig {
     fn default() -> Self {
-        InfiConfig::new("{}".to_owned())
+        InfiConfig::new("{}".to_owned()).expect("Default configuration should be valid")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::error::IndexerError;
+
+    use super::InfiConfig;
+
+    #[test]
+    fn test_config_errors() {
+        assert!(matches!(InfiConfig::new("{ \"preset\": 1 }".to_owned()), Err(IndexerError::Config(_))));
+        assert!(matches!(
+            InfiConfig::new("{ \"indexing_config\": { \"exclude\": [\"a[\"] } }".to_owned()),
+            Err(IndexerError::Config(_))
+        ));
+
+        let config = InfiConfig::new("{ \"indexing_config\": { \"loaders\": { \"DocxLoader\": {} } } }".to_owned())
+            .unwrap();
+        assert!(matches!(config.indexing_config.get_loaders_from_config(), Err(IndexerError::Config(_))));
+
+        let mut config = InfiConfig::new("{ \"indexing_config\": { \"loaders\": { \"DocxLoader\": {} } } }".to_owned())
+            .unwrap();
+        config.indexing_config.register_loader("DocxLoader", |options| {
+            Err(IndexerError::loader_config("DocxLoader", options))
+        });
+        assert!(matches!(
+            config.indexing_config.get_loaders_from_config(),
+            Err(IndexerError::LoaderConfig { .. })
+        ));
+
+        let config = InfiConfig::new("{ \"indexing_config\": { \"loaders\": { \"CsvLoader\": {} } } }".to_owned())
+            .unwrap();
+        assert!(matches!(
+            config.indexing_config.get_loaders_from_config(),
+            Err(IndexerError::LoaderConfig { .. })
+        ));
     }
 }
@@ -1,8 +1,11 @@
 use std::fs::File;
-use std::io::Write;
+use std::io::{self, Write};
+use std::path::Path;
 
 use infisearch_common::language::InfiLanguageConfig;
+use infisearch_common::metadata::FORMAT_VER;
 
+use crate::error::IndexerError;
 use crate::{INFISEARCH_VER, OUTPUT_CONFIG_FILE};
 use crate::field_info::{FieldInfoOutput, EnumInfo};
 use super::Indexer;
@@ -12,8 +15,8 @@ use serde::{Serialize, Deserialize};
 // Separate struct to support serializing for --config-init option but not output config
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct InfiIndexingOutputConfig {
-    pl_names_to_cache: Vec<u32>,
+pub(crate) struct InfiIndexingOutputConfig {
+    pub(crate) pl_names_to_cache: Vec<u32>,
     num_docs_per_block: u32,
     num_pls_per_dir: u32,
     with_positions: bool,
@@ -23,9 +26,11 @@ struct InfiIndexingOutputConfig {
 #[serde(rename_all = "camelCase")]
 pub struct InfiOutputConfig {
     pub ver: String,
+    #[serde(default)]
+    pub format_ver: u32,
     pub index_ver: String,
-    last_doc_id: u32,
-    indexing_config: InfiIndexingOutputConfig,
+    pub(crate) last_doc_id: u32,
+    pub(crate) indexing_config: InfiIndexingOutputConfig,
     lang_config: InfiLanguageConfig,
     cache_all_field_stores: bool,
     pub field_infos: Vec<FieldInfoOutput>,
@@ -34,7 +39,17 @@ pub struct InfiOutputConfig {
     num_stores_per_dir: u32,
 }
 
-pub fn write_output_config(indexer: Indexer, mut enums_ev_strs: Vec<Vec<String>>) {
+impl InfiOutputConfig {
+    pub(crate) fn write(&self, output_folder_path: &Path) -> Result<(), IndexerError> {
+        let output_config_path = output_folder_path.join(OUTPUT_CONFIG_FILE);
+        serde_json::to_vec(self)
+            .map_err(io::Error::from)
+            .and_then(|serialized| File::create(&output_config_path)?.write_all(&serialized))
+            .map_err(|e| IndexerError::io(&output_config_path, e))
+    }
+}
+
+pub fn write_output_config(indexer: Indexer, mut enums_ev_strs: Vec<Vec<String>>) -> Result<(), IndexerError> {
     drop(indexer.doc_miner);
 
     // Add in the enum string values sorted according to their enum_id and ev_ids
@@ -45,8 +60,9 @@ pub fn write_output_config(indexer: Indexer, mut enums_ev_strs: Vec<Vec<String>>
         }
     }
 
-    let serialized = serde_json::to_string(&InfiOutputConfig {
+    InfiOutputConfig {
         ver: INFISEARCH_VER.to_owned(),
+        format_ver: FORMAT_VER,
         index_ver: indexer.index_ver,
         last_doc_id: indexer.doc_id_counter,
         indexing_config: InfiIndexingOutputConfig {
@@ -61,11 +77,6 @@ pub fn write_output_config(indexer: Indexer, mut enums_ev_strs: Vec<Vec<String>>
         num_scored_fields: indexer.field_infos.num_scored_fields,
         num_docs_per_store: indexer.field_infos.num_docs_per_store,
         num_stores_per_dir: indexer.field_infos.num_stores_per_dir,
-    })
-    .unwrap();
-
-    File::create(indexer.output_folder_path.join(OUTPUT_CONFIG_FILE))
-        .unwrap()
-        .write_all(serialized.as_bytes())
-        .unwrap();
+    }
+    .write(&indexer.output_folder_path)
 }
@@ -1,12 +1,19 @@
+pub mod commands;
+pub mod compactor;
+mod completions_writer;
 mod dictionary_writer;
 mod doc_info;
+pub mod error;
 mod incremental_info;
 pub mod indexer;
 mod field_info;
-mod loader;
+pub mod loader;
+pub mod merger;
+mod spelling_index_writer;
 mod spimi_reader;
 mod spimi_writer;
 mod utils;
+pub mod watch;
 mod worker;
 
 #[macro_use]
@@ -1,27 +1,39 @@
-pub mod csv;
-pub mod html;
-pub mod json;
-pub mod pdf;
-pub mod txt;
+pub(crate) mod csv;
+pub(crate) mod html;
+pub(crate) mod json;
+pub(crate) mod markdown;
+pub(crate) mod pdf;
+pub(crate) mod txt;
 
 use std::path::{Path, PathBuf};
 
-use crate::worker::miner::Zone;
+use crate::error::IndexerError;
+pub use crate::field_info::RELATIVE_FP_FIELD;
+pub use crate::worker::miner::{Zone, DEFAULT_ZONE_SEPARATION};
 
 pub type LoaderResultIterator<'a> = Box<dyn Iterator<Item = Box<dyn LoaderResult + Send>> + 'a>;
 
 pub type LoaderBoxed = Box<dyn Loader + Send + Sync>;
 
+/// Creates a loader from its options under `indexing_config.loaders`.
+/// Registered with `InfiIndexingConfig::register_loader`.
+pub type LoaderFactory = Box<dyn Fn(serde_json::Value) -> Result<LoaderBoxed, IndexerError> + Send + Sync>;
+
+/// Converts files into documents, each a sequence of `Zone`s (field texts).
+///
+/// Loaders are tried in no particular order, and the first to return `Some` indexes the file.
 pub trait Loader {
+    /// Returns `Ok(None)` if the file is not handled by this loader.
     fn try_index_file(
         &self,
         absolute_path: &Path,
         relative_path: &Path,
-    ) -> Option<LoaderResultIterator>;
+    ) -> Result<Option<LoaderResultIterator>, IndexerError>;
 
     fn get_name(&self) -> String;
 }
 
+/// A document loaded by a `Loader`, which is converted into its zones in a worker thread.
 pub trait LoaderResult {
     fn get_field_texts_and_path(self: Box<Self>) -> (Vec<Zone>, PathBuf);
 }
@@ -31,6 +43,12 @@ pub struct BasicLoaderResult {
     absolute_path: PathBuf,
 }
 
+impl BasicLoaderResult {
+    pub fn new(field_texts: Vec<Zone>, absolute_path: PathBuf) -> Self {
+        BasicLoaderResult { field_texts, absolute_path }
+    }
+}
+
 impl LoaderResult for BasicLoaderResult {
     fn get_field_texts_and_path(self: Box<Self>) -> (Vec<Zone>, PathBuf) {
         (self.field_texts, self.absolute_path)
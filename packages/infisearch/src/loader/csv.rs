@@ -2,9 +2,11 @@ use std::path::Path;
 use std::path::PathBuf;
 
 use csv::ReaderBuilder;
+use log::error;
 use rustc_hash::FxHashMap;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
+use crate::error::IndexerError;
 use crate::loader::BasicLoaderResult;
 use crate::loader::Loader;
 use crate::loader::LoaderResult;
@@ -75,9 +77,9 @@ pub struct CsvLoader {
 }
 
 impl CsvLoader {
-    pub fn get_new_csv_loader(config: serde_json::Value) -> Box<Self> {
-        let mut csv_loader_options: CsvLoaderOptions =
-            serde_json::from_value(config).expect("CsvLoader options did not match schema!");
+    pub fn get_new_csv_loader(config: serde_json::Value) -> Result<Box<Self>, IndexerError> {
+        let mut csv_loader_options: CsvLoaderOptions = serde_json::from_value(config)
+            .map_err(|e| IndexerError::loader_config("CsvLoader", e))?;
 
         let use_headers = if !csv_loader_options.header_field_map.is_empty()
             && csv_loader_options.index_field_map.is_empty()
@@ -88,7 +90,10 @@ impl CsvLoader {
         {
             false
         } else {
-            panic!("One of CsvLoader header_field_map and index_field_map must be specified.");
+            return Err(IndexerError::loader_config(
+                "CsvLoader",
+                "One of header_field_map and index_field_map must be specified.",
+            ));
         };
 
         if use_headers {
@@ -115,19 +120,18 @@ impl CsvLoader {
             .escape(csv_loader_parse_opts.escape)
             .comment(csv_loader_parse_opts.comment);
 
-        Box::new(CsvLoader { use_headers, options: csv_loader_options, reader_builder })
+        Ok(Box::new(CsvLoader { use_headers, options: csv_loader_options, reader_builder }))
     }
 
     // Uses indexes
     fn unwrap_csv_read_result(
         &self,
-        read_result: Result<csv::StringRecord, csv::Error>,
+        record: csv::StringRecord,
         num_fields: usize,
         absolute_path: PathBuf,
     ) -> Box<dyn LoaderResult + Send> {
         let mut field_texts: Vec<Zone> = Vec::with_capacity(num_fields);
 
-        let record = read_result.expect("Failed to unwrap csv record result!");
         for idx in self.options.index_field_order.iter() {
             if let Some(text) = record.get(*idx) {
                 field_texts.push(Zone {
@@ -173,7 +177,7 @@ impl Loader for CsvLoader {
         &'a self,
         absolute_path: &Path,
         relative_path: &Path,
-    ) -> Option<LoaderResultIterator<'a>> {
+    ) -> Result<Option<LoaderResultIterator<'a>>, IndexerError> {
         if let Some(extension) = relative_path.extension() {
             if extension == "csv" {
                 let num_fields = if self.use_headers {
@@ -183,35 +187,45 @@ impl Loader for CsvLoader {
                 };
 
                 let absolute_path_as_buf = PathBuf::from(absolute_path);
+                let reader = self.reader_builder
+                    .from_path(absolute_path)
+                    .map_err(|e| IndexerError::loader(absolute_path, e))?;
 
-                return Some(if self.use_headers {
+                return Ok(Some(if self.use_headers {
                     Box::new(
-                        self.reader_builder.from_path(absolute_path)
-                            .unwrap_or_else(|_| panic!("Failed to read csv {}", relative_path.as_os_str().to_string_lossy()))
+                        reader
                             .into_deserialize()
-                            .map(
-                                move |result| self.unwrap_csv_deserialize_result(
-                                    result.expect("Failed to unwrap csv record result"),
+                            .filter_map(move |result| match result {
+                                Ok(record) => Some(self.unwrap_csv_deserialize_result(
+                                    record,
                                     num_fields,
                                     absolute_path_as_buf.clone(),
-                                ),
-                            ),
+                                )),
+                                Err(e) => {
+                                    error!("Invalid csv record in {}, skipping it. {}", absolute_path_as_buf.to_string_lossy(), e);
+                                    None
+                                }
+                            }),
                     )
                 } else {
                     Box::new(
-                        self.reader_builder
-                            .from_path(absolute_path)
-                            .unwrap_or_else(|_| panic!("Failed to read csv {}", relative_path.as_os_str().to_string_lossy()))
+                        reader
                             .into_records()
-                            .map(move |result| self.unwrap_csv_read_result(
-                                result, num_fields, absolute_path_as_buf.clone(),
-                            )),
+                            .filter_map(move |result| match result {
+                                Ok(record) => Some(self.unwrap_csv_read_result(
+                                    record, num_fields, absolute_path_as_buf.clone(),
+                                )),
+                                Err(e) => {
+                                    error!("Invalid csv record in {}, skipping it. {}", absolute_path_as_buf.to_string_lossy(), e);
+                                    None
+                                }
+                            }),
                     )
-                });
+                }));
             }
         }
 
-        None
+        Ok(None)
     }
 
     fn get_name(&self) -> String {
@@ -12,14 +12,15 @@ use scraper::Html;
 use scraper::Selector;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
+use crate::error::IndexerError;
 use crate::field_info::RELATIVE_FP_FIELD;
 use crate::loader::Loader;
 use crate::loader::LoaderResult;
 use crate::loader::LoaderResultIterator;
 use crate::worker::miner::{DEFAULT_ZONE_SEPARATION, Zone};
 
-const HTML_ZONE_SEPARATION: u32 = 3;
-const SEPARATOR_EL_SEPARATION: u32 = 0;
+pub const HTML_ZONE_SEPARATION: u32 = 3;
+pub const SEPARATOR_EL_SEPARATION: u32 = 0;
 
 pub struct HtmlLoaderSelector {
     selector: Selector,
@@ -131,9 +132,9 @@ struct HtmlLoaderResult {
 }
 
 impl HtmlLoader {
-    pub fn get_new_html_loader(config: serde_json::Value) -> Box<Self> {
-        let mut html_loader_options_raw: HtmlLoaderOptionsRaw =
-            serde_json::from_value(config).expect("HtmlLoader options did not match schema!");
+    pub fn get_new_html_loader(config: serde_json::Value) -> Result<Box<Self>, IndexerError> {
+        let mut html_loader_options_raw: HtmlLoaderOptionsRaw = serde_json::from_value(config)
+            .map_err(|e| IndexerError::loader_config("HtmlLoader", e))?;
 
         // --------------------------------------------------------------
         // Merge/update the default selectors
@@ -150,23 +151,27 @@ impl HtmlLoader {
         selectors.sort_by_key(|(_selector, opt)| opt.priority);
         selectors.reverse();
 
+        let parse_selector = |selector: &str| Selector::parse(selector).map_err(|e| {
+            IndexerError::loader_config("HtmlLoader", format!("Invalid selector {}. {:?}", selector, e))
+        });
+
         let options = Arc::new(HtmlLoaderOptions {
             selectors: selectors
                 .into_iter()
-                .map(|(selector, opt)| HtmlLoaderSelector {
-                    selector: Selector::parse(selector).expect("Invalid selector!"),
+                .map(|(selector, opt)| Ok(HtmlLoaderSelector {
+                    selector: parse_selector(selector)?,
                     field_name: opt.field_name.clone(),
                     attr_map: opt.attr_map.clone(),
-                })
-                .collect(),
+                }))
+                .collect::<Result<_, IndexerError>>()?,
             exclude_selectors: html_loader_options_raw
                 .exclude_selectors
                 .iter()
-                .map(|selector| Selector::parse(selector).expect("Invalid exclude selector!"))
-                .collect(),
+                .map(|selector| parse_selector(selector))
+                .collect::<Result<_, IndexerError>>()?,
         });
 
-        Box::new(HtmlLoader { raw_options: html_loader_options_raw, options })
+        Ok(Box::new(HtmlLoader { raw_options: html_loader_options_raw, options }))
     }
 }
 
@@ -175,25 +180,26 @@ impl Loader for HtmlLoader {
         &'a self,
         absolute_path: &Path,
         relative_path: &Path,
-    ) -> Option<LoaderResultIterator<'a>> {
+    ) -> Result<Option<LoaderResultIterator<'a>>, IndexerError> {
         if let Some(extension) = relative_path.extension() {
             if extension == "html" {
                 let absolute_path_as_buf = PathBuf::from(absolute_path);
 
                 if let Some(relative_path) = relative_path.to_slash() {
-                    return Some(Box::new(std::iter::once(Box::new(HtmlLoaderResult {
+                    return Ok(Some(Box::new(std::iter::once(Box::new(HtmlLoaderResult {
                         link: relative_path.into_owned(),
-                        text: std::fs::read_to_string(absolute_path).expect("Failed to read file!"),
+                        text: std::fs::read_to_string(absolute_path)
+                            .map_err(|e| IndexerError::io(absolute_path, e))?,
                         options: self.options.clone(),
                         absolute_path: absolute_path_as_buf,
-                    }) as Box<dyn LoaderResult + Send>)));
+                    }) as Box<dyn LoaderResult + Send>))));
                 } else {
                     error!("Unable to index {} containing non-unicode characters", relative_path.to_slash_lossy());
                 }
             }
         }
 
-        None
+        Ok(None)
     }
 
     fn get_name(&self) -> String {
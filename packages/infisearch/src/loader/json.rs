@@ -9,6 +9,7 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Map;
 use serde_json::value::Value;
 
+use crate::error::IndexerError;
 use crate::field_info::{ADD_FILES_FIELD, RELATIVE_FP_FIELD};
 use crate::loader::BasicLoaderResult;
 use crate::loader::Loader;
@@ -24,14 +25,83 @@ pub struct JsonLoaderOptions {
     field_order: Vec<String>,
 }
 
+/// Converts a json string or number into a field text, or `Ok(None)` for nulls, which are skipped.
+fn get_field_text(value: &Value) -> Result<Option<String>, &'static str> {
+    if let Some(text) = value.as_str() {
+        Ok(Some(text.to_owned()))
+    } else if let Some(int) = value.as_i64() {
+        Ok(Some(int.to_string()))
+    } else if let Some(double) = value.as_f64() {
+        Ok(Some(double.to_string()))
+    } else if value.is_null() {
+        Ok(None)
+    } else {
+        Err("expected String or Number or null")
+    }
+}
+
+/// Parses a document for `Indexer::add_document`, as read line by line from stdin:
+///
+/// `{ "id": "external id", "fields": { "title": "...", "body": ["...", "..."] } }`
+///
+/// Arrays of texts are indexed as separate zones of the same field.
+/// `fields` can also be an array of `["fieldName", "text"]` pairs, to specify the order of the zones.
+pub fn parse_json_document(json: &str) -> Result<(String, Vec<Zone>), IndexerError> {
+    let document: Value = serde_json::from_str(json).map_err(|e| IndexerError::Document(e.to_string()))?;
+
+    let external_id = document["id"]
+        .as_str()
+        .ok_or_else(|| IndexerError::Document("Missing string \"id\"".to_owned()))?
+        .to_owned();
+
+    let mut field_values: Vec<(&str, &Value)> = Vec::new();
+    if let Some(map) = document["fields"].as_object() {
+        for (field_name, value) in map {
+            if let Some(values) = value.as_array() {
+                field_values.extend(values.iter().map(|value| (field_name.as_str(), value)));
+            } else {
+                field_values.push((field_name, value));
+            }
+        }
+    } else if let Some(pairs) = document["fields"].as_array() {
+        for pair in pairs {
+            match (pair[0].as_str(), pair.get(1)) {
+                (Some(field_name), Some(value)) => field_values.push((field_name, value)),
+                _ => return Err(IndexerError::Document(
+                    format!("Invalid field {} for {}, expected a [fieldName, text] pair", pair, external_id),
+                )),
+            }
+        }
+    } else {
+        return Err(IndexerError::Document(format!("Missing \"fields\" object or array for {}", external_id)));
+    }
+
+    let mut zones = Vec::with_capacity(field_values.len());
+    for (field_name, value) in field_values {
+        let field_text = get_field_text(value).map_err(|expected| {
+            IndexerError::Document(format!("Invalid value for {} in {}, {}", field_name, external_id, expected))
+        })?;
+
+        if let Some(field_text) = field_text {
+            zones.push(Zone {
+                field_name: field_name.to_owned(),
+                field_text,
+                separation: DEFAULT_ZONE_SEPARATION,
+            });
+        }
+    }
+
+    Ok((external_id, zones))
+}
+
 pub struct JsonLoader {
     options: JsonLoaderOptions,
 }
 
 impl JsonLoader {
-    pub fn get_new_json_loader(config: serde_json::Value) -> Box<Self> {
-        let mut json_loader_options: JsonLoaderOptions =
-            serde_json::from_value(config).expect("JsonLoader options did not match schema!");
+    pub fn get_new_json_loader(config: serde_json::Value) -> Result<Box<Self>, IndexerError> {
+        let mut json_loader_options: JsonLoaderOptions = serde_json::from_value(config)
+            .map_err(|e| IndexerError::loader_config("JsonLoader", e))?;
 
         if json_loader_options.field_order.is_empty() {
             let arbitrary_field_order: Vec<String> = json_loader_options.field_map
@@ -39,12 +109,20 @@ impl JsonLoader {
                 .map(String::to_owned)
                 .collect();
             json_loader_options.field_order = arbitrary_field_order;
+        } else if let Some(unmapped) = json_loader_options.field_order
+            .iter()
+            .find(|json_key| !json_loader_options.field_map.contains_key(*json_key))
+        {
+            return Err(IndexerError::loader_config(
+                "JsonLoader",
+                format!("field_order key {} is missing from field_map.", unmapped),
+            ));
         }
 
         json_loader_options.field_order.push(ADD_FILES_FIELD.to_owned());
         json_loader_options.field_map.insert(ADD_FILES_FIELD.to_owned(), ADD_FILES_FIELD.to_owned());
 
-        Box::new(JsonLoader { options: json_loader_options })
+        Ok(Box::new(JsonLoader { options: json_loader_options }))
     }
 
     fn unwrap_json_deserialize_result(
@@ -62,26 +140,24 @@ impl JsonLoader {
         });
 
         for header_name in self.options.field_order.iter() {
-            if let Some((field_name, value)) = map.get_key_value(header_name) {
-                let field_text = if let Some(text) = value.as_str() {
-                    text.to_owned()
-                } else if let Some(int) = value.as_i64() {
-                    int.to_string()
-                } else if let Some(double) = value.as_f64() {
-                    double.to_string()
-                } else if value.is_null() {
-                    continue;
-                } else {
-                    panic!(
-                        "Invalid JSON value in array {}, expected String or Number or null.",
-                        absolute_path.to_slash_lossy()
-                    );
+            let field_name = self.options.field_map.get(header_name);
+            if let (Some(value), Some(field_name)) = (map.get(header_name), field_name) {
+                let field_text = match get_field_text(value) {
+                    Ok(Some(field_text)) => field_text,
+                    Ok(None) => continue,
+                    Err(expected) => {
+                        error!(
+                            "Invalid JSON value for {} in {}, {}. Skipping.",
+                            header_name,
+                            absolute_path.to_slash_lossy(),
+                            expected,
+                        );
+                        continue;
+                    }
                 };
 
                 field_texts.push(Zone {
-                    field_name: self.options.field_map.get(field_name)
-                        .expect("field_order does not match field_map!")
-                        .to_owned(),
+                    field_name: field_name.to_owned(),
                     field_text,
                     separation: DEFAULT_ZONE_SEPARATION,
                 });
@@ -97,24 +173,24 @@ impl Loader for JsonLoader {
         &'a self,
         absolute_path: &Path,
         relative_path: &Path,
-    ) -> Option<LoaderResultIterator<'a>> {
+    ) -> Result<Option<LoaderResultIterator<'a>>, IndexerError> {
         if let Some(extension) = relative_path.extension() {
             if extension == "json" {
                 let as_value: Value = serde_json::from_str(
-                    &std::fs::read_to_string(absolute_path).expect("Failed to read json file!"),
-                ).unwrap_or_else(|_err| panic!("Invalid json! {}", relative_path.to_slash_lossy()));
+                    &std::fs::read_to_string(absolute_path).map_err(|e| IndexerError::io(absolute_path, e))?,
+                ).map_err(|e| IndexerError::loader(absolute_path, format!("Invalid json! {}", e)))?;
 
                 let link = relative_path.to_slash();
                 if link.is_none() {
                     error!("Unable to index {} containing non-unicode characters", relative_path.to_slash_lossy());
-                    return None;
+                    return Ok(None);
                 }
 
                 let link = unsafe { link.unwrap_unchecked().into_owned() };
                 let absolute_path_as_buf = PathBuf::from(absolute_path);
 
                 if let Some(values) = as_value.as_array() {
-                    return Some(Box::new({
+                    return Ok(Some(Box::new({
                         let doc_count = values.len();
                         let links = vec![link; doc_count];
                         values.to_owned()
@@ -134,11 +210,11 @@ impl Loader for JsonLoader {
                                     None
                                 }
                             )
-                    }));
+                    })));
                 } else if let Some(map) = as_value.as_object() {
-                    return Some(Box::new(std::iter::once(self.unwrap_json_deserialize_result(
+                    return Ok(Some(Box::new(std::iter::once(self.unwrap_json_deserialize_result(
                         map, link, absolute_path_as_buf,
-                    ))));
+                    )))));
                 } else {
                     warn!(
                         "Invalid JSON document {}, expected Map or Vec<Map>. Skipping.",
@@ -148,7 +224,7 @@ impl Loader for JsonLoader {
             }
         }
 
-        None
+        Ok(None)
     }
 
     fn get_name(&self) -> String {
@@ -173,3 +249,53 @@ impl<'de> Deserialize<'de> for JsonLoader {
         panic!("Called deserialize for JsonLoader")
     }
 }
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use crate::worker::miner::{DEFAULT_ZONE_SEPARATION, Zone};
+
+    use super::{parse_json_document, JsonLoader};
+
+    fn zone(field_name: &str, field_text: &str) -> Zone {
+        Zone {
+            field_name: field_name.to_owned(),
+            field_text: field_text.to_owned(),
+            separation: DEFAULT_ZONE_SEPARATION,
+        }
+    }
+
+    #[test]
+    fn test_parse_json_document() {
+        let (external_id, zones) = parse_json_document(
+            r#"{ "id": "doc-1", "fields": { "body": ["lorem", "ipsum"], "price": 10, "title": null } }"#,
+        ).unwrap();
+        assert_eq!(external_id, "doc-1");
+        assert_eq!(zones, vec![zone("body", "lorem"), zone("body", "ipsum"), zone("price", "10")]);
+
+        let (_external_id, zones) = parse_json_document(
+            r#"{ "id": "doc-2", "fields": [["title", "Lorem"], ["body", "ipsum"], ["heading", "dolor"]] }"#,
+        ).unwrap();
+        assert_eq!(zones, vec![zone("title", "Lorem"), zone("body", "ipsum"), zone("heading", "dolor")]);
+
+        assert!(parse_json_document(r#"{ "fields": {} }"#).is_err());
+        assert!(parse_json_document(r#"{ "id": "doc-3" }"#).is_err());
+        assert!(parse_json_document(r#"{ "id": "doc-3", "fields": { "body": { "nested": 1 } } }"#).is_err());
+        assert!(parse_json_document(r#"{ "id": "doc-3", "fields": [["body"]] }"#).is_err());
+        assert!(parse_json_document("{ invalid").is_err());
+    }
+
+    #[test]
+    fn test_field_order() {
+        assert!(JsonLoader::get_new_json_loader(serde_json::json!({
+            "field_map": { "title": "title", "content": "body" },
+            "field_order": ["content", "title"],
+        })).is_ok());
+
+        assert!(JsonLoader::get_new_json_loader(serde_json::json!({
+            "field_map": { "title": "title" },
+            "field_order": ["content", "title"],
+        })).is_err());
+    }
+}
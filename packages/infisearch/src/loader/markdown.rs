@@ -0,0 +1,481 @@
+use std::iter::FromIterator;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use log::{error, warn};
+use path_slash::PathExt;
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag};
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+
+use crate::error::IndexerError;
+use crate::field_info::RELATIVE_FP_FIELD;
+use crate::loader::html::{HTML_ZONE_SEPARATION, SEPARATOR_EL_SEPARATION};
+use crate::loader::Loader;
+use crate::loader::LoaderResult;
+use crate::loader::LoaderResultIterator;
+use crate::worker::miner::{DEFAULT_ZONE_SEPARATION, Zone};
+
+// Same field names used by the default HtmlLoader selectors
+const BODY_FIELD: &str = "body";
+const H1_FIELD: &str = "h1";
+const HEADING_FIELD: &str = "heading";
+const HEADING_LINK_FIELD: &str = "headingLink";
+
+fn get_default_field_map() -> FxHashMap<String, String> {
+    FxHashMap::from_iter(vec![
+        ("title".to_owned(), "title".to_owned()),
+    ])
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct MarkdownLoaderOptions {
+    // Front matter key -> field name
+    #[serde(default = "get_default_field_map")]
+    field_map: FxHashMap<String, String>,
+}
+
+pub struct MarkdownLoader {
+    options: Arc<MarkdownLoaderOptions>,
+}
+
+struct MarkdownLoaderResult {
+    link: String,
+    text: String,
+    options: Arc<MarkdownLoaderOptions>,
+    absolute_path: PathBuf,
+}
+
+impl MarkdownLoader {
+    pub fn get_new_markdown_loader(config: serde_json::Value) -> Result<Box<Self>, IndexerError> {
+        let markdown_loader_options: MarkdownLoaderOptions = serde_json::from_value(config)
+            .map_err(|e| IndexerError::loader_config("MarkdownLoader", e))?;
+
+        Ok(Box::new(MarkdownLoader { options: Arc::new(markdown_loader_options) }))
+    }
+}
+
+impl Loader for MarkdownLoader {
+    fn try_index_file<'a>(
+        &'a self,
+        absolute_path: &Path,
+        relative_path: &Path,
+    ) -> Result<Option<LoaderResultIterator<'a>>, IndexerError> {
+        if let Some(extension) = relative_path.extension() {
+            if extension == "md" {
+                let absolute_path_as_buf = PathBuf::from(absolute_path);
+
+                if let Some(relative_path) = relative_path.to_slash() {
+                    return Ok(Some(Box::new(std::iter::once(Box::new(MarkdownLoaderResult {
+                        link: relative_path.into_owned(),
+                        text: std::fs::read_to_string(absolute_path)
+                            .map_err(|e| IndexerError::io(absolute_path, e))?,
+                        options: self.options.clone(),
+                        absolute_path: absolute_path_as_buf,
+                    }) as Box<dyn LoaderResult + Send>))));
+                } else {
+                    error!("Unable to index {} containing non-unicode characters", relative_path.to_slash_lossy());
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn get_name(&self) -> String {
+        "MarkdownLoader".to_owned()
+    }
+}
+
+impl Serialize for MarkdownLoader {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.options.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for MarkdownLoader {
+    fn deserialize<D>(_deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        panic!("Called deserialize for MarkdownLoader")
+    }
+}
+
+/// Splits the YAML (`---`) or TOML (`+++`) front matter, if any, from the markdown content.
+///
+/// Returns the parsed front matter and the remaining content.
+fn split_front_matter<'a>(text: &'a str, link: &str) -> (Option<Value>, &'a str) {
+    let text_without_bom = text.trim_start_matches('\u{feff}');
+
+    for (delimiter, is_yaml) in [("---", true), ("+++", false)] {
+        let first_line_end = text_without_bom.find('\n').unwrap_or(text_without_bom.len());
+        if text_without_bom[..first_line_end].trim_end() != delimiter {
+            continue;
+        }
+
+        let rest = &text_without_bom[(first_line_end + 1).min(text_without_bom.len())..];
+
+        let mut line_start = 0;
+        for line in rest.split_inclusive('\n') {
+            if line.trim_end() == delimiter {
+                let front_matter = &rest[..line_start];
+                let content = &rest[(line_start + line.len())..];
+
+                let parsed: Result<Value, String> = if is_yaml {
+                    serde_yaml::from_str(front_matter).map_err(|e| e.to_string())
+                } else {
+                    toml::from_str(front_matter)
+                        .map(toml_to_json)
+                        .map_err(|e| e.to_string())
+                };
+
+                return match parsed {
+                    Ok(value) => (Some(value), content),
+                    Err(e) => {
+                        warn!("Invalid front matter in {}, indexing without it. {}", link, e);
+                        (None, content)
+                    }
+                };
+            }
+
+            line_start += line.len();
+        }
+
+        warn!("Unclosed front matter in {}, indexing it as markdown.", link);
+        break;
+    }
+
+    (None, text_without_bom)
+}
+
+// Converted manually as toml datetimes otherwise serialize into a special map
+fn toml_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => Value::from(i),
+        toml::Value::Float(f) => Value::from(f),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(dt) => Value::String(dt.to_string()),
+        toml::Value::Array(arr) => Value::Array(arr.into_iter().map(toml_to_json).collect()),
+        toml::Value::Table(table) => Value::Object(
+            table.into_iter().map(|(k, v)| (k, toml_to_json(v))).collect()
+        ),
+    }
+}
+
+fn front_matter_value_to_text(value: &Value) -> Option<String> {
+    if let Some(text) = value.as_str() {
+        Some(text.to_owned())
+    } else if value.is_number() || value.is_boolean() {
+        Some(value.to_string())
+    } else {
+        None
+    }
+}
+
+/// Heading ids following the convention of most markdown renderers (e.g. mdBook),
+/// suffixing duplicates with a counter.
+fn get_heading_id(heading_text: &str, heading_id_counts: &mut FxHashMap<String, usize>) -> String {
+    let mut id = String::with_capacity(heading_text.len());
+    for c in heading_text.trim().chars() {
+        if c.is_alphanumeric() || c == '_' || c == '-' {
+            // Some characters lowercase to several (e.g. 'İ')
+            id.extend(c.to_lowercase());
+        } else if c.is_whitespace() {
+            id.push('-');
+        }
+    }
+
+    let count = heading_id_counts.entry(id.clone()).or_insert(0);
+    let unique_id = if *count == 0 { id } else { format!("{}-{}", id, count) };
+    *count += 1;
+
+    unique_id
+}
+
+impl MarkdownLoaderResult {
+    fn add_front_matter_zones(&self, front_matter: Value, link: &str, field_texts: &mut Vec<Zone>) {
+        let map = if let Value::Object(map) = front_matter {
+            map
+        } else {
+            warn!("Front matter in {} is not a map, ignoring it.", link);
+            return;
+        };
+
+        for (key, value) in map.iter() {
+            if let Some(field_name) = self.options.field_map.get(key) {
+                let texts: Vec<String> = if let Some(values) = value.as_array() {
+                    values.iter().filter_map(front_matter_value_to_text).collect()
+                } else if let Some(text) = front_matter_value_to_text(value) {
+                    vec![text]
+                } else {
+                    if !value.is_null() {
+                        warn!("Unsupported front matter value for {} in {}, skipping it.", key, link);
+                    }
+                    continue;
+                };
+
+                for field_text in texts {
+                    field_texts.push(Zone {
+                        field_name: field_name.to_owned(),
+                        field_text,
+                        separation: DEFAULT_ZONE_SEPARATION,
+                    });
+                }
+            }
+        }
+    }
+}
+
+fn add_body_text(text: &str, do_separate: &mut bool, field_texts: &mut Vec<Zone>) {
+    let last = unsafe { field_texts.last_mut().unwrap_unchecked() };
+    if last.field_name.as_str() == BODY_FIELD {
+        if *do_separate {
+            field_texts.push(Zone {
+                field_name: BODY_FIELD.to_owned(),
+                field_text: text.to_owned(),
+                separation: SEPARATOR_EL_SEPARATION,
+            });
+            *do_separate = false;
+        } else {
+            last.field_text += text;
+        }
+    } else {
+        field_texts.push(Zone {
+            field_name: BODY_FIELD.to_owned(),
+            field_text: text.to_owned(),
+            separation: HTML_ZONE_SEPARATION,
+        });
+        *do_separate = false;
+    }
+}
+
+/// Text outside of the tags of raw html, whose tags may span several `Event::Html` lines.
+fn strip_html_tags(html: &str, is_in_tag: &mut bool) -> String {
+    let mut text = String::with_capacity(html.len());
+    for c in html.chars() {
+        match c {
+            '<' => *is_in_tag = true,
+            '>' if *is_in_tag => *is_in_tag = false,
+            _ if !*is_in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text
+}
+
+impl LoaderResult for MarkdownLoaderResult {
+    fn get_field_texts_and_path(mut self: Box<Self>) -> (Vec<Zone>, PathBuf) {
+        let mut field_texts: Vec<Zone> = Vec::with_capacity(20);
+
+        field_texts.push(Zone {
+            field_name: RELATIVE_FP_FIELD.to_owned(),
+            field_text: std::mem::take(&mut self.link),
+            separation: DEFAULT_ZONE_SEPARATION,
+        });
+
+        let text = std::mem::take(&mut self.text);
+        let link = &field_texts[0].field_text;
+        let (front_matter, content) = split_front_matter(&text, link);
+        if let Some(front_matter) = front_matter {
+            let link = link.clone();
+            self.add_front_matter_zones(front_matter, &link, &mut field_texts);
+        }
+
+        let parser = Parser::new_ext(
+            content,
+            Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_HEADING_ATTRIBUTES,
+        );
+
+        // Text of the heading currently being parsed, if any
+        let mut heading: Option<(HeadingLevel, Option<String>, String)> = None;
+        let mut heading_id_counts: FxHashMap<String, usize> = FxHashMap::default();
+        // Whether to add a new body Zone for the next text, analogous to HtmlLoader's separated elements
+        let mut do_separate = false;
+        let mut is_in_html_tag = false;
+
+        for event in parser {
+            match event {
+                Event::Start(Tag::Heading(level, id, _classes)) => {
+                    heading = Some((level, id.map(str::to_owned), String::new()));
+                }
+                Event::End(Tag::Heading(..)) => {
+                    if let Some((level, id, heading_text)) = heading.take() {
+                        if level == HeadingLevel::H1 {
+                            field_texts.push(Zone {
+                                field_name: H1_FIELD.to_owned(),
+                                field_text: heading_text,
+                                separation: HTML_ZONE_SEPARATION,
+                            });
+                        } else {
+                            let id = id.unwrap_or_else(|| get_heading_id(&heading_text, &mut heading_id_counts));
+                            field_texts.push(Zone {
+                                field_name: HEADING_LINK_FIELD.to_owned(),
+                                field_text: id,
+                                separation: 2,
+                            });
+                            field_texts.push(Zone {
+                                field_name: HEADING_FIELD.to_owned(),
+                                field_text: heading_text,
+                                separation: HTML_ZONE_SEPARATION,
+                            });
+                        }
+                    }
+                    do_separate = true;
+                }
+                Event::Text(text) | Event::Code(text) => {
+                    if let Some((_level, _id, heading_text)) = heading.as_mut() {
+                        *heading_text += &text;
+                    } else {
+                        add_body_text(&text, &mut do_separate, &mut field_texts);
+                    }
+                }
+                Event::Html(html) => {
+                    let text = strip_html_tags(&html, &mut is_in_html_tag);
+                    if let Some((_level, _id, heading_text)) = heading.as_mut() {
+                        *heading_text += &text;
+                    } else if !text.trim().is_empty() {
+                        add_body_text(&text, &mut do_separate, &mut field_texts);
+                    }
+                }
+                Event::SoftBreak | Event::HardBreak => {
+                    if let Some((_level, _id, heading_text)) = heading.as_mut() {
+                        heading_text.push(' ');
+                    } else {
+                        add_body_text(" ", &mut do_separate, &mut field_texts);
+                    }
+                }
+                Event::End(
+                    Tag::Paragraph
+                    | Tag::BlockQuote
+                    | Tag::CodeBlock(_)
+                    | Tag::Item
+                    | Tag::TableHead
+                    | Tag::TableRow
+                    | Tag::TableCell,
+                )
+                | Event::Rule => {
+                    do_separate = true;
+                }
+                _ => {}
+            }
+        }
+
+        (field_texts, self.absolute_path)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{sync::Arc, path::PathBuf};
+
+    use pretty_assertions::assert_eq;
+
+    use crate::{loader::LoaderResult, worker::miner::{Zone, DEFAULT_ZONE_SEPARATION}, field_info::RELATIVE_FP_FIELD};
+    use crate::loader::html::{HTML_ZONE_SEPARATION, SEPARATOR_EL_SEPARATION};
+
+    use super::{MarkdownLoaderOptions, MarkdownLoaderResult, get_default_field_map};
+
+    fn zone(field_name: &str, field_text: &str, separation: u32) -> Zone {
+        Zone { field_name: field_name.to_owned(), field_text: field_text.to_owned(), separation }
+    }
+
+    fn get_zones(text: &str) -> Vec<Zone> {
+        let mut field_map = get_default_field_map();
+        field_map.insert("tags".to_owned(), "tags".to_owned());
+
+        let loader_result = Box::new(MarkdownLoaderResult {
+            link: "test.md".to_owned(),
+            text: text.to_owned(),
+            options: Arc::new(MarkdownLoaderOptions { field_map }),
+            absolute_path: PathBuf::new(),
+        });
+
+        loader_result.get_field_texts_and_path().0
+    }
+
+    #[test]
+    fn test_yaml_front_matter_and_headings() {
+        let zones = get_zones(
+            "---\ntitle: Some Title\ntags: [one, 2]\nignored: true\n---\n\
+            # Main Heading\n\n\
+            Intro *text*\nwith `code`.\n\n\
+            ## Sub Heading\n\n\
+            - item one\n- item two\n\n\
+            ## Sub Heading\n\n\
+            ### Custom {#custom-id}\n\n\
+            end\n",
+        );
+
+        assert_eq!(zones, vec![
+            zone(RELATIVE_FP_FIELD, "test.md", DEFAULT_ZONE_SEPARATION),
+            zone("tags", "one", DEFAULT_ZONE_SEPARATION),
+            zone("tags", "2", DEFAULT_ZONE_SEPARATION),
+            zone("title", "Some Title", DEFAULT_ZONE_SEPARATION),
+            zone("h1", "Main Heading", HTML_ZONE_SEPARATION),
+            zone("body", "Intro text with code.", HTML_ZONE_SEPARATION),
+            zone("headingLink", "sub-heading", 2),
+            zone("heading", "Sub Heading", HTML_ZONE_SEPARATION),
+            zone("body", "item one", HTML_ZONE_SEPARATION),
+            zone("body", "item two", SEPARATOR_EL_SEPARATION),
+            zone("headingLink", "sub-heading-1", 2),
+            zone("heading", "Sub Heading", HTML_ZONE_SEPARATION),
+            zone("headingLink", "custom-id", 2),
+            zone("heading", "Custom", HTML_ZONE_SEPARATION),
+            zone("body", "end", HTML_ZONE_SEPARATION),
+        ]);
+    }
+
+    #[test]
+    fn test_toml_and_missing_front_matter() {
+        let zones = get_zones("+++\ntitle = \"Toml Title\"\ndate = 2022-01-01\n+++\nbody text\n");
+        assert_eq!(zones, vec![
+            zone(RELATIVE_FP_FIELD, "test.md", DEFAULT_ZONE_SEPARATION),
+            zone("title", "Toml Title", DEFAULT_ZONE_SEPARATION),
+            zone("body", "body text", HTML_ZONE_SEPARATION),
+        ]);
+
+        let zones = get_zones("body text\n\n---\n\nmore text\n");
+        assert_eq!(zones, vec![
+            zone(RELATIVE_FP_FIELD, "test.md", DEFAULT_ZONE_SEPARATION),
+            zone("body", "body text", HTML_ZONE_SEPARATION),
+            zone("body", "more text", SEPARATOR_EL_SEPARATION),
+        ]);
+    }
+
+    #[test]
+    fn test_non_ascii_headings() {
+        let zones = get_zones("## Überblick: Größe\n\n## ΣΟΦΊΑ\n\n## Überblick: Größe\n");
+        assert_eq!(zones, vec![
+            zone(RELATIVE_FP_FIELD, "test.md", DEFAULT_ZONE_SEPARATION),
+            zone("headingLink", "überblick-größe", 2),
+            zone("heading", "Überblick: Größe", HTML_ZONE_SEPARATION),
+            zone("headingLink", "σοφία", 2),
+            zone("heading", "ΣΟΦΊΑ", HTML_ZONE_SEPARATION),
+            zone("headingLink", "überblick-größe-1", 2),
+            zone("heading", "Überblick: Größe", HTML_ZONE_SEPARATION),
+        ]);
+    }
+
+    #[test]
+    fn test_html() {
+        let zones = get_zones(
+            "lorem <b>ipsum</b>\n\n\
+            <div class=\"note\">\n<p>dolor <i>sit</i></p>\n</div>\n\n\
+            ## Amet <code>consectetur</code>\n",
+        );
+        assert_eq!(zones, vec![
+            zone(RELATIVE_FP_FIELD, "test.md", DEFAULT_ZONE_SEPARATION),
+            zone("body", "lorem ipsum", HTML_ZONE_SEPARATION),
+            zone("body", "dolor sit\n", SEPARATOR_EL_SEPARATION),
+            zone("headingLink", "amet-consectetur", 2),
+            zone("heading", "Amet consectetur", HTML_ZONE_SEPARATION),
+        ]);
+    }
+}
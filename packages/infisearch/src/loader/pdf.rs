@@ -6,6 +6,7 @@ use log::error;
 use path_slash::PathExt;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
+use crate::error::IndexerError;
 use crate::field_info::RELATIVE_FP_FIELD;
 use crate::loader::Loader;
 use crate::loader::LoaderResult;
@@ -33,11 +34,11 @@ struct PdfLoaderResult {
 }
 
 impl PdfLoader {
-    pub fn get_new_pdf_loader(config: serde_json::Value) -> Box<Self> {
+    pub fn get_new_pdf_loader(config: serde_json::Value) -> Result<Box<Self>, IndexerError> {
         let opts: PdfLoaderOptions = serde_json::from_value(config)
-            .expect("PdfLoader options did not match schema!");
+            .map_err(|e| IndexerError::loader_config("PdfLoader", e))?;
 
-        Box::new(PdfLoader { options: Arc::new(opts) })
+        Ok(Box::new(PdfLoader { options: Arc::new(opts) }))
     }
 
     fn get_pdf_loader_result(&self, absolute_path: &Path, link: String) -> Box<dyn LoaderResult + Send> {
@@ -54,24 +55,24 @@ impl Loader for PdfLoader {
         &'a self,
         absolute_path: &Path,
         relative_path: &Path,
-    ) -> Option<LoaderResultIterator<'a>> {
+    ) -> Result<Option<LoaderResultIterator<'a>>, IndexerError> {
         if let Some(extension) = relative_path.extension() {
             if extension == "pdf" {
                 let link = relative_path.to_slash();
                 if link.is_none() {
                     error!("Unable to index {} containing non-unicode characters", relative_path.to_slash_lossy());
-                    return None;
+                    return Ok(None);
                 }
 
                 let link = unsafe { link.unwrap_unchecked().into_owned() };
 
-                return Some(Box::new(std::iter::once(
+                return Ok(Some(Box::new(std::iter::once(
                     self.get_pdf_loader_result(absolute_path, link),
-                )));
+                ))));
             }
         }
 
-        None
+        Ok(None)
     }
 
     fn get_name(&self) -> String {
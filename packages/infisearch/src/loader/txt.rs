@@ -5,6 +5,7 @@ use log::error;
 use path_slash::PathExt;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
+use crate::error::IndexerError;
 use crate::field_info::RELATIVE_FP_FIELD;
 use crate::loader::BasicLoaderResult;
 use crate::loader::Loader;
@@ -22,11 +23,11 @@ pub struct TxtLoader {
 }
 
 impl TxtLoader {
-    pub fn get_new_txt_loader(config: serde_json::Value) -> Box<Self> {
+    pub fn get_new_txt_loader(config: serde_json::Value) -> Result<Box<Self>, IndexerError> {
         let json_loader_options: TxtLoaderOptions = serde_json::from_value(config)
-            .expect("TxtLoader options did not match schema!");
+            .map_err(|e| IndexerError::loader_config("TxtLoader", e))?;
 
-        Box::new(TxtLoader { options: json_loader_options })
+        Ok(Box::new(TxtLoader { options: json_loader_options }))
     }
 
     fn get_txt_loader_result(&self, text: String, link: String, absolute_path: PathBuf) -> Box<dyn LoaderResult + Send> {
@@ -51,26 +52,26 @@ impl Loader for TxtLoader {
         &'a self,
         absolute_path: &Path,
         relative_path: &Path,
-    ) -> Option<LoaderResultIterator<'a>> {
+    ) -> Result<Option<LoaderResultIterator<'a>>, IndexerError> {
         if let Some(extension) = relative_path.extension() {
             if extension == "txt" {
                 let absolute_path_as_buf = PathBuf::from(absolute_path);
                 let text = std::fs::read_to_string(absolute_path)
-                    .unwrap_or_else(|_| panic!("Failed to read .txt file {}", absolute_path.to_string_lossy().into_owned()));
+                    .map_err(|e| IndexerError::io(absolute_path, e))?;
                 let link = relative_path.to_slash();
                 if link.is_none() {
                     error!("Unable to index {} containing non-unicode characters", relative_path.to_slash_lossy());
-                    return None;
+                    return Ok(None);
                 }
 
                 let link = unsafe { link.unwrap_unchecked().into_owned() };
-                return Some(Box::new(std::iter::once(
+                return Ok(Some(Box::new(std::iter::once(
                     self.get_txt_loader_result(text, link, absolute_path_as_buf),
-                )));
+                ))));
             }
         }
 
-        None
+        Ok(None)
     }
 
     fn get_name(&self) -> String {
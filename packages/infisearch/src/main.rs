@@ -4,12 +4,19 @@ use std::panic;
 use std::path::Path;
 use std::path::PathBuf;
 use std::process;
+use std::time::Duration;
 
 use infisearch::SOURCE_CONFIG_FILE;
-use infisearch::indexer::Indexer;
+use infisearch::commands;
+use infisearch::commands::inspect::Inspection;
+use infisearch::compactor;
+use infisearch::error::IndexerError;
+use infisearch::indexer::{parse_json_document, Indexer};
 use infisearch::indexer::input_config::InfiConfig;
+use infisearch::merger;
 use infisearch::assets;
 use infisearch::i_debug;
+use infisearch::watch::{self, SourceWatcher};
 
 use log::LevelFilter;
 use log::{info, error};
@@ -19,16 +26,18 @@ use log4rs::config::Appender;
 use log4rs::config::Logger;
 use log4rs::config::Root;
 use path_absolutize::Absolutize;
+use structopt::clap::{self, AppSettings};
 use structopt::StructOpt;
 use walkdir::WalkDir;
 
 #[derive(StructOpt, Debug)]
-#[structopt()]
+#[structopt(setting = AppSettings::SubcommandsNegateReqs, setting = AppSettings::ArgsNegateSubcommands)]
 struct CliArgs {
+    // Required when indexing, i.e. when no subcommand is given
     #[structopt(parse(from_os_str))]
-    source_folder_path: PathBuf,
+    source_folder_path: Option<PathBuf>,
     #[structopt(parse(from_os_str))]
-    output_folder_path: PathBuf,
+    output_folder_path: Option<PathBuf>,
     #[structopt(
         short,
         long,
@@ -39,6 +48,12 @@ struct CliArgs {
     config_file_path: Option<PathBuf>,
     #[structopt(long, help = "Allows you to input your indexer configuration via stdin in json format. The entire json should be serialized in one line. Intended for programmatic use.")]
     config_stdin: bool,
+    #[structopt(
+        long,
+        conflicts_with_all = &["config-stdin", "watch"],
+        help = "Also indexes newline-delimited json documents from stdin, in the form {\"id\": \"...\", \"fields\": {\"title\": \"...\", \"body\": \"...\"}}"
+    )]
+    docs_stdin: bool,
     #[structopt(
         short,
         long,
@@ -50,6 +65,17 @@ struct CliArgs {
         help = "Prefer incremental indexing using content hashes. This flag is required even when running a full (re)index, if intending to use incremental indexing runs later"
     )]
     incremental_content_hash: bool,
+    #[structopt(
+        long,
+        help = "Purges documents deleted or updated by incremental indexing runs after indexing, renumbering document ids"
+    )]
+    compact: bool,
+    #[structopt(
+        short,
+        long,
+        help = "Keeps running after indexing, watching the source folder for changes and reindexing incrementally"
+    )]
+    watch: bool,
     #[structopt(long, hidden = true)]
     perf: bool,
     #[structopt(
@@ -58,6 +84,108 @@ struct CliArgs {
         default_value = "info"
     )]
     log_level: String,
+    #[structopt(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(StructOpt, Debug)]
+enum Command {
+    /// Runs a query against a built index, printing the ranked results and parsed query
+    Query {
+        #[structopt(parse(from_os_str), help = "Output folder of a previous indexing run")]
+        output_folder_path: PathBuf,
+        query: String,
+        #[structopt(short, long, default_value = "10", help = "Number of results to print")]
+        num_results: usize,
+        #[structopt(
+            long,
+            default_value = "100",
+            help = "Number of characters to truncate stored field texts and snippets to. 0 prints them in full"
+        )]
+        snippet_len: usize,
+        #[structopt(long, help = "Prints the results in json format")]
+        json: bool,
+        #[structopt(
+            long = "federate",
+            parse(from_os_str),
+            help = "Output folder of another index to search together with the first. May be repeated"
+        )]
+        federated_folder_paths: Vec<PathBuf>,
+    },
+    /// Serves the output folder's files, and json search and completion endpoints at /search and /complete
+    Serve {
+        #[structopt(parse(from_os_str), help = "Output folder of a previous indexing run")]
+        output_folder_path: PathBuf,
+        #[structopt(short, long, default_value = "127.0.0.1:8080", help = "Address to listen on")]
+        address: String,
+    },
+    /// Reports statistics of a built index, such as file size distributions, for tuning the indexer's options
+    Stats {
+        #[structopt(parse(from_os_str), help = "Output folder of a previous indexing run")]
+        output_folder_path: PathBuf,
+        #[structopt(long, default_value = "10", help = "Number of terms with the highest document frequencies to report")]
+        top_terms: usize,
+        #[structopt(long, help = "Prints the statistics in json format")]
+        json: bool,
+    },
+    /// Decodes the binary index files of a built index, for debugging unexpected results
+    Inspect {
+        #[structopt(parse(from_os_str), help = "Output folder of a previous indexing run")]
+        output_folder_path: PathBuf,
+        #[structopt(long, help = "Prints the output in json format")]
+        json: bool,
+        #[structopt(subcommand)]
+        inspect_command: InspectCommand,
+    },
+    /// Purges documents deleted or updated by incremental indexing runs from a built index, renumbering document ids
+    Compact {
+        #[structopt(parse(from_os_str), help = "Output folder of a previous indexing run")]
+        output_folder_path: PathBuf,
+    },
+    /// Merges indexes built separately with the same fields and language configuration into one
+    Merge {
+        #[structopt(parse(from_os_str), help = "Output folder of the merged index, whose contents are removed first")]
+        output_folder_path: PathBuf,
+        #[structopt(parse(from_os_str), min_values = 2, required = true, help = "Output folders of the indexing runs to merge")]
+        index_folder_paths: Vec<PathBuf>,
+    },
+    /// Checks a built index for inconsistencies between its files, exiting with an error if any are found
+    Verify {
+        #[structopt(parse(from_os_str), help = "Output folder of a previous indexing run")]
+        output_folder_path: PathBuf,
+    },
+}
+
+#[derive(StructOpt, Debug)]
+enum InspectCommand {
+    /// Lists dictionary terms with their document frequencies and postings list locations
+    Terms {
+        #[structopt(short, long, default_value = "", help = "Only lists terms starting with this prefix")]
+        prefix: String,
+        #[structopt(short, long, default_value = "100", help = "Number of terms to list")]
+        limit: usize,
+    },
+    /// Decodes the postings list of a dictionary term into document ids, term frequencies and positions
+    Postings {
+        term: String,
+    },
+    /// Prints a document's stored fields, enum and i64 values, and field length factors
+    Doc {
+        doc_id: u32,
+    },
+    /// Lists the document ids deleted or updated by incremental indexing runs
+    Invalidated,
+}
+
+impl From<InspectCommand> for Inspection {
+    fn from(inspect_command: InspectCommand) -> Self {
+        match inspect_command {
+            InspectCommand::Terms { prefix, limit } => Inspection::Terms { prefix, limit },
+            InspectCommand::Postings { term } => Inspection::Postings { term },
+            InspectCommand::Doc { doc_id } => Inspection::Doc { doc_id },
+            InspectCommand::Invalidated => Inspection::Invalidated,
+        }
+    }
 }
 
 fn get_relative_or_absolute_path(from_path: &Path, path: &Path) -> PathBuf {
@@ -114,9 +242,23 @@ fn initialize_logger(log_level: &str) {
     log4rs::init_config(log_config).expect("log4rs initialisation should not fail");
 }
 
-fn initialise_config(config_file_path: PathBuf, args: &CliArgs) -> Option<InfiConfig> {
+fn initialise_config(config_file_path: &Path, args: &CliArgs) -> Option<InfiConfig> {
     let config: InfiConfig = if config_file_path.exists() && config_file_path.is_file() {
-        InfiConfig::new(std::fs::read_to_string(&config_file_path).unwrap())
+        let raw_config = match std::fs::read_to_string(config_file_path) {
+            Ok(raw_config) => raw_config,
+            Err(e) => {
+                error!("Failed to read configuration file {}. {}", config_file_path.to_str().unwrap(), e);
+                return None;
+            }
+        };
+
+        match InfiConfig::new(raw_config) {
+            Ok(config) => config,
+            Err(e) => {
+                error!("{}", e);
+                return None;
+            }
+        }
     } else if args.config_file_path.is_some() {
         error!("Specified configuration file {} not found!", config_file_path.to_str().unwrap());
         return None;
@@ -126,17 +268,124 @@ fn initialise_config(config_file_path: PathBuf, args: &CliArgs) -> Option<InfiCo
     Some(config)
 }
 
+fn run_command(command: Command) {
+    match command {
+        Command::Query { output_folder_path, query, num_results, snippet_len, json, federated_folder_paths } => {
+            let query_results = if federated_folder_paths.is_empty() {
+                commands::query::get_query_results(&output_folder_path, &query, num_results, snippet_len)
+            } else {
+                let mut output_folder_paths = vec![output_folder_path.clone()];
+                output_folder_paths.extend(federated_folder_paths);
+                commands::query::get_federated_query_results(&output_folder_paths, &query, num_results, snippet_len)
+            };
+            match query_results {
+                Ok(query_results) => {
+                    if json {
+                        println!("{}", query_results);
+                    } else {
+                        commands::query::print_query_results(&query_results);
+                    }
+                }
+                Err(e) => {
+                    error!("Failed to query the index at {}. {}", output_folder_path.to_string_lossy(), e);
+                    process::exit(1);
+                }
+            }
+        }
+        Command::Serve { output_folder_path, address } => {
+            if let Err(e) = commands::serve::serve(&output_folder_path, &address) {
+                error!("Failed to serve the index at {}. {}", output_folder_path.to_string_lossy(), e);
+                process::exit(1);
+            }
+        }
+        Command::Stats { output_folder_path, top_terms, json } => {
+            match commands::stats::get_index_stats(&output_folder_path, top_terms) {
+                Ok(stats) => {
+                    if json {
+                        println!("{}", stats);
+                    } else {
+                        commands::stats::print_index_stats(&stats);
+                    }
+                }
+                Err(e) => {
+                    error!("Failed to read the index at {}. {}", output_folder_path.to_string_lossy(), e);
+                    process::exit(1);
+                }
+            }
+        }
+        Command::Inspect { output_folder_path, json, inspect_command } => {
+            let inspection = Inspection::from(inspect_command);
+            match commands::inspect::inspect(&output_folder_path, &inspection) {
+                Ok(inspected) => {
+                    if json {
+                        println!("{}", inspected);
+                    } else {
+                        commands::inspect::print_inspection(&inspection, &inspected);
+                    }
+                }
+                Err(e) => {
+                    error!("Failed to inspect the index at {}. {}", output_folder_path.to_string_lossy(), e);
+                    process::exit(1);
+                }
+            }
+        }
+        Command::Compact { output_folder_path } => {
+            if let Err(e) = compactor::compact(&output_folder_path) {
+                error!("Failed to compact the index at {}. {}", output_folder_path.to_string_lossy(), e);
+                process::exit(1);
+            }
+        }
+        Command::Merge { output_folder_path, index_folder_paths } => {
+            if let Err(e) = merger::merge(&index_folder_paths, &output_folder_path) {
+                error!("Failed to merge the indexes into {}. {}", output_folder_path.to_string_lossy(), e);
+                process::exit(1);
+            }
+
+            assets::write_infisearch_assets(&output_folder_path.join("assets"));
+        }
+        Command::Verify { output_folder_path } => {
+            match commands::verify::verify_index(&output_folder_path) {
+                Ok(report) => {
+                    commands::verify::print_verify_report(&report);
+                    if !report.problems.is_empty() {
+                        error!("Found {} problems in the index at {}.", report.problems.len(), output_folder_path.to_string_lossy());
+                        process::exit(1);
+                    }
+                }
+                Err(e) => {
+                    error!("Failed to read the index at {}. {}", output_folder_path.to_string_lossy(), e);
+                    process::exit(1);
+                }
+            }
+        }
+    }
+}
+
 fn main() {
     let args: CliArgs = CliArgs::from_args();
 
+    initialize_logger(&args.log_level);
+
+    let (source_folder_path, output_folder_path) = match args.command {
+        Some(command) => {
+            run_command(command);
+            return;
+        }
+        None => match (&args.source_folder_path, &args.output_folder_path) {
+            (Some(source_folder_path), Some(output_folder_path)) => (source_folder_path, output_folder_path),
+            _ => clap::Error::with_description(
+                "The source and output folder paths are required for indexing",
+                clap::ErrorKind::MissingRequiredArgument,
+            ).exit(),
+        },
+    };
+
     let (input_folder_path, output_folder_path, config_file_path) = resolve_folder_paths(
-        &args.source_folder_path,
-        &args.output_folder_path,
+        source_folder_path,
+        output_folder_path,
         args.config_file_path.as_ref(),
     );
 
-    initialize_logger(&args.log_level);
-
     i_debug!(
         "Resolved Paths:\n  Input folder: {}\n  Output folder: {}\n  Config file: {}",
         input_folder_path.to_str().unwrap(),
@@ -144,7 +393,7 @@ fn main() {
         config_file_path.to_str().unwrap(),
     );
 
-    let config = if args.config_stdin {
+    let config_stdin = if args.config_stdin {
         let mut buf = Vec::new();
         for line in io::stdin().lines() {
             if let Ok(line) = line {
@@ -153,12 +402,14 @@ fn main() {
                 panic!("Failed to read config from stdin!");
             }
         }
-        InfiConfig::new(buf.join("\n"))
+        Some(buf.join("\n"))
     } else {
-        match initialise_config(config_file_path, &args) {
-            Some(value) => value,
-            None => return,
-        }
+        None
+    };
+
+    let config = match get_config(&config_file_path, &args, &config_stdin) {
+        Some(value) => value,
+        None => return,
     };
 
     let orig_hook = panic::take_hook();
@@ -169,19 +420,50 @@ fn main() {
         process::exit(1);
     }));
 
+    if let Err(e) = run_indexer(&input_folder_path, &output_folder_path, config, &args, args.incremental) {
+        error!("{}", e);
+        process::exit(1);
+    }
+
+    if args.watch {
+        watch(&input_folder_path, &output_folder_path, &config_file_path, &args, &config_stdin);
+    }
+}
+
+fn get_config(config_file_path: &Path, args: &CliArgs, config_stdin: &Option<String>) -> Option<InfiConfig> {
+    if let Some(config_stdin) = config_stdin {
+        match InfiConfig::new(config_stdin.clone()) {
+            Ok(config) => Some(config),
+            Err(e) => {
+                error!("{}", e);
+                None
+            }
+        }
+    } else {
+        initialise_config(config_file_path, args)
+    }
+}
+
+fn run_indexer(
+    input_folder_path: &Path,
+    output_folder_path: &Path,
+    config: InfiConfig,
+    args: &CliArgs,
+    is_incremental: bool,
+) -> Result<(), IndexerError> {
     let mut indexer = Indexer::new(
-        &input_folder_path,
-        &output_folder_path,
+        input_folder_path,
+        output_folder_path,
         config,
-        args.incremental,
+        is_incremental,
         args.incremental_content_hash,
         args.preserve_output_folder,
         args.perf,
-    );
+    )?;
 
     info!("Finding files to index.");
 
-    for entry in WalkDir::new(input_folder_path.clone()) {
+    for entry in WalkDir::new(input_folder_path) {
         match entry {
             Ok(dir_entry) => {
                 if !dir_entry.file_type().is_file() {
@@ -189,9 +471,11 @@ fn main() {
                 }
 
                 let path = dir_entry.path();
-                let relative_path = path.strip_prefix(&input_folder_path).unwrap();
+                let relative_path = path.strip_prefix(input_folder_path).unwrap();
 
-                indexer.index_file(path, relative_path);
+                if let Err(e) = indexer.index_file(path, relative_path) {
+                    error!("Skipping file. {}", e);
+                }
             }
             Err(e) => {
                 error!("Error processing entry. {}", e)
@@ -199,11 +483,102 @@ fn main() {
         }
     }
 
+    if args.docs_stdin {
+        index_stdin_documents(&mut indexer);
+    }
+
     info!("All documents indexed, merging results.");
 
-    let total_documents = indexer.finish_writing_docs();
+    let total_documents = indexer.finish_writing_docs()?;
+
+    if args.compact {
+        compactor::compact(output_folder_path)?;
+    }
 
     assets::write_infisearch_assets(&output_folder_path.join("assets"));
 
     info!("{} documents indexed.", total_documents);
+
+    Ok(())
+}
+
+fn index_stdin_documents(indexer: &mut Indexer) {
+    info!("Reading documents from stdin.");
+
+    for (line_number, line) in io::stdin().lines().enumerate() {
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => {
+                error!("Failed to read documents from stdin. {}", e);
+                break;
+            }
+        };
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match parse_json_document(&line) {
+            Ok((external_id, zones)) => {
+                if let Err(e) = indexer.add_document(&external_id, zones) {
+                    error!("Skipping document on line {}. {}", line_number + 1, e);
+                }
+            }
+            Err(e) => error!("Skipping document on line {}. {}", line_number + 1, e),
+        }
+    }
+}
+
+/// Reindexes incrementally on every (debounced) burst of changes to the source folder.
+///
+/// Only changes that need reindexing are acted on, see `watch::has_relevant_changes`.
+fn watch(
+    input_folder_path: &Path,
+    output_folder_path: &Path,
+    config_file_path: &Path,
+    args: &CliArgs,
+    config_stdin: &Option<String>,
+) {
+    let watcher = match SourceWatcher::new(
+        input_folder_path,
+        output_folder_path,
+        config_file_path,
+        Duration::from_millis(300),
+    ) {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            error!("Failed to watch {}. {}", input_folder_path.to_string_lossy(), e);
+            process::exit(1);
+        }
+    };
+
+    info!("Watching {} for changes.", input_folder_path.to_string_lossy());
+
+    while let Some(changed_paths) = watcher.next_changes() {
+        let config = match get_config(config_file_path, args, config_stdin) {
+            Some(value) => value,
+            None => continue,
+        };
+
+        if !watch::has_relevant_changes(
+            &changed_paths,
+            input_folder_path,
+            output_folder_path,
+            config_file_path,
+            &config.indexing_config,
+        ) {
+            continue;
+        }
+
+        for path in changed_paths.iter() {
+            i_debug!("Changed: {}", path.to_string_lossy());
+        }
+        info!("Changes detected, reindexing.");
+
+        if let Err(e) = run_indexer(input_folder_path, output_folder_path, config, args, true) {
+            error!("{}", e);
+        }
+
+        info!("Watching {} for changes.", input_folder_path.to_string_lossy());
+    }
 }
@@ -0,0 +1,639 @@
+use std::cmp::Reverse;
+use std::collections::hash_map::Entry;
+use std::collections::BinaryHeap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use log::info;
+use rustc_hash::FxHashMap;
+use serde_json::Value;
+
+use infisearch_common::{bitmap, FILE_EXT, METADATA_FILE};
+use infisearch_common::dictionary::{Dictionary, TermInfo};
+use infisearch_common::metadata::{EnumMax, MetadataReader, FORMAT_VER};
+use infisearch_common::utils::varint::decode_var_int;
+use infisearch_search::native::OutputConfig;
+
+use crate::completions_writer;
+use crate::dictionary_writer::DictWriter;
+use crate::doc_info::DocInfos;
+use crate::error::IndexerError;
+use crate::field_info::{FieldInfoOutput, FieldInfos};
+use crate::incremental_info::IncrementalIndexInfo;
+use crate::indexer::{self, input_config::InfiConfig, input_config::InfiIndexingConfig, output_config::InfiOutputConfig};
+use crate::spimi_reader::common::{self, PlWriter, TermDocsForMerge};
+use crate::spimi_writer::fields;
+use crate::utils::{fs_utils, varint};
+use crate::{INFISEARCH_VER, OLD_SOURCE_CONFIG, OUTPUT_CONFIG_FILE};
+
+fn read_to_string(path: &Path) -> Result<String, IndexerError> {
+    fs::read_to_string(path).map_err(|e| IndexerError::io(path, e))
+}
+
+/// A previously built index, which is read from to write a compacted or merged index.
+pub(crate) struct SourceIndex {
+    pub(crate) config: InfiConfig,
+    pub(crate) output_config: InfiOutputConfig,
+    search_output_config: OutputConfig,
+    metadata_rdr: MetadataReader,
+    pub(crate) output_folder_path_inner: PathBuf,
+    /// Maps each doc id of this index to its doc id in the new index, `None` if it was invalidated
+    pub(crate) new_doc_ids: Vec<Option<u32>>,
+    /// Number of documents that were not invalidated
+    pub(crate) num_docs: u32,
+}
+
+impl SourceIndex {
+    /// Reads the index in the output folder, numbering its valid documents in the new index
+    /// from `first_doc_id` onwards, alongside its incremental indexing info.
+    pub(crate) fn read(
+        output_folder_path: &Path,
+        first_doc_id: u32,
+    ) -> Result<(SourceIndex, IncrementalIndexInfo), IndexerError> {
+        let config = InfiConfig::new(read_to_string(&output_folder_path.join(OLD_SOURCE_CONFIG))?)?;
+
+        let output_config_str = read_to_string(&output_folder_path.join(OUTPUT_CONFIG_FILE))?;
+        let output_config_err = |e: serde_json::Error| IndexerError::IncompatibleIncremental(
+            format!("Output config deserialization failed! {}", e),
+        );
+        let output_config: InfiOutputConfig = serde_json::from_str(&output_config_str).map_err(output_config_err)?;
+        let search_output_config: OutputConfig = serde_json::from_str(&output_config_str).map_err(output_config_err)?;
+        if output_config.ver != INFISEARCH_VER {
+            return Err(IndexerError::IncompatibleIncremental("InfiSearch version changed!".to_owned()));
+        }
+        if output_config.format_ver != FORMAT_VER {
+            return Err(IndexerError::IncompatibleIncremental("Index format changed!".to_owned()));
+        }
+
+        let mut incremental_info = IncrementalIndexInfo::read_from_output_folder(output_folder_path)?;
+
+        let output_folder_path_inner = output_folder_path.join(&output_config.index_ver);
+        let metadata_path = output_folder_path_inner.join(METADATA_FILE);
+        let metadata_rdr = MetadataReader::new(fs::read(&metadata_path).map_err(|e| IndexerError::io(&metadata_path, e))?);
+        metadata_rdr.get_invalidation_vec(&mut incremental_info.invalidation_vector);
+
+        let mut num_docs = 0;
+        let new_doc_ids: Vec<Option<u32>> = (0..output_config.last_doc_id)
+            .map(|doc_id| {
+                if bitmap::check(&incremental_info.invalidation_vector, doc_id as usize) {
+                    None
+                } else {
+                    num_docs += 1;
+                    Some(first_doc_id + num_docs - 1)
+                }
+            })
+            .collect();
+
+        Ok((
+            SourceIndex {
+                config,
+                output_config,
+                search_output_config,
+                metadata_rdr,
+                output_folder_path_inner,
+                new_doc_ids,
+                num_docs,
+            },
+            incremental_info,
+        ))
+    }
+
+    /// Reads the document infos of the valid documents, renumbered to their new doc ids
+    pub(crate) fn read_doc_infos(&mut self, field_infos: &Arc<FieldInfos>) -> DocInfos {
+        let mut doc_infos = DocInfos::init_doc_infos(field_infos, Some(&mut self.metadata_rdr));
+        doc_infos.compact(&self.new_doc_ids, field_infos);
+        doc_infos
+    }
+
+    fn read_pl_vec<'a>(
+        &self,
+        pl_vecs: &'a mut FxHashMap<u32, Vec<u8>>,
+        pl_name: u32,
+    ) -> Result<&'a [u8], IndexerError> {
+        // Terms added by incremental indexing runs are appended to new postings lists,
+        // so these are read in whole as they are needed and kept
+        Ok(match pl_vecs.entry(pl_name) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let pl_path = self.output_folder_path_inner
+                    .join(format!("pl_{}", pl_name / self.config.indexing_config.num_pls_per_dir))
+                    .join(format!("pl_{}.{}", pl_name, FILE_EXT));
+                entry.insert(fs::read(&pl_path).map_err(|e| IndexerError::io(&pl_path, e))?)
+            }
+        })
+    }
+}
+
+/// Copies the postings of a term's valid documents from its old postings list into `term_docs`,
+/// renumbering their doc ids, and returns its new document frequency.
+fn copy_postings(
+    pl_vec: &[u8],
+    term_info: &TermInfo,
+    new_doc_ids: &[Option<u32>],
+    with_positions: bool,
+    term_docs: &mut TermDocsForMerge,
+) -> u32 {
+    term_docs.combined_var_ints.clear();
+    term_docs.doc_freq = 0;
+
+    let mut pl_vec_pos = term_info.postings_file_offset as usize;
+    let mut prev_doc_id = 0;
+    for _i in 0..term_info.doc_freq {
+        prev_doc_id += decode_var_int(pl_vec, &mut pl_vec_pos);
+
+        let start = pl_vec_pos;
+        common::skip_doc_fields(pl_vec, &mut pl_vec_pos, with_positions);
+
+        if let Some(new_doc_id) = new_doc_ids[prev_doc_id as usize] {
+            // The first doc id is written separately, see write_new_term_postings
+            if term_docs.doc_freq == 0 {
+                term_docs.first_doc_id = new_doc_id;
+            } else {
+                varint::get_var_int_vec(new_doc_id - term_docs.last_doc_id, &mut term_docs.combined_var_ints);
+            }
+
+            // The fields' term frequencies and positions do not depend on the doc id
+            term_docs.combined_var_ints.extend_from_slice(&pl_vec[start..pl_vec_pos]);
+            term_docs.last_doc_id = new_doc_id;
+            term_docs.doc_freq += 1;
+        }
+    }
+
+    term_docs.doc_freq
+}
+
+/// Rewrites the postings lists of the valid documents of all sources in dictionary order, as a full index would,
+/// dropping terms that only occurred in invalidated documents.
+pub(crate) fn write_pl_files(
+    sources: &[SourceIndex],
+    output_folder_path_inner: &Path,
+    indexing_config: &InfiIndexingConfig,
+    incremental_info: &mut IncrementalIndexInfo,
+) -> Result<DictWriter, IndexerError> {
+    let dictionaries: Vec<Dictionary> = sources.iter()
+        .map(|source| source.metadata_rdr.setup_dictionary())
+        .collect();
+
+    /*
+    N-way merge of the sources' dictionaries according to lexicographical order.
+    Ties are broken by the source's index, so a term's postings are combined
+    in the order of the sources, and hence their (offset) doc ids.
+    */
+    let mut term_iters: Vec<_> = dictionaries.iter().map(|dictionary| dictionary.term_infos.iter()).collect();
+    let mut curr_term_infos: Vec<&TermInfo> = Vec::with_capacity(sources.len());
+    let mut terms_heap: BinaryHeap<Reverse<(&str, usize)>> = BinaryHeap::with_capacity(sources.len());
+    for (idx, term_iter) in term_iters.iter_mut().enumerate() {
+        if let Some((term, term_info)) = term_iter.next() {
+            terms_heap.push(Reverse((term.as_str(), idx)));
+            curr_term_infos.push(*term_info);
+        } else {
+            curr_term_infos.push(&TermInfo { doc_freq: 0, postings_file_name: 0, postings_file_offset: 0 });
+        }
+    }
+
+    let mut dict_writer = DictWriter::new();
+    let mut pl_writer = PlWriter::new(output_folder_path_inner, 0, indexing_config.num_pls_per_dir);
+    incremental_info.pl_names_to_cache.clear();
+
+    let mut pl_vecs: Vec<FxHashMap<u32, Vec<u8>>> = vec![FxHashMap::default(); sources.len()];
+
+    let mut curr_combined_term_docs: Vec<TermDocsForMerge> = Vec::with_capacity(sources.len());
+    let mut varint_buf: [u8; 16] = [0; 16];
+    let mut prev_term = "";
+
+    while let Some(&Reverse((curr_term, _))) = terms_heap.peek() {
+        let mut num_term_docs = 0;
+        let mut doc_freq = 0;
+
+        while let Some(&Reverse((term, idx))) = terms_heap.peek() {
+            if term != curr_term {
+                break;
+            }
+            terms_heap.pop();
+
+            let source = &sources[idx];
+            let pl_vec = source.read_pl_vec(&mut pl_vecs[idx], curr_term_infos[idx].postings_file_name)?;
+
+            if curr_combined_term_docs.len() == num_term_docs {
+                curr_combined_term_docs.push(TermDocsForMerge::default());
+            }
+            let term_doc_freq = copy_postings(
+                pl_vec,
+                curr_term_infos[idx],
+                &source.new_doc_ids,
+                indexing_config.with_positions,
+                &mut curr_combined_term_docs[num_term_docs],
+            );
+            if term_doc_freq != 0 {
+                num_term_docs += 1;
+                doc_freq += term_doc_freq;
+            }
+
+            if let Some((next_term, next_term_info)) = term_iters[idx].next() {
+                terms_heap.push(Reverse((next_term.as_str(), idx)));
+                curr_term_infos[idx] = *next_term_info;
+            }
+        }
+
+        if doc_freq == 0 {
+            continue;
+        }
+
+        let start_pl_offset = common::write_new_term_postings(
+            &mut curr_combined_term_docs[..num_term_docs],
+            &mut varint_buf,
+            Some(&mut dict_writer),
+            &mut pl_writer,
+            &mut incremental_info.pl_names_to_cache,
+            indexing_config,
+            output_folder_path_inner,
+        );
+
+        let (prefix_len, remaining_len) = dict_writer.write_term(prev_term, curr_term);
+        dict_writer.write_dict_table_entry(
+            doc_freq, start_pl_offset, &mut pl_writer.prev_pl_offset,
+            prefix_len, remaining_len,
+        );
+
+        prev_term = curr_term;
+    }
+
+    pl_writer.flush(indexing_config.pl_cache_threshold, &mut incremental_info.pl_names_to_cache);
+
+    incremental_info.last_pl_number = if pl_writer.pl_offset != 0 || pl_writer.pl == 0 {
+        pl_writer.pl
+    } else {
+        pl_writer.pl - 1
+    };
+
+    Ok(dict_writer)
+}
+
+/// Rewrites the field stores with the valid documents of all sources, block by block as a full index would.
+pub(crate) fn write_field_stores(
+    sources: &[SourceIndex],
+    field_infos: &Arc<FieldInfos>,
+    num_docs_per_block: u32,
+) -> Result<(), IndexerError> {
+    let mut block_field_texts: Vec<Vec<u8>> = Vec::with_capacity(num_docs_per_block as usize);
+    let mut doc_id_counter = 0;
+
+    for source in sources {
+        let mut old_field_store: (PathBuf, Vec<Value>) = (PathBuf::new(), Vec::new());
+
+        for (old_doc_id, new_doc_id) in source.new_doc_ids.iter().enumerate() {
+            if new_doc_id.is_none() {
+                continue;
+            }
+
+            let (field_store_path, idx) = source.search_output_config.get_field_store_location(
+                &source.output_folder_path_inner, old_doc_id as u32,
+            );
+            if field_store_path != old_field_store.0 {
+                let field_store = fs::read(&field_store_path).map_err(|e| IndexerError::io(&field_store_path, e))?;
+                let docs = serde_json::from_slice(&field_store).map_err(|e| IndexerError::IncompatibleIncremental(
+                    format!("Field store {} is invalid! {}", field_store_path.to_string_lossy(), e),
+                ))?;
+                old_field_store = (field_store_path, docs);
+            }
+
+            let doc = old_field_store.1.get(idx).ok_or_else(|| IndexerError::IncompatibleIncremental(
+                format!("Field store {} is missing document {}", old_field_store.0.to_string_lossy(), old_doc_id),
+            ))?;
+            block_field_texts.push(serde_json::to_vec(doc).expect("Failed to serialize stored fields"));
+            doc_id_counter += 1;
+
+            if doc_id_counter % num_docs_per_block == 0 {
+                fields::store_fields(
+                    false,
+                    0,
+                    field_infos,
+                    doc_id_counter,
+                    num_docs_per_block,
+                    doc_id_counter / num_docs_per_block - 1,
+                    std::mem::take(&mut block_field_texts),
+                );
+            }
+        }
+    }
+
+    if !block_field_texts.is_empty() {
+        fields::store_fields(
+            false,
+            0,
+            field_infos,
+            doc_id_counter,
+            block_field_texts.len() as u32,
+            doc_id_counter / num_docs_per_block,
+            block_field_texts,
+        );
+    }
+
+    Ok(())
+}
+
+/// Adds the enum values of another index's fields that `field_infos` does not have yet,
+/// returning the mapping of the other index's enum value ids to those of `field_infos`, by enum id.
+fn merge_enum_values(field_infos: &mut [FieldInfoOutput], other_field_infos: &[FieldInfoOutput]) -> Vec<Vec<EnumMax>> {
+    let mut enum_ev_id_maps: Vec<Vec<EnumMax>> = Vec::new();
+
+    for field_info in field_infos.iter_mut() {
+        let FieldInfoOutput { name, enum_info, .. } = field_info;
+        let enum_info = if let Some(enum_info) = enum_info {
+            enum_info
+        } else {
+            continue;
+        };
+
+        let other_enum_values = other_field_infos.iter()
+            .find(|other_field_info| other_field_info.name == *name)
+            .and_then(|other_field_info| other_field_info.enum_info.as_ref())
+            .map(|other_enum_info| other_enum_info.enum_values.as_slice())
+            .unwrap_or_default();
+
+        // 0 is the default enum value id, for documents without a value
+        let mut ev_id_map: Vec<EnumMax> = vec![0];
+        for enum_value in other_enum_values {
+            let ev_id = if let Some(idx) = enum_info.enum_values.iter().position(|v| v == enum_value) {
+                idx + 1
+            } else if enum_info.enum_values.len() < EnumMax::MAX as usize {
+                enum_info.enum_values.push(enum_value.to_owned());
+                enum_info.enum_values.len()
+            } else {
+                // Excess values are ignored, which DocInfos::write_enums warns of
+                0
+            };
+            ev_id_map.push(ev_id as EnumMax);
+        }
+
+        if enum_ev_id_maps.len() <= enum_info.enum_id {
+            enum_ev_id_maps.resize(enum_info.enum_id + 1, Vec::new());
+        }
+        enum_ev_id_maps[enum_info.enum_id] = ev_id_map;
+    }
+
+    enum_ev_id_maps
+}
+
+/// Checks that the index was built with the same configuration as the first index,
+/// in the parts that determine the index's format.
+fn check_compatible(first: &SourceIndex, first_path: &Path, source: &SourceIndex, path: &Path) -> Result<(), IndexerError> {
+    for key in ["fields_config", "lang_config"] {
+        if first.config.json_config.get(key) != source.config.json_config.get(key) {
+            return Err(IndexerError::Merge(format!(
+                "{} of {} differs from that of {}",
+                key, path.to_string_lossy(), first_path.to_string_lossy(),
+            )));
+        }
+    }
+
+    if first.config.indexing_config.with_positions != source.config.indexing_config.with_positions {
+        return Err(IndexerError::Merge(format!(
+            "with_positions of {} differs from that of {}",
+            path.to_string_lossy(), first_path.to_string_lossy(),
+        )));
+    }
+
+    Ok(())
+}
+
+/// Merges the indexes in the given output folders, built separately with the same fields and language configuration,
+/// into one index in `output_folder_path`, whose contents are removed first.
+///
+/// Doc ids of each index are offset by the number of documents in the indexes before it,
+/// with documents invalidated by incremental indexing runs purged as in compaction.
+/// Enum values are unioned, in order of the indexes.
+///
+/// Returns the number of documents in the merged index.
+pub fn merge(index_folder_paths: &[PathBuf], output_folder_path: &Path) -> Result<u32, IndexerError> {
+    if index_folder_paths.is_empty() {
+        return Err(IndexerError::Merge("No indexes to merge were specified.".to_owned()));
+    }
+
+    // The output folder is cleaned, which should not remove the indexes being merged
+    if let Ok(canonical_output_folder_path) = fs::canonicalize(output_folder_path) {
+        for index_folder_path in index_folder_paths {
+            let canonical_index_folder_path = fs::canonicalize(index_folder_path)
+                .map_err(|e| IndexerError::io(index_folder_path, e))?;
+            if canonical_index_folder_path.starts_with(&canonical_output_folder_path) {
+                return Err(IndexerError::Merge(format!(
+                    "{} is inside the output folder.",
+                    index_folder_path.to_string_lossy(),
+                )));
+            }
+        }
+    }
+
+    // ------------------------------------------------------------
+    // Indexes to merge
+
+    let mut sources: Vec<SourceIndex> = Vec::with_capacity(index_folder_paths.len());
+    let mut incremental_info: Option<IncrementalIndexInfo> = None;
+    let mut num_docs = 0;
+    for index_folder_path in index_folder_paths {
+        let (source, mut source_incremental_info) = SourceIndex::read(index_folder_path, num_docs)
+            .map_err(|e| match e {
+                IndexerError::IncompatibleIncremental(message) => IndexerError::Merge(format!(
+                    "{} is not a valid index. {}",
+                    index_folder_path.to_string_lossy(), message,
+                )),
+                e => e,
+            })?;
+
+        if let Some(first) = sources.first() {
+            check_compatible(first, &index_folder_paths[0], &source, index_folder_path)?;
+        }
+
+        source_incremental_info.compact(&source.new_doc_ids);
+        if let Some(incremental_info) = &mut incremental_info {
+            incremental_info.merge(source_incremental_info);
+        } else {
+            incremental_info = Some(source_incremental_info);
+        }
+
+        num_docs += source.num_docs;
+        sources.push(source);
+    }
+    let mut incremental_info = incremental_info.unwrap();
+    // ------------------------------------------------------------
+
+    fs::create_dir_all(output_folder_path).map_err(|e| IndexerError::io(output_folder_path, e))?;
+    fs_utils::clean_dir(output_folder_path);
+
+    let index_ver = indexer::get_index_ver();
+    let output_folder_path_inner = output_folder_path.join(&index_ver);
+    fs::create_dir(&output_folder_path_inner).map_err(|e| IndexerError::io(&output_folder_path_inner, e))?;
+
+    // ------------------------------------------------------------
+    // Metadata
+
+    let (first, rest) = sources.split_first_mut().unwrap();
+
+    let enum_ev_id_maps: Vec<Vec<Vec<EnumMax>>> = rest.iter()
+        .map(|source| merge_enum_values(&mut first.output_config.field_infos, &source.output_config.field_infos))
+        .collect();
+
+    let field_infos = first.config.fields_config.get_field_infos(&output_folder_path_inner, Some(&first.output_config))?;
+
+    let mut doc_infos = first.read_doc_infos(&field_infos);
+    for (source, enum_ev_id_maps) in rest.iter_mut().zip(enum_ev_id_maps.iter()) {
+        doc_infos.merge(source.read_doc_infos(&field_infos), enum_ev_id_maps);
+    }
+
+    let invalidation_vec_ser = incremental_info.write_invalidation_vec(num_docs);
+    let (doc_infos_ser, mut enums_ev_strs) = doc_infos.finalize_and_flush(num_docs, &field_infos, &mut incremental_info);
+
+    let dict_writer = write_pl_files(
+        &sources,
+        &output_folder_path_inner,
+        &sources[0].config.indexing_config,
+        &mut incremental_info,
+    )?;
+
+    indexer::write_metadata(
+        &output_folder_path_inner,
+        sources[0].config.indexing_config.spelling_index,
+        invalidation_vec_ser,
+        doc_infos_ser,
+        dict_writer,
+        false,
+    )?;
+    // ------------------------------------------------------------
+
+    let old_indexes: Vec<(&Path, &[Option<u32>])> = sources.iter()
+        .map(|source| (source.output_folder_path_inner.as_path(), source.new_doc_ids.as_slice()))
+        .collect();
+    completions_writer::copy_completions(&old_indexes, &output_folder_path_inner);
+
+    write_field_stores(&sources, &field_infos, sources[0].config.indexing_config.num_docs_per_block)?;
+
+    // ------------------------------------------------------------
+    // Configuration files, the first index's configuration is used for the merged index
+
+    incremental_info.write_info_file(output_folder_path)?;
+
+    let old_config_path = output_folder_path.join(OLD_SOURCE_CONFIG);
+    fs::write(
+        &old_config_path,
+        serde_json::to_string_pretty(&sources[0].config.json_config)
+            .expect("Failed to serialize the configuration file"),
+    )
+    .map_err(|e| IndexerError::io(&old_config_path, e))?;
+
+    let output_config = &mut sources[0].output_config;
+    output_config.index_ver = index_ver;
+    output_config.last_doc_id = num_docs;
+    output_config.indexing_config.pl_names_to_cache = incremental_info.pl_names_to_cache.clone();
+    for field_info in output_config.field_infos.iter_mut() {
+        if let Some(enum_info) = &mut field_info.enum_info {
+            enum_info.enum_values = std::mem::take(&mut enums_ev_strs[enum_info.enum_id]);
+        }
+    }
+    output_config.write(output_folder_path)?;
+    // ------------------------------------------------------------
+
+    info!("{} indexes merged, {} documents in total.", sources.len(), num_docs);
+
+    Ok(num_docs)
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use infisearch_search::native::{NativeSearcher, NativeSearcherOptions, QueryOpts};
+    use pretty_assertions::assert_eq;
+
+    use crate::commands::verify::verify_index;
+    use crate::error::IndexerError;
+    use crate::indexer::input_config::InfiConfig;
+    use crate::indexer::Indexer;
+    use crate::worker::miner::{Zone, DEFAULT_ZONE_SEPARATION};
+
+    use super::merge;
+
+    static CONFIG: &str = r#"{
+        "fields_config": { "fields": { "team": { "storage": [{ "type": "enum" }] } } },
+        "indexing_config": { "num_docs_per_block": 2 }
+    }"#;
+
+    fn zone(field_name: &str, field_text: &str) -> Zone {
+        Zone { field_name: field_name.to_owned(), field_text: field_text.to_owned(), separation: DEFAULT_ZONE_SEPARATION }
+    }
+
+    fn index(output_folder_path: &Path, config: &str, is_incremental: bool, docs: &[(&str, &str, &str)]) {
+        // Documents are added directly, so the input folder is unused
+        let mut indexer = Indexer::new(
+            output_folder_path.parent().unwrap(),
+            output_folder_path,
+            InfiConfig::new(config.to_owned()).unwrap(),
+            is_incremental,
+            true,
+            false,
+            false,
+        ).unwrap();
+        for (external_id, text, team) in docs {
+            indexer.add_document(external_id, vec![zone("body", text), zone("team", team)]).unwrap();
+        }
+        indexer.finish_writing_docs().unwrap();
+    }
+
+    fn search(output_folder_path: &Path, query: &str, team: Option<&str>) -> Vec<String> {
+        let mut searcher = NativeSearcher::new(output_folder_path, NativeSearcherOptions::default()).unwrap();
+        let query_opts = QueryOpts {
+            enum_filters: team
+                .map(|team| vec![("team".to_owned(), vec![Some(team.to_owned())])])
+                .unwrap_or_default(),
+            ..QueryOpts::default()
+        };
+        let mut query = searcher.run_query(query, &query_opts).unwrap();
+        let mut relative_fps: Vec<String> = query.get_next_n_results(10)
+            .into_iter()
+            .map(|doc_result| {
+                let fields = searcher.load_fields(doc_result.doc_id).unwrap();
+                fields.into_iter().find(|(field_name, _text)| field_name == "_relative_fp").unwrap().1
+            })
+            .collect();
+        relative_fps.sort();
+        relative_fps
+    }
+
+    #[test]
+    fn test_merge() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let first_path = temp_dir.path().join("first");
+        let second_path = temp_dir.path().join("second");
+        let output_folder_path = temp_dir.path().join("output");
+
+        index(&first_path, CONFIG, false, &[
+            ("a1", "lorem ipsum", "docs"),
+            ("a2", "lorem dolor", "api"),
+            ("a3", "sit amet", "api"),
+        ]);
+        // a2 is deleted, and purged when merging
+        index(&first_path, CONFIG, true, &[("a1", "lorem ipsum", "docs"), ("a3", "sit amet", "api")]);
+        index(&second_path, CONFIG, false, &[("b1", "lorem sit", "cli"), ("b2", "dolor amet", "docs")]);
+
+        assert_eq!(merge(&[first_path.clone(), second_path.clone()], &output_folder_path).unwrap(), 4);
+        assert_eq!(verify_index(&output_folder_path).unwrap().problems, Vec::<String>::new());
+
+        assert_eq!(search(&output_folder_path, "lorem", None), vec!["a1", "b1"]);
+        assert_eq!(search(&output_folder_path, "dolor", None), vec!["b2"]);
+        assert_eq!(search(&output_folder_path, "sit", None), vec!["a3", "b1"]);
+        assert_eq!(search(&output_folder_path, "amet", None), vec!["a3", "b2"]);
+
+        // The second index's enum value ids are remapped to the first's
+        assert_eq!(search(&output_folder_path, "lorem amet", Some("docs")), vec!["a1", "b2"]);
+        assert_eq!(search(&output_folder_path, "lorem amet", Some("cli")), vec!["b1"]);
+        assert_eq!(search(&output_folder_path, "lorem amet", Some("api")), vec!["a3"]);
+
+        // Indexes with different fields cannot be merged
+        let third_path = temp_dir.path().join("third");
+        index(&third_path, r#"{ "indexing_config": { "num_docs_per_block": 2 } }"#, false, &[("c1", "lorem", "")]);
+        assert!(matches!(
+            merge(&[first_path, third_path], &output_folder_path),
+            Err(IndexerError::Merge(_)),
+        ));
+    }
+}
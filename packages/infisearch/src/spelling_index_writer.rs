@@ -0,0 +1,133 @@
+use infisearch_common::dictionary::Dictionary;
+use infisearch_lang_ascii::spelling::edit_distance;
+
+use crate::utils::varint;
+
+/// Builds a BK-tree of the dictionary's terms for sub-linear spelling correction,
+/// decoded by `infisearch_lang_ascii::spelling::bk_tree::BkTree`.
+///
+/// Format, in variable integers:
+/// root term id, then for each term id in dictionary order,
+/// the number of children, then the edit distance and term id of each child.
+pub fn write_spelling_index(dictionary: &Dictionary) -> Vec<u8> {
+    let terms: Vec<(&str, u32)> = dictionary.term_infos.iter()
+        .map(|(term, term_info)| (term.as_str(), term_info.doc_freq))
+        .collect();
+    if terms.is_empty() {
+        return Vec::new();
+    }
+
+    // Insert more frequent terms first, which are more likely to be corrected to
+    let mut insertion_order: Vec<u32> = (0..terms.len() as u32).collect();
+    insertion_order.sort_by_key(|&term_id| std::cmp::Reverse(terms[term_id as usize].1));
+
+    let root = insertion_order[0];
+    let mut children: Vec<Vec<(usize, u32)>> = vec![Vec::new(); terms.len()];
+    let mut cache = [255_usize; 255];
+
+    for &term_id in &insertion_order[1..] {
+        let term = terms[term_id as usize].0;
+        let term_char_count = term.chars().count();
+
+        let mut node = root;
+        loop {
+            let edit_distance = edit_distance::levenshtein(
+                terms[node as usize].0,
+                term,
+                term_char_count,
+                &mut cache,
+            );
+
+            let node_children = &mut children[node as usize];
+            if let Some((_edit_distance, child)) = node_children.iter().find(|(d, _child)| *d == edit_distance) {
+                node = *child;
+            } else {
+                node_children.push((edit_distance, term_id));
+                break;
+            }
+        }
+    }
+
+    let mut output = Vec::with_capacity(terms.len() * 4);
+    varint::get_var_int_vec(root, &mut output);
+    for node_children in children {
+        varint::get_var_int_vec(node_children.len() as u32, &mut output);
+        for (edit_distance, child) in node_children {
+            varint::get_var_int_vec(edit_distance as u32, &mut output);
+            varint::get_var_int_vec(child, &mut output);
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use infisearch_common::dictionary::{Dictionary, TermInfo};
+    use infisearch_lang_ascii::spelling::BestTermCorrector;
+    use pretty_assertions::assert_eq;
+    use smartstring::alias::String;
+
+    use super::write_spelling_index;
+
+    fn get_dictionary(terms: &[(&str, u32)]) -> Dictionary {
+        let mut term_infos = BTreeMap::new();
+        for (term, doc_freq) in terms {
+            let term_info: &'static TermInfo = Box::leak(Box::new(TermInfo {
+                doc_freq: *doc_freq,
+                postings_file_name: 0,
+                postings_file_offset: 0,
+            }));
+            term_infos.insert(String::from(*term), term_info);
+        }
+
+        Dictionary { term_infos, spelling_index: Vec::new() }
+    }
+
+    #[test]
+    fn test_matches_linear_correction() {
+        let terms = [
+            ("lorem", 3), ("ipsum", 5), ("dolor", 2), ("sit", 8), ("amet", 4), ("set", 8), ("sat", 2),
+            ("consectetur", 1), ("adipiscing", 2), ("elit", 6), ("edit", 6), ("exit", 3), ("search", 9),
+            ("searches", 4), ("searched", 4), ("research", 2), ("speling", 1), ("spelling", 3), ("spell", 3),
+            ("tokenizer", 2), ("tokenize", 2), ("token", 7), ("taken", 7), ("ascii", 1), ("asci", 1),
+        ];
+        let misspellings = [
+            "lorme", "ipsun", "dolr", "sot", "amt", "st", "consectetor", "adipisicng", "elti", "eit",
+            "serach", "searchs", "reserch", "speling", "speling", "tokeniser", "toke", "tkn", "asii", "zzzz",
+            "", "s", "searchedd", "spellings",
+        ];
+
+        let linear_dictionary = get_dictionary(&terms);
+        let mut dictionary = get_dictionary(&terms);
+        dictionary.spelling_index = write_spelling_index(&dictionary);
+        assert!(!dictionary.spelling_index.is_empty());
+
+        for misspelling in misspellings {
+            assert_eq!(
+                BestTermCorrector::new().get_best_corrected_term(&dictionary, misspelling),
+                BestTermCorrector::new().get_best_corrected_term(&linear_dictionary, misspelling),
+                "{}",
+                misspelling,
+            );
+        }
+
+        // Ties in edit distance are broken by document frequency, then dictionary order
+        assert_eq!(
+            BestTermCorrector::new().get_best_corrected_term(&dictionary, "sxt"),
+            Some("set".to_owned()),
+        );
+        assert_eq!(
+            BestTermCorrector::new().get_best_corrected_term(&dictionary, "tiken"),
+            Some("taken".to_owned()),
+        );
+    }
+
+    #[test]
+    fn test_empty_dictionary() {
+        let dictionary = get_dictionary(&[]);
+        assert!(write_spelling_index(&dictionary).is_empty());
+    }
+}
@@ -12,6 +12,12 @@ use crossbeam::channel::{Receiver, Sender};
 use dashmap::DashMap;
 
 use infisearch_common::FILE_EXT;
+use infisearch_common::packed_var_int::read_bits_from;
+use infisearch_common::postings_list::{
+    LAST_FIELD_MASK, SHORT_FORM_MASK,
+    MIN_CHUNK_SIZE, CHUNK_SIZE,
+};
+use infisearch_common::utils::varint::decode_var_int;
 
 use self::postings_stream::{PostingsStream, POSTINGS_STREAM_BUFFER_SIZE, POSTINGS_STREAM_INITIAL_READ};
 use self::postings_stream_reader::PostingsStreamReader;
@@ -184,6 +190,56 @@ pub fn write_new_term_postings(
     start_pl_offset
 }
 
+/// Forwards `pl_vec_pos` past the fields of a document in a postings list,
+/// which starts right after the document's doc id gap.
+pub fn skip_doc_fields(pl_vec: &[u8], pl_vec_pos: &mut usize, with_positions: bool) {
+    let mut is_last: u8 = 0;
+    while is_last == 0 {
+        let next_int = pl_vec[*pl_vec_pos];
+        *pl_vec_pos += 1;
+
+        is_last = next_int & LAST_FIELD_MASK;
+
+        let field_tf = if (next_int & SHORT_FORM_MASK) != 0 {
+            (next_int & 0b00000111) as u32
+        } else {
+            decode_var_int(pl_vec, pl_vec_pos)
+        };
+
+        if with_positions {
+            if field_tf >= MIN_CHUNK_SIZE {
+                let mut bit_pos = 0;
+
+                let num_chunks = (field_tf / CHUNK_SIZE)
+                    + if field_tf % CHUNK_SIZE == 0 { 0 } else { 1 };
+
+                let slice_starting_here = &pl_vec[*pl_vec_pos..];
+
+                let mut read = 0;
+                for _chunk in 0..num_chunks {
+                    // Read position length in this chunk
+                    let chunk_len = read_bits_from(&mut bit_pos, 5, slice_starting_here) as usize;
+
+                    for _i in 0..CHUNK_SIZE {
+                        bit_pos += chunk_len;
+
+                        read += 1;
+                        if read == field_tf {
+                            break;
+                        }
+                    }
+                }
+
+                *pl_vec_pos += (bit_pos / 8) + if bit_pos % 8 == 0 { 0 } else { 1 };
+            } else {
+                for _j in 0..field_tf {
+                    decode_var_int(pl_vec, pl_vec_pos);
+                }
+            }
+        }
+    }
+}
+
 pub fn cleanup_blocks(first_block: u32, last_block: u32, output_folder_path_inner: &Path) {
     // Remove temporary spimi files
     for idx in first_block..(last_block + 1) {
@@ -13,11 +13,6 @@ use smartstring::SmartString;
 
 use infisearch_common::{bitmap, FILE_EXT};
 use infisearch_common::dictionary::TermInfo;
-use infisearch_common::packed_var_int::read_bits_from;
-use infisearch_common::postings_list::{
-    LAST_FIELD_MASK, SHORT_FORM_MASK,
-    MIN_CHUNK_SIZE, CHUNK_SIZE,
-};
 use infisearch_common::utils::varint::decode_var_int;
 
 use crate::dictionary_writer::DictWriter;
@@ -71,53 +66,8 @@ impl ExistingPlWriter {
 
             let start = pl_vec_pos;
 
-            let mut is_last: u8 = 0;
-            while is_last == 0 {
-                let next_int = self.pl_vec[pl_vec_pos];
-                pl_vec_pos += 1;
-
-                is_last = next_int & LAST_FIELD_MASK;
-
-                let field_tf = if (next_int & SHORT_FORM_MASK) != 0 {
-                    (next_int & 0b00000111) as u32
-                } else {
-                    decode_var_int(&self.pl_vec, &mut pl_vec_pos)
-                };
-
-                if self.with_positions {
-                    // Not interested in positions here, just decode and forward pos
-
-                    if field_tf >= MIN_CHUNK_SIZE {
-                        let mut bit_pos = 0;
-
-                        let num_chunks = (field_tf / CHUNK_SIZE)
-                            + if field_tf % CHUNK_SIZE == 0 { 0 } else { 1 };
-                            
-                        let slice_starting_here = &self.pl_vec[pl_vec_pos..];
-
-                        let mut read = 0;
-                        for _chunk in 0..num_chunks {
-                            // Read position length in this chunk
-                            let chunk_len = read_bits_from(&mut bit_pos, 5, slice_starting_here) as usize;
-
-                            for _i in 0..CHUNK_SIZE {
-                                bit_pos += chunk_len;
-
-                                read += 1;
-                                if read == field_tf {
-                                    break;
-                                }
-                            }
-                        }
-
-                        pl_vec_pos += (bit_pos / 8) + if bit_pos % 8 == 0 { 0 } else { 1 };
-                    } else {
-                        for _j in 0..field_tf {
-                            decode_var_int(&self.pl_vec, &mut pl_vec_pos);
-                        }
-                    }
-                }
-            }
+            // Not interested in the fields here, just decode and forward pos
+            common::skip_doc_fields(&self.pl_vec, &mut pl_vec_pos, self.with_positions);
 
             if bitmap::check(invalidation_vector, prev_doc_id as usize) {
                 new_term_info.doc_freq -= 1;
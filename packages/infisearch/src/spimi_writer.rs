@@ -14,7 +14,7 @@ use crate::worker::miner::TermDoc;
 use crate::worker::miner::WorkerBlockIndexResults;
 use crate::worker::miner::WorkerMinerDocInfo;
 
-mod fields;
+pub mod fields;
 mod terms;
 
 #[allow(clippy::too_many_arguments)]
@@ -0,0 +1,196 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::Duration;
+
+use log::warn;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::incremental_info::IncrementalIndexInfo;
+use crate::indexer::input_config::InfiIndexingConfig;
+
+/// Watches the source folder (and configuration file) for changes.
+///
+/// Changes within the output folder are ignored, which may be nested inside the source folder.
+pub struct SourceWatcher {
+    _watcher: RecommendedWatcher,
+    rx: Receiver<notify::Result<Event>>,
+    output_folder_path: PathBuf,
+    debounce: Duration,
+}
+
+impl SourceWatcher {
+    pub fn new(
+        input_folder_path: &Path,
+        output_folder_path: &Path,
+        config_file_path: &Path,
+        debounce: Duration,
+    ) -> notify::Result<SourceWatcher> {
+        let (tx, rx) = mpsc::channel();
+
+        let mut watcher = notify::recommended_watcher(tx)?;
+        watcher.watch(input_folder_path, RecursiveMode::Recursive)?;
+
+        // Configuration files specified with -c may live outside the source folder
+        if !config_file_path.starts_with(input_folder_path) && config_file_path.is_file() {
+            watcher.watch(config_file_path, RecursiveMode::NonRecursive)?;
+        }
+
+        Ok(SourceWatcher {
+            _watcher: watcher,
+            rx,
+            output_folder_path: output_folder_path.to_path_buf(),
+            debounce,
+        })
+    }
+
+    /// Blocks until the next burst of changes, returning once no further changes
+    /// are seen for the debounce duration.
+    ///
+    /// Returns the deduplicated changed paths, or `None` if the watcher stopped.
+    pub fn next_changes(&self) -> Option<Vec<PathBuf>> {
+        let mut changed_paths = Vec::new();
+
+        // Wait indefinitely for the first relevant change
+        while changed_paths.is_empty() {
+            let event = self.rx.recv().ok()?;
+            self.add_changed_paths(event, &mut changed_paths);
+        }
+
+        loop {
+            match self.rx.recv_timeout(self.debounce) {
+                Ok(event) => self.add_changed_paths(event, &mut changed_paths),
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return None,
+            }
+        }
+
+        Some(changed_paths)
+    }
+
+    fn add_changed_paths(&self, event: notify::Result<Event>, changed_paths: &mut Vec<PathBuf>) {
+        match event {
+            Ok(event) => {
+                if let EventKind::Access(_) = event.kind {
+                    return;
+                }
+
+                for path in event.paths {
+                    if !path.starts_with(&self.output_folder_path) && !changed_paths.contains(&path) {
+                        changed_paths.push(path);
+                    }
+                }
+            }
+            Err(e) => warn!("Error watching the source folder. {}", e),
+        }
+    }
+}
+
+/// Whether any of the changed paths need reindexing.
+///
+/// Changes to files excluded by the indexing configuration are ignored,
+/// while changes to the configuration file itself are not.
+/// As include patterns only match files, removed paths (e.g. deleted folders)
+/// are checked against the files indexed under them instead.
+pub fn has_relevant_changes(
+    changed_paths: &[PathBuf],
+    input_folder_path: &Path,
+    output_folder_path: &Path,
+    config_file_path: &Path,
+    indexing_config: &InfiIndexingConfig,
+) -> bool {
+    // Read only once some removed path needs it
+    let mut incremental_info: Option<Option<IncrementalIndexInfo>> = None;
+
+    changed_paths.iter().any(|path| {
+        if path == config_file_path {
+            return true;
+        }
+
+        let relative_path = match path.strip_prefix(input_folder_path) {
+            Ok(relative_path) => relative_path,
+            Err(_) => return false,
+        };
+        if !indexing_config.is_excluded(relative_path) {
+            return true;
+        }
+
+        !path.exists()
+            && incremental_info
+                .get_or_insert_with(|| IncrementalIndexInfo::read_from_output_folder(output_folder_path).ok())
+                .as_ref()
+                .is_some_and(|incremental_info| incremental_info.has_files_under(relative_path))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::time::Duration;
+
+    use crate::indexer::input_config::InfiConfig;
+    use crate::indexer::Indexer;
+
+    use super::{has_relevant_changes, SourceWatcher};
+
+    #[test]
+    fn test_next_changes() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let input_folder_path = temp_dir.path().join("input");
+        let output_folder_path = input_folder_path.join("output");
+        fs::create_dir_all(&output_folder_path).unwrap();
+
+        let watcher = SourceWatcher::new(
+            &input_folder_path,
+            &output_folder_path,
+            &input_folder_path.join("infi_search.json"),
+            Duration::from_millis(200),
+        ).unwrap();
+
+        // Written into the output folder, which is ignored
+        fs::write(output_folder_path.join("output_config.json"), "{}").unwrap();
+
+        fs::write(input_folder_path.join("a.html"), "a").unwrap();
+        fs::write(input_folder_path.join("a.html"), "aa").unwrap();
+        fs::write(input_folder_path.join("b.html"), "b").unwrap();
+
+        let changed_paths = watcher.next_changes().unwrap();
+        assert_eq!(changed_paths, vec![input_folder_path.join("a.html"), input_folder_path.join("b.html")]);
+    }
+
+    #[test]
+    fn test_removed_folder() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let input_folder_path = temp_dir.path().join("input");
+        let output_folder_path = temp_dir.path().join("output");
+        let config_file_path = input_folder_path.join("infi_search.json");
+        fs::create_dir_all(input_folder_path.join("docs")).unwrap();
+        fs::create_dir_all(input_folder_path.join("assets")).unwrap();
+
+        let get_config = || InfiConfig::new(r#"{ "indexing_config": { "include": ["docs/*.html"] } }"#.to_owned()).unwrap();
+        fs::write(input_folder_path.join("docs/a.html"), "<html><body><p>lorem</p></body></html>").unwrap();
+        let mut indexer = Indexer::new(&input_folder_path, &output_folder_path, get_config(), false, false, false, false)
+            .unwrap();
+        indexer.index_file(&input_folder_path.join("docs/a.html"), "docs/a.html".as_ref()).unwrap();
+        indexer.finish_writing_docs().unwrap();
+
+        let config = get_config();
+        let is_relevant = |changed_path: &str| has_relevant_changes(
+            &[input_folder_path.join(changed_path)],
+            &input_folder_path,
+            &output_folder_path,
+            &config_file_path,
+            &config.indexing_config,
+        );
+
+        assert!(is_relevant("docs/b.html"));
+        assert!(!is_relevant("assets/c.html"));
+        assert!(is_relevant("infi_search.json"));
+
+        // Folders don't match the include patterns, but removing one removes the files indexed under it
+        assert!(!is_relevant("docs"));
+        fs::remove_dir_all(input_folder_path.join("docs")).unwrap();
+        fs::remove_dir_all(input_folder_path.join("assets")).unwrap();
+        assert!(is_relevant("docs"));
+        assert!(!is_relevant("assets"));
+    }
+}
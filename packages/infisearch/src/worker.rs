@@ -9,6 +9,7 @@ use std::thread;
 
 use crossbeam::channel::{Receiver, Sender};
 
+use infisearch_common::synonyms::Synonyms;
 use infisearch_common::tokenize::IndexerTokenizer;
 
 use crate::i_debug;
@@ -60,6 +61,7 @@ pub fn create_worker(
     sndr: Sender<WorkerToMainMessage>,
     rcvr: Receiver<MainToWorkerMessage>,
     tokenizer: Arc<dyn IndexerTokenizer + Send + Sync>,
+    synonyms: Option<Arc<Synonyms>>,
     field_infos: Arc<FieldInfos>,
     indexing_config: Arc<InfiIndexingConfig>,
     expected_num_docs_per_reset: usize,
@@ -74,6 +76,7 @@ pub fn create_worker(
         indexing_config.with_positions,
         expected_num_docs_per_reset,
         &tokenizer,
+        synonyms,
         input_folder_path,
         &loaders,
         #[cfg(debug_assertions)]
@@ -1,22 +1,26 @@
+use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::io::Write;
 use std::path::{PathBuf, Path};
 use std::sync::Arc;
 
-use chrono::{DateTime, NaiveDate, NaiveTime, TimeZone, FixedOffset, NaiveDateTime};
-use log::warn;
+use log::{error, warn};
 use path_absolutize::Absolutize;
 use rustc_hash::FxHashMap;
 
+use infisearch_common::synonyms::Synonyms;
 use infisearch_common::tokenize::IndexerTokenizer;
 
-use crate::field_info::{ADD_FILES_FIELD, FieldInfo, FieldInfos, EnumKind, EnumInfo, I64Info, I64ParseStrategy};
+use crate::field_info::{ADD_FILES_FIELD, FieldInfo, FieldInfos, EnumKind, EnumInfo, I64Info};
 use crate::loader::LoaderBoxed;
 use crate::i_debug;
 use crate::utils::escape_json;
 
 pub const DEFAULT_ZONE_SEPARATION: u32 = 10;
 
+// Longer texts are unlikely to be useful query completions
+const MAX_COMPLETION_PHRASE_CHARS: usize = 100;
+
 #[cfg_attr(test, derive(PartialEq, Eq, Debug))]
 pub struct Zone {
     pub field_name: String,
@@ -48,6 +52,8 @@ pub struct WorkerMinerDocInfo {
     pub doc_nums: Vec<Option<i64>>,
     pub field_lengths: Vec<u32>,
     pub field_texts: Vec<u8>,
+    /// Field id and text of fields with completions enabled
+    pub completions: Vec<(u8, String)>,
 }
 
 // Intermediate BSBI miner for use in a worker
@@ -58,10 +64,14 @@ pub struct WorkerMiner {
     pub terms: FxHashMap<String, Vec<TermDoc>>,
     pub doc_infos: Vec<WorkerMinerDocInfo>,
     pub tokenizer: Arc<dyn IndexerTokenizer + Send + Sync>,
+    /// Synonyms expanded at index time, if any
+    synonyms: Option<Arc<Synonyms>>,
 
     input_folder: PathBuf,
     loaders: Arc<Vec<LoaderBoxed>>,
     secondary_inv_mappings: FxHashMap<u32, Vec<String>>,
+    // Completion phrases of the document being indexed
+    doc_completions: Vec<(u8, String)>,
 
     #[cfg(debug_assertions)]
     pub id: usize,
@@ -123,6 +133,41 @@ impl PartialEq for DocIdAndFieldLengthsComparator {
     }
 }
 
+fn add_term(
+    terms: &mut FxHashMap<String, Vec<TermDoc>>,
+    term: Cow<str>,
+    doc_id: u32,
+    field_id: usize,
+    num_scored_fields: usize,
+    with_positions: bool,
+    pos: u32,
+) {
+    let term_docs = if let Some(existing) = terms.get_mut(&term[..]) {
+        existing
+    } else {
+        terms.entry(term.into_owned()).or_insert_with(|| vec![TermDoc {
+            doc_id,
+            doc_fields: vec![DocField::default(); num_scored_fields],
+        }])
+    };
+
+    let mut term_doc = term_docs.last_mut().unwrap();
+    if term_doc.doc_id != doc_id {
+        term_docs.push(TermDoc {
+            doc_id,
+            doc_fields: vec![DocField::default(); num_scored_fields],
+        });
+        term_doc = term_docs.last_mut().unwrap();
+    }
+
+    let doc_field = term_doc.doc_fields.get_mut(field_id).unwrap();
+    doc_field.field_tf += 1;
+    // Synonyms spanning multiple terms may have already occupied this position
+    if with_positions && doc_field.positions.last().is_none_or(|&last| last < pos) {
+        doc_field.positions.push(pos);
+    }
+}
+
 lazy_static! {
     static ref NULL_FIELD: FieldInfo = FieldInfo {
         name: "".to_owned(),
@@ -131,16 +176,19 @@ lazy_static! {
         enum_info: None,
         store_text: false,
         i64_info: None,
-        weight: 0.0, k: 0.0, b: 0.0
+        weight: 0.0, k: 0.0, b: 0.0,
+        completions: false,
     };
 }
 
 impl WorkerMiner {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         field_infos: &Arc<FieldInfos>,
         with_positions: bool,
         expected_num_docs_per_reset: usize,
         tokenizer: &Arc<dyn IndexerTokenizer + Send + Sync>,
+        synonyms: Option<Arc<Synonyms>>,
         input_folder: PathBuf,
         loaders: &Arc<Vec<LoaderBoxed>>,
         #[cfg(debug_assertions)]
@@ -152,9 +200,11 @@ impl WorkerMiner {
             terms: FxHashMap::default(),
             doc_infos: Vec::with_capacity(expected_num_docs_per_reset),
             tokenizer: Arc::clone(tokenizer),
+            synonyms,
             input_folder,
             loaders: Arc::clone(loaders),
             secondary_inv_mappings: FxHashMap::default(),
+            doc_completions: Vec::new(),
 
             #[cfg(debug_assertions)]
             id,
@@ -256,26 +306,32 @@ impl WorkerMiner {
         }
 
         for loader in Arc::clone(&self.loaders).iter() {
-            if let Some(loader_results) = loader.try_index_file(&absolute_path, &relative_path)
-            {
-                for loader_result in loader_results {
-                    let (field_texts, path) = loader_result.get_field_texts_and_path();
-                    self.process_field_texts(
-                        field_texts,
-                        path,
-                        is_first_stored_field,
-                        field_store_buffered_writer,
-                        doc_enums,
-                        doc_nums,
-                        field_lengths,
-                        doc_id,
-                        num_scored_fields,
-                        pos,
-                    );
+            let loader_results = match loader.try_index_file(&absolute_path, &relative_path) {
+                Ok(Some(loader_results)) => loader_results,
+                Ok(None) => continue,
+                Err(e) => {
+                    error!("Failed to link in {}. {}", absolute_path.to_string_lossy(), e);
+                    break;
                 }
-        
-                break;
+            };
+
+            for loader_result in loader_results {
+                let (field_texts, path) = loader_result.get_field_texts_and_path();
+                self.process_field_texts(
+                    field_texts,
+                    path,
+                    is_first_stored_field,
+                    field_store_buffered_writer,
+                    doc_enums,
+                    doc_nums,
+                    field_lengths,
+                    doc_id,
+                    num_scored_fields,
+                    pos,
+                );
             }
+
+            break;
         }
     }
 
@@ -330,6 +386,16 @@ impl WorkerMiner {
             }
             // ----------------------------------------------
 
+            if field_info.completions {
+                let phrase = field_text.split_whitespace().collect::<Vec<_>>().join(" ");
+                if !phrase.is_empty()
+                    && phrase.chars().count() <= MAX_COMPLETION_PHRASE_CHARS
+                    && !self.doc_completions.iter().any(|(id, p)| *id == field_info.id && *p == phrase)
+                {
+                    self.doc_completions.push((field_info.id, phrase));
+                }
+            }
+
             // ----------------------------------------------
             // Enums and Numbers
             if let Some(EnumInfo { enum_id, enum_values: _ }) = &field_info.enum_info {
@@ -342,37 +408,17 @@ impl WorkerMiner {
             if let Some(I64Info { id, parse, default: _ }) = &field_info.i64_info {
                 let existing = unsafe { doc_nums.get_unchecked_mut(*id) };
                 if existing.is_none() {
-                    *existing = Some(match parse {
-                        I64ParseStrategy::Integer => {
-                            field_text.parse::<i64>().expect("Failed to parse i64")
-                        },
-                        I64ParseStrategy::Round => {
-                            field_text.parse::<f64>().expect("Failed to parse i64 as f64").round() as i64
-                        },
-                        I64ParseStrategy::Datetime { datetime_fmt: format, time, timezone } => {
-                            if let Some(timezone) = timezone {
-                                let naive_date_time = if let Some(time) = time {
-                                    NaiveDate::parse_from_str(&field_text, format)
-                                        .unwrap()
-                                        .and_time(
-                                            NaiveTime::from_num_seconds_from_midnight_opt(*time, 0)
-                                                .expect("Invalid default time provided")
-                                        )
-                                } else {
-                                    NaiveDateTime::parse_from_str(&field_text, format).unwrap()
-                                };
-
-                                FixedOffset::east_opt(*timezone)
-                                    .expect("Invalid default timezone provided")
-                                    .from_utc_datetime(&naive_date_time)
-                                    .timestamp()
-                            } else if let Some(_time) = time {
-                                panic!("Default time without timezone specified, a timezone is required to calculate the UNIX timestamp");
-                            } else {
-                                DateTime::parse_from_str(&field_text, format).unwrap().timestamp()
-                            }
-                        },
-                    });
+                    // Falls back to the field's default, as if the document did not have the field
+                    match parse.parse(&field_text) {
+                        Ok(value) => *existing = Some(value),
+                        Err(e) => warn!(
+                            "Skipping invalid {} value \"{}\" in {}. {}",
+                            field_name,
+                            field_text,
+                            original_absolute_path.to_string_lossy(),
+                            e,
+                        ),
+                    }
                 }
             }
             // ----------------------------------------------
@@ -386,45 +432,64 @@ impl WorkerMiner {
                 self.total_len += field_text.len() as u64;
             }
 
-            let terms = self.tokenizer.tokenize(&mut field_text);
+            let terms = self.tokenizer.tokenize_field(&field_info.name, &mut field_text);
             let field_id = field_info.id as usize;
             let field_lengths = field_lengths.get_mut(field_id).unwrap();
-
-            for term in terms {
-                if let Some(term) = term {
-                    *field_lengths += 1;
-
-                    #[cfg(debug_assertions)]
-                    {
-                        self.total_terms += 1;
-                    }
-
-                    let term_docs = if let Some(existing) = self.terms.get_mut(&term[..]) {
-                        existing
-                    } else {
-                        self.terms.entry(term.into_owned()).or_insert_with(|| vec![TermDoc {
+            #[cfg(debug_assertions)]
+            let prev_field_length = *field_lengths;
+
+            if let Some(synonyms) = &self.synonyms {
+                let terms: Vec<_> = terms.collect();
+                for (idx, term) in terms.iter().enumerate() {
+                    if let Some(term) = term {
+                        *field_lengths += 1;
+                        add_term(
+                            &mut self.terms,
+                            Cow::Borrowed(term.as_ref()),
                             doc_id,
-                            doc_fields: vec![DocField::default(); num_scored_fields],
-                        }])
-                    };
+                            field_id,
+                            num_scored_fields,
+                            self.with_positions,
+                            *pos,
+                        );
+                    }
 
-                    let mut term_doc = term_docs.last_mut().unwrap();
-                    if term_doc.doc_id != doc_id {
-                        term_docs.push(TermDoc {
-                            doc_id,
-                            doc_fields: vec![DocField::default(); num_scored_fields],
-                        });
-                        term_doc = term_docs.last_mut().unwrap();
+                    // Index the synonyms of the longest matching sequence of terms at the same positions
+                    let following_terms = terms[idx..].iter().map_while(|term| term.as_deref());
+                    if let Some((_num_terms, matched)) = synonyms.get_longest_match(following_terms) {
+                        for synonym in matched {
+                            for (offset, synonym_term) in synonym.iter().enumerate() {
+                                add_term(
+                                    &mut self.terms,
+                                    Cow::Borrowed(synonym_term),
+                                    doc_id,
+                                    field_id,
+                                    num_scored_fields,
+                                    self.with_positions,
+                                    *pos + offset as u32,
+                                );
+                            }
+                        }
                     }
 
-                    let doc_field = term_doc.doc_fields.get_mut(field_id).unwrap();
-                    doc_field.field_tf += 1;
-                    if self.with_positions {
-                        doc_field.positions.push(*pos);
+                    *pos += 1;
+                }
+            } else {
+                for term in terms {
+                    if let Some(term) = term {
+                        *field_lengths += 1;
+                        add_term(
+                            &mut self.terms, term, doc_id, field_id, num_scored_fields, self.with_positions, *pos,
+                        );
                     }
+
+                    *pos += 1;
                 }
+            }
 
-                *pos += 1;
+            #[cfg(debug_assertions)]
+            {
+                self.total_terms += *field_lengths - prev_field_length;
             }
 
             // To split up "zones" positionally
@@ -473,6 +538,7 @@ impl WorkerMiner {
             doc_nums,
             field_lengths,
             field_texts: field_store_buffered_writer,
+            completions: std::mem::take(&mut self.doc_completions),
         });
     }
 }
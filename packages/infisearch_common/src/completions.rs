@@ -0,0 +1,29 @@
+use crate::utils::varint;
+
+/// A field text of a document, for completing whole phrases.
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct CompletionPhrase {
+    pub doc_id: u32,
+    pub field_id: u8,
+    pub phrase: String,
+}
+
+/// Decodes the completions file, written by the indexer as a sequence of:
+/// doc id (varint), field id (1 byte), phrase length in bytes (varint), phrase
+pub fn decode_completion_phrases(buf: &[u8]) -> Vec<CompletionPhrase> {
+    let mut phrases = Vec::new();
+
+    let mut pos = 0;
+    while pos < buf.len() {
+        let doc_id = varint::decode_var_int(buf, &mut pos);
+        let field_id = buf[pos];
+        pos += 1;
+        let phrase_len = varint::decode_var_int(buf, &mut pos) as usize;
+        let phrase = String::from_utf8_lossy(&buf[pos..pos + phrase_len]).into_owned();
+        pos += phrase_len;
+
+        phrases.push(CompletionPhrase { doc_id, field_id, phrase });
+    }
+
+    phrases
+}
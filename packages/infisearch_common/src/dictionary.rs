@@ -19,6 +19,8 @@ pub struct TermInfo {
 
 pub struct Dictionary {
     pub term_infos: BTreeMap<String, &'static TermInfo>,
+    /// Serialized BK-tree of the terms for spelling correction, empty if it was not indexed
+    pub spelling_index: Vec<u8>,
 }
 
 struct DictionaryConstructor<'a> {
@@ -103,7 +105,7 @@ pub fn setup_dictionary(
         prev_term: String::from(""),
     });
 
-    Dictionary { term_infos }
+    Dictionary { term_infos, spelling_index: Vec::new() }
 }
 
 impl Dictionary {
@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 #[cfg(feature = "indexer")]
 use serde::{Serialize, Deserialize};
 
@@ -26,6 +28,77 @@ impl Default for InfiLanguageConfigOpts {
     }
 }
 
+/// Filters applied to the entire text, before it is split into terms
+#[cfg_attr(feature = "indexer", derive(Serialize, Deserialize, Clone))]
+#[cfg_attr(feature = "indexer", serde(tag = "type", rename_all = "snake_case"))]
+pub enum InfiCharFilter {
+    Lowercase,
+    RegexReplace {
+        pattern: String,
+        #[cfg_attr(feature = "indexer", serde(default))]
+        replacement: String,
+    },
+}
+
+/// How the (char filtered) text is split into terms
+#[derive(Default)]
+#[cfg_attr(feature = "indexer", derive(Serialize, Deserialize, Clone))]
+#[cfg_attr(feature = "indexer", serde(rename_all = "snake_case"))]
+pub enum InfiAnalyzerTokenizer {
+    /// Splits on sentences, then whitespace and punctuation
+    #[default]
+    Standard,
+    /// Splits on whitespace only
+    Whitespace,
+}
+
+/// Filters applied to each term in order
+#[cfg_attr(feature = "indexer", derive(Serialize, Deserialize, Clone))]
+#[cfg_attr(feature = "indexer", serde(tag = "type", rename_all = "snake_case"))]
+pub enum InfiTokenFilter {
+    Lowercase,
+    AsciiFolding,
+    /// Removes punctuation at the boundaries of terms, and common intra-word punctuation (e.g. "today's")
+    Punctuation,
+    /// Removes stop words, which defaults to the language's stop words list
+    StopWords {
+        #[cfg_attr(feature = "indexer", serde(default))]
+        words: Option<Vec<String>>,
+    },
+    Stemmer {
+        language: String,
+    },
+    /// Removes terms with fewer or more bytes than this
+    Length {
+        #[cfg_attr(feature = "indexer", serde(default))]
+        min: Option<usize>,
+        #[cfg_attr(feature = "indexer", serde(default))]
+        max: Option<usize>,
+    },
+    RegexReplace {
+        pattern: String,
+        #[cfg_attr(feature = "indexer", serde(default))]
+        replacement: String,
+    },
+}
+
+/// A declarative text analysis pipeline, replacing the language's hardwired one
+#[cfg_attr(feature = "indexer", derive(Serialize, Deserialize, Clone))]
+pub struct InfiAnalyzerConfig {
+    #[cfg_attr(feature = "indexer", serde(default))]
+    pub char_filters: Vec<InfiCharFilter>,
+
+    #[cfg_attr(feature = "indexer", serde(default))]
+    pub tokenizer: InfiAnalyzerTokenizer,
+
+    #[cfg_attr(feature = "indexer", serde(default))]
+    pub token_filters: Vec<InfiTokenFilter>,
+
+    /// Fields analyzed with this analyzer. Unused for the `"default"` analyzer, which applies to all other fields.
+    #[cfg_attr(feature = "indexer", serde(default))]
+    pub fields: Vec<String>,
+}
+
 #[cfg_attr(feature = "indexer", derive(Serialize, Deserialize, Clone))]
 pub struct InfiLanguageConfig {
     #[cfg_attr(feature = "indexer", serde(default = "get_default_language"))]
@@ -33,6 +106,37 @@ pub struct InfiLanguageConfig {
 
     #[cfg_attr(feature = "indexer", serde(default))]
     pub options: InfiLanguageConfigOpts,
+
+    /// Named analyzers, which replace the language's pipeline if any are present
+    #[cfg_attr(feature = "indexer", serde(default, skip_serializing_if = "BTreeMap::is_empty"))]
+    pub analyzers: BTreeMap<String, InfiAnalyzerConfig>,
+
+    #[cfg_attr(feature = "indexer", serde(default, skip_serializing_if = "InfiSynonymsConfig::is_empty"))]
+    pub synonyms: InfiSynonymsConfig,
+}
+
+/// Synonyms, whose terms are normalized by the indexer's tokenizer in the output configuration.
+/// Multi-word synonyms are space separated.
+#[derive(Default)]
+#[cfg_attr(feature = "indexer", derive(Serialize, Deserialize, Clone))]
+pub struct InfiSynonymsConfig {
+    /// Sets of synonyms that are equivalent to one another
+    #[cfg_attr(feature = "indexer", serde(default, skip_serializing_if = "Vec::is_empty"))]
+    pub equivalent: Vec<Vec<String>>,
+
+    /// One-way mappings, where searching for the key also searches for its synonyms, but not vice versa
+    #[cfg_attr(feature = "indexer", serde(default, skip_serializing_if = "BTreeMap::is_empty"))]
+    pub mappings: BTreeMap<String, Vec<String>>,
+
+    /// Whether to index synonyms alongside the original terms, instead of expanding the query
+    #[cfg_attr(feature = "indexer", serde(default))]
+    pub expand_at_index_time: bool,
+}
+
+impl InfiSynonymsConfig {
+    pub fn is_empty(&self) -> bool {
+        self.equivalent.is_empty() && self.mappings.is_empty()
+    }
 }
 
 #[cfg(feature = "indexer")]
@@ -41,6 +145,8 @@ impl Default for InfiLanguageConfig {
         InfiLanguageConfig {
             lang: get_default_language(),
             options: InfiLanguageConfigOpts::default(),
+            analyzers: BTreeMap::new(),
+            synonyms: InfiSynonymsConfig::default(),
         }
     }
 }
@@ -1,11 +1,14 @@
 pub mod bitmap;
+pub mod completions;
 pub mod dictionary;
 pub mod language;
 pub mod metadata;
 pub mod packed_var_int;
 pub mod postings_list;
+pub mod synonyms;
 pub mod tokenize;
 pub mod utils;
 
 pub static FILE_EXT: &str = "mls";
 pub static METADATA_FILE: &str = "metadata.json";
+pub static COMPLETIONS_FILE: &str = "completions.json";
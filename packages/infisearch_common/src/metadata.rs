@@ -6,11 +6,30 @@ use crate::utils::{push, varint};
 
 pub type EnumMax = u8;
 
+/// The metadata file starts with 4 u32 offsets, see `Indexer::flush_metadata`
+pub const HEADER_LEN: usize = 16;
+
+/// Version of the binary index files, stored in the output config and checked by the indexer and searcher.
+///
+/// Increment this on any change that older indexes cannot be read with, such as to the header above.
+/// Output configs without one are version 0.
+pub const FORMAT_VER: u32 = 1;
+
+/// Byte lengths of each metadata section, in the order they are stored
+pub struct MetadataSectionLengths {
+    pub dict_string: usize,
+    pub dict_table: usize,
+    pub invalidation_vec: usize,
+    pub doc_infos: usize,
+    pub spelling_index: usize,
+}
+
 pub struct MetadataReader {
     buf: Vec<u8>,
     dict_table_offset: usize,
     invalidation_vec_offset: usize,
     doc_infos_offset: usize,
+    spelling_index_offset: usize,
     doc_infos_pos: usize,
 }
 
@@ -19,18 +38,30 @@ impl MetadataReader {
         let dict_table_offset = LittleEndian::read_u32(&buf) as usize;
         let invalidation_vec_offset = LittleEndian::read_u32(&buf[4..]) as usize;
         let doc_infos_offset = LittleEndian::read_u32(&buf[8..]) as usize;
+        let spelling_index_offset = LittleEndian::read_u32(&buf[12..]) as usize;
 
         MetadataReader {
             buf,
             dict_table_offset,
             invalidation_vec_offset,
             doc_infos_offset,
+            spelling_index_offset,
             doc_infos_pos: 0,
         }
     }
 }
 
 impl MetadataReader {
+    pub fn get_section_lengths(&self) -> MetadataSectionLengths {
+        MetadataSectionLengths {
+            dict_string: self.dict_table_offset - HEADER_LEN,
+            dict_table: self.invalidation_vec_offset - self.dict_table_offset,
+            invalidation_vec: self.doc_infos_offset - self.invalidation_vec_offset,
+            doc_infos: self.spelling_index_offset - self.doc_infos_offset,
+            spelling_index: self.buf.len() - self.spelling_index_offset,
+        }
+    }
+
     pub fn get_invalidation_vec(&self, output: &mut Vec<u8>) {
         output.extend(&self.buf[self.invalidation_vec_offset..self.doc_infos_offset]);
     }
@@ -128,9 +159,11 @@ impl MetadataReader {
     }
 
     pub fn setup_dictionary(&self) -> Dictionary {
-        dictionary::setup_dictionary(
+        let mut dictionary = dictionary::setup_dictionary(
             &self.buf[self.dict_table_offset..self.invalidation_vec_offset],
-            &self.buf[12..self.dict_table_offset],
-        )
+            &self.buf[HEADER_LEN..self.dict_table_offset],
+        );
+        dictionary.spelling_index.extend_from_slice(&self.buf[self.spelling_index_offset..]);
+        dictionary
     }
 }
@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+
+use crate::language::InfiSynonymsConfig;
+
+/// Maps sequences of normalized terms to their synonyms, which may also span multiple terms
+#[derive(Default)]
+pub struct Synonyms {
+    synonyms: HashMap<Vec<String>, Vec<Vec<String>>>,
+    max_num_terms: usize,
+}
+
+fn split_terms(synonym: &str) -> Vec<String> {
+    synonym.split(' ').filter(|term| !term.is_empty()).map(|term| term.to_owned()).collect()
+}
+
+impl Synonyms {
+    /// Synonyms for expanding queries, where the keys of one-way mappings expand to their synonyms
+    pub fn for_query(config: &InfiSynonymsConfig) -> Self {
+        let mut synonyms = Synonyms::default();
+        synonyms.add_equivalent(config);
+        for (from, to) in config.mappings.iter() {
+            for to in to {
+                synonyms.add(from, to);
+            }
+        }
+        synonyms
+    }
+
+    /// Synonyms for expanding documents, where one-way mappings are reversed,
+    /// so that the documents containing the synonyms are found when searching for the key.
+    pub fn for_index(config: &InfiSynonymsConfig) -> Self {
+        let mut synonyms = Synonyms::default();
+        synonyms.add_equivalent(config);
+        for (from, to) in config.mappings.iter() {
+            for to in to {
+                synonyms.add(to, from);
+            }
+        }
+        synonyms
+    }
+
+    fn add_equivalent(&mut self, config: &InfiSynonymsConfig) {
+        for set in config.equivalent.iter() {
+            for from in set {
+                for to in set {
+                    self.add(from, to);
+                }
+            }
+        }
+    }
+
+    fn add(&mut self, from: &str, to: &str) {
+        let from = split_terms(from);
+        let to = split_terms(to);
+        if from.is_empty() || to.is_empty() || from == to {
+            return;
+        }
+
+        self.max_num_terms = self.max_num_terms.max(from.len());
+        let synonyms = self.synonyms.entry(from).or_default();
+        if !synonyms.contains(&to) {
+            synonyms.push(to);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.synonyms.is_empty()
+    }
+
+    /// Finds the longest sequence of terms from the start of `terms` that has synonyms,
+    /// returning its number of terms and its synonyms.
+    pub fn get_longest_match<'a>(
+        &self,
+        terms: impl Iterator<Item = &'a str>,
+    ) -> Option<(usize, &[Vec<String>])> {
+        if self.synonyms.is_empty() {
+            return None;
+        }
+
+        let mut key = Vec::with_capacity(self.max_num_terms);
+        let mut longest_match = None;
+        for term in terms.take(self.max_num_terms) {
+            key.push(term.to_owned());
+            if let Some(synonyms) = self.synonyms.get(&key) {
+                longest_match = Some((key.len(), synonyms.as_slice()));
+            }
+        }
+
+        longest_match
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use super::Synonyms;
+    use crate::language::InfiSynonymsConfig;
+
+    fn get_config() -> InfiSynonymsConfig {
+        let mut mappings = BTreeMap::new();
+        mappings.insert("tv".to_owned(), vec!["television".to_owned(), "television set".to_owned()]);
+
+        InfiSynonymsConfig {
+            equivalent: vec![vec!["car".to_owned(), "automobile".to_owned(), "motor vehicle".to_owned()]],
+            mappings,
+            expand_at_index_time: false,
+        }
+    }
+
+    fn get_match(synonyms: &Synonyms, terms: &[&str]) -> Option<(usize, Vec<String>)> {
+        synonyms.get_longest_match(terms.iter().copied())
+            .map(|(num_terms, synonyms)| (num_terms, synonyms.iter().map(|s| s.join(" ")).collect()))
+    }
+
+    #[test]
+    fn test_query_synonyms() {
+        let synonyms = Synonyms::for_query(&get_config());
+
+        assert_eq!(
+            get_match(&synonyms, &["car", "wash"]),
+            Some((1, vec!["automobile".to_owned(), "motor vehicle".to_owned()])),
+        );
+        assert_eq!(
+            get_match(&synonyms, &["motor", "vehicle", "wash"]),
+            Some((2, vec!["car".to_owned(), "automobile".to_owned()])),
+        );
+        assert_eq!(
+            get_match(&synonyms, &["tv"]),
+            Some((1, vec!["television".to_owned(), "television set".to_owned()])),
+        );
+        assert_eq!(get_match(&synonyms, &["television"]), None);
+        assert_eq!(get_match(&synonyms, &["motor"]), None);
+        assert_eq!(get_match(&synonyms, &[]), None);
+    }
+
+    #[test]
+    fn test_index_synonyms() {
+        let synonyms = Synonyms::for_index(&get_config());
+
+        assert_eq!(
+            get_match(&synonyms, &["car"]),
+            Some((1, vec!["automobile".to_owned(), "motor vehicle".to_owned()])),
+        );
+        assert_eq!(get_match(&synonyms, &["television", "set"]), Some((2, vec!["tv".to_owned()])));
+        assert_eq!(get_match(&synonyms, &["television"]), Some((1, vec!["tv".to_owned()])));
+        assert_eq!(get_match(&synonyms, &["tv"]), None);
+    }
+}
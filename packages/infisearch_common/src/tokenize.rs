@@ -1,4 +1,5 @@
 use std::borrow::Cow;
+use std::ops::Range;
 
 use crate::dictionary::Dictionary;
 
@@ -8,6 +9,11 @@ pub type TermIter<'a> = Box<dyn Iterator<Item = Option<Cow<'a, str>>> + 'a>;
 
 pub trait IndexerTokenizer {
     fn tokenize<'a>(&'a self, text: &'a mut str) -> TermIter<'a>;
+
+    /// Tokenizes the text of a field, for tokenizers that analyze some fields differently
+    fn tokenize_field<'a>(&'a self, _field_name: &str, text: &'a mut str) -> TermIter<'a> {
+        self.tokenize(text)
+    }
 }
 
 pub trait SearchTokenizer {
@@ -23,6 +29,28 @@ pub trait SearchTokenizer {
     fn is_stop_word(&self, term: &str) -> bool;
 
     fn is_valid_prefix_op_terminator(&self, c: char) -> bool;
+
+    /// Lowercases and folds a partially typed term as the indexer would, for completing it from the dictionary.
+    ///
+    /// Returns `None` if the term is removed, or if terms are stemmed,
+    /// as completing a prefix to a stem would not give a word.
+    fn fold_term(&self, _term: &str) -> Option<String> {
+        None
+    }
+
+    /// Splits a field's text into words as the indexer does, for highlighting the searched terms.
+    ///
+    /// Returns the byte range of each word, and its term.
+    /// By default, words are runs of alphanumeric characters, lowercased.
+    fn split_text(&self, _field_name: &str, text: &str) -> Vec<(Range<usize>, String)> {
+        split_words(text, |c| !c.is_alphanumeric())
+            .into_iter()
+            .map(|range| {
+                let term = text[range.clone()].to_lowercase();
+                (range, term)
+            })
+            .collect()
+    }
 }
 
 pub struct SearchTokenizeResult {
@@ -34,9 +62,78 @@ pub struct SearchTokenizeTerm {
     pub term: Option<String>,
     pub term_inflections: Vec<String>,
     pub original_term: String,
+    /// Start and end char indices of the term's text in the query
+    pub query_span: (usize, usize),
     pub suffix_wildcard: bool,
     pub is_corrected: bool,
+    /// For corrected terms, the word replacing the term's text in spelling suggestions
+    pub corrected_word: Option<String>,
     pub prefix_ops: PrefixResult,
+    /// Alternatives from the analyzers of other fields as (field name, term) pairs,
+    /// which are only searched in their field
+    pub field_terms: Vec<(String, String)>,
+}
+
+/// Splits a text into the byte ranges of its words, for `SearchTokenizer::split_text`.
+///
+/// Delimiters are words of their own if they are alphanumeric, such as CJK characters.
+pub fn split_words(text: &str, is_delimiter: impl Fn(char) -> bool) -> Vec<Range<usize>> {
+    let mut words = Vec::new();
+    let mut start = 0;
+    for (idx, c) in text.char_indices() {
+        if is_delimiter(c) {
+            words.extend(trim_word(text, start..idx));
+            words.extend(trim_word(text, idx..idx + c.len_utf8()));
+            start = idx + c.len_utf8();
+        }
+    }
+    words.extend(trim_word(text, start..text.len()));
+
+    words
+}
+
+/// Trims the non-alphanumeric characters at either end of a word, so that only the term is highlighted.
+///
+/// Returns `None` if there is no alphanumeric character.
+pub fn trim_word(text: &str, range: Range<usize>) -> Option<Range<usize>> {
+    let word = &text[range.clone()];
+    let trimmed_start = word.trim_start_matches(|c: char| !c.is_alphanumeric());
+    let trimmed = trimmed_start.trim_end_matches(|c: char| !c.is_alphanumeric());
+    if trimmed.is_empty() {
+        return None;
+    }
+
+    let start = range.start + (word.len() - trimmed_start.len());
+    Some(start..start + trimmed.len())
+}
+
+/// Maps the correction of a term back to a word to replace its query text with.
+///
+/// Dictionary terms may be stems, so the query text's remainder after its common prefix
+/// with the original term is kept if analyzing the result still gives the corrected term.
+/// For example, "runnning" stemmed to "runn" and corrected to "run" gives "running".
+pub fn get_corrected_word(
+    query_text: &str,
+    original_term: &str,
+    corrected_term: &str,
+    analyze: impl Fn(&str) -> Option<String>,
+) -> String {
+    let query_text = query_text.to_lowercase();
+    let common_prefix_len: usize = query_text.chars()
+        .zip(original_term.chars())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _b)| a.len_utf8())
+        .sum();
+
+    let remainder = &query_text[common_prefix_len..];
+    if !remainder.is_empty() {
+        let word = corrected_term.to_owned() + remainder;
+        if analyze(&word).is_some_and(|analyzed| analyzed == corrected_term) {
+            return word;
+        }
+    }
+
+    corrected_term.to_owned()
 }
 
 #[derive(Default)]
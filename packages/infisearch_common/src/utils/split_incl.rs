@@ -25,7 +25,8 @@ impl<'a, F> Iterator for SplitIncl<'a, F> where F: Fn(char) -> bool {
         let remaining_slice = unsafe { self.s.get_unchecked(self.idx..) };
         let mut seen_first = false;
         for (char_idx, (idx, c)) in remaining_slice.char_indices().chain(std::iter::once((remaining_slice.len(), ','))).enumerate() {
-            if (self.is_delimiter)(c) {
+            // The end of the string is a delimiter as well, whatever is_delimiter is
+            if idx == remaining_slice.len() || (self.is_delimiter)(c) {
                 if seen_first {
                     let ret = (self.char_idx, unsafe { remaining_slice.get_unchecked(..idx) });
                     self.idx += idx;
@@ -46,3 +47,18 @@ impl<'a, F> Iterator for SplitIncl<'a, F> where F: Fn(char) -> bool {
         None
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::SplitIncl;
+
+    #[test]
+    fn test_split_incl() {
+        let split: Vec<_> = SplitIncl::split("lorem, ipsum", |c| c == ',' || c == ' ').map(|(_idx, s)| s).collect();
+        assert_eq!(split, vec!["lorem", ",", " ", "ipsum"]);
+
+        // The last slice is kept even if the delimiters don't include the end sentinel
+        let split: Vec<_> = SplitIncl::split("foo() bar.baz", char::is_whitespace).map(|(_idx, s)| s).collect();
+        assert_eq!(split, vec!["foo()", " ", "bar.baz"]);
+    }
+}
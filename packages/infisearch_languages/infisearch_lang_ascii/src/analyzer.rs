@@ -0,0 +1,759 @@
+//! Tokenizers built from the declarative `lang_config.analyzers`,
+//! which both the indexer and searcher construct from the same configuration.
+
+use std::borrow::Cow;
+#[cfg(feature = "indexer")]
+use std::collections::HashSet;
+use std::ops::Range;
+
+#[cfg(feature = "regex")]
+use regex::{Captures, Regex};
+
+use infisearch_common::dictionary::Dictionary;
+use infisearch_common::language::{
+    InfiAnalyzerConfig, InfiAnalyzerTokenizer, InfiCharFilter, InfiLanguageConfig, InfiTokenFilter,
+};
+#[cfg(feature = "indexer")]
+use infisearch_common::tokenize::{IndexerTokenizer, TermIter};
+use infisearch_common::tokenize::{self, SearchTokenizeResult, SearchTokenizer, SearchTokenizeTerm};
+use infisearch_common::utils::split_incl::SplitIncl;
+
+#[cfg(feature = "indexer")]
+use crate::ascii::SENTENCE_SPLITTER;
+use crate::ascii_folding_filter;
+use crate::spelling::BestTermCorrector;
+use crate::stop_words::get_stop_words;
+use crate::utils;
+
+/// Name of the analyzer used for fields not assigned to any other analyzer
+pub const DEFAULT_ANALYZER: &str = "default";
+
+// Terms are stored with a u8 length in the dictionary
+#[cfg(feature = "indexer")]
+const MAX_TERM_LEN: usize = 250;
+
+/// Stems terms for the `stemmer` token filter, supplied by languages that support stemming
+pub trait TermStemmer: Send + Sync {
+    fn stem<'a>(&self, term: &'a str) -> Cow<'a, str>;
+}
+
+/// Creates the stemmer for the language of a `stemmer` token filter
+pub type GetStemmer<'a> = &'a dyn Fn(&str) -> Result<Box<dyn TermStemmer>, String>;
+
+// Remove HashSet from the search binary, where speed benefits are minimal
+#[cfg(feature = "indexer")]
+type StopWords = HashSet<String>;
+#[cfg(not(feature = "indexer"))]
+type StopWords = Vec<String>;
+
+enum CharFilter {
+    Lowercase,
+    #[cfg(feature = "regex")]
+    RegexReplace(Regex, String),
+}
+
+enum TokenFilter {
+    Lowercase,
+    AsciiFolding,
+    Punctuation,
+    StopWords,
+    Stemmer(Box<dyn TermStemmer>),
+    Length(usize, usize),
+    #[cfg(feature = "regex")]
+    RegexReplace(Regex, String),
+}
+
+enum AnalyzedTerm<'a> {
+    Term(Cow<'a, str>),
+    StopWord(Cow<'a, str>),
+    Removed,
+}
+
+#[cfg(feature = "regex")]
+fn get_regex(pattern: &str) -> Result<Regex, String> {
+    Regex::new(pattern).map_err(|e| format!("Invalid regex {}. {}", pattern, e))
+}
+
+// Keeps the regex crate out of the search bundle
+#[cfg(not(feature = "regex"))]
+const REGEX_UNSUPPORTED: &str = "regex_replace filters are not supported by this build of the searcher, \
+    which requires the regex_filters feature";
+
+/// Replaces the term only if the mapping changed it
+fn map_term<'a>(term: Cow<'a, str>, f: impl FnOnce(&str) -> Cow<str>) -> Cow<'a, str> {
+    let mapped = match f(&term) {
+        Cow::Owned(mapped) => Some(mapped),
+        Cow::Borrowed(_unchanged) => None,
+    };
+    mapped.map_or(term, Cow::Owned)
+}
+
+fn lowercase(text: &str) -> Cow<'_, str> {
+    if text.chars().any(|c| c.to_lowercase().next() != Some(c)) {
+        Cow::Owned(text.chars().flat_map(char::to_lowercase).collect())
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+pub struct Analyzer {
+    char_filters: Vec<CharFilter>,
+    split_punctuation: bool,
+    token_filters: Vec<TokenFilter>,
+    stop_words: StopWords,
+}
+
+impl Analyzer {
+    pub fn new(
+        config: &InfiAnalyzerConfig,
+        lang_config: &InfiLanguageConfig,
+        default_stop_words: &[&'static str],
+        get_stemmer: GetStemmer,
+    ) -> Result<Analyzer, String> {
+        let mut char_filters = Vec::with_capacity(config.char_filters.len());
+        for char_filter in config.char_filters.iter() {
+            char_filters.push(match char_filter {
+                InfiCharFilter::Lowercase => CharFilter::Lowercase,
+                #[cfg(feature = "regex")]
+                InfiCharFilter::RegexReplace { pattern, replacement } => {
+                    CharFilter::RegexReplace(get_regex(pattern)?, replacement.clone())
+                }
+                #[cfg(not(feature = "regex"))]
+                InfiCharFilter::RegexReplace { .. } => return Err(REGEX_UNSUPPORTED.to_owned()),
+            });
+        }
+
+        let mut stop_words = None;
+        let mut token_filters = Vec::with_capacity(config.token_filters.len());
+        for token_filter in config.token_filters.iter() {
+            token_filters.push(match token_filter {
+                InfiTokenFilter::Lowercase => TokenFilter::Lowercase,
+                InfiTokenFilter::AsciiFolding => TokenFilter::AsciiFolding,
+                InfiTokenFilter::Punctuation => TokenFilter::Punctuation,
+                InfiTokenFilter::StopWords { words } => {
+                    if let Some(words) = words {
+                        stop_words = Some(words.iter().cloned().collect());
+                    }
+                    TokenFilter::StopWords
+                }
+                InfiTokenFilter::Stemmer { language } => TokenFilter::Stemmer(get_stemmer(language)?),
+                InfiTokenFilter::Length { min, max } => {
+                    TokenFilter::Length(min.unwrap_or(0), max.unwrap_or(usize::MAX))
+                }
+                #[cfg(feature = "regex")]
+                InfiTokenFilter::RegexReplace { pattern, replacement } => {
+                    TokenFilter::RegexReplace(get_regex(pattern)?, replacement.clone())
+                }
+                #[cfg(not(feature = "regex"))]
+                InfiTokenFilter::RegexReplace { .. } => return Err(REGEX_UNSUPPORTED.to_owned()),
+            });
+        }
+
+        Ok(Analyzer {
+            char_filters,
+            split_punctuation: matches!(config.tokenizer, InfiAnalyzerTokenizer::Standard),
+            token_filters,
+            stop_words: stop_words.unwrap_or_else(|| get_stop_words(lang_config, default_stop_words)),
+        })
+    }
+
+    /// The language's pipeline as configured by `lang_config.options`, for when there is no default analyzer
+    pub fn from_options(
+        lang_config: &InfiLanguageConfig,
+        default_stop_words: &[&'static str],
+        stemmer: Option<Box<dyn TermStemmer>>,
+    ) -> Analyzer {
+        let mut token_filters = vec![TokenFilter::AsciiFolding, TokenFilter::Punctuation];
+        if lang_config.options.ignore_stop_words.unwrap_or(false) {
+            token_filters.push(TokenFilter::StopWords);
+        }
+        if let Some(stemmer) = stemmer {
+            token_filters.push(TokenFilter::Stemmer(stemmer));
+        }
+        token_filters.push(TokenFilter::Length(1, lang_config.options.max_term_len.unwrap_or(80).min(250)));
+
+        Analyzer {
+            char_filters: vec![CharFilter::Lowercase],
+            split_punctuation: true,
+            token_filters,
+            stop_words: get_stop_words(lang_config, default_stop_words),
+        }
+    }
+
+    fn is_delimiter(&self, c: char) -> bool {
+        if self.split_punctuation {
+            utils::split_terms(c)
+        } else {
+            c.is_whitespace()
+        }
+    }
+
+    #[cfg(feature = "indexer")]
+    fn is_stop_word(&self, term: &str) -> bool {
+        self.stop_words.contains(term)
+    }
+
+    #[cfg(not(feature = "indexer"))]
+    fn is_stop_word(&self, term: &str) -> bool {
+        self.stop_words.iter().any(|t| t == term)
+    }
+
+    #[cfg(feature = "indexer")]
+    fn filter_text<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut text = Cow::Borrowed(text);
+        for char_filter in self.char_filters.iter() {
+            text = match char_filter {
+                CharFilter::Lowercase => map_term(text, lowercase),
+                #[cfg(feature = "regex")]
+                CharFilter::RegexReplace(regex, replacement) => {
+                    map_term(text, |text| regex.replace_all(text, replacement.as_str()))
+                }
+            };
+        }
+        text
+    }
+
+    /// Applies the char filters to the query,
+    /// keeping the index of the original character each resulting character came from.
+    fn filter_query(&self, query_chars: &[char]) -> Vec<(char, usize)> {
+        let mut chars: Vec<(char, usize)> = query_chars.iter().copied().zip(0..).collect();
+
+        for char_filter in self.char_filters.iter() {
+            chars = match char_filter {
+                CharFilter::Lowercase => chars
+                    .into_iter()
+                    .flat_map(|(c, idx)| c.to_lowercase().map(move |lowercased| (lowercased, idx)))
+                    .collect(),
+                #[cfg(feature = "regex")]
+                CharFilter::RegexReplace(regex, replacement) => {
+                    let text: String = chars.iter().map(|(c, _idx)| c).collect();
+                    replace_mapped(&chars, regex.captures_iter(&text), replacement)
+                }
+            };
+        }
+
+        chars
+    }
+
+    /// Splits the query after applying the char filters, returning each term with the indices
+    /// of the query characters it starts at and ends before, and whether it has a wildcard suffix.
+    fn split_query(&self, query_slice: &[char]) -> Vec<(String, (usize, usize), bool)> {
+        let filtered = self.filter_query(query_slice);
+        let text: String = filtered.iter().map(|(c, _original_idx)| c).collect();
+
+        let split: Vec<_> = SplitIncl::split(&text, |c| c == '*' || self.is_delimiter(c)).collect();
+        split
+            .iter()
+            .enumerate()
+            .filter(|(_idx, (_char_idx, s))| !s.chars().all(|c| c == '*' || self.is_delimiter(c)))
+            .map(|(idx, (char_idx, s))| {
+                let suffix_wildcard = split.get(idx + 1).is_some_and(|(_char_idx, next)| *next == "*");
+                let original_idx = filtered.get(*char_idx).map_or(query_slice.len(), |(_c, original_idx)| *original_idx);
+                let original_end = filtered
+                    .get(*char_idx + s.chars().count() - 1)
+                    .map_or(query_slice.len(), |(_c, original_idx)| *original_idx + 1);
+                ((*s).to_owned(), (original_idx, original_end), suffix_wildcard)
+            })
+            .collect()
+    }
+
+    /// Splits a field text as the query is, see `SearchTokenizer::split_text`.
+    fn split_text(&self, text: &str) -> Vec<(Range<usize>, String)> {
+        let chars: Vec<char> = text.chars().collect();
+        let char_starts: Vec<usize> = text.char_indices()
+            .map(|(idx, _c)| idx)
+            .chain(std::iter::once(text.len()))
+            .collect();
+
+        self.split_query(&chars)
+            .into_iter()
+            .filter(|(_s, (start, end), _suffix_wildcard)| start < end)
+            .filter_map(|(s, (start, end), _suffix_wildcard)| {
+                let term = match self.filter_term(&s, None) {
+                    // Stop words are kept so that passages are not cut at them
+                    AnalyzedTerm::Term(term) | AnalyzedTerm::StopWord(term) => term.into_owned(),
+                    AnalyzedTerm::Removed => return None,
+                };
+                tokenize::trim_word(text, char_starts[start]..char_starts[end]).map(|range| (range, term))
+            })
+            .collect()
+    }
+
+    /// Applies the char and token filters to a single term, see `SearchTokenizer::fold_term`.
+    fn fold_term(&self, term: &str) -> Option<String> {
+        if self.token_filters.iter().any(|token_filter| matches!(token_filter, TokenFilter::Stemmer(_))) {
+            return None;
+        }
+
+        let query_slice: Vec<char> = term.chars().collect();
+        let filtered: String = self.filter_query(&query_slice).into_iter().map(|(c, _original_idx)| c).collect();
+        match self.filter_term(&filtered, None) {
+            AnalyzedTerm::Term(term) | AnalyzedTerm::StopWord(term) => Some(term.into_owned()),
+            AnalyzedTerm::Removed => None,
+        }
+    }
+
+    fn filter_term<'a>(&self, term: &'a str, mut term_inflections: Option<&mut Vec<String>>) -> AnalyzedTerm<'a> {
+        let mut term = Cow::Borrowed(term);
+
+        for token_filter in self.token_filters.iter() {
+            if term.is_empty() {
+                return AnalyzedTerm::Removed;
+            }
+
+            term = match token_filter {
+                TokenFilter::Lowercase => map_term(term, lowercase),
+                TokenFilter::AsciiFolding => map_term(term, ascii_folding_filter::to_ascii),
+                TokenFilter::Punctuation => utils::term_filter(term),
+                TokenFilter::StopWords => {
+                    if self.is_stop_word(&term) {
+                        return AnalyzedTerm::StopWord(term);
+                    }
+                    term
+                }
+                TokenFilter::Stemmer(stemmer) => map_term(term, |term| stemmer.stem(term)),
+                TokenFilter::Length(min, max) => {
+                    if term.len() < *min || term.len() > *max {
+                        return AnalyzedTerm::Removed;
+                    }
+                    term
+                }
+                #[cfg(feature = "regex")]
+                TokenFilter::RegexReplace(regex, replacement) => {
+                    map_term(term, |term| regex.replace_all(term, replacement.as_str()))
+                }
+            };
+
+            // Intermediate forms are highlighted in the search interface too
+            if let (Some(term_inflections), Cow::Owned(inflection)) = (term_inflections.as_deref_mut(), &term) {
+                if !inflection.is_empty() && !term_inflections.contains(inflection) {
+                    term_inflections.push(inflection.clone());
+                }
+            }
+        }
+
+        if term.is_empty() {
+            AnalyzedTerm::Removed
+        } else {
+            AnalyzedTerm::Term(term)
+        }
+    }
+
+    #[cfg(feature = "indexer")]
+    fn split_and_filter<'a>(&'a self, text: &'a str) -> TermIter<'a> {
+        let filter = move |term_slice: &'a str| match self.filter_term(term_slice, None) {
+            AnalyzedTerm::Term(term) if term.len() <= MAX_TERM_LEN => Some(term),
+            _ => None,
+        };
+
+        if self.split_punctuation {
+            Box::new(SENTENCE_SPLITTER.split(text).flat_map(move |sent_slice| {
+                sent_slice.split(utils::split_terms)
+                    .filter(|&s| !s.is_empty())
+                    .filter_map(filter)
+                    .map(Some)
+                    .chain(std::iter::once(None))
+            }))
+        } else {
+            Box::new(text.split(char::is_whitespace).filter(|&s| !s.is_empty()).filter_map(filter).map(Some))
+        }
+    }
+
+    #[cfg(feature = "indexer")]
+    fn tokenize<'a>(&'a self, text: &'a str) -> TermIter<'a> {
+        match self.filter_text(text) {
+            Cow::Borrowed(text) => self.split_and_filter(text),
+            Cow::Owned(text) => {
+                let terms: Vec<_> = self.split_and_filter(&text)
+                    .map(|term| term.map(|term| Cow::Owned(term.into_owned())))
+                    .collect();
+                Box::new(terms.into_iter())
+            }
+        }
+    }
+}
+
+/// Regex replacement over mapped characters, mapping replaced characters to the start of their match
+#[cfg(feature = "regex")]
+fn replace_mapped<'t>(
+    chars: &[(char, usize)],
+    captures: impl Iterator<Item = Captures<'t>>,
+    replacement: &str,
+) -> Vec<(char, usize)> {
+    let mut replaced = Vec::with_capacity(chars.len());
+    let mut char_idx = 0;
+    let mut byte_idx = 0;
+
+    for captures in captures {
+        let whole_match = captures.get(0).expect("capture group 0 is always present");
+        while byte_idx < whole_match.start() {
+            replaced.push(chars[char_idx]);
+            byte_idx += chars[char_idx].0.len_utf8();
+            char_idx += 1;
+        }
+
+        let original_idx = chars
+            .get(char_idx)
+            .or_else(|| chars.last())
+            .map_or(0, |(_c, original_idx)| *original_idx);
+        let mut expanded = String::new();
+        captures.expand(replacement, &mut expanded);
+        replaced.extend(expanded.chars().map(|c| (c, original_idx)));
+
+        while byte_idx < whole_match.end() {
+            byte_idx += chars[char_idx].0.len_utf8();
+            char_idx += 1;
+        }
+    }
+
+    replaced.extend_from_slice(&chars[char_idx..]);
+    replaced
+}
+
+/// Analyzes fields using the analyzer they are assigned to, or the default analyzer.
+///
+/// Queries are analyzed with every analyzer, with the terms of field analyzers
+/// searched as alternatives to the default analyzer's terms, in their fields only.
+pub struct Tokenizer {
+    default_analyzer: Analyzer,
+    field_analyzers: Vec<(Vec<String>, Analyzer)>,
+    best_term_corrector: BestTermCorrector,
+}
+
+pub fn new_with_analyzers(
+    lang_config: &InfiLanguageConfig,
+    default_stop_words: &[&'static str],
+    default_stemmer: Option<Box<dyn TermStemmer>>,
+    get_stemmer: GetStemmer,
+) -> Result<Tokenizer, String> {
+    let get_analyzer = |name: &str, config: &InfiAnalyzerConfig| {
+        Analyzer::new(config, lang_config, default_stop_words, get_stemmer)
+            .map_err(|e| format!("Invalid analyzer {}. {}", name, e))
+    };
+
+    let default_analyzer = match lang_config.analyzers.get(DEFAULT_ANALYZER) {
+        Some(config) => get_analyzer(DEFAULT_ANALYZER, config)?,
+        None => Analyzer::from_options(lang_config, default_stop_words, default_stemmer),
+    };
+
+    let mut field_analyzers = Vec::new();
+    for (name, config) in lang_config.analyzers.iter() {
+        if name != DEFAULT_ANALYZER {
+            field_analyzers.push((config.fields.clone(), get_analyzer(name, config)?));
+        }
+    }
+
+    Ok(Tokenizer {
+        default_analyzer,
+        field_analyzers,
+        best_term_corrector: BestTermCorrector::new(),
+    })
+}
+
+impl Tokenizer {
+    fn get_field_analyzer(&self, field_name: &str) -> &Analyzer {
+        self.field_analyzers
+            .iter()
+            .find(|(fields, _analyzer)| fields.iter().any(|field| field == field_name))
+            .map_or(&self.default_analyzer, |(_fields, analyzer)| analyzer)
+    }
+
+    /// Analyzes the query with each field analyzer, returning the terms in the dictionary
+    /// that the default analyzer does not produce.
+    ///
+    /// Each term is returned with the index of the query character it starts at,
+    /// as (field name, term) pairs for each field of the analyzer, and with its inflections.
+    #[allow(clippy::type_complexity)]
+    fn get_field_analyzer_terms(
+        &self,
+        query_slice: &[char],
+        default_terms: &[String],
+        dict: &Dictionary,
+    ) -> Vec<(usize, Vec<(String, String)>, Vec<String>)> {
+        let mut field_terms = Vec::new();
+        for (fields, analyzer) in self.field_analyzers.iter() {
+            for (s, (original_idx, _original_end), suffix_wildcard) in analyzer.split_query(query_slice) {
+                if suffix_wildcard {
+                    continue;
+                }
+
+                let mut term_inflections = vec![s.clone()];
+                if let AnalyzedTerm::Term(term) = analyzer.filter_term(&s, Some(&mut term_inflections)) {
+                    if dict.get_term_info(&term).is_some() && !default_terms.iter().any(|t| *t == term) {
+                        let field_term_pairs = fields.iter().map(|field| (field.clone(), term.to_string())).collect();
+                        field_terms.push((original_idx, field_term_pairs, term_inflections));
+                    }
+                }
+            }
+        }
+        field_terms
+    }
+}
+
+#[cfg(feature = "indexer")]
+impl IndexerTokenizer for Tokenizer {
+    fn tokenize<'a>(&'a self, text: &'a mut str) -> TermIter<'a> {
+        self.default_analyzer.tokenize(text)
+    }
+
+    fn tokenize_field<'a>(&'a self, field_name: &str, text: &'a mut str) -> TermIter<'a> {
+        self.get_field_analyzer(field_name).tokenize(text)
+    }
+}
+
+impl SearchTokenizer for Tokenizer {
+    fn search_tokenize(
+        &mut self,
+        query_chars: &[char],
+        query_chars_offset: usize,
+        query_chars_offset_end: usize,
+        escape_indices: &[usize],
+        dict: &Dictionary,
+    ) -> SearchTokenizeResult {
+        let query_slice = unsafe { query_chars.get_unchecked(query_chars_offset..query_chars_offset_end) };
+        let should_expand = query_slice.last().is_some_and(|&c| c != ' ');
+
+        let split = self.default_analyzer.split_query(query_slice);
+        let analyzed: Vec<_> = split
+            .iter()
+            .map(|(s, _original_span, _suffix_wildcard)| {
+                let mut term_inflections = vec![s.clone()];
+                let analyzed_term = self.default_analyzer.filter_term(s, Some(&mut term_inflections));
+                (analyzed_term, term_inflections)
+            })
+            .collect();
+        let default_terms: Vec<String> = analyzed
+            .iter()
+            .filter_map(|(analyzed_term, _term_inflections)| match analyzed_term {
+                AnalyzedTerm::Term(term) => Some(term.to_string()),
+                _ => None,
+            })
+            .collect();
+
+        // Each field analyzer splits the query independently, so their terms are attached
+        // to the default analyzer's term spanning the same query characters
+        let mut field_analyzer_terms = self.get_field_analyzer_terms(query_slice, &default_terms, dict);
+
+        let mut terms = Vec::new();
+        for (idx, ((s, (original_idx, original_end), suffix_wildcard), (analyzed_term, mut term_inflections))) in
+            split.iter().zip(analyzed).enumerate()
+        {
+            let query_span = (original_idx + query_chars_offset, original_end + query_chars_offset);
+            let span_end = split.get(idx + 1).map_or(usize::MAX, |(_s, (next_original_idx, _), _)| *next_original_idx);
+            let mut field_terms = Vec::new();
+            field_analyzer_terms.retain(|(field_original_idx, field_term_pairs, field_term_inflections)| {
+                let is_in_span = (idx == 0 || field_original_idx >= original_idx) && *field_original_idx < span_end;
+                if is_in_span && !suffix_wildcard {
+                    field_terms.extend(field_term_pairs.iter().cloned());
+                    for inflection in field_term_inflections.iter() {
+                        if !term_inflections.contains(inflection) {
+                            term_inflections.push(inflection.clone());
+                        }
+                    }
+                }
+                !is_in_span
+            });
+
+            let prefix_ops = tokenize::get_prefix_ops(
+                original_idx + query_chars_offset, 1, query_chars_offset, query_chars, escape_indices, self,
+            );
+
+            let term = match analyzed_term {
+                AnalyzedTerm::Term(term) => term,
+                AnalyzedTerm::StopWord(term) => {
+                    // Removed from the index, so this comes before spelling correction
+                    terms.push(SearchTokenizeTerm {
+                        term: None,
+                        term_inflections,
+                        original_term: term.into_owned(),
+                        query_span,
+                        suffix_wildcard: *suffix_wildcard,
+                        is_corrected: false,
+                        corrected_word: None,
+                        prefix_ops,
+                        field_terms,
+                    });
+                    continue;
+                }
+                AnalyzedTerm::Removed => {
+                    if !field_terms.is_empty() {
+                        terms.push(SearchTokenizeTerm {
+                            term: None,
+                            term_inflections,
+                            original_term: s.clone(),
+                            query_span,
+                            suffix_wildcard: *suffix_wildcard,
+                            is_corrected: false,
+                            corrected_word: None,
+                            prefix_ops,
+                            field_terms,
+                        });
+                    }
+                    continue;
+                }
+            };
+
+            let original_term = term.clone().into_owned();
+            let mut is_corrected = false;
+            let mut corrected_word = None;
+
+            let term = if dict.get_term_info(&term).is_some() {
+                Some(term.into_owned())
+            } else if *suffix_wildcard || !field_terms.is_empty() {
+                None
+            } else if let Some(corrected_term) = self.best_term_corrector.get_best_corrected_term(dict, &term) {
+                term_inflections.push(corrected_term.clone());
+                is_corrected = true;
+                corrected_word = Some(tokenize::get_corrected_word(s, &original_term, &corrected_term, |word| {
+                    match self.default_analyzer.filter_term(word, None) {
+                        AnalyzedTerm::Term(term) => Some(term.into_owned()),
+                        _ => None,
+                    }
+                }));
+                Some(corrected_term)
+            } else {
+                None
+            };
+
+            terms.push(SearchTokenizeTerm {
+                term,
+                term_inflections,
+                original_term,
+                query_span,
+                suffix_wildcard: *suffix_wildcard,
+                is_corrected,
+                corrected_word,
+                prefix_ops,
+                field_terms,
+            })
+        }
+
+        SearchTokenizeResult {
+            terms,
+            auto_suffix_wildcard: should_expand,
+        }
+    }
+
+    #[inline(never)]
+    fn is_stop_word(&self, term: &str) -> bool {
+        self.default_analyzer.is_stop_word(term)
+    }
+
+    fn is_valid_prefix_op_terminator(&self, c: char) -> bool {
+        c.is_ascii_whitespace()
+    }
+
+    fn fold_term(&self, term: &str) -> Option<String> {
+        self.default_analyzer.fold_term(term)
+    }
+
+    fn split_text(&self, field_name: &str, text: &str) -> Vec<(Range<usize>, String)> {
+        self.get_field_analyzer(field_name).split_text(text)
+    }
+}
+
+#[cfg(all(test, feature = "indexer"))]
+mod test {
+    use std::borrow::Cow;
+    use std::collections::BTreeMap;
+
+    use infisearch_common::language::{
+        InfiAnalyzerConfig, InfiAnalyzerTokenizer, InfiCharFilter, InfiLanguageConfig, InfiTokenFilter,
+    };
+    use infisearch_common::tokenize::{IndexerTokenizer, SearchTokenizer};
+
+    use super::{new_with_analyzers, Tokenizer};
+
+    fn analyzer(
+        char_filters: Vec<InfiCharFilter>,
+        tokenizer: InfiAnalyzerTokenizer,
+        token_filters: Vec<InfiTokenFilter>,
+        fields: &[&str],
+    ) -> InfiAnalyzerConfig {
+        InfiAnalyzerConfig {
+            char_filters,
+            tokenizer,
+            token_filters,
+            fields: fields.iter().map(|field| (*field).to_owned()).collect(),
+        }
+    }
+
+    fn get_tokenizer(analyzers: Vec<(&str, InfiAnalyzerConfig)>) -> Result<Tokenizer, String> {
+        let lang_config = InfiLanguageConfig {
+            analyzers: analyzers.into_iter().map(|(name, config)| (name.to_owned(), config)).collect::<BTreeMap<_, _>>(),
+            ..InfiLanguageConfig::default()
+        };
+        new_with_analyzers(&lang_config, &["the"], None, &|_language| Err("No stemmers".to_owned()))
+    }
+
+    fn tokenize(tokenizer: &Tokenizer, field_name: &str, text: &str) -> Vec<Option<String>> {
+        let mut text = text.to_owned();
+        tokenizer.tokenize_field(field_name, &mut text).map(|term| term.map(Cow::into_owned)).collect()
+    }
+
+    fn terms(terms: &[&str]) -> Vec<Option<String>> {
+        terms.iter().map(|term| Some((*term).to_owned())).collect()
+    }
+
+    fn regex_replace(pattern: &str, replacement: &str) -> InfiTokenFilter {
+        InfiTokenFilter::RegexReplace { pattern: pattern.to_owned(), replacement: replacement.to_owned() }
+    }
+
+    #[test]
+    fn test_default_analyzer() {
+        let tokenizer = get_tokenizer(vec![("default", analyzer(
+            vec![
+                InfiCharFilter::Lowercase,
+                InfiCharFilter::RegexReplace { pattern: "c\\+\\+".to_owned(), replacement: "cplusplus".to_owned() },
+            ],
+            InfiAnalyzerTokenizer::Whitespace,
+            vec![
+                InfiTokenFilter::AsciiFolding,
+                InfiTokenFilter::Punctuation,
+                InfiTokenFilter::StopWords { words: None },
+                InfiTokenFilter::Length { min: None, max: Some(10) },
+            ],
+            &[],
+        ))]).unwrap();
+
+        assert_eq!(
+            tokenize(&tokenizer, "body", "The Café's C++ lorem-ipsum"),
+            terms(&["cafes", "cplusplus", "loremipsum"]),
+        );
+        assert_eq!(tokenize(&tokenizer, "body", "C++ consectetur"), terms(&["cplusplus"]));
+    }
+
+    #[test]
+    fn test_field_analyzers() {
+        let tokenizer = get_tokenizer(vec![("code", analyzer(
+            Vec::new(),
+            InfiAnalyzerTokenizer::Whitespace,
+            vec![regex_replace("[()]", "")],
+            &["code"],
+        ))]).unwrap();
+
+        // The language's pipeline, with sentence gaps
+        assert_eq!(tokenize(&tokenizer, "body", "The foo(). Bar"), vec![
+            Some("the".to_owned()), Some("foo".to_owned()), None, Some("bar".to_owned()), None,
+        ]);
+        assert_eq!(tokenize(&tokenizer, "code", "Foo() bar.baz"), terms(&["Foo", "bar.baz"]));
+
+        // Field texts are split the same way for snippets
+        assert_eq!(tokenizer.split_text("code", "Foo() bar.baz"), vec![
+            (0..3, "Foo".to_owned()), (6..13, "bar.baz".to_owned()),
+        ]);
+        assert_eq!(tokenizer.split_text("body", "The foo(). Bar"), vec![
+            (0..3, "the".to_owned()), (4..7, "foo".to_owned()), (11..14, "bar".to_owned()),
+        ]);
+    }
+
+    #[test]
+    fn test_invalid_analyzers() {
+        let invalid_regex = analyzer(Vec::new(), InfiAnalyzerTokenizer::Standard, vec![regex_replace("(", "")], &[]);
+        assert!(get_tokenizer(vec![("default", invalid_regex)]).is_err());
+
+        let stemmer = InfiTokenFilter::Stemmer { language: "english".to_owned() };
+        let unsupported_stemmer = analyzer(Vec::new(), InfiAnalyzerTokenizer::Standard, vec![stemmer], &["title"]);
+        assert!(get_tokenizer(vec![("title", unsupported_stemmer)]).is_err());
+    }
+}
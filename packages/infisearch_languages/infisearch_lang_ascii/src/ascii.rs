@@ -1,11 +1,13 @@
 #[cfg(feature = "indexer")]
 use std::collections::HashSet;
+use std::ops::Range;
 
 use infisearch_common::dictionary::Dictionary;
 use infisearch_common::utils::split_incl::SplitIncl;
 #[cfg(feature = "indexer")]
 use regex::Regex;
 
+use crate::analyzer;
 #[cfg(feature = "indexer")]
 use crate::ascii_folding_filter;
 use crate::spelling::BestTermCorrector;
@@ -37,13 +39,15 @@ pub struct Tokenizer {
     best_term_corrector: BestTermCorrector,
 }
 
+// Same list from tantivy
+pub const DEFAULT_STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "but", "by", "for", "if", "in", "into", "is", "it", "no",
+    "not", "of", "on", "or", "such", "that", "the", "their", "then", "there", "these", "they", "this",
+    "to", "was", "will", "with"
+];
+
 pub fn new_with_options(lang_config: &InfiLanguageConfig) -> Tokenizer {
-    let stop_words = get_stop_words(lang_config, &[
-        // Same list from tantivy
-        "a", "an", "and", "are", "as", "at", "be", "but", "by", "for", "if", "in", "into", "is", "it", "no",
-        "not", "of", "on", "or", "such", "that", "the", "their", "then", "there", "these", "they", "this",
-        "to", "was", "will", "with"
-    ]);
+    let stop_words = get_stop_words(lang_config, DEFAULT_STOP_WORDS);
 
     #[cfg(feature = "indexer")]
     let max_term_len = lang_config.options.max_term_len.unwrap_or(80).min(250);
@@ -57,6 +61,13 @@ pub fn new_with_options(lang_config: &InfiLanguageConfig) -> Tokenizer {
     }
 }
 
+/// Creates a tokenizer from `lang_config.analyzers`, which supports all but the `stemmer` token filter
+pub fn new_with_analyzers(lang_config: &InfiLanguageConfig) -> Result<analyzer::Tokenizer, String> {
+    analyzer::new_with_analyzers(lang_config, DEFAULT_STOP_WORDS, None, &|_language| {
+        Err("The stemmer token filter requires the ascii_stemmer language".to_owned())
+    })
+}
+
 #[cfg(feature = "indexer")]
 impl IndexerTokenizer for Tokenizer {
     fn tokenize<'a>(&'a self, text: &'a mut str) -> TermIter<'a> {
@@ -99,7 +110,12 @@ impl SearchTokenizer for Tokenizer {
             utils::split_terms,
         ).collect();
 
+        let mut num_chars = 0;
         for (idx, (char_idx, s)) in split.iter().enumerate() {
+            let span_start = query_chars_offset + num_chars;
+            num_chars += s.chars().count();
+            let query_span = (span_start, query_chars_offset + num_chars);
+
             if s.is_empty() {
                 continue;
             }
@@ -118,6 +134,7 @@ impl SearchTokenizer for Tokenizer {
 
             let original_term = preprocessed.clone().into_owned();
             let mut is_corrected = false;
+            let mut corrected_word = None;
 
             // This comes before spelling correction,
             // as ignore_stop_words removes from the index (won't be present in the dictionary)
@@ -126,9 +143,12 @@ impl SearchTokenizer for Tokenizer {
                     term: None,
                     term_inflections,
                     original_term,
+                    query_span,
                     suffix_wildcard,
                     is_corrected,
+                    corrected_word,
                     prefix_ops,
+                    field_terms: Vec::new(),
                 });
                 continue;
             }
@@ -139,6 +159,7 @@ impl SearchTokenizer for Tokenizer {
                 } else if let Some(corrected_term) = self.best_term_corrector.get_best_corrected_term(dict, &preprocessed) {
                     term_inflections.push(corrected_term.clone());
                     is_corrected = true;
+                    corrected_word = Some(corrected_term.clone());
                     Some(corrected_term)
                 } else {
                     None
@@ -151,9 +172,12 @@ impl SearchTokenizer for Tokenizer {
                 term,
                 term_inflections,
                 original_term,
+                query_span,
                 suffix_wildcard,
                 is_corrected,
+                corrected_word,
                 prefix_ops,
+                field_terms: Vec::new(),
             })
         }
 
@@ -171,4 +195,21 @@ impl SearchTokenizer for Tokenizer {
     fn is_valid_prefix_op_terminator(&self, c: char) -> bool {
         c.is_ascii_whitespace()
     }
+
+    fn fold_term(&self, term: &str) -> Option<String> {
+        let lowercased = term.to_lowercase();
+        let folded = utils::ascii_and_nonword_filter(&mut Vec::new(), &lowercased, utils::term_filter);
+        Some(folded.into_owned()).filter(|folded| !folded.is_empty())
+    }
+
+    fn split_text(&self, _field_name: &str, text: &str) -> Vec<(Range<usize>, String)> {
+        tokenize::split_words(text, utils::split_terms)
+            .into_iter()
+            .filter_map(|range| {
+                let word = text[range.clone()].to_ascii_lowercase();
+                let term = utils::ascii_and_nonword_filter(&mut Vec::new(), &word, utils::term_filter);
+                Some((range, term.into_owned())).filter(|(_range, term)| !term.is_empty())
+            })
+            .collect()
+    }
 }
@@ -1,3 +1,4 @@
+pub mod analyzer;
 pub mod ascii_folding_filter;
 pub mod ascii;
 pub mod spelling;
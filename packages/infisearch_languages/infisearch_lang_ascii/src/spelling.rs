@@ -1,12 +1,17 @@
 use infisearch_common::dictionary::Dictionary;
 
-mod edit_distance;
+use bk_tree::BkTree;
+
+pub mod bk_tree;
+pub mod edit_distance;
 
 const CACHE_SIZE: usize = 8;
 
 pub struct BestTermCorrector {
     cache: [(String, Option<String>); CACHE_SIZE],
     cache_idx: usize,
+    // Decoded on the first correction
+    bk_tree: Option<BkTree>,
 }
 
 impl BestTermCorrector {
@@ -24,6 +29,7 @@ impl BestTermCorrector {
                 ("".to_owned(), None),
             ],
             cache_idx: 0,
+            bk_tree: None,
         }
     }
 
@@ -33,48 +39,22 @@ impl BestTermCorrector {
             return corrected.clone();
         }
 
-        let mut best_term = None;
-        let mut max_doc_freq = 0;
-    
         let base_term_char_count = misspelled_term.chars().count();
-        let mut min_edit_distance: usize = match base_term_char_count {
+        let max_edit_distance: usize = match base_term_char_count {
             0..=4 => 1,
             5..=8 => 2,
             _ => 3,
         };
-    
+
         let mut cache = [255_usize; 255];
-    
-        for (term, term_info) in dict.term_infos.iter() {
-            if term.chars().count().abs_diff(base_term_char_count) > min_edit_distance {
-                continue;
-            }
-    
-            if min_edit_distance == 1 && term_info.doc_freq < max_doc_freq {
-                continue;
-            }
-    
-            let edit_distance = edit_distance::levenshtein(
-                term,
-                misspelled_term,
-                base_term_char_count,
-                &mut cache,
-            );
-            if edit_distance < min_edit_distance {
-                min_edit_distance = edit_distance;
-                max_doc_freq = term_info.doc_freq;
-                best_term = Some(term);
-            } else if edit_distance == min_edit_distance && term_info.doc_freq > max_doc_freq {
-                max_doc_freq = term_info.doc_freq;
-                best_term = Some(term);
-            }
-        }
-    
-        let result = if let Some(best_term) = best_term {
-            let normal_string = std::string::String::from(best_term.as_str());
-            Some(normal_string)
+
+        let result = if dict.spelling_index.is_empty() {
+            get_best_term_linear(dict, misspelled_term, base_term_char_count, max_edit_distance, &mut cache)
         } else {
-            None
+            self.bk_tree
+                .get_or_insert_with(|| BkTree::new(dict))
+                .get_best_term(misspelled_term, base_term_char_count, max_edit_distance, &mut cache)
+                .map(|term| term.to_owned())
         };
 
         unsafe {
@@ -87,3 +67,43 @@ impl BestTermCorrector {
         result
     }
 }
+
+/// Runs the edit distance against every term, for indexes without a spelling index
+fn get_best_term_linear(
+    dict: &Dictionary,
+    misspelled_term: &str,
+    base_term_char_count: usize,
+    max_edit_distance: usize,
+    cache: &mut [usize],
+) -> Option<String> {
+    let mut best_term = None;
+    let mut max_doc_freq = 0;
+    let mut min_edit_distance = max_edit_distance;
+
+    for (term, term_info) in dict.term_infos.iter() {
+        if term.chars().count().abs_diff(base_term_char_count) > min_edit_distance {
+            continue;
+        }
+
+        if min_edit_distance == 1 && term_info.doc_freq < max_doc_freq {
+            continue;
+        }
+
+        let edit_distance = edit_distance::levenshtein(
+            term,
+            misspelled_term,
+            base_term_char_count,
+            cache,
+        );
+        if edit_distance < min_edit_distance {
+            min_edit_distance = edit_distance;
+            max_doc_freq = term_info.doc_freq;
+            best_term = Some(term);
+        } else if edit_distance == min_edit_distance && term_info.doc_freq > max_doc_freq {
+            max_doc_freq = term_info.doc_freq;
+            best_term = Some(term);
+        }
+    }
+
+    best_term.map(|best_term| std::string::String::from(best_term.as_str()))
+}
@@ -0,0 +1,103 @@
+use infisearch_common::dictionary::Dictionary;
+use infisearch_common::utils::varint;
+
+use super::edit_distance;
+
+/// BK-tree of the dictionary's terms, decoded from `Dictionary::spelling_index`.
+///
+/// Format, in variable integers (see spelling_index_writer.rs in the indexer):
+/// root term id, then for each term id in dictionary order,
+/// the number of children, then the edit distance and term id of each child.
+///
+/// Terms are referred to by their id (order in the dictionary),
+/// which is resolved once here into the term strings.
+pub struct BkTree {
+    root: u32,
+    terms: Vec<String>,
+    doc_freqs: Vec<u32>,
+    child_offsets: Vec<u32>,
+    children: Vec<(usize, u32)>,
+}
+
+impl BkTree {
+    pub fn new(dict: &Dictionary) -> Self {
+        let (terms, doc_freqs): (Vec<_>, Vec<_>) = dict.term_infos.iter()
+            .map(|(term, term_info)| (String::from(term.as_str()), term_info.doc_freq))
+            .unzip();
+
+        let raw = &dict.spelling_index;
+        let mut pos = 0;
+        let root = varint::decode_var_int(raw, &mut pos);
+
+        let mut child_offsets = Vec::with_capacity(doc_freqs.len() + 1);
+        let mut children = Vec::with_capacity(doc_freqs.len());
+        child_offsets.push(0);
+        for _term_id in 0..doc_freqs.len() {
+            let num_children = varint::decode_var_int(raw, &mut pos);
+            for _i in 0..num_children {
+                let edit_distance = varint::decode_var_int(raw, &mut pos) as usize;
+                children.push((edit_distance, varint::decode_var_int(raw, &mut pos)));
+            }
+            child_offsets.push(children.len() as u32);
+        }
+
+        BkTree { root, terms, doc_freqs, child_offsets, children }
+    }
+
+    /// Finds the term with the smallest edit distance no greater than `max_edit_distance`,
+    /// breaking ties by the highest document frequency, then the term's order in the dictionary.
+    pub fn get_best_term(
+        &self,
+        misspelled_term: &str,
+        misspelled_term_char_count: usize,
+        max_edit_distance: usize,
+        cache: &mut [usize],
+    ) -> Option<&str> {
+        if self.terms.is_empty() {
+            return None;
+        }
+
+        let mut max_edit_distance = max_edit_distance;
+        // (edit distance, doc freq, term id)
+        let mut best: Option<(usize, u32, u32)> = None;
+        let mut stack = vec![self.root];
+
+        while let Some(term_id) = stack.pop() {
+            let term = &self.terms[term_id as usize];
+            let doc_freq = &self.doc_freqs[term_id as usize];
+            let edit_distance = edit_distance::levenshtein(
+                term,
+                misspelled_term,
+                misspelled_term_char_count,
+                cache,
+            );
+
+            if edit_distance <= max_edit_distance {
+                let is_better = best.is_none_or(|(best_edit_distance, best_doc_freq, best_term_id)| {
+                    edit_distance < best_edit_distance
+                        || (edit_distance == best_edit_distance && (
+                            *doc_freq > best_doc_freq
+                            || (*doc_freq == best_doc_freq && term_id < best_term_id)
+                        ))
+                });
+
+                if is_better {
+                    best = Some((edit_distance, *doc_freq, term_id));
+                    // Terms with a larger edit distance can no longer be better
+                    max_edit_distance = edit_distance;
+                }
+            }
+
+            let start = self.child_offsets[term_id as usize] as usize;
+            let end = self.child_offsets[term_id as usize + 1] as usize;
+            for (child_edit_distance, child_term_id) in &self.children[start..end] {
+                // Triangle inequality
+                if child_edit_distance.abs_diff(edit_distance) <= max_edit_distance {
+                    stack.push(*child_term_id);
+                }
+            }
+        }
+
+        best.map(|(_edit_distance, _doc_freq, term_id)| self.terms[term_id as usize].as_str())
+    }
+}
@@ -1,6 +1,7 @@
 use std::borrow::Cow;
 #[cfg(feature = "indexer")]
 use std::collections::HashSet;
+use std::ops::Range;
 
 use rust_stemmers::{Algorithm, Stemmer};
 
@@ -16,6 +17,8 @@ use infisearch_lang_ascii::ascii_folding_filter;
 use infisearch_lang_ascii::{utils as ascii_utils, spelling::BestTermCorrector};
 #[cfg(feature = "indexer")]
 use infisearch_lang_ascii::ascii::SENTENCE_SPLITTER;
+use infisearch_lang_ascii::analyzer::{self, TermStemmer};
+use infisearch_lang_ascii::ascii::DEFAULT_STOP_WORDS;
 use infisearch_lang_ascii::stop_words::get_stop_words;
 #[cfg(feature = "indexer")]
 use infisearch_lang_ascii::utils::term_filter;
@@ -38,39 +41,41 @@ pub struct Tokenizer {
     best_term_corrector: BestTermCorrector,
 }
 
+fn get_algorithm(language: &str) -> Option<Algorithm> {
+    match language.to_lowercase().as_str() {
+        "arabic" => Some(Algorithm::Arabic),
+        "danish" => Some(Algorithm::Danish),
+        "dutch" => Some(Algorithm::Dutch),
+        "english" => Some(Algorithm::English),
+        "finnish" => Some(Algorithm::Finnish),
+        "french" => Some(Algorithm::French),
+        "german" => Some(Algorithm::German),
+        "greek" => Some(Algorithm::Greek),
+        "hungarian" => Some(Algorithm::Hungarian),
+        "italian" => Some(Algorithm::Italian),
+        "norwegian" => Some(Algorithm::Norwegian),
+        "portuguese" => Some(Algorithm::Portuguese),
+        "romanian" => Some(Algorithm::Romanian),
+        "russian" => Some(Algorithm::Russian),
+        "spanish" => Some(Algorithm::Spanish),
+        "swedish" => Some(Algorithm::Swedish),
+        "tamil" => Some(Algorithm::Tamil),
+        "turkish" => Some(Algorithm::Turkish),
+        _ => None,
+    }
+}
+
+fn get_options_algorithm(lang_config: &InfiLanguageConfig) -> Algorithm {
+    lang_config.options.stemmer
+        .as_deref()
+        .and_then(get_algorithm)
+        .unwrap_or(Algorithm::English)
+}
+
 pub fn new_with_options(lang_config: &InfiLanguageConfig) -> Tokenizer {
-    let stop_words = get_stop_words(lang_config, &[
-        // Same list from tantivy
-        "a", "an", "and", "are", "as", "at", "be", "but", "by", "for", "if", "in", "into", "is", "it", "no",
-        "not", "of", "on", "or", "such", "that", "the", "their", "then", "there", "these", "they", "this",
-        "to", "was", "will", "with"
-    ]);
-
-    let stemmer = if let Some(stemmer) = &lang_config.options.stemmer {
-        match stemmer.to_lowercase().as_str() {
-            "arabic" => Stemmer::create(Algorithm::Arabic),
-            "danish" => Stemmer::create(Algorithm::Danish),
-            "dutch" => Stemmer::create(Algorithm::Dutch),
-            "english" => Stemmer::create(Algorithm::English),
-            "finnish" => Stemmer::create(Algorithm::Finnish),
-            "french" => Stemmer::create(Algorithm::French),
-            "german" => Stemmer::create(Algorithm::German),
-            "greek" => Stemmer::create(Algorithm::Greek),
-            "hungarian" => Stemmer::create(Algorithm::Hungarian),
-            "italian" => Stemmer::create(Algorithm::Italian),
-            "norwegian" => Stemmer::create(Algorithm::Norwegian),
-            "portuguese" => Stemmer::create(Algorithm::Portuguese),
-            "romanian" => Stemmer::create(Algorithm::Romanian),
-            "russian" => Stemmer::create(Algorithm::Russian),
-            "spanish" => Stemmer::create(Algorithm::Spanish),
-            "swedish" => Stemmer::create(Algorithm::Swedish),
-            "tamil" => Stemmer::create(Algorithm::Tamil),
-            "turkish" => Stemmer::create(Algorithm::Turkish),
-            _ => Stemmer::create(Algorithm::English),
-        }
-    } else {
-        Stemmer::create(Algorithm::English)
-    };
+    let stop_words = get_stop_words(lang_config, DEFAULT_STOP_WORDS);
+
+    let stemmer = Stemmer::create(get_options_algorithm(lang_config));
 
     #[cfg(feature = "indexer")]
     let max_term_len = lang_config.options.max_term_len.unwrap_or(80).min(250);
@@ -85,6 +90,28 @@ pub fn new_with_options(lang_config: &InfiLanguageConfig) -> Tokenizer {
     }
 }
 
+struct SnowballStemmer(Stemmer);
+
+impl TermStemmer for SnowballStemmer {
+    fn stem<'a>(&self, term: &'a str) -> Cow<'a, str> {
+        self.0.stem(term)
+    }
+}
+
+/// Creates a tokenizer from `lang_config.analyzers`.
+///
+/// Without a default analyzer, other fields are stemmed with the `stemmer` option.
+pub fn new_with_analyzers(lang_config: &InfiLanguageConfig) -> Result<analyzer::Tokenizer, String> {
+    let default_stemmer = SnowballStemmer(Stemmer::create(get_options_algorithm(lang_config)));
+
+    analyzer::new_with_analyzers(lang_config, DEFAULT_STOP_WORDS, Some(Box::new(default_stemmer)), &|language| {
+        match get_algorithm(language) {
+            Some(algorithm) => Ok(Box::new(SnowballStemmer(Stemmer::create(algorithm))) as Box<dyn TermStemmer>),
+            None => Err(format!("Unsupported stemmer language {}", language)),
+        }
+    })
+}
+
 #[cfg(feature = "indexer")]
 impl IndexerTokenizer for Tokenizer {
     fn tokenize<'a>(&'a self, text: &'a mut str) -> TermIter<'a> {
@@ -133,7 +160,12 @@ impl SearchTokenizer for Tokenizer {
             ascii_utils::split_terms,
         ).collect();
 
+        let mut num_chars = 0;
         for (idx, (char_idx, s)) in split.iter().enumerate() {
+            let span_start = query_chars_offset + num_chars;
+            num_chars += s.chars().count();
+            let query_span = (span_start, query_chars_offset + num_chars);
+
             if s.is_empty() {
                 continue;
             }
@@ -159,6 +191,7 @@ impl SearchTokenizer for Tokenizer {
 
             let original_term = stemmed.clone().into_owned();
             let mut is_corrected = false;
+            let mut corrected_word = None;
 
             // This comes before spelling correction,
             // as ignore_stop_words removes from the index (won't be present in the dictionary)
@@ -167,9 +200,12 @@ impl SearchTokenizer for Tokenizer {
                     term: None,
                     term_inflections,
                     original_term,
+                    query_span,
                     suffix_wildcard,
                     is_corrected,
+                    corrected_word,
                     prefix_ops,
+                    field_terms: Vec::new(),
                 });
                 continue;
             }
@@ -180,6 +216,10 @@ impl SearchTokenizer for Tokenizer {
                 } else if let Some(corrected_term) = self.best_term_corrector.get_best_corrected_term(dict, &stemmed) {
                     term_inflections.push(corrected_term.clone());
                     is_corrected = true;
+                    corrected_word = Some(tokenize::get_corrected_word(s, &original_term, &corrected_term, |word| {
+                        let preprocessed = ascii_utils::ascii_and_nonword_filter(&mut Vec::new(), word, ascii_utils::term_filter);
+                        Some(self.stemmer.stem(&preprocessed).into_owned())
+                    }));
                     Some(corrected_term)
                 } else {
                     None
@@ -192,9 +232,12 @@ impl SearchTokenizer for Tokenizer {
                 term,
                 term_inflections,
                 original_term,
+                query_span,
                 suffix_wildcard,
                 is_corrected,
+                corrected_word,
                 prefix_ops,
+                field_terms: Vec::new(),
             })
         }
 
@@ -212,4 +255,16 @@ impl SearchTokenizer for Tokenizer {
     fn is_valid_prefix_op_terminator(&self, c: char) -> bool {
         c.is_ascii_whitespace()
     }
+
+    fn split_text(&self, _field_name: &str, text: &str) -> Vec<(Range<usize>, String)> {
+        tokenize::split_words(text, ascii_utils::split_terms)
+            .into_iter()
+            .filter_map(|range| {
+                let word = text[range.clone()].to_ascii_lowercase();
+                let preprocessed = ascii_utils::ascii_and_nonword_filter(&mut Vec::new(), &word, ascii_utils::term_filter);
+                let term = self.stemmer.stem(&preprocessed).into_owned();
+                Some((range, term)).filter(|(_range, term)| !term.is_empty())
+            })
+            .collect()
+    }
 }
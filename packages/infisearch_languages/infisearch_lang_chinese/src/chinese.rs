@@ -1,6 +1,7 @@
 use std::borrow::Cow;
 #[cfg(feature = "indexer")]
 use std::collections::HashSet;
+use std::ops::Range;
 
 #[cfg(feature = "indexer")]
 use regex::Regex;
@@ -134,7 +135,12 @@ impl SearchTokenizer for Tokenizer {
             |c| utils::split_terms(c) || utils::is_chinese_char(c),
         ).collect();
 
+        let mut num_chars = 0;
         for (idx, (char_idx, s)) in split.iter().enumerate() {
+            let span_start = query_chars_offset + num_chars;
+            num_chars += s.chars().count();
+            let query_span = (span_start, query_chars_offset + num_chars);
+
             if s.is_empty() {
                 continue;
             }
@@ -155,15 +161,19 @@ impl SearchTokenizer for Tokenizer {
 
             let original_term = preprocessed.clone().into_owned();
             let mut is_corrected = false;
+            let mut corrected_word = None;
 
             if self.ignore_stop_words && self.is_stop_word(&preprocessed) {
                 terms.push(SearchTokenizeTerm {
                     term: None,
                     term_inflections,
                     original_term,
+                    query_span,
                     suffix_wildcard,
                     is_corrected,
+                    corrected_word,
                     prefix_ops,
+                    field_terms: Vec::new(),
                 });
                 continue;
             }
@@ -174,6 +184,7 @@ impl SearchTokenizer for Tokenizer {
                 } else if let Some(corrected_term) = self.best_term_corrector.get_best_corrected_term(dict, &preprocessed) {
                     term_inflections.push(corrected_term.clone());
                     is_corrected = true;
+                    corrected_word = Some(corrected_term.clone());
                     Some(corrected_term)
                 } else {
                     None
@@ -186,9 +197,12 @@ impl SearchTokenizer for Tokenizer {
                 term,
                 term_inflections,
                 original_term,
+                query_span,
                 suffix_wildcard,
                 is_corrected,
+                corrected_word,
                 prefix_ops,
+                field_terms: Vec::new(),
             })
         }
 
@@ -206,12 +220,31 @@ impl SearchTokenizer for Tokenizer {
     fn is_valid_prefix_op_terminator(&self, c: char) -> bool {
         c.is_ascii_whitespace() || utils::is_chinese_char(c)
     }
+
+    fn fold_term(&self, term: &str) -> Option<String> {
+        let lowercased = term.to_lowercase();
+        let folded = ts::normalize(ascii_and_nonword_filter(&mut Vec::new(), &lowercased), None);
+        Some(folded.into_owned()).filter(|folded| !folded.is_empty())
+    }
+
+    fn split_text(&self, _field_name: &str, text: &str) -> Vec<(Range<usize>, String)> {
+        tokenize::split_words(text, |c| utils::split_terms(c) || utils::is_chinese_char(c))
+            .into_iter()
+            .filter_map(|range| {
+                let word = text[range.clone()].to_ascii_lowercase();
+                let term = ts::normalize(ascii_and_nonword_filter(&mut Vec::new(), &word), None);
+                Some((range, term.into_owned())).filter(|(_range, term)| !term.is_empty())
+            })
+            .collect()
+    }
 }
 
 
 #[cfg(test)]
 mod test {
-    use infisearch_common::language::{InfiLanguageConfig, InfiLanguageConfigOpts};
+    use std::collections::BTreeMap;
+
+    use infisearch_common::language::{InfiLanguageConfig, InfiLanguageConfigOpts, InfiSynonymsConfig};
     use infisearch_lang_ascii::spelling::BestTermCorrector;
 
     use super::Tokenizer;
@@ -221,6 +254,8 @@ mod test {
         let lang_config = InfiLanguageConfig {
             lang: "chinese".to_owned(),
             options: InfiLanguageConfigOpts::default(),
+            analyzers: BTreeMap::new(),
+            synonyms: InfiSynonymsConfig::default(),
         };
 
         let stop_words = infisearch_lang_ascii::stop_words::get_stop_words(&lang_config, &[]);
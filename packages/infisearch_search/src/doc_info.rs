@@ -1,8 +1,12 @@
 use infisearch_common::metadata::{MetadataReader, EnumMax};
 use infisearch_common::utils::push;
 
+#[derive(Clone)]
 pub struct DocInfo {
     pub doc_length_factors: Vec<f64>,
+    /// Only needed to rescale the factors for federated search
+    #[cfg(feature = "native")]
+    pub avg_doc_lengths: Vec<f64>,
     pub doc_length_factors_len: u32,
     pub doc_enum_vals: Vec<EnumMax>,
     pub doc_i64_vals: Vec<i64>,
@@ -42,6 +46,8 @@ impl DocInfo {
 
         DocInfo {
             doc_length_factors,
+            #[cfg(feature = "native")]
+            avg_doc_lengths,
             doc_length_factors_len: doc_id_counter,
             doc_enum_vals,
             doc_i64_vals,
@@ -52,6 +58,27 @@ impl DocInfo {
         }
     }
 
+    /// Replaces the number of documents and average field lengths used in scoring,
+    /// rescaling the document length factors accordingly.
+    #[cfg(feature = "native")]
+    pub fn set_collection_stats(&mut self, num_docs: u32, avg_doc_lengths: Vec<f64>) {
+        let rescale_factors: Vec<f64> = self.avg_doc_lengths.iter()
+            .zip(avg_doc_lengths.iter())
+            .map(|(&old_avg, &new_avg)| if old_avg > 0.0 && new_avg > 0.0 { old_avg / new_avg } else { 1.0 })
+            .collect();
+
+        if self.num_fields > 0 {
+            for doc_length_factors in self.doc_length_factors.chunks_exact_mut(self.num_fields) {
+                for (doc_length_factor, rescale_factor) in doc_length_factors.iter_mut().zip(rescale_factors.iter()) {
+                    *doc_length_factor *= rescale_factor;
+                }
+            }
+        }
+
+        self.num_docs = num_docs;
+        self.avg_doc_lengths = avg_doc_lengths;
+    }
+
     #[inline(always)]
     pub fn get_doc_length_factor(&self, doc_id: usize, field_id: usize) -> f32 {
         debug_assert!(((doc_id * self.num_fields) + field_id) < self.doc_length_factors.len());
@@ -72,7 +99,7 @@ impl DocInfo {
 
     #[inline(always)]
     pub fn get_num_val(&self, doc_id: usize, num_id: usize) -> i64 {
-        debug_assert!(((doc_id * self.num_i64_fields) + num_id) < self.doc_enum_vals.len());
+        debug_assert!(((doc_id * self.num_i64_fields) + num_id) < self.doc_i64_vals.len());
 
         unsafe {
             *self.doc_i64_vals.get_unchecked((doc_id * self.num_i64_fields) + num_id)
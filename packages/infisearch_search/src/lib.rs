@@ -4,3 +4,15 @@ mod postings_list;
 mod postings_list_cache;
 mod searcher;
 mod utils;
+
+pub use searcher::tokenizer_registry;
+#[cfg(feature = "native")]
+pub use searcher::federated;
+#[cfg(feature = "native")]
+pub use searcher::native;
+#[cfg(feature = "native")]
+pub use searcher::completions::{Completions, PhraseCompletion, TermCompletion};
+#[cfg(feature = "native")]
+pub use searcher::query::{DocResult, Query, SpellingCorrection, Suggestion};
+#[cfg(feature = "native")]
+pub use searcher::query_parser::{QueryPart, QueryPartType};
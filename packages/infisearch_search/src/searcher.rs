@@ -1,14 +1,29 @@
+pub mod completions;
 pub mod query;
 pub mod query_parser;
 pub mod query_preprocessor;
 pub mod query_processor;
 pub mod query_retriever;
+#[cfg(feature = "native")]
+pub mod snippet;
+pub mod tokenizer_registry;
 mod futures;
+#[cfg(feature = "native")]
+pub mod federated;
+#[cfg(feature = "native")]
+pub mod native;
 
 use byteorder::ByteOrder;
 use byteorder::LittleEndian;
 use infisearch_common::metadata::{EnumMax, MetadataReader};
-use infisearch_common::language::InfiLanguageConfigOpts;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use infisearch_common::language::{
+    InfiAnalyzerConfig, InfiAnalyzerTokenizer, InfiCharFilter, InfiLanguageConfigOpts, InfiSynonymsConfig,
+    InfiTokenFilter,
+};
+use infisearch_common::synonyms::Synonyms;
 
 use infisearch_common::utils::push;
 use wasm_bindgen::prelude::wasm_bindgen;
@@ -19,6 +34,8 @@ use wasm_bindgen::JsValue;
 use crate::dictionary::Dictionary;
 use crate::doc_info::DocInfo;
 use crate::postings_list_cache::PostingsListCache;
+use crate::searcher::query_processor::SortKey;
+use crate::searcher::tokenizer_registry::TokenizerRegistry;
 use crate::utils;
 
 #[cfg(feature = "lang_ascii")]
@@ -60,39 +77,70 @@ struct SearcherOptions {
     pub use_query_term_proximity: bool,
     pl_lazy_cache_threshold: u32,
     result_limit: Option<u32>,
+    // Searches the original query, only suggesting spelling corrections
+    spelling_suggestions_only: bool,
 }
 
 #[wasm_bindgen]
 pub struct Searcher {
     dictionary: Dictionary,
     tokenizer: Box<dyn SearchTokenizer>,
-    doc_info: DocInfo,
+    // Synonyms expanded at query time
+    synonyms: Synonyms,
+    // Shared with the Query and its result heap comparator
+    doc_info: Rc<DocInfo>,
     searcher_config: SearcherConfig,
     invalidation_vector: Vec<u8>,
     postings_list_cache: PostingsListCache,
+    // Loaded on the first completion, see `set_completion_phrases`
+    completion_phrases: Option<completions::CompletionPhrases>,
 
     // For soft dismax scoring
     num_scored_fields_less_one: f32,
 }
 
+fn get_tokenizer(
+    lang_config: &InfiLanguageConfig,
+    custom_tokenizers: &TokenizerRegistry,
+) -> Result<Box<dyn SearchTokenizer>, String> {
+    match custom_tokenizers.create_tokenizer(lang_config) {
+        Some(tokenizer) => Ok(tokenizer),
+        None => get_builtin_tokenizer(lang_config),
+    }
+}
+
 #[cfg(feature = "lang_ascii")]
-fn get_tokenizer(lang_config: &InfiLanguageConfig) -> Box<dyn SearchTokenizer> {
-    Box::new(ascii::new_with_options(lang_config))
+fn get_builtin_tokenizer(lang_config: &InfiLanguageConfig) -> Result<Box<dyn SearchTokenizer>, String> {
+    if lang_config.analyzers.is_empty() {
+        Ok(Box::new(ascii::new_with_options(lang_config)))
+    } else {
+        Ok(Box::new(ascii::new_with_analyzers(lang_config)?))
+    }
 }
 
 #[cfg(feature = "lang_ascii_stemmer")]
-fn get_tokenizer(lang_config: &InfiLanguageConfig) -> Box<dyn SearchTokenizer> {
-    Box::new(ascii_stemmer::new_with_options(lang_config))
+fn get_builtin_tokenizer(lang_config: &InfiLanguageConfig) -> Result<Box<dyn SearchTokenizer>, String> {
+    if lang_config.analyzers.is_empty() {
+        Ok(Box::new(ascii_stemmer::new_with_options(lang_config)))
+    } else {
+        Ok(Box::new(ascii_stemmer::new_with_analyzers(lang_config)?))
+    }
 }
 
 #[cfg(feature = "lang_chinese")]
-fn get_tokenizer(lang_config: &InfiLanguageConfig) -> Box<dyn SearchTokenizer> {
-    Box::new(chinese::new_with_options(lang_config))
+fn get_builtin_tokenizer(lang_config: &InfiLanguageConfig) -> Result<Box<dyn SearchTokenizer>, String> {
+    Ok(Box::new(chinese::new_with_options(lang_config)))
+}
+
+// For builds with only custom tokenizers
+#[cfg(not(any(feature = "lang_ascii", feature = "lang_ascii_stemmer", feature = "lang_chinese")))]
+fn get_builtin_tokenizer(lang_config: &InfiLanguageConfig) -> Result<Box<dyn SearchTokenizer>, String> {
+    Err(format!("No tokenizer registered for {}", lang_config.lang))
 }
 
 #[allow(clippy::too_many_arguments)]
 #[allow(dead_code)]
-#[wasm_bindgen]
+#[cfg_attr(not(feature = "custom_tokenizers"), wasm_bindgen)]
 pub fn get_new_searcher(
     metadata_buf: JsValue,
     num_pls_per_dir: u32,
@@ -102,6 +150,60 @@ pub fn get_new_searcher(
     ignore_stop_words: Option<bool>,
     stemmer: Option<String>,
     max_term_len: Option<usize>,
+    analyzers_raw: JsValue, // custom uint8array, serialized in workerSearcher.ts
+    synonyms_raw: JsValue, // custom uint8array, serialized in workerSearcher.ts
+    field_infos_raw: JsValue, // custom uint8array, serialized in workerSearcher.ts
+    num_scored_fields: usize,
+    url: String,
+    inner_url: String,
+    max_auto_suffix_search_terms: usize,
+    max_suffix_search_terms: usize,
+    use_query_term_proximity: bool,
+    pl_lazy_cache_threshold: u32,
+    result_limit: Option<u32>,
+    spelling_suggestions_only: bool,
+) -> Result<Searcher, JsValue> {
+    get_new_searcher_with_tokenizers(
+        metadata_buf,
+        num_pls_per_dir,
+        with_positions,
+        lang,
+        stop_words,
+        ignore_stop_words,
+        stemmer,
+        max_term_len,
+        analyzers_raw,
+        synonyms_raw,
+        field_infos_raw,
+        num_scored_fields,
+        url,
+        inner_url,
+        max_auto_suffix_search_terms,
+        max_suffix_search_terms,
+        use_query_term_proximity,
+        pl_lazy_cache_threshold,
+        result_limit,
+        spelling_suggestions_only,
+        &TokenizerRegistry::default(),
+    )
+}
+
+/// `get_new_searcher`, with custom tokenizers taking precedence over the built-in ones.
+///
+/// WebAssembly builds using custom tokenizers enable the `custom_tokenizers` feature,
+/// which leaves out this crate's `get_new_searcher` export, and export their own calling this instead.
+#[allow(clippy::too_many_arguments)]
+pub fn get_new_searcher_with_tokenizers(
+    metadata_buf: JsValue,
+    num_pls_per_dir: u32,
+    with_positions: bool,
+    lang: String,
+    stop_words: JsValue,  // serialized in workerSearcher.ts
+    ignore_stop_words: Option<bool>,
+    stemmer: Option<String>,
+    max_term_len: Option<usize>,
+    analyzers_raw: JsValue, // custom uint8array, serialized in workerSearcher.ts
+    synonyms_raw: JsValue, // custom uint8array, serialized in workerSearcher.ts
     field_infos_raw: JsValue, // custom uint8array, serialized in workerSearcher.ts
     num_scored_fields: usize,
     url: String,
@@ -111,7 +213,9 @@ pub fn get_new_searcher(
     use_query_term_proximity: bool,
     pl_lazy_cache_threshold: u32,
     result_limit: Option<u32>,
-) -> Searcher {
+    spelling_suggestions_only: bool,
+    custom_tokenizers: &TokenizerRegistry,
+) -> Result<Searcher, JsValue> {
     #[cfg(feature = "perf")]
     let window: web_sys::Window = js_sys::global().unchecked_into();
     #[cfg(feature = "perf")]
@@ -180,6 +284,16 @@ pub fn get_new_searcher(
                 stemmer,
                 max_term_len,
             },
+            analyzers: if analyzers_raw.is_undefined() {
+                BTreeMap::new()
+            } else {
+                decode_analyzers(&js_sys::Uint8Array::new(&analyzers_raw).to_vec())
+            },
+            synonyms: if synonyms_raw.is_undefined() {
+                InfiSynonymsConfig::default()
+            } else {
+                decode_synonyms(&js_sys::Uint8Array::new(&synonyms_raw).to_vec())
+            },
         },
         field_infos,
         valid_fields,
@@ -192,49 +306,139 @@ pub fn get_new_searcher(
             use_query_term_proximity,
             pl_lazy_cache_threshold,
             result_limit,
+            spelling_suggestions_only,
         }
     };
 
-    let mut metadata_rdr = MetadataReader::new(
-        js_sys::Uint8Array::new(&metadata_buf).to_vec()
-    );
-
-    let mut invalidation_vector = Vec::new();
-    metadata_rdr.get_invalidation_vec(&mut invalidation_vector);
-
-    let doc_info = DocInfo::create(&mut metadata_rdr, searcher_config.num_scored_fields);
-
-    let tokenizer = get_tokenizer(&searcher_config.lang_config);
+    let tokenizer = get_tokenizer(&searcher_config.lang_config, custom_tokenizers).map_err(|e| JsValue::from_str(&e))?;
 
-    let dictionary = metadata_rdr.setup_dictionary();
+    let searcher = Searcher::new(
+        js_sys::Uint8Array::new(&metadata_buf).to_vec(),
+        searcher_config,
+        tokenizer,
+    );
 
     #[cfg(feature = "perf")]
     {
         web_sys::console::log_1(&format!("Finished reading metadata.").into());
         web_sys::console::log_1(
             &format!("Dictionary initial setup took {}, num terms {}",
-            performance.now() - start, dictionary.term_infos.len(),
+            performance.now() - start, searcher.dictionary.term_infos.len(),
         ).into());
         web_sys::console::log_1(
             &format!("Setup took {}", performance.now() - start).into(),
         );
     }
 
-    let num_scored_fields_less_one = if searcher_config.num_scored_fields <= 1 {
-        1.0
-    } else {
-        (searcher_config.num_scored_fields - 1) as f32
-    };
+    Ok(searcher)
+}
 
-    Searcher {
-        dictionary,
-        tokenizer,
-        doc_info,
-        searcher_config,
-        invalidation_vector,
-        postings_list_cache: PostingsListCache::new(),
-        num_scored_fields_less_one
+fn decode_strings(raw: &[u8]) -> Vec<String> {
+    let mut strings = Vec::new();
+    let mut i = 0;
+    while i < raw.len() {
+        let len = LittleEndian::read_u32(&raw[i..]) as usize;
+        i += 4;
+        strings.push(unsafe { std::str::from_utf8_unchecked(&raw[i..i + len]) }.to_owned());
+        i += len;
     }
+    strings
+}
+
+/// Decodes the type and parameters of a char or token filter, which are key value pairs.
+/// Array parameters are joined with '\0'.
+fn decode_filter_params(strings: &mut impl Iterator<Item = String>) -> (String, Vec<(String, String)>) {
+    let filter_type = strings.next().unwrap();
+    let num_params: usize = strings.next().unwrap().parse().unwrap();
+    let params = (0..num_params).map(|_| (strings.next().unwrap(), strings.next().unwrap())).collect();
+    (filter_type, params)
+}
+
+fn get_param(params: &[(String, String)], key: &str) -> Option<String> {
+    params.iter().find(|(k, _v)| k == key).map(|(_k, v)| v.clone())
+}
+
+fn decode_char_filter(strings: &mut impl Iterator<Item = String>) -> InfiCharFilter {
+    let (filter_type, params) = decode_filter_params(strings);
+    match filter_type.as_str() {
+        "lowercase" => InfiCharFilter::Lowercase,
+        "regex_replace" => InfiCharFilter::RegexReplace {
+            pattern: get_param(&params, "pattern").unwrap(),
+            replacement: get_param(&params, "replacement").unwrap_or_default(),
+        },
+        _ => panic!("Unknown char filter {}", filter_type),
+    }
+}
+
+fn decode_token_filter(strings: &mut impl Iterator<Item = String>) -> InfiTokenFilter {
+    let (filter_type, params) = decode_filter_params(strings);
+    let get_usize_param = |key| get_param(&params, key).map(|v| v.parse().unwrap());
+    match filter_type.as_str() {
+        "lowercase" => InfiTokenFilter::Lowercase,
+        "ascii_folding" => InfiTokenFilter::AsciiFolding,
+        "punctuation" => InfiTokenFilter::Punctuation,
+        "stop_words" => InfiTokenFilter::StopWords {
+            words: get_param(&params, "words")
+                .map(|words| words.split('\0').filter(|word| !word.is_empty()).map(|word| word.to_owned()).collect()),
+        },
+        "stemmer" => InfiTokenFilter::Stemmer { language: get_param(&params, "language").unwrap() },
+        "length" => InfiTokenFilter::Length { min: get_usize_param("min"), max: get_usize_param("max") },
+        "regex_replace" => InfiTokenFilter::RegexReplace {
+            pattern: get_param(&params, "pattern").unwrap(),
+            replacement: get_param(&params, "replacement").unwrap_or_default(),
+        },
+        _ => panic!("Unknown token filter {}", filter_type),
+    }
+}
+
+/// Decodes `lang_config.analyzers`, serialized in workerSearcher.ts as u32 length prefixed strings of:
+/// name, tokenizer, number of fields, fields..., number of char filters, char filters...,
+/// number of token filters, token filters..., for each analyzer.
+/// Each filter is its type, number of parameters, then the parameter key value pairs.
+fn decode_analyzers(analyzers_raw: &[u8]) -> BTreeMap<String, InfiAnalyzerConfig> {
+    let mut strings = decode_strings(analyzers_raw).into_iter();
+    let mut analyzers = BTreeMap::new();
+    while let Some(name) = strings.next() {
+        let tokenizer = match strings.next().unwrap().as_str() {
+            "whitespace" => InfiAnalyzerTokenizer::Whitespace,
+            _ => InfiAnalyzerTokenizer::Standard,
+        };
+
+        let num_fields: usize = strings.next().unwrap().parse().unwrap();
+        let fields = (0..num_fields).map(|_| strings.next().unwrap()).collect();
+
+        let num_char_filters: usize = strings.next().unwrap().parse().unwrap();
+        let char_filters = (0..num_char_filters).map(|_| decode_char_filter(&mut strings)).collect();
+
+        let num_token_filters: usize = strings.next().unwrap().parse().unwrap();
+        let token_filters = (0..num_token_filters).map(|_| decode_token_filter(&mut strings)).collect();
+
+        analyzers.insert(name, InfiAnalyzerConfig { char_filters, tokenizer, token_filters, fields });
+    }
+
+    analyzers
+}
+
+fn decode_synonyms_list(strings: &mut impl Iterator<Item = String>) -> Vec<String> {
+    let num_synonyms: usize = strings.next().unwrap().parse().unwrap();
+    (0..num_synonyms).map(|_| strings.next().unwrap()).collect()
+}
+
+/// Decodes `lang_config.synonyms`, serialized in workerSearcher.ts as u32 length prefixed strings of:
+/// number of equivalent sets, then the number of synonyms and synonyms... of each set,
+/// followed by the key, number of synonyms and synonyms... of each one-way mapping.
+fn decode_synonyms(synonyms_raw: &[u8]) -> InfiSynonymsConfig {
+    let mut strings = decode_strings(synonyms_raw).into_iter();
+
+    let num_equivalent: usize = strings.next().unwrap().parse().unwrap();
+    let equivalent = (0..num_equivalent).map(|_| decode_synonyms_list(&mut strings)).collect();
+
+    let mut mappings = BTreeMap::new();
+    while let Some(from) = strings.next() {
+        mappings.insert(from, decode_synonyms_list(&mut strings));
+    }
+
+    InfiSynonymsConfig { equivalent, mappings, expand_at_index_time: false }
 }
 
 #[wasm_bindgen]
@@ -244,6 +448,50 @@ impl Searcher {
     }
 }
 
+impl Searcher {
+    /// Sets up the dictionary, document infos and invalidation vector from the raw metadata file.
+    /// 
+    /// Shared between the WASM (`get_new_searcher`) and native (`native::NativeSearcher`) entry points.
+    fn new(
+        metadata_buf: Vec<u8>,
+        searcher_config: SearcherConfig,
+        tokenizer: Box<dyn SearchTokenizer>,
+    ) -> Searcher {
+        let mut metadata_rdr = MetadataReader::new(metadata_buf);
+
+        let mut invalidation_vector = Vec::new();
+        metadata_rdr.get_invalidation_vec(&mut invalidation_vector);
+
+        let doc_info = Rc::new(DocInfo::create(&mut metadata_rdr, searcher_config.num_scored_fields));
+
+        let dictionary = metadata_rdr.setup_dictionary();
+
+        let synonyms = if searcher_config.lang_config.synonyms.expand_at_index_time {
+            Synonyms::default()
+        } else {
+            Synonyms::for_query(&searcher_config.lang_config.synonyms)
+        };
+
+        let num_scored_fields_less_one = if searcher_config.num_scored_fields <= 1 {
+            1.0
+        } else {
+            (searcher_config.num_scored_fields - 1) as f32
+        };
+
+        Searcher {
+            dictionary,
+            tokenizer,
+            synonyms,
+            doc_info,
+            searcher_config,
+            invalidation_vector,
+            postings_list_cache: PostingsListCache::new(),
+            completion_phrases: None,
+            num_scored_fields_less_one
+        }
+    }
+}
+
 #[allow(dead_code)]
 #[wasm_bindgen]
 pub async fn get_query(searcher: *mut Searcher, params_raw: JsValue) -> Result<query::Query, JsValue> {
@@ -343,23 +591,75 @@ pub async fn get_query(searcher: *mut Searcher, params_raw: JsValue) -> Result<q
 
     // -----------------------------------
     // Sort parameters
-    let has_sort = unsafe { *params_raw.get_unchecked(params_raw_pos) } == 1;
+
+    // Format:
+    // num sort keys (1 byte)
+    //   sort key type (1 byte) - 0 for score, 1 for i64 fields, 2 for enum fields
+    //   i64 / enum id (1 byte), unused for score
+    //   is ascending (1 byte)
+    let num_sort_keys = unsafe { *params_raw.get_unchecked(params_raw_pos) } as usize;
+    params_raw_pos += 1;
+
+    let mut sort_keys = Vec::with_capacity(num_sort_keys);
+    for _i in 0..num_sort_keys {
+        let sort_key_type = unsafe { *params_raw.get_unchecked(params_raw_pos) };
+        let id = unsafe { *params_raw.get_unchecked(params_raw_pos + 1) } as usize;
+        let ascending = unsafe { *params_raw.get_unchecked(params_raw_pos + 2) } == 1;
+        params_raw_pos += 3;
+
+        let sort_key = match sort_key_type {
+            1 => SortKey::I64(id),
+            2 => SortKey::Enum(id),
+            _ => SortKey::Score,
+        };
+        push::push_wo_grow(&mut sort_keys, (sort_key, ascending));
+    }
+
+    // -----------------------------------
+    // Facets
+
+    // Format:
+    // num enum facets (1 byte)
+    //   enum id (1 byte)
+    // num i64 facets (1 byte)
+    //   i64 id (1 byte)
+    //   number of bucket boundaries (1 byte)
+    //   bucket boundaries (times number of boundaries) (8 bytes each)
+    let num_enum_facets = unsafe { *params_raw.get_unchecked(params_raw_pos) } as usize;
     params_raw_pos += 1;
-    let number_sort = if has_sort {
-        let number_sort = Some(unsafe { *params_raw.get_unchecked(params_raw_pos) } as usize);
+
+    let mut enum_facets = Vec::with_capacity(num_enum_facets);
+    for _i in 0..num_enum_facets {
+        push::push_wo_grow(&mut enum_facets, unsafe { *params_raw.get_unchecked(params_raw_pos) } as usize);
         params_raw_pos += 1;
-        number_sort
-    } else {
-        None
-    };
+    }
+
+    let num_i64_facets = unsafe { *params_raw.get_unchecked(params_raw_pos) } as usize;
+    params_raw_pos += 1;
 
-    let reverse_sort = unsafe { *params_raw.get_unchecked(params_raw_pos) } == 1;
+    let mut i64_facets = Vec::with_capacity(num_i64_facets);
+    for _i in 0..num_i64_facets {
+        let i64_id = unsafe { *params_raw.get_unchecked(params_raw_pos) } as usize;
+        params_raw_pos += 1;
+        let num_boundaries = unsafe { *params_raw.get_unchecked(params_raw_pos) } as usize;
+        params_raw_pos += 1;
+
+        let mut boundaries = Vec::with_capacity(num_boundaries);
+        for _j in 0..num_boundaries {
+            push::push_wo_grow(&mut boundaries, LittleEndian::read_i64(
+                unsafe { params_raw.get_unchecked(params_raw_pos..) }
+            ));
+            params_raw_pos += 8;
+        }
+
+        push::push_wo_grow(&mut i64_facets, (i64_id, boundaries));
+    }
 
     // --------------------------------------------------------------------------
 
     let searcher_val = unsafe { &mut *searcher };
     let mut query_parts = query_parser::parse_query(
-        query_string,
+        query_string.clone(),
         &mut *searcher_val.tokenizer,
         &searcher_val.searcher_config.valid_fields,
         searcher_val.searcher_config.indexing_config.with_positions,
@@ -369,7 +669,7 @@ pub async fn get_query(searcher: *mut Searcher, params_raw: JsValue) -> Result<q
     #[cfg(feature = "perf")]
     web_sys::console::log_1(&format!("parse query took {}", performance.now() - start).into());
 
-    searcher_val.expand_term_postings_lists(&mut query_parts);
+    let spelling_corrections = searcher_val.expand_term_postings_lists(&mut query_parts);
 
     #[cfg(feature = "perf")]
     web_sys::console::log_1(&format!("Preprocess took {}", performance.now() - start).into());
@@ -380,14 +680,16 @@ pub async fn get_query(searcher: *mut Searcher, params_raw: JsValue) -> Result<q
     web_sys::console::log_1(&format!("Population took {}", performance.now() - start).into());
 
     let result_heap = searcher_val.process_and_rank(
-        &mut query_parts, &term_pls, enum_filters, i64_filters, number_sort, reverse_sort,
+        &mut query_parts, &term_pls, enum_filters, i64_filters, sort_keys,
     );
 
     #[cfg(feature = "perf")]
     web_sys::console::log_1(&format!("Process took {}", performance.now() - start).into());
 
     let result_limit = searcher_val.searcher_config.searcher_options.result_limit;
-    let query = searcher_val.create_query(query_parts, result_heap, result_limit);
+    let query = searcher_val.create_query(
+        &query_string, query_parts, spelling_corrections, result_heap, result_limit, &enum_facets, &i64_facets,
+    );
 
     Ok(query)
 }
@@ -395,8 +697,13 @@ pub async fn get_query(searcher: *mut Searcher, params_raw: JsValue) -> Result<q
 #[cfg(test)]
 pub mod test {
     use std::collections::BTreeMap;
+    use std::rc::Rc;
 
-    use infisearch_common::language::{InfiLanguageConfig, InfiLanguageConfigOpts};
+    use infisearch_common::language::{
+        InfiAnalyzerTokenizer, InfiCharFilter, InfiLanguageConfig, InfiLanguageConfigOpts, InfiSynonymsConfig,
+        InfiTokenFilter,
+    };
+    use infisearch_common::synonyms::Synonyms;
     use infisearch_lang_ascii::ascii;
 
     use super::{FieldInfo, IndexingConfig, Searcher, SearcherConfig, SearcherOptions};
@@ -420,13 +727,18 @@ pub mod test {
         }
 
         Searcher {
-            dictionary: Dictionary { term_infos: BTreeMap::default() },
+            dictionary: Dictionary { term_infos: BTreeMap::default(), spelling_index: Vec::new() },
             tokenizer: Box::new(ascii::new_with_options(&InfiLanguageConfig {
                 lang: "ascii".to_owned(),
                 options: InfiLanguageConfigOpts::default(),
+                analyzers: BTreeMap::new(),
+                synonyms: InfiSynonymsConfig::default(),
             })),
-            doc_info: DocInfo {
+            synonyms: Synonyms::default(),
+            doc_info: Rc::new(DocInfo {
                 doc_length_factors: vec![1.0; num_docs * num_fields],
+                #[cfg(feature = "native")]
+                avg_doc_lengths: vec![1.0; num_fields],
                 doc_length_factors_len: num_docs as u32,
                 doc_enum_vals: Vec::new(),
                 doc_i64_vals: Vec::new(),
@@ -434,7 +746,7 @@ pub mod test {
                 num_fields,
                 num_enum_fields: 0,
                 num_i64_fields: 0,
-            },
+            }),
             searcher_config: SearcherConfig {
                 indexing_config: IndexingConfig {
                     num_pls_per_dir: 0,
@@ -443,6 +755,8 @@ pub mod test {
                 lang_config: InfiLanguageConfig {
                     lang: "ascii_stemmer".to_owned(),
                     options: InfiLanguageConfigOpts::default(),
+                    analyzers: BTreeMap::new(),
+                    synonyms: InfiSynonymsConfig::default(),
                 },
                 field_infos,
                 valid_fields,
@@ -455,11 +769,66 @@ pub mod test {
                     use_query_term_proximity: true,
                     pl_lazy_cache_threshold: 0,
                     result_limit: None,
+                    spelling_suggestions_only: false,
                 },
             },
             invalidation_vector: vec![0; num_docs],
             postings_list_cache: PostingsListCache::new(),
+            completion_phrases: None,
             num_scored_fields_less_one: 1.0
         }
     }
+
+    fn encode_strings(strings: &[&str]) -> Vec<u8> {
+        let mut raw = Vec::new();
+        for string in strings {
+            raw.extend_from_slice(&(string.len() as u32).to_le_bytes());
+            raw.extend_from_slice(string.as_bytes());
+        }
+        raw
+    }
+
+    #[test]
+    fn test_decode_analyzers() {
+        let strings = [
+            "code", "whitespace", "1", "code",
+            "1", "regex_replace", "2", "pattern", "c\\+\\+", "replacement", "cplusplus",
+            "2", "stop_words", "1", "words", "foo\0bar", "length", "1", "max", "10",
+            "default", "standard", "0", "0", "1", "stop_words", "0",
+        ];
+
+        let analyzers = super::decode_analyzers(&encode_strings(&strings));
+        assert_eq!(analyzers.len(), 2);
+
+        let code = &analyzers["code"];
+        assert!(matches!(code.tokenizer, InfiAnalyzerTokenizer::Whitespace));
+        assert_eq!(code.fields, vec!["code".to_owned()]);
+        assert!(matches!(
+            &code.char_filters[..],
+            [InfiCharFilter::RegexReplace { pattern, replacement }] if pattern == "c\\+\\+" && replacement == "cplusplus",
+        ));
+        assert!(matches!(
+            &code.token_filters[..],
+            [InfiTokenFilter::StopWords { words: Some(words) }, InfiTokenFilter::Length { min: None, max: Some(10) }]
+                if words == &["foo".to_owned(), "bar".to_owned()],
+        ));
+
+        let default = &analyzers["default"];
+        assert!(matches!(default.tokenizer, InfiAnalyzerTokenizer::Standard));
+        assert!(matches!(&default.token_filters[..], [InfiTokenFilter::StopWords { words: None }]));
+    }
+
+    #[test]
+    fn test_decode_synonyms() {
+        let strings = [
+            "2", "2", "car", "motor vehicl", "1", "tv",
+            "laptop", "1", "notebook",
+        ];
+
+        let synonyms = super::decode_synonyms(&encode_strings(&strings));
+        assert_eq!(synonyms.equivalent, vec![vec!["car".to_owned(), "motor vehicl".to_owned()], vec!["tv".to_owned()]]);
+        assert_eq!(synonyms.mappings.len(), 1);
+        assert_eq!(synonyms.mappings["laptop"], vec!["notebook".to_owned()]);
+        assert!(!synonyms.expand_at_index_time);
+    }
 }
@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+use std::ops::Bound::{Excluded, Unbounded};
+
+use infisearch_common::bitmap;
+use infisearch_common::completions::{self, CompletionPhrase};
+use smartstring::alias::String as SmartString;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::searcher::query_parser::wrap_string;
+use crate::searcher::Searcher;
+
+// Phrases starting with the query are preferred over those that merely contain it
+const PHRASE_START_BOOST: f32 = 2.0;
+
+/// The query with its last, partially typed term completed
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct TermCompletion {
+    pub query: String,
+    pub term: String,
+    pub doc_freq: u32,
+}
+
+/// A text of a field with completions enabled, containing the query
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct PhraseCompletion {
+    pub phrase: String,
+    pub field_name: String,
+    /// Number of documents with this phrase
+    pub num_docs: u32,
+}
+
+pub struct Completions {
+    pub terms: Vec<TermCompletion>,
+    pub phrases: Vec<PhraseCompletion>,
+}
+
+impl Completions {
+    fn serialize(&self) -> String {
+        let terms: Vec<String> = self.terms.iter()
+            .map(|completion| format!(
+                r#"{{"query":{},"term":{},"docFreq":{}}}"#,
+                wrap_string(&completion.query),
+                wrap_string(&completion.term),
+                completion.doc_freq,
+            ))
+            .collect();
+
+        let phrases: Vec<String> = self.phrases.iter()
+            .map(|completion| format!(
+                r#"{{"phrase":{},"fieldName":{},"numDocs":{}}}"#,
+                wrap_string(&completion.phrase),
+                wrap_string(&completion.field_name),
+                completion.num_docs,
+            ))
+            .collect();
+
+        format!(r#"{{"terms":[{}],"phrases":[{}]}}"#, terms.join(","), phrases.join(","))
+    }
+}
+
+pub struct IndexedPhrase {
+    // For case insensitive matching
+    lowercased: String,
+    phrase: CompletionPhrase,
+}
+
+pub struct CompletionPhrases {
+    phrases: Vec<IndexedPhrase>,
+    /// Lowercased words of the phrases keyed by their folded form, where it differs,
+    /// to complete terms to words as they were written
+    surface_words: HashMap<String, String>,
+}
+
+/// Finds the first occurence of `query` in `phrase` that starts at a word boundary
+fn find_at_word_boundary(phrase: &str, query: &str) -> Option<usize> {
+    phrase.match_indices(query)
+        .map(|(idx, _match)| idx)
+        .find(|&idx| phrase[..idx].chars().next_back().is_none_or(|c| !c.is_alphanumeric()))
+}
+
+#[wasm_bindgen]
+impl Searcher {
+    pub fn has_completion_phrases(&self) -> bool {
+        self.completion_phrases.is_some()
+    }
+
+    /// Sets up the phrases of the completions file, which is loaded lazily on the first completion.
+    pub fn set_completion_phrases(&mut self, completions_buf: &[u8]) {
+        let phrases: Vec<IndexedPhrase> = completions::decode_completion_phrases(completions_buf)
+            .into_iter()
+            .map(|phrase| IndexedPhrase { lowercased: phrase.phrase.to_lowercase(), phrase })
+            .collect();
+
+        let mut surface_words: HashMap<String, String> = HashMap::new();
+        for indexed_phrase in phrases.iter() {
+            for word in indexed_phrase.lowercased.split(|c: char| !c.is_alphanumeric()).filter(|word| !word.is_empty()) {
+                if let Some(folded) = self.tokenizer.fold_term(word).filter(|folded| folded != word) {
+                    surface_words.entry(folded).or_insert_with(|| word.to_owned());
+                }
+            }
+        }
+
+        self.completion_phrases = Some(CompletionPhrases { phrases, surface_words });
+    }
+
+    /// Returns the top `n` term and phrase completions of the query as a json string.
+    pub fn get_completions(&self, query: &str, n: usize) -> String {
+        self.complete(query, n).serialize()
+    }
+}
+
+impl Searcher {
+    /// Completes a partial query using only the dictionary and completion phrases,
+    /// without retrieving any postings lists.
+    pub fn complete(&self, query: &str, n: usize) -> Completions {
+        Completions {
+            terms: self.get_term_completions(query, n),
+            phrases: self.get_phrase_completions(query, n),
+        }
+    }
+
+    /// Completes the last term of the query with the most frequent terms it prefixes,
+    /// after folding it as the indexer does.
+    /// 
+    /// The last term is only completed if the query does not end with a separator,
+    /// and if the tokenizer does not stem terms.
+    /// Terms are replaced by their original form in the completion phrases where there is one.
+    fn get_term_completions(&self, query: &str, n: usize) -> Vec<TermCompletion> {
+        let last_term_start = if let Some((idx, _c)) = query.char_indices()
+            .rev()
+            .take_while(|(_idx, c)| c.is_alphanumeric())
+            .last()
+        {
+            idx
+        } else {
+            return Vec::new();
+        };
+
+        let prefix = if let Some(prefix) = self.tokenizer.fold_term(&query[last_term_start..]) {
+            prefix
+        } else {
+            return Vec::new();
+        };
+        let mut completions: Vec<(&SmartString, u32)> = self.dictionary.term_infos
+            .range((Excluded(SmartString::from(prefix.as_str())), Unbounded))
+            .take_while(|(term, _term_info)| term.starts_with(prefix.as_str()))
+            .filter(|(term, _term_info)| !self.tokenizer.is_stop_word(term))
+            .map(|(term, term_info)| (term, term_info.doc_freq))
+            .collect();
+
+        // Stable, so that terms with equal document frequencies stay in alphabetical order
+        completions.sort_by(|(_term_a, doc_freq_a), (_term_b, doc_freq_b)| doc_freq_b.cmp(doc_freq_a));
+        completions.truncate(n);
+
+        let surface_words = self.completion_phrases.as_ref().map(|completion_phrases| &completion_phrases.surface_words);
+        completions.into_iter()
+            .map(|(term, doc_freq)| {
+                let word = surface_words
+                    .and_then(|surface_words| surface_words.get(term.as_str()))
+                    .map_or(term.as_str(), |word| word.as_str());
+                TermCompletion {
+                    query: format!("{}{}", &query[..last_term_start], word),
+                    term: term.to_string(),
+                    doc_freq,
+                }
+            })
+            .collect()
+    }
+
+    /// Finds phrases containing the query at a word boundary, ignoring case and whitespace differences.
+    /// 
+    /// Phrases are ranked by their field's weight, boosted if the phrase starts with the query,
+    /// then by the number of documents with the phrase, then by the shortest phrase.
+    fn get_phrase_completions(&self, query: &str, n: usize) -> Vec<PhraseCompletion> {
+        let completion_phrases = if let Some(completion_phrases) = &self.completion_phrases {
+            &completion_phrases.phrases
+        } else {
+            return Vec::new();
+        };
+
+        let query = query.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+        if query.is_empty() {
+            return Vec::new();
+        }
+
+        // (score, phrase, documents with the phrase)
+        let mut matches: Vec<(f32, &IndexedPhrase, Vec<u32>)> = Vec::new();
+        let mut match_idxs: HashMap<&str, usize> = HashMap::new();
+        for indexed_phrase in completion_phrases {
+            if bitmap::check(&self.invalidation_vector, indexed_phrase.phrase.doc_id as usize) {
+                continue;
+            }
+
+            let match_start = if let Some(match_start) = find_at_word_boundary(&indexed_phrase.lowercased, &query) {
+                match_start
+            } else {
+                continue;
+            };
+
+            let weight = self.searcher_config.field_infos
+                .get(indexed_phrase.phrase.field_id as usize)
+                .map_or(1.0, |field_info| field_info.weight);
+            let score = if match_start == 0 { weight * PHRASE_START_BOOST } else { weight };
+
+            if let Some(&idx) = match_idxs.get(indexed_phrase.lowercased.as_str()) {
+                let existing = &mut matches[idx];
+                // Counted once when the phrase is in several fields of a document
+                if !existing.2.contains(&indexed_phrase.phrase.doc_id) {
+                    existing.2.push(indexed_phrase.phrase.doc_id);
+                }
+                if score > existing.0 {
+                    existing.0 = score;
+                    existing.1 = indexed_phrase;
+                }
+            } else {
+                match_idxs.insert(&indexed_phrase.lowercased, matches.len());
+                matches.push((score, indexed_phrase, vec![indexed_phrase.phrase.doc_id]));
+            }
+        }
+
+        matches.sort_by(|(score_a, phrase_a, doc_ids_a), (score_b, phrase_b, doc_ids_b)| {
+            score_b.total_cmp(score_a)
+                .then(doc_ids_b.len().cmp(&doc_ids_a.len()))
+                .then(phrase_a.lowercased.len().cmp(&phrase_b.lowercased.len()))
+        });
+        matches.truncate(n);
+
+        matches.into_iter()
+            .map(|(_score, indexed_phrase, doc_ids)| PhraseCompletion {
+                phrase: indexed_phrase.phrase.phrase.clone(),
+                field_name: self.searcher_config.field_infos
+                    .get(indexed_phrase.phrase.field_id as usize)
+                    .map_or_else(String::new, |field_info| field_info.name.clone()),
+                num_docs: doc_ids.len() as u32,
+            })
+            .collect()
+    }
+}
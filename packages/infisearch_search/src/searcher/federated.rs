@@ -0,0 +1,422 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::io::{self, Error, ErrorKind};
+use std::path::PathBuf;
+
+use super::native::{NativeSearcher, NativeSearcherOptions, QueryOpts, SCORE_SORT_KEY};
+use super::query::{DocResult, Query, SpellingCorrection, Suggestion};
+use super::query_parser::QueryPart;
+
+/// A result of a `FederatedQuery`, identifying the index it is from.
+pub struct FederatedDocResult {
+    /// Position of the result's index in the output folder paths given to `FederatedSearcher::new`
+    pub index: usize,
+    pub doc_id: u32,
+    pub score: f32,
+}
+
+/// Searches several indexes, built and published separately, as if they were one.
+///
+/// Documents are scored using the number of documents and average field lengths of all indexes,
+/// and the document frequencies of query terms summed over all dictionaries,
+/// so that the scores of results from different indexes are comparable.
+pub struct FederatedSearcher {
+    searchers: Vec<NativeSearcher>,
+    result_limit: Option<u32>,
+}
+
+/// A key to sort the results of all indexes by, see `QueryOpts::sort_by`.
+enum FederatedSortKey {
+    Score,
+    /// Id of the i64 field in each index, if the index has the field
+    I64(Vec<Option<usize>>),
+}
+
+/// The results of a query across all indexes of a `FederatedSearcher`.
+pub struct FederatedQuery {
+    queries: Vec<Query>,
+    sort_keys: Vec<(FederatedSortKey, bool)>,
+    results_retrieved: u32,
+    pub results_total: usize,
+    result_limit: Option<u32>,
+    suggestion: Option<Suggestion>,
+}
+
+impl FederatedQuery {
+    /// Returns the next n top ranked documents of all indexes, see `Query::get_next_n_results`.
+    ///
+    /// Results that rank equally are returned in the order of their indexes.
+    pub fn get_next_n_results(&mut self, n: usize) -> Vec<FederatedDocResult> {
+        let mut results = Vec::with_capacity(n.min(self.results_total));
+
+        while results.len() < n
+            && self.result_limit.is_none_or(|result_limit| self.results_retrieved < result_limit)
+        {
+            let mut next: Option<(usize, &DocResult)> = None;
+            for (index, query) in self.queries.iter().enumerate() {
+                if let Some(doc_result) = query.peek_next_result() {
+                    let is_next = match next {
+                        Some(next) => self.cmp_results((index, doc_result), next) == Ordering::Greater,
+                        None => true,
+                    };
+                    if is_next {
+                        next = Some((index, doc_result));
+                    }
+                }
+            }
+
+            let index = if let Some((index, _doc_result)) = next {
+                index
+            } else {
+                break;
+            };
+
+            let doc_result = self.queries[index].get_next_n_results(1).pop().unwrap();
+            results.push(FederatedDocResult { index, doc_id: doc_result.doc_id, score: doc_result.score });
+            self.results_retrieved += 1;
+        }
+
+        results
+    }
+
+    /// Orders results as `process_and_rank` does, with documents lacking an i64 field ranked lowest.
+    fn cmp_results(&self, a: (usize, &DocResult), b: (usize, &DocResult)) -> Ordering {
+        let ((a_index, a), (b_index, b)) = (a, b);
+        for (sort_key, ascending) in self.sort_keys.iter() {
+            let cmp = match sort_key {
+                FederatedSortKey::Score => a.score.total_cmp(&b.score),
+                FederatedSortKey::I64(i64_ids) => {
+                    let a_val = i64_ids[a_index].map(|i64_id| self.queries[a_index].get_i64_value(a.doc_id, i64_id));
+                    let b_val = i64_ids[b_index].map(|i64_id| self.queries[b_index].get_i64_value(b.doc_id, i64_id));
+                    a_val.cmp(&b_val)
+                }
+            };
+
+            let cmp = if *ascending { cmp.reverse() } else { cmp };
+            if cmp != Ordering::Equal {
+                return cmp;
+            }
+        }
+
+        // Ties are broken by the document's score, unless already sorted by it
+        a.score.total_cmp(&b.score)
+    }
+
+    /// The query with terms misspelled in every index corrected, see `Query::suggestion`.
+    pub fn suggestion(&self) -> Option<&Suggestion> {
+        self.suggestion.as_ref()
+    }
+
+    /// Returns the spelling suggestion as a json string, or "null" if no terms were corrected.
+    pub fn get_suggestion(&self) -> String {
+        self.suggestion.as_ref().map_or_else(|| "null".to_owned(), Suggestion::serialize)
+    }
+
+    /// The query run against the index at this position, for its query parts, facets, and snippets.
+    pub fn query(&self, index: usize) -> &Query {
+        &self.queries[index]
+    }
+}
+
+impl FederatedSearcher {
+    /// Loads the index in each output folder.
+    ///
+    /// Spelling corrections are only suggested, never searched,
+    /// as a term missing from one index may still be in the others.
+    pub fn new(output_folder_paths: &[PathBuf], options: NativeSearcherOptions) -> io::Result<FederatedSearcher> {
+        let result_limit = options.result_limit;
+        let mut searchers = output_folder_paths
+            .iter()
+            .map(|output_folder_path| NativeSearcher::new(output_folder_path, NativeSearcherOptions {
+                spelling_suggestions_only: true,
+                ..options.clone()
+            }))
+            .collect::<io::Result<Vec<_>>>()?;
+
+        let num_docs = searchers.iter().map(NativeSearcher::num_docs).sum();
+
+        // Field name, and its total length and number of documents over all indexes
+        let mut total_doc_lengths: HashMap<String, (f64, u32)> = HashMap::new();
+        for searcher in searchers.iter() {
+            for (field_name, avg_doc_length) in searcher.get_avg_doc_lengths() {
+                let (total_doc_length, field_num_docs) = total_doc_lengths.entry(field_name.to_owned()).or_default();
+                *total_doc_length += avg_doc_length * searcher.num_docs() as f64;
+                *field_num_docs += searcher.num_docs();
+            }
+        }
+        let avg_doc_lengths: HashMap<&str, f64> = total_doc_lengths
+            .iter()
+            .filter(|(_field_name, (_total_doc_length, field_num_docs))| *field_num_docs > 0)
+            .map(|(field_name, (total_doc_length, field_num_docs))| {
+                (field_name.as_str(), total_doc_length / *field_num_docs as f64)
+            })
+            .collect();
+
+        for searcher in searchers.iter_mut() {
+            searcher.set_collection_stats(num_docs, &avg_doc_lengths);
+        }
+
+        Ok(FederatedSearcher { searchers, result_limit })
+    }
+
+    /// The searcher of the index at this position, for loading the fields of its results.
+    pub fn searcher(&self, index: usize) -> &NativeSearcher {
+        &self.searchers[index]
+    }
+
+    /// Number of indexes searched
+    pub fn num_indexes(&self) -> usize {
+        self.searchers.len()
+    }
+
+    /// Runs the query against every index, see `NativeSearcher::run_query`.
+    ///
+    /// Filters and facets apply to each index separately.
+    /// Sorting by enum fields is unsupported, as their enum value ids differ between indexes.
+    pub fn run_query(&mut self, query: &str, opts: &QueryOpts) -> io::Result<FederatedQuery> {
+        let sort_keys = self.get_sort_keys(opts)?;
+
+        let parsed_queries: Vec<_> = self.searchers
+            .iter_mut()
+            .map(|searcher| searcher.parse_query(query))
+            .collect();
+
+        let mut doc_freqs: HashMap<String, u32> = HashMap::new();
+        for (query_parts, _spelling_corrections) in parsed_queries.iter() {
+            collect_terms(query_parts, &mut doc_freqs);
+        }
+        for (term, doc_freq) in doc_freqs.iter_mut() {
+            *doc_freq = self.searchers.iter().map(|searcher| searcher.get_doc_freq(term)).sum();
+        }
+
+        let mut queries = Vec::with_capacity(self.searchers.len());
+        let mut spelling_corrections = Vec::with_capacity(self.searchers.len());
+        for (searcher, (query_parts, index_spelling_corrections)) in self.searchers.iter_mut().zip(parsed_queries) {
+            let index_sort_keys = searcher.get_sort_keys(&opts.sort_by);
+            queries.push(searcher.rank_query(query, query_parts, Vec::new(), opts, index_sort_keys, Some(&doc_freqs))?);
+            spelling_corrections.push(index_spelling_corrections);
+        }
+
+        Ok(FederatedQuery {
+            results_total: queries.iter().map(|query| query.results_total).sum(),
+            queries,
+            sort_keys,
+            results_retrieved: 0,
+            result_limit: self.result_limit,
+            suggestion: Suggestion::new(query, combine_spelling_corrections(spelling_corrections)),
+        })
+    }
+
+    fn get_sort_keys(&self, opts: &QueryOpts) -> io::Result<Vec<(FederatedSortKey, bool)>> {
+        opts.sort_by
+            .iter()
+            .map(|(field_name, ascending)| {
+                if field_name == SCORE_SORT_KEY {
+                    return Ok((FederatedSortKey::Score, *ascending));
+                }
+
+                let mut i64_ids = Vec::with_capacity(self.searchers.len());
+                for searcher in self.searchers.iter() {
+                    let field_info = searcher.output_config.get_field_info(field_name);
+                    let i64_id = field_info.and_then(|field_info| field_info.i64_info.as_ref()).map(|i64_info| i64_info.id);
+                    if i64_id.is_none() && field_info.is_some_and(|field_info| field_info.enum_info.is_some()) {
+                        return Err(Error::new(
+                            ErrorKind::InvalidInput,
+                            format!("Enum field {} can't be sorted by across indexes", field_name),
+                        ));
+                    }
+                    i64_ids.push(i64_id);
+                }
+
+                Ok((FederatedSortKey::I64(i64_ids), *ascending))
+            })
+            .collect()
+    }
+}
+
+fn collect_terms(query_parts: &[QueryPart], terms: &mut HashMap<String, u32>) {
+    for query_part in query_parts {
+        if let Some(term) = &query_part.term {
+            terms.entry(term.clone()).or_insert(0);
+        } else if let Some(children) = &query_part.children {
+            collect_terms(children, terms);
+        }
+    }
+}
+
+/// Keeps the corrections of terms corrected in every index, that is those missing from all of them,
+/// choosing the correction found in the most documents of an index.
+fn combine_spelling_corrections(spelling_corrections: Vec<Vec<SpellingCorrection>>) -> Vec<SpellingCorrection> {
+    let num_indexes = spelling_corrections.len();
+
+    let mut combined: Vec<(SpellingCorrection, usize)> = Vec::new();
+    for correction in spelling_corrections.into_iter().flatten() {
+        let existing = combined
+            .iter_mut()
+            .find(|(combined_correction, _num_indexes)| combined_correction.original_term == correction.original_term);
+        if let Some((combined_correction, num_indexes)) = existing {
+            *num_indexes += 1;
+            if correction.doc_freq > combined_correction.doc_freq {
+                *combined_correction = correction;
+            }
+        } else {
+            combined.push((correction, 1));
+        }
+    }
+
+    combined
+        .into_iter()
+        .filter(|(_correction, correction_num_indexes)| *correction_num_indexes == num_indexes)
+        .map(|(correction, _num_indexes)| correction)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::io;
+    use std::path::{Path, PathBuf};
+
+    use infisearch::indexer::input_config::InfiConfig;
+    use infisearch::indexer::{Indexer, Zone, DEFAULT_ZONE_SEPARATION};
+    use infisearch::merger;
+    use pretty_assertions::assert_eq;
+
+    use super::{combine_spelling_corrections, FederatedSearcher};
+    use crate::searcher::native::{NativeSearcher, NativeSearcherOptions, QueryOpts};
+    use crate::searcher::query::SpellingCorrection;
+
+    fn zone(field_name: &str, field_text: &str) -> Zone {
+        Zone { field_name: field_name.to_owned(), field_text: field_text.to_owned(), separation: DEFAULT_ZONE_SEPARATION }
+    }
+
+    fn index(output_folder_path: &Path, config: InfiConfig, docs: Vec<(&str, Vec<Zone>)>) {
+        // Documents are added directly, so the input folder is unused
+        let mut indexer = Indexer::new(
+            output_folder_path.parent().unwrap(),
+            output_folder_path,
+            config,
+            false,
+            false,
+            false,
+            false,
+        ).unwrap();
+        for (external_id, zones) in docs {
+            indexer.add_document(external_id, zones).unwrap();
+        }
+        indexer.finish_writing_docs().unwrap();
+    }
+
+    fn index_bodies(output_folder_path: &Path, docs: &[(&str, &str)]) {
+        let docs = docs.iter().map(|(external_id, text)| (*external_id, vec![zone("body", text)])).collect();
+        index(output_folder_path, InfiConfig::default(), docs);
+    }
+
+    fn get_relative_fp(searcher: &NativeSearcher, doc_id: u32) -> String {
+        let fields = searcher.load_fields(doc_id).unwrap();
+        fields.into_iter().find(|(field_name, _text)| field_name == "_relative_fp").unwrap().1
+    }
+
+    /// Index, relative file path and score of each result
+    fn search(searcher: &mut FederatedSearcher, query: &str, opts: &QueryOpts) -> Vec<(usize, String, f32)> {
+        let mut query = searcher.run_query(query, opts).unwrap();
+        query.get_next_n_results(10)
+            .into_iter()
+            .map(|result| (result.index, get_relative_fp(searcher.searcher(result.index), result.doc_id), result.score))
+            .collect()
+    }
+
+    #[test]
+    fn test_federated_ranking() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let output_folder_paths = vec![temp_dir.path().join("output1"), temp_dir.path().join("output2")];
+        index_bodies(&output_folder_paths[0], &[("a", "lorem ipsum"), ("b", "dolor sit amet consectetur")]);
+        index_bodies(&output_folder_paths[1], &[
+            ("c", "lorem lorem dolor sit amet consectetur adipiscing"),
+            ("d", "amet"),
+            ("e", "sed do eiusmod tempor"),
+        ]);
+
+        let mut searcher = FederatedSearcher::new(&output_folder_paths, NativeSearcherOptions::default()).unwrap();
+        let indexes: Vec<usize> = search(&mut searcher, "lorem", &QueryOpts::default())
+            .into_iter()
+            .map(|(index, _relative_fp, _score)| index)
+            .collect();
+        assert_eq!(indexes, vec![0, 1]);
+
+        // Scores are the same as searching the merged indexes
+        let merged_folder_path = temp_dir.path().join("merged");
+        merger::merge(&output_folder_paths, &merged_folder_path).unwrap();
+        let mut merged_searcher = NativeSearcher::new(&merged_folder_path, NativeSearcherOptions::default()).unwrap();
+        for query in ["lorem", "amet", "dolor adipiscing", "sit amet lorem"] {
+            let federated_results = search(&mut searcher, query, &QueryOpts::default());
+            let mut merged_query = merged_searcher.run_query(query, &QueryOpts::default()).unwrap();
+            let merged_results = merged_query.get_next_n_results(10);
+            assert_eq!(federated_results.len(), merged_results.len());
+            for ((_index, federated_fp, federated_score), merged_result) in federated_results.iter().zip(merged_results) {
+                assert_eq!(*federated_fp, get_relative_fp(&merged_searcher, merged_result.doc_id));
+                assert!((federated_score - merged_result.score).abs() < 1e-4, "{} {} {}", query, federated_score, merged_result.score);
+            }
+        }
+
+        // Only terms missing from every index are corrected
+        let query = searcher.run_query("lorme", &QueryOpts::default()).unwrap();
+        let suggestion = query.suggestion().unwrap();
+        assert_eq!(suggestion.query, "lorem");
+        assert_eq!(suggestion.corrections[0].doc_freq, 1);
+        let query = searcher.run_query("adipiscing", &QueryOpts::default()).unwrap();
+        assert!(query.suggestion().is_none());
+        assert_eq!(query.results_total, 1);
+    }
+
+    #[test]
+    fn test_federated_sort() {
+        let config = || InfiConfig::new(r#"{
+            "fields_config": { "fields": {
+                "price": { "storage": [{ "type": "i64", "default": 0 }] },
+                "team": { "storage": [{ "type": "enum" }] }
+            } }
+        }"#.to_owned()).unwrap();
+        let doc = |price: &str| vec![zone("body", "lorem"), zone("price", price), zone("team", "docs")];
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let output_folder_paths: Vec<PathBuf> = vec![temp_dir.path().join("output1"), temp_dir.path().join("output2")];
+        index(&output_folder_paths[0], config(), vec![("a", doc("30")), ("b", doc("10"))]);
+        index(&output_folder_paths[1], config(), vec![("c", doc("20")), ("d", doc("40"))]);
+
+        let mut searcher = FederatedSearcher::new(&output_folder_paths, NativeSearcherOptions::default()).unwrap();
+        let sort_by = |field_name: &str| QueryOpts { sort_by: vec![(field_name.to_owned(), true)], ..QueryOpts::default() };
+
+        let results: Vec<(usize, String)> = search(&mut searcher, "lorem", &sort_by("price"))
+            .into_iter()
+            .map(|(index, relative_fp, _score)| (index, relative_fp))
+            .collect();
+        assert_eq!(results, vec![
+            (0, "b".to_owned()), (1, "c".to_owned()), (0, "a".to_owned()), (1, "d".to_owned()),
+        ]);
+
+        // Enum value ids differ between indexes
+        let error = searcher.run_query("lorem", &sort_by("team")).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    fn correction(original_term: &str, corrected_term: &str, doc_freq: u32) -> SpellingCorrection {
+        SpellingCorrection {
+            original_term: original_term.to_owned(),
+            corrected_term: corrected_term.to_owned(),
+            doc_freq,
+            replacements: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_combine_spelling_corrections() {
+        let combined = combine_spelling_corrections(vec![
+            vec![correction("lorme", "lorem", 2), correction("ipsm", "ipsum", 5)],
+            vec![correction("lorme", "lorel", 3)],
+            vec![correction("lorme", "lorem", 1)],
+        ]);
+
+        // "ipsm" is in the 2nd and 3rd indexes' dictionaries
+        assert_eq!(combined, vec![correction("lorme", "lorel", 3)]);
+    }
+}
@@ -0,0 +1,849 @@
+//! Native (non-WASM) entry point to the search pipeline.
+//!
+//! Performs the same setup as `workerSearcher.ts` and `get_new_searcher`,
+//! but reads the output folder directly from disk instead of fetching it.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Error, ErrorKind};
+use std::ops::Bound::{Included, Unbounded};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use infisearch_common::bitmap;
+use infisearch_common::dictionary::TermInfo;
+use infisearch_common::language::InfiLanguageConfig;
+use infisearch_common::metadata::{EnumMax, FORMAT_VER};
+use infisearch_common::tokenize::SearchTokenizer;
+use infisearch_common::{COMPLETIONS_FILE, FILE_EXT, METADATA_FILE};
+use infisearch_lang_ascii::ascii;
+use infisearch_lang_ascii_stemmer::ascii_stemmer;
+use infisearch_lang_chinese::chinese;
+use serde::Deserialize;
+use smartstring::alias::String as SmartString;
+
+use super::completions::Completions;
+use super::query::{Query, SpellingCorrection};
+use super::query_parser::{self, QueryPart};
+use super::query_processor::SortKey;
+use super::snippet::{self, Snippet, SnippetOptions};
+use super::tokenizer_registry::TokenizerRegistry;
+use super::{FieldInfo, IndexingConfig, Searcher, SearcherConfig, SearcherOptions};
+use crate::postings_list::PostingsList;
+use crate::utils;
+
+pub static OUTPUT_CONFIG_FILE: &str = "output_config.json";
+
+/// Sort key name for sorting by the document's relevance, see `QueryOpts`
+pub static SCORE_SORT_KEY: &str = "_score";
+
+// ------------------------------------------------------------
+// output_config.json, see InfiOutputConfig in the indexer
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputIndexingConfig {
+    pub num_docs_per_block: u32,
+    pub num_pls_per_dir: u32,
+    pub with_positions: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputEnumInfo {
+    pub enum_id: usize,
+    pub enum_values: Vec<String>,
+}
+
+#[derive(Deserialize)]
+pub struct OutputI64Info {
+    pub id: usize,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputFieldInfo {
+    pub id: u8,
+    pub name: String,
+    pub weight: f32,
+    pub k: f32,
+    pub b: f32,
+    pub store_text: bool,
+    pub enum_info: Option<OutputEnumInfo>,
+    pub i64_info: Option<OutputI64Info>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputConfig {
+    pub ver: String,
+    #[serde(default)]
+    pub format_ver: u32,
+    pub index_ver: String,
+    pub last_doc_id: u32,
+    pub indexing_config: OutputIndexingConfig,
+    pub lang_config: InfiLanguageConfig,
+    pub field_infos: Vec<OutputFieldInfo>,
+    pub num_scored_fields: usize,
+    pub num_docs_per_store: u32,
+    pub num_stores_per_dir: u32,
+}
+
+impl OutputConfig {
+    pub fn get_field_info(&self, field_name: &str) -> Option<&OutputFieldInfo> {
+        self.field_infos.iter().find(|field_info| field_info.name == field_name)
+    }
+
+    /// Path of the field store file containing the document's texts, and the document's index in it.
+    /// 
+    /// Same as the search library's `getFieldUrl`, as field stores are split further by indexing blocks.
+    pub fn get_field_store_location(&self, index_folder_path: &Path, doc_id: u32) -> (PathBuf, usize) {
+        let file_number = doc_id / self.num_docs_per_store;
+        let num_docs_per_block = self.indexing_config.num_docs_per_block;
+        let block_number = doc_id / num_docs_per_block;
+        let field_store_path = index_folder_path
+            .join("field_store")
+            .join((file_number / self.num_stores_per_dir).to_string())
+            .join(block_number.to_string())
+            .join(format!("{}.json", file_number));
+
+        let mut idx = doc_id % self.num_docs_per_store;
+        if num_docs_per_block < self.num_docs_per_store {
+            idx %= num_docs_per_block;
+        }
+
+        (field_store_path, idx as usize)
+    }
+}
+
+// ------------------------------------------------------------
+
+/// Equivalent of the search library's `SearcherOptions`, less the networking / caching options.
+#[derive(Clone)]
+pub struct NativeSearcherOptions {
+    pub max_auto_suffix_search_terms: usize,
+    pub max_suffix_search_terms: usize,
+    pub use_query_term_proximity: bool,
+    pub result_limit: Option<u32>,
+    /// Searches the original query, only suggesting spelling corrections, see `Query::suggestion`
+    pub spelling_suggestions_only: bool,
+    /// See `register_tokenizer`
+    pub custom_tokenizers: TokenizerRegistry,
+}
+
+impl Default for NativeSearcherOptions {
+    fn default() -> Self {
+        NativeSearcherOptions {
+            max_auto_suffix_search_terms: 3,
+            max_suffix_search_terms: 5,
+            use_query_term_proximity: true,
+            result_limit: None,
+            spelling_suggestions_only: false,
+            custom_tokenizers: TokenizerRegistry::default(),
+        }
+    }
+}
+
+impl NativeSearcherOptions {
+    /// Registers a custom tokenizer, used when the index's `lang_config.lang` is `lang`.
+    ///
+    /// Registering a built-in language's name replaces it.
+    /// It should match the `IndexerTokenizer` the index was built with, see `InfiConfig::register_tokenizer`.
+    pub fn register_tokenizer(
+        &mut self,
+        lang: &str,
+        factory: impl Fn(&InfiLanguageConfig) -> Box<dyn SearchTokenizer> + Send + Sync + 'static,
+    ) {
+        self.custom_tokenizers.register_tokenizer(lang, factory);
+    }
+}
+
+/// Equivalent of the search library's `QueryOpts`, with fields referenced by name.
+///
+/// Unknown field names and enum values are ignored, as in `serializeGetQueryParams`.
+#[derive(Default)]
+pub struct QueryOpts {
+    /// Enum field name, and the enum values to keep. `None` keeps documents without a value.
+    pub enum_filters: Vec<(String, Vec<Option<String>>)>,
+    /// I64 field name, lower bound (inclusive), upper bound (inclusive)
+    pub i64_filters: Vec<(String, Option<i64>, Option<i64>)>,
+    /// Field names to sort by in order, and whether to sort them in ascending order.
+    ///
+    /// `SCORE_SORT_KEY` sorts by relevance, which also breaks any remaining ties in descending order.
+    pub sort_by: Vec<(String, bool)>,
+    /// Enum field names to count the enum values of over all results
+    pub enum_facets: Vec<String>,
+    /// I64 field name, and the bucket boundaries to count the field's values into.
+    ///
+    /// Boundaries are sorted and deduplicated, so n distinct boundaries produce n + 1 buckets.
+    pub i64_facets: Vec<(String, Vec<i64>)>,
+}
+
+/// Enum field name, and the number of results for each enum value. `None` is the lack of a value.
+pub type EnumFacet<'a> = (&'a str, Vec<(Option<&'a str>, u32)>);
+
+/// A document's entry in a term's postings list, see `NativeSearcher::get_postings`
+pub struct TermPosting {
+    pub doc_id: u32,
+    /// Field name, term frequency, and term positions of each field containing the term.
+    /// Positions are empty if they were not indexed.
+    pub fields: Vec<(String, u32, Vec<u32>)>,
+    pub is_invalidated: bool,
+}
+
+pub struct NativeSearcher {
+    searcher: Searcher,
+    pub output_config: OutputConfig,
+    index_folder_path: PathBuf,
+}
+
+fn get_tokenizer(
+    lang_config: &InfiLanguageConfig,
+    custom_tokenizers: &TokenizerRegistry,
+) -> io::Result<Box<dyn SearchTokenizer>> {
+    if let Some(tokenizer) = custom_tokenizers.create_tokenizer(lang_config) {
+        return Ok(tokenizer);
+    }
+
+    if !lang_config.analyzers.is_empty() {
+        return get_analyzer_tokenizer(lang_config).map_err(|e| Error::new(ErrorKind::InvalidData, e));
+    }
+
+    match lang_config.lang.as_str() {
+        "ascii" => Ok(Box::new(ascii::new_with_options(lang_config))),
+        "ascii_stemmer" => Ok(Box::new(ascii_stemmer::new_with_options(lang_config))),
+        "chinese" => Ok(Box::new(chinese::new_with_options(lang_config))),
+        _ => Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Unsupported language {}", lang_config.lang),
+        )),
+    }
+}
+
+fn get_analyzer_tokenizer(lang_config: &InfiLanguageConfig) -> Result<Box<dyn SearchTokenizer>, String> {
+    match lang_config.lang.as_str() {
+        "ascii" => Ok(Box::new(ascii::new_with_analyzers(lang_config)?)),
+        "ascii_stemmer" => Ok(Box::new(ascii_stemmer::new_with_analyzers(lang_config)?)),
+        _ => Err(format!("Analyzers are not supported for the {} language", lang_config.lang)),
+    }
+}
+
+fn get_searcher_config(
+    output_config: &OutputConfig,
+    options: NativeSearcherOptions,
+    output_folder_path: &Path,
+    index_folder_path: &Path,
+) -> SearcherConfig {
+    let mut field_infos = Vec::with_capacity(output_config.field_infos.len());
+    let mut valid_fields = Vec::new();
+    for field_info in output_config.field_infos.iter() {
+        if field_info.weight > 0.0 {
+            valid_fields.push(field_info.name.clone());
+        }
+        field_infos.push(FieldInfo {
+            name: field_info.name.clone(),
+            weight: field_info.weight,
+            k: field_info.k,
+            b: field_info.b,
+        });
+    }
+    utils::insertion_sort(&mut valid_fields, |a, b| a.len() > b.len());
+
+    let with_positions = output_config.indexing_config.with_positions;
+
+    SearcherConfig {
+        indexing_config: IndexingConfig {
+            num_pls_per_dir: output_config.indexing_config.num_pls_per_dir,
+            with_positions,
+        },
+        lang_config: output_config.lang_config.clone(),
+        field_infos,
+        valid_fields,
+        num_scored_fields: output_config.num_scored_fields,
+        inner_url: index_folder_path.to_string_lossy().into_owned(),
+        searcher_options: SearcherOptions {
+            url: output_folder_path.to_string_lossy().into_owned(),
+            max_auto_suffix_search_terms: options.max_auto_suffix_search_terms,
+            max_suffix_search_terms: options.max_suffix_search_terms,
+            use_query_term_proximity: options.use_query_term_proximity && with_positions,
+            pl_lazy_cache_threshold: 0,
+            result_limit: options.result_limit,
+            spelling_suggestions_only: options.spelling_suggestions_only,
+        },
+    }
+}
+
+fn read_pl_file(index_folder_path: &Path, num_pls_per_dir: u32, pl_num: u32) -> io::Result<Vec<u8>> {
+    fs::read(
+        index_folder_path
+            .join(format!("pl_{}", pl_num / num_pls_per_dir))
+            .join(format!("pl_{}.{}", pl_num, FILE_EXT)),
+    )
+}
+
+impl NativeSearcher {
+    /// Opens the index in `output_folder_path`, which is the output folder passed to the indexer.
+    pub fn new(output_folder_path: &Path, options: NativeSearcherOptions) -> io::Result<NativeSearcher> {
+        let output_config: OutputConfig = serde_json::from_slice(
+            &fs::read(output_folder_path.join(OUTPUT_CONFIG_FILE))?,
+        )?;
+
+        if output_config.ver != env!("CARGO_PKG_VERSION") {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "Index version {} does not match searcher version {}",
+                    output_config.ver,
+                    env!("CARGO_PKG_VERSION"),
+                ),
+            ));
+        }
+
+        if output_config.format_ver != FORMAT_VER {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "Index format {} does not match searcher format {}, the index should be rebuilt",
+                    output_config.format_ver,
+                    FORMAT_VER,
+                ),
+            ));
+        }
+
+        let index_folder_path = output_folder_path.join(&output_config.index_ver);
+
+        let tokenizer = get_tokenizer(&output_config.lang_config, &options.custom_tokenizers)?;
+        let searcher_config = get_searcher_config(
+            &output_config,
+            options,
+            output_folder_path,
+            &index_folder_path,
+        );
+        let metadata_buf = fs::read(index_folder_path.join(METADATA_FILE))?;
+
+        Ok(NativeSearcher {
+            searcher: Searcher::new(metadata_buf, searcher_config, tokenizer),
+            output_config,
+            index_folder_path,
+        })
+    }
+
+    /// Runs the query through the same pipeline as `get_query`.
+    pub fn run_query(&mut self, query: &str, opts: &QueryOpts) -> io::Result<Query> {
+        let sort_keys = self.get_sort_keys(&opts.sort_by);
+        let (query_parts, spelling_corrections) = self.parse_query(query);
+        self.rank_query(query, query_parts, spelling_corrections, opts, sort_keys, None)
+    }
+
+    /// Parses the query, expanding its terms with this index's dictionary.
+    pub(super) fn parse_query(&mut self, query: &str) -> (Vec<QueryPart>, Vec<SpellingCorrection>) {
+        let searcher = &mut self.searcher;
+        let mut query_parts = query_parser::parse_query(
+            query.to_owned(),
+            &mut *searcher.tokenizer,
+            &searcher.searcher_config.valid_fields,
+            searcher.searcher_config.indexing_config.with_positions,
+            &searcher.dictionary,
+        );
+
+        let spelling_corrections = searcher.expand_term_postings_lists(&mut query_parts);
+
+        (query_parts, spelling_corrections)
+    }
+
+    /// Retrieves the postings lists of a query parsed by `parse_query`, and ranks its results.
+    ///
+    /// `doc_freqs` replaces the document frequencies of the query's terms in this index, if given.
+    pub(super) fn rank_query(
+        &mut self,
+        query: &str,
+        mut query_parts: Vec<QueryPart>,
+        spelling_corrections: Vec<SpellingCorrection>,
+        opts: &QueryOpts,
+        sort_keys: Vec<(SortKey, bool)>,
+        doc_freqs: Option<&HashMap<String, u32>>,
+    ) -> io::Result<Query> {
+        let enum_filters = self.get_enum_filters(opts);
+        let i64_filters = self.get_i64_filters(opts);
+        let enum_facets: Vec<usize> = self.get_enum_facet_infos(opts)
+            .map(|(_field_name, enum_info)| enum_info.enum_id)
+            .collect();
+        let i64_facets: Vec<(usize, Vec<i64>)> = self.get_i64_facet_infos(opts)
+            .map(|(_field_name, i64_info, boundaries)| (i64_info.id, boundaries.clone()))
+            .collect();
+
+        let searcher = &mut self.searcher;
+        let index_folder_path = &self.index_folder_path;
+        let num_pls_per_dir = self.output_config.indexing_config.num_pls_per_dir;
+        let term_pls = searcher.retrieve_term_pls_sync(&mut query_parts, doc_freqs, |pl_num| {
+            read_pl_file(index_folder_path, num_pls_per_dir, pl_num)
+        })?;
+
+        let result_heap = searcher.process_and_rank(
+            &mut query_parts, &term_pls, enum_filters, i64_filters, sort_keys,
+        );
+
+        let result_limit = searcher.searcher_config.searcher_options.result_limit;
+        Ok(searcher.create_query(
+            query, query_parts, spelling_corrections, result_heap, result_limit, &enum_facets, &i64_facets,
+        ))
+    }
+
+    /// Returns the top `n` term and phrase completions of a partially typed query.
+    ///
+    /// The completions file is read on the first call.
+    pub fn complete(&mut self, query: &str, n: usize) -> io::Result<Completions> {
+        if !self.searcher.has_completion_phrases() {
+            let completions_buf = match fs::read(self.index_folder_path.join(COMPLETIONS_FILE)) {
+                Ok(completions_buf) => completions_buf,
+                // Indexes without any completion fields
+                Err(err) if err.kind() == ErrorKind::NotFound => Vec::new(),
+                Err(err) => return Err(err),
+            };
+            self.searcher.set_completion_phrases(&completions_buf);
+        }
+
+        Ok(self.searcher.complete(query, n))
+    }
+
+    pub(super) fn get_sort_keys(&self, sort_by: &[(String, bool)]) -> Vec<(SortKey, bool)> {
+        sort_by
+            .iter()
+            .filter_map(|(field_name, ascending)| {
+                if field_name == SCORE_SORT_KEY {
+                    return Some((SortKey::Score, *ascending));
+                }
+
+                let field_info = self.output_config.get_field_info(field_name)?;
+                if let Some(i64_info) = &field_info.i64_info {
+                    Some((SortKey::I64(i64_info.id), *ascending))
+                } else {
+                    field_info.enum_info
+                        .as_ref()
+                        .map(|enum_info| (SortKey::Enum(enum_info.enum_id), *ascending))
+                }
+            })
+            .collect()
+    }
+
+    fn get_enum_facet_infos<'a>(
+        &'a self,
+        opts: &'a QueryOpts,
+    ) -> impl Iterator<Item = (&'a str, &'a OutputEnumInfo)> + 'a {
+        opts.enum_facets.iter().filter_map(move |field_name| {
+            let enum_info = self.output_config.get_field_info(field_name)?.enum_info.as_ref()?;
+            Some((field_name.as_str(), enum_info))
+        })
+    }
+
+    fn get_i64_facet_infos<'a>(
+        &'a self,
+        opts: &'a QueryOpts,
+    ) -> impl Iterator<Item = (&'a str, &'a OutputI64Info, &'a Vec<i64>)> + 'a {
+        opts.i64_facets.iter().filter_map(move |(field_name, boundaries)| {
+            let i64_info = self.output_config.get_field_info(field_name)?.i64_info.as_ref()?;
+            Some((field_name.as_str(), i64_info, boundaries))
+        })
+    }
+
+    /// Enum value counts over all results of a query run with the same `opts`, by enum field name.
+    ///
+    /// Values without any results are omitted. `None` counts documents without a value.
+    pub fn get_enum_facets<'a>(
+        &'a self,
+        query: &Query,
+        opts: &'a QueryOpts,
+    ) -> Vec<EnumFacet<'a>> {
+        self.get_enum_facet_infos(opts)
+            .zip(query.enum_facet_counts())
+            .map(|((field_name, enum_info), counts)| {
+                let value_counts = counts
+                    .iter()
+                    .enumerate()
+                    .filter(|(_ev_id, &count)| count != 0)
+                    .map(|(ev_id, &count)| {
+                        let enum_value = if ev_id == 0 {
+                            None
+                        } else {
+                            enum_info.enum_values.get(ev_id - 1).map(|ev| ev.as_str())
+                        };
+                        (enum_value, count)
+                    })
+                    .collect();
+                (field_name, value_counts)
+            })
+            .collect()
+    }
+
+    /// Bucket counts over all results of a query run with the same `opts`, by i64 field name.
+    pub fn get_i64_facets<'a>(&'a self, query: &Query, opts: &'a QueryOpts) -> Vec<(&'a str, Vec<u32>)> {
+        self.get_i64_facet_infos(opts)
+            .zip(query.i64_facet_counts())
+            .map(|((field_name, _i64_info, _boundaries), counts)| (field_name, counts.clone()))
+            .collect()
+    }
+
+    fn get_enum_filters(&self, opts: &QueryOpts) -> Vec<(usize, [bool; EnumMax::MAX as usize])> {
+        opts.enum_filters
+            .iter()
+            .filter_map(|(field_name, allowed_enum_values)| {
+                let enum_info = self.output_config.get_field_info(field_name)?.enum_info.as_ref()?;
+
+                let mut ev_ids = [false; EnumMax::MAX as usize];
+                for allowed_enum_value in allowed_enum_values {
+                    if let Some(allowed_enum_value) = allowed_enum_value {
+                        // +1 as 0 is the "default" enum value
+                        if let Some(ev_id) = enum_info.enum_values
+                            .iter()
+                            .position(|ev| ev == allowed_enum_value)
+                            .and_then(|idx| ev_ids.get_mut(idx + 1))
+                        {
+                            *ev_id = true;
+                        }
+                    } else {
+                        ev_ids[0] = true;
+                    }
+                }
+
+                Some((enum_info.enum_id, ev_ids))
+            })
+            .collect()
+    }
+
+    fn get_i64_filters(&self, opts: &QueryOpts) -> Vec<(usize, Option<i64>, Option<i64>)> {
+        opts.i64_filters
+            .iter()
+            .filter_map(|(field_name, gte, lte)| {
+                let i64_info = self.output_config.get_field_info(field_name)?.i64_info.as_ref()?;
+                Some((i64_info.id, *gte, *lte))
+            })
+            .collect()
+    }
+
+    /// Reads the stored field texts of a document, in the order they were indexed.
+    pub fn load_fields(&self, doc_id: u32) -> io::Result<Vec<(String, String)>> {
+        let (field_store_path, idx) = self.output_config.get_field_store_location(&self.index_folder_path, doc_id);
+
+        let mut docs: Vec<Vec<(String, String)>> = serde_json::from_slice(&fs::read(field_store_path)?)?;
+
+        if idx < docs.len() {
+            Ok(docs.swap_remove(idx))
+        } else {
+            Err(Error::new(
+                ErrorKind::NotFound,
+                format!("Document {} not found in its field store", doc_id),
+            ))
+        }
+    }
+
+    /// Best passage of the document's scored fields (e.g. title, heading, body),
+    /// highlighting the terms searched by the query. See `snippet::get_snippet`.
+    ///
+    /// `fields` are the document's stored texts from `load_fields`.
+    pub fn get_snippet(&self, query: &Query, fields: &[(String, String)], max_len: usize) -> Option<Snippet> {
+        let scored_fields: Vec<(&str, &str)> = fields
+            .iter()
+            .filter(|(field_name, _text)| {
+                self.output_config.get_field_info(field_name).is_some_and(|field_info| field_info.weight > 0.0)
+            })
+            .map(|(field_name, text)| (field_name.as_str(), text.as_str()))
+            .collect();
+
+        snippet::get_snippet(
+            &scored_fields,
+            &snippet::get_searched_terms(query.query_parts()),
+            &*self.searcher.tokenizer,
+            &SnippetOptions { max_len },
+        )
+    }
+
+    /// Enum value of the document for the given enum field, if any.
+    pub fn get_enum_value(&self, doc_id: u32, field_name: &str) -> Option<&str> {
+        let enum_info = self.output_config.get_field_info(field_name)?.enum_info.as_ref()?;
+        if !self.is_valid_doc_id(doc_id) {
+            return None;
+        }
+
+        let ev_id = self.searcher.doc_info.get_enum_val(doc_id as usize, enum_info.enum_id) as usize;
+        if ev_id == 0 {
+            None
+        } else {
+            enum_info.enum_values.get(ev_id - 1).map(|ev| ev.as_str())
+        }
+    }
+
+    /// I64 value of the document for the given i64 field
+    pub fn get_i64_value(&self, doc_id: u32, field_name: &str) -> Option<i64> {
+        let i64_info = self.output_config.get_field_info(field_name)?.i64_info.as_ref()?;
+        if !self.is_valid_doc_id(doc_id) {
+            return None;
+        }
+
+        Some(self.searcher.doc_info.get_num_val(doc_id as usize, i64_info.id))
+    }
+
+    // ------------------------------------------------------------
+    // Collection statistics, for searching several indexes together
+
+    /// Number of documents in the index, less those invalidated by incremental indexing runs
+    pub(super) fn num_docs(&self) -> u32 {
+        self.searcher.doc_info.num_docs
+    }
+
+    /// Document frequency of a dictionary term, 0 if it is not in the dictionary
+    pub(super) fn get_doc_freq(&self, term: &str) -> u32 {
+        self.searcher.dictionary.get_term_info(term).map_or(0, |term_info| term_info.doc_freq)
+    }
+
+    /// Field name and average length of each scored field
+    pub(super) fn get_avg_doc_lengths(&self) -> impl Iterator<Item = (&str, f64)> + '_ {
+        self.searcher.searcher_config.field_infos
+            .iter()
+            .zip(self.searcher.doc_info.avg_doc_lengths.iter())
+            .map(|(field_info, &avg_doc_length)| (field_info.name.as_str(), avg_doc_length))
+    }
+
+    /// Scores documents with the given collection statistics instead of this index's,
+    /// where `avg_doc_lengths` is the average length of each scored field by field name.
+    pub(super) fn set_collection_stats(&mut self, num_docs: u32, avg_doc_lengths: &HashMap<&str, f64>) {
+        let avg_doc_lengths: Vec<f64> = self.get_avg_doc_lengths()
+            .map(|(field_name, avg_doc_length)| avg_doc_lengths.get(field_name).copied().unwrap_or(avg_doc_length))
+            .collect();
+        // Queries already run keep the previous statistics
+        Rc::make_mut(&mut self.searcher.doc_info).set_collection_stats(num_docs, avg_doc_lengths);
+    }
+    // ------------------------------------------------------------
+
+    // ------------------------------------------------------------
+    // Inspection of the raw index, for debugging
+
+    /// Dictionary terms starting with the prefix in alphabetical order, and their term infos
+    pub fn get_term_infos<'a>(&'a self, prefix: &'a str) -> impl Iterator<Item = (&'a str, &'a TermInfo)> + 'a {
+        self.searcher.dictionary.term_infos
+            .range((Included(SmartString::from(prefix)), Unbounded))
+            .take_while(move |(term, _term_info)| term.starts_with(prefix))
+            .map(|(term, term_info)| (term.as_str(), *term_info))
+    }
+
+    /// Decodes the postings list of a dictionary term, including the postings of invalidated documents.
+    /// 
+    /// Returns `None` if the term is not in the dictionary.
+    pub fn get_postings(&self, term: &str) -> io::Result<Option<Vec<TermPosting>>> {
+        let term_info = if let Some(term_info) = self.searcher.dictionary.term_infos.get(term) {
+            *term_info
+        } else {
+            return Ok(None);
+        };
+
+        let pl_vec = read_pl_file(
+            &self.index_folder_path,
+            self.output_config.indexing_config.num_pls_per_dir,
+            term_info.postings_file_name,
+        )?;
+
+        let mut pl = PostingsList {
+            term_docs: Vec::new(),
+            idf: 0.0,
+            term: Some(term.to_owned()),
+            term_info: Some(term_info.clone()),
+        };
+        // Nothing is invalidated here, so that parse_pl keeps every posting
+        let no_invalidation_vector = vec![0; self.searcher.invalidation_vector.len()];
+        pl.parse_pl(
+            &pl_vec,
+            &no_invalidation_vector,
+            self.output_config.num_scored_fields,
+            self.output_config.indexing_config.with_positions,
+        );
+
+        let field_infos = &self.searcher.searcher_config.field_infos;
+        Ok(Some(
+            pl.term_docs
+                .into_iter()
+                .map(|term_doc| TermPosting {
+                    doc_id: term_doc.doc_id,
+                    fields: term_doc.fields
+                        .into_iter()
+                        .enumerate()
+                        .filter(|(_field_id, field)| field.field_tf > 0.0)
+                        .map(|(field_id, field)| (
+                            field_infos[field_id].name.clone(),
+                            field.field_tf as u32,
+                            field.field_positions,
+                        ))
+                        .collect(),
+                    is_invalidated: self.is_invalidated(term_doc.doc_id),
+                })
+                .collect(),
+        ))
+    }
+
+    /// Field name and length factor (the field length divided by the average) of each scored field,
+    /// as used in BM25 scoring.
+    pub fn get_doc_length_factors(&self, doc_id: u32) -> Vec<(&str, f64)> {
+        if !self.is_valid_doc_id(doc_id) {
+            return Vec::new();
+        }
+
+        let doc_info = &self.searcher.doc_info;
+        self.searcher.searcher_config.field_infos
+            .iter()
+            .take(doc_info.num_fields)
+            .enumerate()
+            .map(|(field_id, field_info)| (
+                field_info.name.as_str(),
+                doc_info.doc_length_factors[doc_id as usize * doc_info.num_fields + field_id],
+            ))
+            .collect()
+    }
+
+    /// Whether the document was deleted or updated by an incremental indexing run
+    pub fn is_invalidated(&self, doc_id: u32) -> bool {
+        self.is_valid_doc_id(doc_id) && bitmap::check(&self.searcher.invalidation_vector, doc_id as usize)
+    }
+    // ------------------------------------------------------------
+
+    fn is_valid_doc_id(&self, doc_id: u32) -> bool {
+        doc_id < self.searcher.doc_info.doc_length_factors_len
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::path::Path;
+
+    use infisearch::indexer::input_config::InfiConfig;
+    use infisearch::indexer::Indexer;
+    use pretty_assertions::assert_eq;
+
+    use super::{
+        ascii, get_searcher_config, get_tokenizer, NativeSearcher, NativeSearcherOptions, OutputConfig, QueryOpts,
+        TokenizerRegistry,
+    };
+
+    fn get_output_config() -> OutputConfig {
+        serde_json::from_str(r#"{
+            "ver": "0.10.1",
+            "formatVer": 1,
+            "indexVer": "1234",
+            "lastDocId": 5,
+            "indexingConfig": {
+                "plNamesToCache": [],
+                "numDocsPerBlock": 1000,
+                "numPlsPerDir": 1000,
+                "withPositions": false
+            },
+            "langConfig": { "lang": "ascii", "options": {} },
+            "cacheAllFieldStores": true,
+            "fieldInfos": [
+                { "id": 0, "name": "title", "weight": 2.0, "k": 1.2, "b": 0.15, "storeText": true,
+                  "enumInfo": null, "i64Info": null },
+                { "id": 1, "name": "heading", "weight": 1.5, "k": 1.2, "b": 0.25, "storeText": true,
+                  "enumInfo": null, "i64Info": null },
+                { "id": 2, "name": "_relative_fp", "weight": 0.0, "k": 1.2, "b": 0.75, "storeText": true,
+                  "enumInfo": null, "i64Info": null },
+                { "id": 3, "name": "category", "weight": 0.0, "k": 1.2, "b": 0.75, "storeText": false,
+                  "enumInfo": { "enumId": 0, "enumValues": ["a", "b"] }, "i64Info": null },
+                { "id": 4, "name": "date", "weight": 0.0, "k": 1.2, "b": 0.75, "storeText": false,
+                  "enumInfo": null, "i64Info": { "id": 0, "parse": "normal", "default": 0 } }
+            ],
+            "numScoredFields": 2,
+            "numDocsPerStore": 100000000,
+            "numStoresPerDir": 1000
+        }"#).unwrap()
+    }
+
+    #[test]
+    fn test_searcher_config() {
+        let output_config = get_output_config();
+        let searcher_config = get_searcher_config(
+            &output_config,
+            NativeSearcherOptions::default(),
+            Path::new("/output"),
+            Path::new("/output/1234"),
+        );
+
+        assert_eq!(searcher_config.valid_fields, vec!["heading".to_owned(), "title".to_owned()]);
+        assert_eq!(
+            searcher_config.field_infos.iter().map(|fi| fi.name.as_str()).collect::<Vec<_>>(),
+            vec!["title", "heading", "_relative_fp", "category", "date"],
+        );
+        assert_eq!(searcher_config.num_scored_fields, 2);
+        // Positions were not indexed
+        assert!(!searcher_config.searcher_options.use_query_term_proximity);
+        assert!(!searcher_config.indexing_config.with_positions);
+
+        assert_eq!(output_config.get_field_info("category").unwrap().enum_info.as_ref().unwrap().enum_id, 0);
+        assert_eq!(output_config.get_field_info("date").unwrap().i64_info.as_ref().unwrap().id, 0);
+        assert!(output_config.get_field_info("body").is_none());
+    }
+
+    #[test]
+    fn test_field_store_location() {
+        let mut output_config = get_output_config();
+        let index_folder_path = Path::new("/output/1234");
+
+        // Each block's documents are stored in separate files, even if they fit in one store
+        assert_eq!(
+            output_config.get_field_store_location(index_folder_path, 1500),
+            (index_folder_path.join("field_store/0/1/0.json"), 500),
+        );
+
+        output_config.num_docs_per_store = 100;
+        output_config.num_stores_per_dir = 10;
+        assert_eq!(
+            output_config.get_field_store_location(index_folder_path, 1550),
+            (index_folder_path.join("field_store/1/1/15.json"), 50),
+        );
+    }
+
+    #[test]
+    fn test_unsupported_language() {
+        let mut output_config = get_output_config();
+        assert!(get_tokenizer(&output_config.lang_config, &TokenizerRegistry::default()).is_ok());
+
+        output_config.lang_config.lang = "klingon".to_owned();
+        assert!(get_tokenizer(&output_config.lang_config, &TokenizerRegistry::default()).is_err());
+
+        // Only for searchers it is registered with
+        let mut custom_tokenizers = TokenizerRegistry::default();
+        custom_tokenizers.register_tokenizer("klingon", |lang_config| Box::new(ascii::new_with_options(lang_config)));
+        assert!(get_tokenizer(&output_config.lang_config, &custom_tokenizers).is_ok());
+        assert!(get_tokenizer(&output_config.lang_config, &TokenizerRegistry::default()).is_err());
+    }
+
+    #[test]
+    fn test_query_outlives_searcher() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let input_folder_path = temp_dir.path().join("input");
+        let output_folder_path = temp_dir.path().join("output");
+        fs::create_dir_all(&input_folder_path).unwrap();
+
+        let mut indexer = Indexer::new(
+            &input_folder_path,
+            &output_folder_path,
+            InfiConfig::default(),
+            false,
+            false,
+            false,
+            false,
+        ).unwrap();
+        for (file_name, text) in [("a.html", "lorem ipsum dolor"), ("b.html", "lorem"), ("c.html", "dolor")] {
+            fs::write(input_folder_path.join(file_name), format!("<html><body><p>{}</p></body></html>", text)).unwrap();
+            indexer.index_file(&input_folder_path.join(file_name), file_name.as_ref()).unwrap();
+        }
+        indexer.finish_writing_docs().unwrap();
+
+        let mut searcher = NativeSearcher::new(&output_folder_path, NativeSearcherOptions::default()).unwrap();
+        let mut lorem_query = searcher.run_query("lorem ", &QueryOpts::default()).unwrap();
+        let mut dolor_query = searcher.run_query("dolor ", &QueryOpts::default()).unwrap();
+        drop(searcher);
+
+        // Results are ranked lazily, using the document infos the queries share with the searcher
+        let doc_ids: Vec<u32> = lorem_query.get_next_n_results(10).into_iter().map(|doc_result| doc_result.doc_id).collect();
+        assert_eq!(doc_ids, vec![1, 0]);
+        let doc_ids: Vec<u32> = dolor_query.get_next_n_results(10).into_iter().map(|doc_result| doc_result.doc_id).collect();
+        assert_eq!(doc_ids, vec![2, 0]);
+    }
+}
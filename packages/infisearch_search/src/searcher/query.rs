@@ -1,11 +1,13 @@
 use std::cmp::Ordering;
+use std::rc::Rc;
 
 use binary_heap_plus::BinaryHeap;
+use infisearch_common::metadata::EnumMax;
 use infisearch_common::utils::push;
 use wasm_bindgen::prelude::wasm_bindgen;
 
 use crate::doc_info::DocInfo;
-use crate::searcher::query_parser::QueryPart;
+use crate::searcher::query_parser::{self, QueryPart};
 use crate::searcher::Searcher;
 
 pub struct DocResult {
@@ -15,6 +17,72 @@ pub struct DocResult {
 
 pub type DocResultComparator = dyn Fn(&DocResult, &DocResult) -> Ordering;
 
+#[cfg_attr(test, derive(Debug, Eq, PartialEq))]
+pub struct SpellingCorrection {
+    pub original_term: String,
+    pub corrected_term: String,
+    /// Number of documents containing the corrected term
+    pub doc_freq: u32,
+    /// Start and end char indices of each occurence of the term in the query, and the word to replace it with
+    pub replacements: Vec<(usize, usize, String)>,
+}
+
+/// An alternative query with misspelled terms replaced by their corrections
+#[cfg_attr(test, derive(Debug, Eq, PartialEq))]
+pub struct Suggestion {
+    pub query: String,
+    pub corrections: Vec<SpellingCorrection>,
+}
+
+impl Suggestion {
+    /// Replaces the query text of each corrected term.
+    ///
+    /// Returns `None` if there are no corrections.
+    pub fn new(query: &str, corrections: Vec<SpellingCorrection>) -> Option<Suggestion> {
+        if corrections.is_empty() {
+            return None;
+        }
+
+        let mut replacements: Vec<&(usize, usize, String)> = corrections.iter()
+            .flat_map(|correction| correction.replacements.iter())
+            .collect();
+        replacements.sort_by_key(|(start, _end, _word)| *start);
+
+        let mut suggested_query = String::with_capacity(query.len());
+        let mut replacements = replacements.into_iter().peekable();
+        let mut skip_until = 0;
+        for (char_idx, c) in query.chars().enumerate() {
+            if let Some((_start, end, word)) = replacements.next_if(|(start, _end, _word)| *start == char_idx) {
+                suggested_query.push_str(word);
+                skip_until = *end;
+            }
+
+            if char_idx >= skip_until {
+                suggested_query.push(c);
+            }
+        }
+
+        Some(Suggestion { query: suggested_query, corrections })
+    }
+
+    pub(super) fn serialize(&self) -> String {
+        let corrections: Vec<String> = self.corrections.iter()
+            .map(|correction| format!(
+                r#"{{"originalTerm":{},"correctedTerm":{},"docFreq":{}}}"#,
+                query_parser::wrap_string(&correction.original_term),
+                query_parser::wrap_string(&correction.corrected_term),
+                correction.doc_freq,
+            ))
+            .collect();
+
+        format!(
+            r#"{{"query":{},"corrections":[{}]}}"#,
+            query_parser::wrap_string(&self.query),
+            corrections.join(","),
+        )
+    }
+}
+
 #[wasm_bindgen]
 pub struct Query {
     query_parts: Vec<QueryPart>,
@@ -22,7 +90,10 @@ pub struct Query {
     results_retrieved: u32,
     pub results_total: usize,
     result_limit: Option<u32>,
-    doc_infos: *const DocInfo,
+    doc_infos: Rc<DocInfo>,
+    enum_facet_counts: Vec<Vec<u32>>,
+    i64_facet_counts: Vec<Vec<u32>>,
+    suggestion: Option<Suggestion>,
 }
 
 #[wasm_bindgen]
@@ -39,7 +110,7 @@ impl Query {
     /// ...
     /// doc id 2
     pub fn get_next_n(&mut self, n: usize) -> Vec<u8> {
-        let doc_infos = unsafe { &*self.doc_infos };
+        let doc_infos = &self.doc_infos;
 
         let mut raw: Vec<u8> = Vec::with_capacity(n * (
             4 + doc_infos.num_enum_fields + (8 * doc_infos.num_i64_fields)
@@ -81,23 +152,241 @@ impl Query {
     pub fn get_query_parts(&self) -> String {
         QueryPart::serialize_parts(&self.query_parts)
     }
+
+    /// Returns the spelling suggestion as a json string, or "null" if no terms were corrected.
+    pub fn get_suggestion(&self) -> String {
+        self.suggestion.as_ref().map_or_else(|| "null".to_owned(), Suggestion::serialize)
+    }
+
+    /// Returns the facet counts over all results, in the order the facets were requested.
+    ///
+    /// Format:
+    /// number of enum value counts for the 1st enum facet
+    /// count for enum value id 0 (documents without a value)
+    /// count for enum value id 1
+    /// ...
+    /// number of enum value counts for the 2nd enum facet
+    /// ...
+    /// number of buckets for the 1st i64 facet
+    /// count for bucket 1
+    /// ...
+    pub fn get_facet_counts(&self) -> Vec<u32> {
+        let mut raw = Vec::new();
+        for counts in self.enum_facet_counts.iter().chain(self.i64_facet_counts.iter()) {
+            raw.push(counts.len() as u32);
+            raw.extend_from_slice(counts);
+        }
+        raw
+    }
+}
+
+#[cfg(feature = "native")]
+impl Query {
+    /// Native counterpart of `get_next_n`, returning the next n top ranked documents with their scores.
+    pub fn get_next_n_results(&mut self, n: usize) -> Vec<DocResult> {
+        let mut results = Vec::with_capacity(n.min(self.result_heap.len()));
+
+        while !self.result_heap.is_empty()
+            && results.len() < n
+            && (self.result_limit.is_none() || self.results_retrieved < unsafe { self.result_limit.unwrap_unchecked() })
+        {
+            results.push(unsafe { self.result_heap.pop().unwrap_unchecked() });
+            self.results_retrieved += 1;
+        }
+
+        results
+    }
+
+    /// The next top ranked document, without retrieving it.
+    pub fn peek_next_result(&self) -> Option<&DocResult> {
+        if self.result_limit.is_some_and(|result_limit| self.results_retrieved >= result_limit) {
+            return None;
+        }
+
+        self.result_heap.peek()
+    }
+
+    pub(super) fn get_i64_value(&self, doc_id: u32, i64_id: usize) -> i64 {
+        self.doc_infos.get_num_val(doc_id as usize, i64_id)
+    }
+
+    pub fn query_parts(&self) -> &Vec<QueryPart> {
+        &self.query_parts
+    }
+
+    /// The query with its misspelled terms corrected, see `get_suggestion`.
+    pub fn suggestion(&self) -> Option<&Suggestion> {
+        self.suggestion.as_ref()
+    }
+
+    /// Counts of each enum value id for the requested enum facets, see `get_facet_counts`.
+    pub fn enum_facet_counts(&self) -> &Vec<Vec<u32>> {
+        &self.enum_facet_counts
+    }
+
+    /// Bucket counts for the requested i64 facets, see `get_facet_counts`.
+    pub fn i64_facet_counts(&self) -> &Vec<Vec<u32>> {
+        &self.i64_facet_counts
+    }
 }
 
 impl Searcher {
+    #[allow(clippy::too_many_arguments)]
     pub fn create_query(
         &self,
+        query: &str,
         query_parts: Vec<QueryPart>,
+        spelling_corrections: Vec<SpellingCorrection>,
         result_heap: BinaryHeap<DocResult, Box<DocResultComparator>>,
         result_limit: Option<u32>,
+        enum_facets: &[usize],
+        i64_facets: &[(usize, Vec<i64>)],
     ) -> Query {
         let results_total = result_heap.len();
+        let (enum_facet_counts, i64_facet_counts) = self.count_facets(&result_heap, enum_facets, i64_facets);
         Query {
             query_parts,
             result_heap,
             results_retrieved: 0,
             results_total,
             result_limit,
-            doc_infos: (&self.doc_info) as *const DocInfo
+            doc_infos: Rc::clone(&self.doc_info),
+            enum_facet_counts,
+            i64_facet_counts,
+            suggestion: Suggestion::new(query, spelling_corrections),
+        }
+    }
+
+    /// Counts the enum values and i64 buckets of all results, before any are retrieved.
+    ///
+    /// Enum value counts are indexed by the enum value id, with trailing zero counts trimmed.
+    ///
+    /// I64 buckets are delimited by the given boundaries, after sorting and deduplicating them,
+    /// where n boundaries produce n + 1 buckets: (-inf, b1), [b1, b2), ..., [bn, inf).
+    fn count_facets(
+        &self,
+        result_heap: &BinaryHeap<DocResult, Box<DocResultComparator>>,
+        enum_facets: &[usize],
+        i64_facets: &[(usize, Vec<i64>)],
+    ) -> (Vec<Vec<u32>>, Vec<Vec<u32>>) {
+        let i64_facets: Vec<(usize, Vec<i64>)> = i64_facets
+            .iter()
+            .map(|(i64_id, boundaries)| {
+                let mut boundaries = boundaries.clone();
+                boundaries.sort_unstable();
+                boundaries.dedup();
+                (*i64_id, boundaries)
+            })
+            .collect();
+
+        let mut enum_facet_counts = vec![vec![0; EnumMax::MAX as usize + 1]; enum_facets.len()];
+        let mut i64_facet_counts: Vec<Vec<u32>> = i64_facets
+            .iter()
+            .map(|(_i64_id, boundaries)| vec![0; boundaries.len() + 1])
+            .collect();
+
+        if enum_facets.is_empty() && i64_facets.is_empty() {
+            return (enum_facet_counts, i64_facet_counts);
+        }
+
+        for doc_result in result_heap.iter() {
+            let doc_id = doc_result.doc_id as usize;
+
+            for (&enum_id, counts) in enum_facets.iter().zip(enum_facet_counts.iter_mut()) {
+                counts[self.doc_info.get_enum_val(doc_id, enum_id) as usize] += 1;
+            }
+
+            for ((i64_id, boundaries), counts) in i64_facets.iter().zip(i64_facet_counts.iter_mut()) {
+                let val = self.doc_info.get_num_val(doc_id, *i64_id);
+                counts[boundaries.partition_point(|&boundary| boundary <= val)] += 1;
+            }
         }
+
+        for counts in enum_facet_counts.iter_mut() {
+            let len = counts.iter().rposition(|&count| count != 0).map_or(0, |idx| idx + 1);
+            counts.truncate(len);
+        }
+
+        (enum_facet_counts, i64_facet_counts)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::rc::Rc;
+
+    use binary_heap_plus::BinaryHeap;
+    use pretty_assertions::assert_eq;
+
+    use super::{DocResult, DocResultComparator, SpellingCorrection, Suggestion};
+    use crate::searcher::test as searcher_test;
+
+    #[test]
+    fn test_facet_counts() {
+        let mut searcher = searcher_test::create_searcher(5);
+        let doc_info = Rc::get_mut(&mut searcher.doc_info).unwrap();
+        doc_info.num_enum_fields = 1;
+        doc_info.doc_enum_vals = vec![1, 2, 0, 1, 3];
+        doc_info.num_i64_fields = 1;
+        doc_info.doc_i64_vals = vec![-5, 0, 10, 20, 100];
+
+        // Document 4 was filtered out
+        let doc_results = (0..4).map(|doc_id| DocResult { doc_id, score: 1.0 }).collect();
+        let comparator: Box<DocResultComparator> = Box::new(|a, b| a.score.total_cmp(&b.score));
+        let result_heap = BinaryHeap::from_vec_cmp(doc_results, comparator);
+
+        let query = searcher.create_query(
+            "", Vec::new(), Vec::new(), result_heap, None, &[0], &[(0, vec![0, 20])],
+        );
+
+        assert_eq!(query.results_total, 4);
+        // Trailing enum value id 3 is trimmed
+        assert_eq!(query.enum_facet_counts, vec![vec![1, 2, 1]]);
+        assert_eq!(query.i64_facet_counts, vec![vec![1, 2, 1]]);
+        assert_eq!(query.get_facet_counts(), vec![3, 1, 2, 1, 3, 1, 2, 1]);
+
+        // Boundaries in any order, with duplicates
+        let doc_results = (0..5).map(|doc_id| DocResult { doc_id, score: 1.0 }).collect();
+        let comparator: Box<DocResultComparator> = Box::new(|a, b| a.score.total_cmp(&b.score));
+        let result_heap = BinaryHeap::from_vec_cmp(doc_results, comparator);
+
+        let query = searcher.create_query(
+            "", Vec::new(), Vec::new(), result_heap, None, &[], &[(0, vec![20, 0, 50, 20])],
+        );
+        assert_eq!(query.i64_facet_counts, vec![vec![1, 2, 1, 1]]);
+    }
+
+    fn correction(original_term: &str, corrected_term: &str, replacements: &[(usize, usize, &str)]) -> SpellingCorrection {
+        SpellingCorrection {
+            original_term: original_term.to_owned(),
+            corrected_term: corrected_term.to_owned(),
+            doc_freq: 1,
+            replacements: replacements.iter().map(|(start, end, word)| (*start, *end, (*word).to_owned())).collect(),
+        }
+    }
+
+    #[test]
+    fn test_suggestion() {
+        assert_eq!(Suggestion::new("lorme ipsum", Vec::new()), None);
+
+        let suggestion = Suggestion::new(
+            "+Lorme \"ipsum dolr\" title:lorme lormes dolr",
+            vec![
+                correction("lorme", "lorem", &[(1, 6, "lorem"), (26, 31, "lorem")]),
+                correction("dolr", "dolor", &[(14, 18, "dolor"), (39, 43, "dolor")]),
+            ],
+        ).unwrap();
+        assert_eq!(suggestion.query, "+lorem \"ipsum dolor\" title:lorem lormes dolor");
+        assert_eq!(
+            suggestion.serialize(),
+            r#"{"query":"+lorem \"ipsum dolor\" title:lorem lormes dolor","corrections":[{"originalTerm":"lorme","correctedTerm":"lorem","docFreq":1},{"originalTerm":"dolr","correctedTerm":"dolor","docFreq":1}]}"#,
+        );
+
+        // Spans are in chars, and words can be longer or shorter than the text they replace
+        let suggestion = Suggestion::new(
+            "café runnning",
+            vec![correction("runn", "run", &[(5, 13, "running")])],
+        ).unwrap();
+        assert_eq!(suggestion.query, "café running");
     }
 }
@@ -21,12 +21,19 @@ pub struct QueryPart {
     pub field_name: Option<String>,
 
     pub suffix_wildcard: bool,
+
+    /// For phrase queries with a ~N suffix, the number of extra positions allowed between its terms
+    pub slop: Option<u32>,
     // --------------------------------
 
     pub is_corrected: bool,
     pub auto_suffix_wildcard: bool,
     pub is_suffixed: bool,
     pub original_term: Option<String>,
+    /// Start and end char indices of the term's text in the query, for spelling suggestions
+    pub query_span: Option<(usize, usize)>,
+    /// Word replacing the corrected term's text in spelling suggestions
+    pub corrected_word: Option<String>,
     pub term: Option<String>,
     pub terms_searched: Option<Vec<String>>,
     pub part_type: QueryPartType,
@@ -37,6 +44,7 @@ pub struct QueryPart {
 #[cfg(test)]
 impl Eq for QueryPart {}
 
+// Query spans and corrected words are left out, as they only affect suggestions
 #[cfg(test)]
 impl PartialEq for QueryPart {
     fn eq(&self, other: &Self) -> bool {
@@ -52,13 +60,14 @@ impl PartialEq for QueryPart {
             && self.terms_searched == other.terms_searched
             && self.part_type == other.part_type
             && self.field_name == other.field_name
+            && self.slop == other.slop
             && self.children == other.children
             && (self.weight - other.weight).abs() < 0.001
     }
 }
 
 #[inline(never)]
-fn wrap_string(s: &String) -> String {
+pub fn wrap_string(s: &str) -> String {
     let mut output = String::with_capacity(s.len() + 2);
     output.push('"');
 
@@ -66,6 +75,10 @@ fn wrap_string(s: &String) -> String {
         match c {
             '"' => output.push_str("\\\""),
             '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if c < '\u{20}' => output.push_str(&format!("\\u{:04x}", c as u32)),
             _ => output.push(c),
         }
     }
@@ -90,7 +103,7 @@ fn get_null() -> String {
 #[inline(never)]
 pub fn serialize_string_vec(v: &Vec<String>) -> String {
     let mut output = "[".to_owned();
-    let wrapped: Vec<String> = v.iter().map(wrap_string).collect();
+    let wrapped: Vec<String> = v.iter().map(|s| wrap_string(s)).collect();
     output.push_str(wrapped.join(",").as_str());
     output.push(']');
     output
@@ -152,6 +165,13 @@ impl QueryPart {
             get_null()
         });
 
+        output.push_str(r#","slop":"#);
+        output.push_str(&if let Some(v) = self.slop {
+            v.to_string()
+        } else {
+            get_null()
+        });
+
         output.push_str(r#","children":"#);
         output.push_str(&if let Some(children) = &self.children {
             Self::serialize_parts(children)
@@ -173,10 +193,13 @@ impl QueryPart {
             suffix_wildcard: false,
             is_suffixed: false,
             original_term: None,
+            query_span: None,
+            corrected_word: None,
             term: None,
             terms_searched: None,
             part_type,
             field_name: None,
+            slop: None,
             children: None,
             weight: 1.0,
         }
@@ -189,6 +212,16 @@ enum Operator {
         field_name: String,
         prefix_ops: PrefixResult,
     },
+    /// From the AND keyword, equivalent to the + prefix operator
+    Mandatory,
+    /// From the NOT keyword, equivalent to the - prefix operator
+    Subtract,
+}
+
+enum KeywordOperator {
+    And,
+    Or,
+    Not,
 }
 
 enum QueryParseState {
@@ -218,8 +251,98 @@ fn handle_op(query_parts: &mut Vec<QueryPart>, operator_stack: &mut Vec<Operator
                     }
                 }
             }
+            Operator::Mandatory => {
+                if let Some(last) = query_parts.last_mut() {
+                    set_prefix_ops(PrefixResult { is_mandatory: true, ..Default::default() }, last);
+                }
+            }
+            Operator::Subtract => {
+                if let Some(last) = query_parts.last_mut() {
+                    last.is_mandatory = false;
+                    last.is_subtracted = true;
+                }
+            }
+        }
+    }
+}
+
+#[inline(never)]
+fn is_keyword_boundary(c: char) -> bool {
+    c.is_whitespace() || c == '(' || c == ')' || is_double_quote(c)
+}
+
+/// Matches the uppercase AND, OR, NOT keywords starting at j, which must be delimited
+/// by whitespace, brackets, quotes, or the start / end of the query.
+///
+/// Returns the operator and its length.
+#[inline(never)]
+fn get_keyword_operator(query_chars: &[char], j: usize) -> Option<(KeywordOperator, usize)> {
+    if j > 0 && !is_keyword_boundary(query_chars[j - 1]) {
+        return None;
+    }
+
+    for (keyword, operator) in [
+        ("AND", KeywordOperator::And),
+        ("OR", KeywordOperator::Or),
+        ("NOT", KeywordOperator::Not),
+    ] {
+        let end = j + keyword.len();
+        if end <= query_chars.len()
+            && query_chars[j..end].iter().copied().eq(keyword.chars())
+            && (end == query_chars.len() || is_keyword_boundary(query_chars[end]))
+        {
+            return Some((operator, keyword.len()));
+        }
+    }
+
+    None
+}
+
+/// Splits the parts of a group into OR clauses at the given split indices.
+///
+/// Clauses with multiple parts are wrapped in a bracket, so that AND (+) binds tighter than OR.
+/// Each clause is then made optional, as the group must only match one of them,
+/// with subtracted clauses (e.g. a OR -b) becoming inverted ones (a OR ~b).
+#[inline(never)]
+fn group_disjunctions(parts: Vec<QueryPart>, or_splits: &[usize]) -> Vec<QueryPart> {
+    if or_splits.is_empty() {
+        return parts;
+    }
+
+    let mut clauses: Vec<Vec<QueryPart>> = vec![Vec::new()];
+    for (idx, part) in parts.into_iter().enumerate() {
+        while clauses.len() <= or_splits.iter().filter(|&&split| split <= idx).count() {
+            clauses.push(Vec::new());
         }
+        unsafe { clauses.last_mut().unwrap_unchecked() }.push(part);
+    }
+    clauses.retain(|clause| !clause.is_empty());
+
+    if clauses.len() == 1 {
+        return unsafe { clauses.pop().unwrap_unchecked() };
     }
+
+    clauses
+        .into_iter()
+        .map(|mut clause| {
+            let mut clause_part = if clause.len() == 1 {
+                unsafe { clause.pop().unwrap_unchecked() }
+            } else {
+                QueryPart {
+                    children: Some(clause),
+                    ..QueryPart::get_base(QueryPartType::Bracket)
+                }
+            };
+
+            clause_part.is_mandatory = false;
+            if clause_part.is_subtracted {
+                clause_part.is_subtracted = false;
+                clause_part.is_inverted = !clause_part.is_inverted;
+            }
+
+            clause_part
+        })
+        .collect()
 }
 
 #[inline(never)]
@@ -251,7 +374,48 @@ fn is_double_quote(c: char) -> bool {
     }
 }
 
-/// Called when 1 of the operators: NOT, AND, (, ), ", :, is encountered
+/// Matches a ~N proximity suffix after the closing quote of a phrase query at j.
+///
+/// Returns the slop and the index of its last digit.
+#[inline(never)]
+fn get_phrase_slop(query_chars: &[char], j: usize) -> Option<(u32, usize)> {
+    if query_chars.get(j + 1) != Some(&'~') {
+        return None;
+    }
+
+    let num_digits = query_chars[j + 2..].iter().take_while(|c| c.is_ascii_digit()).count();
+    if num_digits == 0 {
+        return None;
+    }
+
+    let slop = query_chars[j + 2..j + 2 + num_digits]
+        .iter()
+        .fold(0_u32, |acc, c| acc.saturating_mul(10).saturating_add(c.to_digit(10).unwrap_or(0)));
+    Some((slop, j + 1 + num_digits))
+}
+
+/// Wraps a term with its alternatives from field analyzers in a QueryPartType::Bracket,
+/// each of which only matches in its field.
+#[inline(never)]
+fn wrap_field_terms(part: QueryPart, field_terms: Vec<(String, String)>) -> QueryPart {
+    let mut children = Vec::with_capacity(field_terms.len() + 1);
+    children.push(part);
+    for (field_name, term) in field_terms {
+        children.push(QueryPart {
+            field_name: Some(field_name),
+            terms_searched: Some(vec![term.clone()]),
+            term: Some(term),
+            ..QueryPart::get_base(QueryPartType::Term)
+        });
+    }
+
+    QueryPart {
+        children: Some(children),
+        ..QueryPart::get_base(QueryPartType::Bracket)
+    }
+}
+
+/// Called when 1 of the operators: (, ), ", :, |, AND, OR, NOT, is encountered
 /// or at the end of input
 /// 
 /// Tokenizes the current slice into term query parts,
@@ -288,9 +452,12 @@ fn handle_terminator(
         term,
         term_inflections,
         original_term,
+        query_span,
         suffix_wildcard,
         is_corrected,
+        corrected_word,
         prefix_ops,
+        field_terms,
     } in tokenize_result.terms {
         let mut part = QueryPart {
             auto_suffix_wildcard: tokenize_result.auto_suffix_wildcard,
@@ -298,9 +465,14 @@ fn handle_terminator(
             is_corrected,
             term,
             original_term: Some(original_term),
+            query_span: Some(query_span),
+            corrected_word,
             terms_searched: Some(term_inflections),
             ..QueryPart::get_base(QueryPartType::Term)
         };
+        if !field_terms.is_empty() {
+            part = wrap_field_terms(part, field_terms);
+        }
         set_prefix_ops(prefix_ops, &mut part);
         query_parts.push(part);
 
@@ -324,6 +496,8 @@ pub fn parse_query(
     let mut did_encounter_escape = false;
     let mut escape_indices: Vec<usize> = Vec::new();
     let mut op_stack: Vec<Operator> = Vec::new();
+    // Indices into query_parts where OR operators were encountered, for each open group
+    let mut or_splits: Vec<Vec<usize>> = vec![Vec::new()];
 
     let mut i = 0; // start of current slice
     let mut j = 0; // end of current slice
@@ -355,14 +529,20 @@ pub fn parse_query(
                         term_inflections,
                         is_corrected,
                         original_term,
+                        query_span,
+                        corrected_word,
                         suffix_wildcard: _, // TODO unsupported for now
                         prefix_ops: _,
+                        // Positions in phrases are only those of the default analyzer
+                        field_terms: _,
                     } in tokenize_result.terms {
                         children.push(QueryPart {
                             is_mandatory: term.is_some(),
                             is_corrected,
                             term,
                             original_term: Some(original_term),
+                            query_span: Some(query_span),
+                            corrected_word,
                             terms_searched: Some(term_inflections),
                             ..QueryPart::get_base(QueryPartType::Term)
                         });
@@ -377,6 +557,11 @@ pub fn parse_query(
                     );
                     set_prefix_ops(prefix_ops, &mut phrase_part);
 
+                    if let Some((slop, slop_end)) = get_phrase_slop(&query_chars, j) {
+                        phrase_part.slop = Some(slop);
+                        j = slop_end;
+                    }
+
                     query_parts.push(phrase_part);
                     handle_op(&mut query_parts, &mut op_stack);
 
@@ -385,6 +570,14 @@ pub fn parse_query(
                 }
             }
             QueryParseState::None => {
+                let keyword_operator = if did_encounter_escape {
+                    None
+                } else if c == '|' {
+                    Some((KeywordOperator::Or, 1))
+                } else {
+                    get_keyword_operator(&query_chars, j)
+                };
+
                 if !did_encounter_escape && ((with_positions && is_double_quote(c)) || c == '(' || c == ')') {
                     handle_terminator(
                         tokenizer,
@@ -410,7 +603,13 @@ pub fn parse_query(
                         
                         query_parts.push(part);
                         op_stack.push(Operator::OpenGroup);
+                        or_splits.push(Vec::new());
                     } else if c == ')' {
+                        // Dangling AND / NOT operators, e.g. (a AND)
+                        while matches!(op_stack.last(), Some(Operator::Mandatory | Operator::Subtract)) {
+                            op_stack.pop();
+                        }
+
                         // Guard against ')' without a matching '(' (just treat it literally, almost)
                         if !op_stack.is_empty() && matches!(unsafe { op_stack.last().unwrap_unchecked() }, Operator::OpenGroup)
                         {
@@ -431,7 +630,14 @@ pub fn parse_query(
                             
                             if let Some(idx) = open_bracket_querypart_idx {
                                 let children: Vec<QueryPart> = query_parts.drain(idx + 1..).collect();
-                                unsafe { query_parts.last_mut().unwrap_unchecked() }.children = Some(children);
+                                let group_or_splits: Vec<usize> = or_splits
+                                    .pop()
+                                    .unwrap_or_default()
+                                    .into_iter()
+                                    .map(|split| split - (idx + 1))
+                                    .collect();
+                                unsafe { query_parts.last_mut().unwrap_unchecked() }.children =
+                                    Some(group_disjunctions(children, &group_or_splits));
 
                                 op_stack.pop(); // throw the OpenGroup
                                 handle_op(&mut query_parts, &mut op_stack);
@@ -439,6 +645,39 @@ pub fn parse_query(
                             }
                         }
                     }
+                } else if let Some((keyword_operator, len)) = keyword_operator {
+                    handle_terminator(
+                        tokenizer,
+                        &query_chars,
+                        i,
+                        j,
+                        &escape_indices,
+                        &mut query_parts,
+                        &mut op_stack,
+                        dict,
+                    );
+
+                    match keyword_operator {
+                        KeywordOperator::And => {
+                            // The preceding part, unless it is the group the operator is in
+                            if let Some(last) = query_parts.last_mut() {
+                                if !(matches!(last.part_type, QueryPartType::Bracket) && last.children.is_none()) {
+                                    set_prefix_ops(PrefixResult { is_mandatory: true, ..Default::default() }, last);
+                                }
+                            }
+                            op_stack.push(Operator::Mandatory);
+                        }
+                        KeywordOperator::Or => {
+                            if let Some(group_or_splits) = or_splits.last_mut() {
+                                group_or_splits.push(query_parts.len());
+                            }
+                        }
+                        KeywordOperator::Not => op_stack.push(Operator::Subtract),
+                    }
+
+                    j += len - 1;
+                    i = j + 1;
+                    k = i;
                 } else if !did_encounter_escape && c == ':' {
                     for field_name in valid_fields {
                         if j >= field_name.len() {
@@ -496,14 +735,14 @@ pub fn parse_query(
         dict,
     );
 
-    query_parts
+    group_disjunctions(query_parts, &or_splits[0])
 }
 
 #[cfg(test)]
 pub mod test {
     use std::collections::BTreeMap;
 
-    use infisearch_common::language::{InfiLanguageConfig, InfiLanguageConfigOpts};
+    use infisearch_common::language::{InfiLanguageConfig, InfiLanguageConfigOpts, InfiSynonymsConfig};
     use infisearch_common::dictionary::{Dictionary, TermInfo};
     use pretty_assertions::assert_eq;
 
@@ -511,7 +750,7 @@ pub mod test {
     use infisearch_lang_chinese::chinese;
     use smartstring::{SmartString, LazyCompact};
 
-    use super::{QueryPart, QueryPartType};
+    use super::{wrap_string, QueryPart, QueryPartType};
 
     impl QueryPart {
         fn mandatory(mut self) -> QueryPart {
@@ -612,7 +851,7 @@ pub mod test {
             );
         }
 
-        Dictionary { term_infos }
+        Dictionary { term_infos, spelling_index: Vec::new() }
     }
 
     fn wrap_in_parentheses(query_parts: Vec<QueryPart>) -> QueryPart {
@@ -625,10 +864,13 @@ pub mod test {
             suffix_wildcard: false,
             is_suffixed: false,
             original_term: None,
+            query_span: None,
+            corrected_word: None,
             term: None,
             terms_searched: None,
             part_type: QueryPartType::Bracket,
             field_name: None,
+            slop: None,
             children: Some(query_parts),
             weight: 1.0,
         }
@@ -644,10 +886,13 @@ pub mod test {
             suffix_wildcard: false,
             is_suffixed: false,
             original_term: Some(term.to_owned()),
+            query_span: None,
+            corrected_word: None,
             term: Some(term.to_owned()),
             terms_searched: Some(vec![term.to_owned()]),
             part_type: QueryPartType::Term,
             field_name: None,
+            slop: None,
             children: None,
             weight: 1.0,
         }
@@ -675,10 +920,13 @@ pub mod test {
             suffix_wildcard: false,
             is_suffixed: false,
             original_term: None,
+            query_span: None,
+            corrected_word: None,
             term: None,
             terms_searched: None,
             part_type: QueryPartType::Phrase,
             field_name: None,
+            slop: None,
             children: Some(children),
             weight: 1.0,
         }
@@ -688,6 +936,8 @@ pub mod test {
         let mut tokenizer = ascii::new_with_options(&InfiLanguageConfig {
             lang: "ascii".to_owned(),
             options: InfiLanguageConfigOpts::default(),
+            analyzers: BTreeMap::new(),
+            synonyms: InfiSynonymsConfig::default(),
         });
 
         super::parse_query(
@@ -703,6 +953,8 @@ pub mod test {
         let mut tokenizer = ascii::new_with_options(&InfiLanguageConfig {
             lang: "ascii_stemmer".to_owned(),
             options: InfiLanguageConfigOpts::default(),
+            analyzers: BTreeMap::new(),
+            synonyms: InfiSynonymsConfig::default(),
         });
 
         super::parse_query(
@@ -718,6 +970,8 @@ pub mod test {
         let mut tokenizer = chinese::new_with_options(&InfiLanguageConfig {
             lang: "chinese".to_owned(),
             options: InfiLanguageConfigOpts::default(),
+            analyzers: BTreeMap::new(),
+            synonyms: InfiSynonymsConfig::default(),
         });
 
         super::parse_query(
@@ -739,6 +993,8 @@ pub mod test {
                 stemmer: None,
                 max_term_len: None,
             },
+            analyzers: BTreeMap::new(),
+            synonyms: InfiSynonymsConfig::default(),
         });
 
         super::parse_query(
@@ -870,6 +1126,70 @@ pub mod test {
         ]);
     }
 
+    #[test]
+    fn phrase_slop_test() {
+        let mut near = get_phrase(vec![get_lorem().mandatory(), get_ipsum().mandatory()]);
+        near.slop = Some(5);
+        assert_eq!(parse("\"lorem ipsum\"~5"), vec![near]);
+
+        let mut near = get_phrase(vec![get_lorem().mandatory(), get_ipsum().mandatory()]);
+        near.slop = Some(12);
+        assert_eq!(parse("lorem \"lorem ipsum\"~12 ipsum"), vec![get_lorem().no_expand(), near, get_ipsum()]);
+
+        // ~ without a number is the inversion operator
+        assert_eq!(parse("\"lorem ipsum\" ~lorem"), vec![
+            get_phrase(vec![get_lorem().mandatory(), get_ipsum().mandatory()]),
+            get_lorem().negated(),
+        ]);
+    }
+
+    #[test]
+    fn keyword_operator_test() {
+        assert_eq!(parse("lorem AND ipsum"), vec![get_lorem().no_expand().mandatory(), get_ipsum().mandatory()]);
+        assert_eq!(parse("lorem NOT ipsum"), vec![get_lorem().no_expand(), get_ipsum().subtracted()]);
+        assert_eq!(parse("-lorem AND ipsum"), vec![get_lorem().no_expand().subtracted(), get_ipsum().mandatory()]);
+
+        // Lowercase, escaped, or non-delimited keywords are treated as terms
+        assert_eq!(parse("lorem and ipsum"), vec![get_lorem(), get_term("and"), get_ipsum()]);
+        assert_eq!(parse("NOTipsum"), vec![get_term("notipsum")]);
+        assert_eq!(parse("\\NOT ipsum"), vec![get_term("not"), get_ipsum()]);
+
+        assert_eq!(parse("(lorem AND)"), vec![wrap_in_parentheses(vec![get_lorem().no_expand().mandatory()])]);
+        assert_eq!(parse("NOT(lorem)"), vec![wrap_in_parentheses(vec![get_lorem()]).subtracted()]);
+    }
+
+    #[test]
+    fn or_test() {
+        assert_eq!(parse("lorem OR ipsum"), vec![get_lorem().no_expand(), get_ipsum()]);
+        assert_eq!(parse("lorem|ipsum"), vec![get_lorem(), get_ipsum()]);
+        assert_eq!(parse("OR lorem OR"), vec![get_lorem().no_expand()]);
+
+        // Clauses are optional
+        assert_eq!(parse("+lorem OR +ipsum"), vec![get_lorem().no_expand(), get_ipsum()]);
+        assert_eq!(parse("lorem OR -ipsum"), vec![get_lorem().no_expand(), get_ipsum().negated()]);
+
+        // AND binds tighter than OR
+        assert_eq!(parse("lorem AND ipsum OR lorem"), vec![
+            wrap_in_parentheses(vec![get_lorem().no_expand().mandatory(), get_ipsum().no_expand().mandatory()]),
+            get_lorem(),
+        ]);
+
+        assert_eq!(parse("(lorem OR ipsum) AND +lorem"), vec![
+            wrap_in_parentheses(vec![get_lorem().no_expand(), get_ipsum()]).mandatory(),
+            get_lorem().mandatory(),
+        ]);
+        assert_eq!(parse("lorem OR (ipsum | lorem ipsum)"), vec![
+            get_lorem().no_expand(),
+            wrap_in_parentheses(vec![
+                get_ipsum().no_expand(),
+                wrap_in_parentheses(vec![get_lorem(), get_ipsum()]),
+            ]),
+        ]);
+        assert_eq!(parse("title:(lorem OR ipsum)"), vec![
+            wrap_in_parentheses(vec![get_lorem().no_expand(), get_ipsum()]).with_field("title"),
+        ]);
+    }
+
     #[test]
     fn phrase_test() {
         assert_eq!(parse_wo_pos("\"lorem ipsum\""), vec![get_term("lorem"), get_term("ipsum")]);
@@ -1290,4 +1610,12 @@ pub mod test {
         assert_eq!(parse("我-(lorem)"), vec![wrap_in_parentheses(vec![get_lorem()])]);
         assert_eq!(parse_zn("我-(lorem)"), vec![get_term("我"), wrap_in_parentheses(vec![get_lorem()]).subtracted()]);
     }
+
+    #[test]
+    fn test_wrap_string() {
+        assert_eq!(wrap_string(r#"lorem "ipsum" \ dolor"#), r#""lorem \"ipsum\" \\ dolor""#);
+        assert_eq!(wrap_string("lorem\tipsum\r\ndolor"), r#""lorem\tipsum\r\ndolor""#);
+        assert_eq!(wrap_string("lorem\u{0}ipsum\u{1f}"), r#""lorem\u0000ipsum\u001f""#);
+        assert_eq!(wrap_string("lorem\u{7f}我"), "\"lorem\u{7f}我\"");
+    }
 }
@@ -1,6 +1,7 @@
 use infisearch_common::utils::push;
 
 use crate::dictionary::SearchDictionary;
+use crate::searcher::query::SpellingCorrection;
 use crate::searcher::query_parser::QueryPart;
 use crate::searcher::Searcher;
 
@@ -9,6 +10,9 @@ use super::query_parser::QueryPartType;
 // **Total** weight of expanded terms for auto suffix search
 const MAXIMUM_TERM_EXPANSION_WEIGHT: f32 = 0.5;
 
+// Weight of each synonym, relative to the original term(s)
+const SYNONYM_WEIGHT: f32 = 0.8;
+
 impl Searcher {
     fn is_term_used(term: &str, query_parts: &Vec<QueryPart>) -> bool {
         for query_part in query_parts {
@@ -26,14 +30,81 @@ impl Searcher {
         return false;
     }
 
-    pub fn expand_term_postings_lists(&self, query_parts: &mut Vec<QueryPart>) {
-        if query_parts.is_empty()
-            || self.searcher_config.searcher_options.max_suffix_search_terms == 0 {
-            return;
+    /// Expands synonyms and suffix wildcards,
+    /// returning the spelling corrections of the query's terms.
+    pub fn expand_term_postings_lists(&self, query_parts: &mut Vec<QueryPart>) -> Vec<SpellingCorrection> {
+        let mut spelling_corrections = Vec::new();
+        if query_parts.is_empty() {
+            return spelling_corrections;
+        }
+
+        let suggestions_only = self.searcher_config.searcher_options.spelling_suggestions_only;
+        if suggestions_only {
+            // Before expansion, so that the original query is searched exactly as it is
+            self.take_spelling_corrections(query_parts, true, &mut spelling_corrections);
+        }
+
+        self.expand_synonyms(query_parts);
+
+        if self.searcher_config.searcher_options.max_suffix_search_terms != 0 {
+            self.expand_wildcard_suffix(query_parts);
+            self.expand_last_query_part(query_parts);
         }
 
-        self.expand_wildcard_suffix(query_parts);
-        self.expand_last_query_part(query_parts);
+        if !suggestions_only {
+            // After expansion, which discards the correction of a suffix searched term
+            self.take_spelling_corrections(query_parts, false, &mut spelling_corrections);
+        }
+
+        spelling_corrections
+    }
+
+    /// Collects the terms corrected by the tokenizer,
+    /// reverting them to the original (unsearchable) terms if `revert` is true.
+    fn take_spelling_corrections(
+        &self,
+        query_parts: &mut Vec<QueryPart>,
+        revert: bool,
+        spelling_corrections: &mut Vec<SpellingCorrection>,
+    ) {
+        for query_part in query_parts {
+            if let Some(children) = &mut query_part.children {
+                self.take_spelling_corrections(children, revert, spelling_corrections);
+            }
+
+            if !query_part.is_corrected {
+                continue;
+            }
+
+            if let (Some(original_term), Some(corrected_term)) = (&query_part.original_term, &query_part.term) {
+                let correction_idx = match spelling_corrections.iter().position(|c| &c.original_term == original_term) {
+                    Some(idx) => idx,
+                    None => {
+                        spelling_corrections.push(SpellingCorrection {
+                            original_term: original_term.clone(),
+                            corrected_term: corrected_term.clone(),
+                            doc_freq: self.dictionary.get_term_info(corrected_term)
+                                .map_or(0, |term_info| term_info.doc_freq),
+                            replacements: Vec::new(),
+                        });
+                        spelling_corrections.len() - 1
+                    }
+                };
+
+                if let Some((start, end)) = query_part.query_span {
+                    let word = query_part.corrected_word.as_ref().unwrap_or(corrected_term);
+                    spelling_corrections[correction_idx].replacements.push((start, end, word.clone()));
+                }
+
+                if revert {
+                    if let Some(terms_searched) = &mut query_part.terms_searched {
+                        terms_searched.retain(|term| term != corrected_term);
+                    }
+                    query_part.term = None;
+                    query_part.is_corrected = false;
+                }
+            }
+        }
     }
 
     fn expand_last_query_part(&self, query_parts: &mut Vec<QueryPart>) {
@@ -78,6 +149,110 @@ impl Searcher {
         }
     }
 
+    /// Replaces the longest sequences of terms with synonyms with a QueryPartType::Bracket wrapper,
+    /// containing the original terms and their synonyms.
+    /// 
+    /// Only the sole term of a sequence may have operators. Terms within phrases are not expanded.
+    fn expand_synonyms(&self, query_parts: &mut Vec<QueryPart>) {
+        if self.synonyms.is_empty() {
+            return;
+        }
+
+        let mut idx = 0;
+        while idx < query_parts.len() {
+            let query_part = &mut query_parts[idx];
+            if let Some(children) = &mut query_part.children {
+                if matches!(query_part.part_type, QueryPartType::Bracket) {
+                    self.expand_synonyms(children);
+                }
+                idx += 1;
+                continue;
+            }
+
+            let first_part = &query_parts[idx];
+            let following_terms = query_parts[idx..].iter()
+                .enumerate()
+                .take_while(|(offset, query_part)| *offset == 0 || (
+                    !has_operators(first_part)
+                    && !has_operators(query_part)
+                    && query_part.field_name == first_part.field_name
+                ))
+                .map_while(|(_offset, query_part)| if matches!(query_part.part_type, QueryPartType::Term) {
+                    query_part.term.as_deref()
+                } else {
+                    None
+                });
+
+            if let Some((num_terms, synonyms)) = self.synonyms.get_longest_match(following_terms) {
+                let synonym_parts: Vec<_> = synonyms.iter()
+                    .map(|synonym| self.get_synonym_query_part(synonym))
+                    .collect();
+
+                let mut original_parts: Vec<_> = query_parts.splice(
+                    idx..idx + num_terms,
+                    std::iter::once(QueryPart::get_base(QueryPartType::Bracket)),
+                ).collect();
+
+                let wrapper_part = &mut query_parts[idx];
+                let first_part = &mut original_parts[0];
+                wrapper_part.is_mandatory = first_part.is_mandatory;
+                wrapper_part.is_subtracted = first_part.is_subtracted;
+                wrapper_part.is_inverted = first_part.is_inverted;
+                wrapper_part.field_name = first_part.field_name.take();
+                first_part.is_mandatory = false;
+                first_part.is_subtracted = false;
+                first_part.is_inverted = false;
+                for query_part in original_parts.iter_mut() {
+                    query_part.field_name = None;
+                }
+
+                let mut children = Vec::with_capacity(synonym_parts.len() + 1);
+                children.push(if original_parts.len() == 1 {
+                    unsafe { original_parts.pop().unwrap_unchecked() }
+                } else {
+                    QueryPart {
+                        children: Some(original_parts),
+                        ..QueryPart::get_base(QueryPartType::Bracket)
+                    }
+                });
+                children.extend(synonym_parts);
+                wrapper_part.children = Some(children);
+            }
+
+            idx += 1;
+        }
+    }
+
+    /// A synonym of multiple terms is searched as a phrase if positions are indexed,
+    /// and otherwise requires all of its terms.
+    fn get_synonym_query_part(&self, synonym: &[String]) -> QueryPart {
+        let get_term_part = |term: &String| QueryPart {
+            term: Some(term.clone()),
+            terms_searched: Some(vec![term.clone()]),
+            ..QueryPart::get_base(QueryPartType::Term)
+        };
+
+        if let [term] = synonym {
+            return QueryPart {
+                weight: SYNONYM_WEIGHT,
+                ..get_term_part(term)
+            };
+        }
+
+        let part_type = if self.searcher_config.indexing_config.with_positions {
+            QueryPartType::Phrase
+        } else {
+            QueryPartType::Bracket
+        };
+        QueryPart {
+            weight: SYNONYM_WEIGHT,
+            children: Some(synonym.iter()
+                .map(|term| QueryPart { is_mandatory: true, ..get_term_part(term) })
+                .collect()),
+            ..QueryPart::get_base(part_type)
+        }
+    }
+
     /// Performs wildcard suffix search on a QueryPart
     /// 
     /// The QueryPart is replaced with a QueryPartType::Bracket wrapper.
@@ -166,6 +341,10 @@ impl Searcher {
     }
 }
 
+fn has_operators(query_part: &QueryPart) -> bool {
+    query_part.is_mandatory || query_part.is_subtracted || query_part.is_inverted
+}
+
 fn is_expand_candidate(query_part: &QueryPart) -> bool {
     matches!(query_part.part_type, QueryPartType::Term)
         && query_part.original_term.is_some()
@@ -9,7 +9,6 @@ use infisearch_common::bitmap;
 use infisearch_common::metadata::EnumMax;
 use infisearch_common::utils::push;
 
-use crate::doc_info::DocInfo;
 use crate::postings_list::{self, Field, PlIterator, PostingsList, Doc, PlAndInfo};
 use crate::searcher::query_parser::QueryPart;
 use crate::searcher::query_parser::QueryPartType;
@@ -17,6 +16,16 @@ use crate::searcher::Searcher;
 
 use super::query::{DocResult, DocResultComparator};
 
+/// A key to sort results by, see `process_and_rank`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SortKey {
+    /// Relevance of the document
+    Score,
+    /// Value of the i64 field with this id
+    I64(usize),
+    /// Enum value id of the enum field with this id, which follows the order enum values were first indexed
+    Enum(usize),
+}
 
 fn empty_pl() -> PostingsList {
     PostingsList {
@@ -233,6 +242,104 @@ impl Searcher {
         new_pl
     }
 
+    /// For proximity (~N) phrase queries, matches documents where all terms occur in the same field,
+    /// in any order, with at most slop extra positions between them.
+    fn populate_near_postings_list(
+        &self,
+        query_parts: &mut Vec<QueryPart>,
+        term_postings_lists: &Vec<Rc<PostingsList>>,
+        weight: f32,
+        slop: u32,
+    ) -> PostingsList {
+        let mut new_pl = empty_pl();
+
+        // Terms removed by the tokenizer (e.g. stop words) are not mandatory
+        let child_postings_lists: Vec<PlAndInfo> = self.process_pls(query_parts, term_postings_lists, weight)
+            .into_iter()
+            .filter(|pl_and_info| pl_and_info.is_mandatory)
+            .collect();
+
+        if child_postings_lists.is_empty() {
+            new_pl.calc_pseudo_idf(self.doc_info.num_docs);
+            return new_pl;
+        }
+
+        let max_window_len = (child_postings_lists.len() - 1) as u32 + slop;
+        let num_scored_fields = self.searcher_config.num_scored_fields;
+
+        let mut idxs = vec![0; child_postings_lists.len()];
+        let mut term_positions: Vec<&[u32]> = Vec::with_capacity(child_postings_lists.len());
+        'outer: loop {
+            // Forward every postings list to the largest current document id
+            let mut doc_id = 0;
+            for (pl_and_info, &idx) in child_postings_lists.iter().zip(idxs.iter()) {
+                if let Some(td) = pl_and_info.pl.term_docs.get(idx) {
+                    doc_id = doc_id.max(td.doc_id);
+                } else {
+                    break 'outer;
+                }
+            }
+
+            let mut is_match = true;
+            for (pl_and_info, idx) in child_postings_lists.iter().zip(idxs.iter_mut()) {
+                let term_docs = &pl_and_info.pl.term_docs;
+                while term_docs[*idx].doc_id < doc_id {
+                    *idx += 1;
+                    if *idx >= term_docs.len() {
+                        break 'outer;
+                    }
+                }
+                is_match = is_match && term_docs[*idx].doc_id == doc_id;
+            }
+
+            if !is_match {
+                continue;
+            }
+
+            let mut doc = Doc {
+                doc_id,
+                fields: vec![Field::default(); num_scored_fields],
+                score: 0.0,
+            };
+            for (field_id, field) in doc.fields.iter_mut().enumerate() {
+                term_positions.clear();
+                for (pl_and_info, &idx) in child_postings_lists.iter().zip(idxs.iter()) {
+                    match pl_and_info.pl.term_docs[idx].fields.get(field_id) {
+                        Some(td_field) if !td_field.field_positions.is_empty() => {
+                            term_positions.push(&td_field.field_positions)
+                        }
+                        _ => break,
+                    }
+                }
+
+                if term_positions.len() == child_postings_lists.len() {
+                    proximity_ranking::find_near_windows(&term_positions, max_window_len, &mut field.field_positions);
+                    field.field_tf = field.field_positions.len() as f32;
+                }
+            }
+
+            if doc.fields.iter().any(|field| field.field_tf > 0.0) {
+                for (pl_and_info, &idx) in child_postings_lists.iter().zip(idxs.iter()) {
+                    let td = &pl_and_info.pl.term_docs[idx];
+                    doc.score += if td.score != 0.0 {
+                        td.score
+                    } else {
+                        self.calc_doc_bm25_score(td, doc_id, &pl_and_info.pl, pl_and_info.weight)
+                    };
+                }
+                new_pl.term_docs.push(doc);
+            }
+
+            for idx in idxs.iter_mut() {
+                *idx += 1;
+            }
+        }
+
+        new_pl.calc_pseudo_idf(self.doc_info.num_docs);
+
+        new_pl
+    }
+
     fn invert_postings_list(&self, pl: Rc<PostingsList>, weight: f32) -> Rc<PostingsList> {
         let mut result_pl = PostingsList {
             term_docs: Vec::with_capacity(self.doc_info.doc_length_factors_len as usize - pl.term_docs.len()),
@@ -347,9 +454,14 @@ impl Searcher {
                 );
 
                 let is_phrase = matches!(query_part.part_type, QueryPartType::Phrase);
-                pl_opt = Some(Rc::new(self.populate_conjunctive_postings_lists(
-                    !is_phrase, is_phrase, children, term_postings_lists, weight,
-                )));
+                pl_opt = Some(Rc::new(match (is_phrase, query_part.slop) {
+                    (true, Some(slop)) => self.populate_near_postings_list(
+                        children, term_postings_lists, weight, slop,
+                    ),
+                    _ => self.populate_conjunctive_postings_lists(
+                        !is_phrase, is_phrase, children, term_postings_lists, weight,
+                    ),
+                }));
             } else if let Some(term) = &query_part.term {
                 debug_assert!(
                     query_part.children.is_none()
@@ -391,8 +503,7 @@ impl Searcher {
         term_postings_lists: &Vec<Rc<PostingsList>>,
         enum_filters: Vec<(usize, [bool; EnumMax::MAX as usize])>,
         i64_filters: Vec<(usize, Option<i64>, Option<i64>)>,
-        num_sort: Option<usize>,
-        reverse_sort: bool,
+        sort_keys: Vec<(SortKey, bool)>,
     ) -> BinaryHeap<DocResult, Box<DocResultComparator>> {
         let root_pl = self.populate_conjunctive_postings_lists(
             false, false, query_parts, term_postings_lists, 1.0,
@@ -433,26 +544,31 @@ impl Searcher {
             }
         }
 
-        let doc_info_pointer = &self.doc_info as *const DocInfo;
+        // Ties are broken by the document's score, unless already sorted by it
+        let mut sort_keys = sort_keys;
+        if !sort_keys.iter().any(|(sort_key, _ascending)| *sort_key == SortKey::Score) {
+            sort_keys.push((SortKey::Score, false));
+        }
+
+        let doc_info = Rc::clone(&self.doc_info);
         BinaryHeap::from_vec_cmp(doc_results, Box::new(move |a: &DocResult, b: &DocResult| {
-            let mut cmp = if let Some(num_sort) = num_sort {
-                let doc_info = unsafe { &*doc_info_pointer };
-                let value_a = doc_info.get_num_val(a.doc_id as usize, num_sort);
-                let value_b = doc_info.get_num_val(b.doc_id as usize, num_sort);
-                if reverse_sort {
-                    value_b.cmp(&value_a)
-                } else {
-                    value_a.cmp(&value_b)
-                }
-            } else {
-                Ordering::Equal
-            };
+            for (sort_key, ascending) in sort_keys.iter() {
+                let cmp = match sort_key {
+                    SortKey::Score => unsafe { a.score.partial_cmp(&b.score).unwrap_unchecked() },
+                    SortKey::I64(num_id) => doc_info.get_num_val(a.doc_id as usize, *num_id)
+                        .cmp(&doc_info.get_num_val(b.doc_id as usize, *num_id)),
+                    SortKey::Enum(enum_id) => doc_info.get_enum_val(a.doc_id as usize, *enum_id)
+                        .cmp(&doc_info.get_enum_val(b.doc_id as usize, *enum_id)),
+                };
 
-            if let Ordering::Equal = cmp {
-                cmp = unsafe { a.score.partial_cmp(&b.score).unwrap_unchecked() };
+                // The heap pops the greatest result first
+                let cmp = if *ascending { cmp.reverse() } else { cmp };
+                if cmp != Ordering::Equal {
+                    return cmp;
+                }
             }
 
-            cmp
+            Ordering::Equal
         }))
     }
 }
@@ -469,6 +585,8 @@ mod test {
     use crate::searcher::query_parser::test as query_parser_test;
     use crate::searcher::test as searcher_test;
 
+    use super::SortKey;
+
     struct TermPostingsListsBuilder(Vec<PostingsList>);
 
     impl TermPostingsListsBuilder {
@@ -1280,4 +1398,112 @@ mod test {
             );
         }
     }
+
+    fn search_doc_ids(query: &str, term_postings_lists: Vec<Rc<PostingsList>>) -> Vec<u32> {
+        search(query, term_postings_lists).term_docs.iter().map(|td| td.doc_id).collect()
+    }
+
+    #[test]
+    fn test_or_queries() {
+        assert_eq!(
+            search_doc_ids(
+                "(lorem OR ipsum) AND by",
+                TermPostingsListsBuilder::new()
+                    .with("lorem", "[[1,[1]]], null,      [[1,[1]]]")
+                    .with("ipsum", "null,      [[1,[1]]]")
+                    .with("by",    "[[1,[2]]], [[1,[2]]], null,      [[1,[1]]]")
+                    .get_rc_wrapped()
+            ),
+            vec![0, 1],
+        );
+
+        assert_eq!(
+            search_doc_ids(
+                "lorem AND ipsum OR by",
+                TermPostingsListsBuilder::new()
+                    .with("lorem", "[[1,[1]]], [[1,[1]]]")
+                    .with("ipsum", "null,      [[1,[2]]], [[1,[1]]]")
+                    .with("by",    "null,      null,      null,      [[1,[1]]]")
+                    .get_rc_wrapped()
+            ),
+            vec![1, 3],
+        );
+
+        // Documents without "by" also match the second clause
+        assert_eq!(
+            search_doc_ids(
+                "+lorem +(ipsum OR NOT by)",
+                TermPostingsListsBuilder::new()
+                    .with("lorem", "[[1,[1]]], [[1,[1]]], [[1,[1]]]")
+                    .with("ipsum", "null,      [[1,[2]]]")
+                    .with("by",    "[[1,[2]]], [[1,[3]]]")
+                    .get_rc_wrapped()
+            ),
+            vec![1, 2],
+        );
+    }
+
+    #[test]
+    fn test_near_queries() {
+        let term_postings_lists = || TermPostingsListsBuilder::new()
+            .with("lorem", "[[1,[1]]], [[1,[1]]],     [[1,[1]]], [[1,[1]]]")
+            .with("ipsum", "[[1,[2]]], [[1,[5]]],     [[0,[]],[1,[2]]], null")
+            .get_rc_wrapped();
+
+        assert_eq!(search_doc_ids("\"lorem ipsum\"~0", term_postings_lists()), vec![0]);
+        assert_eq!(search_doc_ids("\"ipsum lorem\"~0", term_postings_lists()), vec![0]);
+        assert_eq!(search_doc_ids("\"lorem ipsum\"~3", term_postings_lists()), vec![0, 1]);
+        // Different fields don't match
+        assert_eq!(search_doc_ids("\"lorem ipsum\"~10", term_postings_lists()), vec![0, 1]);
+
+        let pl = search("\"lorem ipsum\"~3", term_postings_lists());
+        assert_eq!(pl.term_docs[1].fields[0].field_positions, vec![1]);
+        assert!(pl.term_docs[1].score > 0.0);
+
+        // Repeated terms need as many distinct positions
+        let term_postings_lists = || TermPostingsListsBuilder::new()
+            .with("lorem", "[[1,[1]]], [[2,[1,3]]], [[2,[1,9]]]")
+            .get_rc_wrapped();
+        assert_eq!(search_doc_ids("\"lorem lorem\"~2", term_postings_lists()), vec![1]);
+    }
+
+    fn rank(sort_keys: Vec<(SortKey, bool)>) -> Vec<u32> {
+        let mut searcher = searcher_test::create_searcher(5);
+        let doc_info = Rc::get_mut(&mut searcher.doc_info).unwrap();
+        doc_info.num_enum_fields = 1;
+        doc_info.doc_enum_vals = vec![2, 1, 2, 1, 0];
+        doc_info.num_i64_fields = 1;
+        doc_info.doc_i64_vals = vec![10, 30, 20, 40, 0];
+
+        let mut parsed = query_parser_test::parse("lorem");
+        let term_postings_lists = TermPostingsListsBuilder::new()
+            .with("lorem", "[[1,[1]]], [[1,[1]]], [[2,[1,2]]], [[1,[1]]], [[1,[1]]]")
+            .get_rc_wrapped();
+
+        let mut result_heap = searcher.process_and_rank(
+            &mut parsed, &term_postings_lists, Vec::new(), Vec::new(), sort_keys,
+        );
+
+        let mut doc_ids = Vec::new();
+        while let Some(doc_result) = result_heap.pop() {
+            doc_ids.push(doc_result.doc_id);
+        }
+        doc_ids
+    }
+
+    #[test]
+    fn test_multi_key_sort() {
+        // Document 2 has the highest score, the rest tie
+        assert_eq!(rank(Vec::new())[0], 2);
+
+        assert_eq!(rank(vec![(SortKey::I64(0), true)]), vec![4, 0, 2, 1, 3]);
+        assert_eq!(
+            rank(vec![(SortKey::Enum(0), true), (SortKey::I64(0), false)]),
+            vec![4, 3, 1, 2, 0],
+        );
+        assert_eq!(
+            rank(vec![(SortKey::Enum(0), false), (SortKey::Score, false), (SortKey::I64(0), true)]),
+            vec![2, 0, 1, 3, 4],
+        );
+    }
 }
@@ -181,3 +181,85 @@ pub fn rank<'a>(
 
     return phrase_query_res;
 }
+
+/// For proximity (~N) phrase queries, finds the windows where every term occurs
+/// within max_window_len positions of each other, in any order.
+///
+/// Terms repeated in the query must occur as many times in the window, at distinct positions.
+///
+/// Pushes the starting position of each such window into window_starts.
+pub fn find_near_windows(term_positions: &[&[u32]], max_window_len: u32, window_starts: &mut Vec<u32>) {
+    // Positions of each distinct term, and how many times it is repeated
+    let mut terms: Vec<(&[u32], usize)> = Vec::with_capacity(term_positions.len());
+    for &positions in term_positions {
+        if let Some((_positions, count)) = terms.iter_mut().find(|(other, _count)| *other == positions) {
+            *count += 1;
+        } else {
+            terms.push((positions, 1));
+        }
+    }
+
+    if terms.iter().any(|(positions, count)| positions.len() < *count) {
+        return;
+    }
+
+    let mut idxs = vec![0; terms.len()];
+
+    loop {
+        let mut min_term_idx = 0;
+        let mut min_pos = u32::MAX;
+        let mut max_pos = 0;
+        for (term_idx, (&(positions, count), &idx)) in terms.iter().zip(idxs.iter()).enumerate() {
+            // Each term spans its next count occurrences
+            let pos = positions[idx];
+            if pos < min_pos {
+                min_pos = pos;
+                min_term_idx = term_idx;
+            }
+            max_pos = max_pos.max(positions[idx + count - 1]);
+        }
+
+        if max_pos - min_pos <= max_window_len && window_starts.last() != Some(&min_pos) {
+            window_starts.push(min_pos);
+        }
+
+        let (positions, count) = terms[min_term_idx];
+        idxs[min_term_idx] += 1;
+        if idxs[min_term_idx] + count > positions.len() {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::find_near_windows;
+
+    fn find(term_positions: &[&[u32]], max_window_len: u32) -> Vec<u32> {
+        let mut window_starts = Vec::new();
+        find_near_windows(term_positions, max_window_len, &mut window_starts);
+        window_starts
+    }
+
+    #[test]
+    fn test_find_near_windows() {
+        assert_eq!(find(&[&[1, 10], &[2]], 1), vec![1]);
+        // Any order
+        assert_eq!(find(&[&[3, 10], &[1, 12]], 2), vec![1, 10]);
+        assert_eq!(find(&[&[3], &[1]], 1), Vec::<u32>::new());
+        assert_eq!(find(&[&[1, 20], &[5, 22], &[3, 40]], 4), vec![1]);
+        assert_eq!(find(&[&[1, 20], &[5, 22], &[3, 21]], 4), vec![1, 20]);
+    }
+
+    #[test]
+    fn test_find_near_windows_repeated() {
+        // A single occurrence doesn't match a repeated term
+        assert_eq!(find(&[&[5], &[5]], 2), Vec::<u32>::new());
+        assert_eq!(find(&[&[1, 10], &[1, 10]], 2), Vec::<u32>::new());
+        assert_eq!(find(&[&[1, 3, 10], &[1, 3, 10]], 2), vec![1]);
+        assert_eq!(find(&[&[1, 3, 4], &[1, 3, 4]], 2), vec![1, 3]);
+        // Along with other terms
+        assert_eq!(find(&[&[1, 4, 9], &[2], &[1, 4, 9]], 3), vec![1]);
+        assert_eq!(find(&[&[1, 9], &[2], &[1, 9]], 3), Vec::<u32>::new());
+    }
+}
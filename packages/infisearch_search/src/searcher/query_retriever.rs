@@ -51,43 +51,12 @@ impl Searcher {
 
         self.populate_term_postings_lists(query_parts, &mut postings_lists);
 
-        let mut pl_numbers: Vec<u32> = postings_lists
-            .iter()
-            .filter_map(|pl| {
-                if let Some(term_info) = &pl.term_info {
-                    Some(term_info.postings_file_name)
-                } else {
-                    None
-                }
-            })
-            .collect();
-
-        // --------------------------------------------
-        // Dedup
-
-        if pl_numbers.len() > 1 {
-            for i in (1..pl_numbers.len()).rev() {
-                if unsafe { pl_numbers.get_unchecked(..i).contains(pl_numbers.get_unchecked(i)) } {
-                    pl_numbers.remove(i);
-                }
-            }
-        }
-
-        // --------------------------------------------
+        let pl_numbers = get_pl_numbers(&postings_lists);
         
         let parsed_postings_lists = join_all(
             pl_numbers.into_iter()
                 .map(|pl_num| {
-                    // Postings lists that are populated from the same pl_num (postings list file)
-                    let mut curr_pl_num_pls = Vec::with_capacity(postings_lists.len());
-
-                    for i in (0..postings_lists.len()).rev() {
-                        if let Some(term_info) = &unsafe { postings_lists.get_unchecked(i) }.term_info {
-                            if pl_num == term_info.postings_file_name {
-                                push::push_wo_grow(&mut curr_pl_num_pls, postings_lists.remove(i));
-                            }
-                        }
-                    }
+                    let curr_pl_num_pls = take_pl_num_pls(pl_num, &mut postings_lists);
 
                     #[cfg(feature = "perf")]
                     web_sys::console::log_1(
@@ -114,6 +83,67 @@ impl Searcher {
         postings_lists.into_iter().map(Rc::new).collect()
     }
 
+    /// Synchronous counterpart of `retrieve_term_pls` for native consumers,
+    /// which can read the raw postings list files directly using `read_pl`.
+    ///
+    /// `doc_freqs` overrides the document frequencies of terms in the dictionary when computing
+    /// their idf, for scoring against the collection statistics of several indexes.
+    #[cfg(feature = "native")]
+    pub fn retrieve_term_pls_sync<F>(
+        &mut self,
+        query_parts: &mut Vec<QueryPart>,
+        doc_freqs: Option<&std::collections::HashMap<String, u32>>,
+        read_pl: F,
+    ) -> std::io::Result<Vec<Rc<PostingsList>>>
+    where
+        F: Fn(u32) -> std::io::Result<Vec<u8>>,
+    {
+        let mut postings_lists: Vec<PostingsList> = Vec::new();
+
+        self.populate_term_postings_lists(query_parts, &mut postings_lists);
+
+        if let Some(doc_freqs) = doc_freqs {
+            for pl in postings_lists.iter_mut().filter(|pl| pl.term_info.is_some()) {
+                if let Some(&doc_freq) = pl.term.as_ref().and_then(|term| doc_freqs.get(term)) {
+                    pl.idf = get_idf(self.doc_info.num_docs as f32, doc_freq as f32);
+                }
+            }
+        }
+
+        let mut parsed_postings_lists = Vec::with_capacity(postings_lists.len());
+        for pl_num in get_pl_numbers(&postings_lists) {
+            let mut curr_pl_num_pls = take_pl_num_pls(pl_num, &mut postings_lists);
+
+            let mut raw_pl_to_cache = None;
+            let pl_vec = if let Some(cached) = self.postings_list_cache.get(pl_num) {
+                cached
+            } else {
+                raw_pl_to_cache = Some(read_pl(pl_num)?);
+                unsafe { raw_pl_to_cache.as_ref().unwrap_unchecked() }
+            };
+
+            for pl in curr_pl_num_pls.iter_mut() {
+                pl.parse_pl(
+                    pl_vec,
+                    &self.invalidation_vector,
+                    self.searcher_config.num_scored_fields,
+                    self.searcher_config.indexing_config.with_positions,
+                );
+            }
+
+            if let Some(raw_pl_to_cache) = raw_pl_to_cache {
+                self.postings_list_cache.add(pl_num, raw_pl_to_cache);
+            }
+
+            parsed_postings_lists.append(&mut curr_pl_num_pls);
+        }
+
+        // Postings lists of terms not in the dictionary
+        parsed_postings_lists.append(&mut postings_lists);
+
+        Ok(parsed_postings_lists.into_iter().map(Rc::new).collect())
+    }
+
     /// Fetches a raw postings list file for all PostingList structs that rely on it.
     /// 
     /// Then populates them in `parse_pl`.
@@ -157,6 +187,44 @@ impl Searcher {
     }
 }
 
+/// Unique postings list file numbers of the postings lists to retrieve
+fn get_pl_numbers(postings_lists: &[PostingsList]) -> Vec<u32> {
+    let mut pl_numbers: Vec<u32> = postings_lists
+        .iter()
+        .filter_map(|pl| pl.term_info.as_ref().map(|term_info| term_info.postings_file_name))
+        .collect();
+
+    // --------------------------------------------
+    // Dedup
+
+    if pl_numbers.len() > 1 {
+        for i in (1..pl_numbers.len()).rev() {
+            if unsafe { pl_numbers.get_unchecked(..i).contains(pl_numbers.get_unchecked(i)) } {
+                pl_numbers.remove(i);
+            }
+        }
+    }
+
+    // --------------------------------------------
+
+    pl_numbers
+}
+
+/// Removes the postings lists that are populated from the same pl_num (postings list file)
+fn take_pl_num_pls(pl_num: u32, postings_lists: &mut Vec<PostingsList>) -> Vec<PostingsList> {
+    let mut curr_pl_num_pls = Vec::with_capacity(postings_lists.len());
+
+    for i in (0..postings_lists.len()).rev() {
+        if let Some(term_info) = &unsafe { postings_lists.get_unchecked(i) }.term_info {
+            if pl_num == term_info.postings_file_name {
+                push::push_wo_grow(&mut curr_pl_num_pls, postings_lists.remove(i));
+            }
+        }
+    }
+
+    curr_pl_num_pls
+}
+
 struct FetchPlResult {
     postings_lists: Vec<PostingsList>,
     pl_num: u32,
@@ -0,0 +1,366 @@
+//! Best passage snippet extraction and term highlighting from stored field texts.
+//!
+//! Mirrors `Segment` in the search library's `MatchResult.ts`:
+//! field texts are split into terms by the searcher's tokenizer, as the indexer does,
+//! and the passage with the closest window of terms from the most query parts is chosen.
+//!
+//! Only native consumers use this (`NativeSearcher::get_snippet`, the `query` and `serve` commands).
+//! The browser search library keeps highlighting with `Segment`, as field texts are loaded
+//! on the main thread, not in the WebAssembly worker.
+
+use std::ops::Range;
+
+use infisearch_common::tokenize::SearchTokenizer;
+
+use crate::searcher::query_parser::QueryPart;
+
+pub struct SnippetOptions {
+    /// Maximum snippet length in characters, or 0 to return the entire field text
+    pub max_len: usize,
+}
+
+impl Default for SnippetOptions {
+    fn default() -> Self {
+        SnippetOptions {
+            max_len: 160,
+        }
+    }
+}
+
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct Snippet {
+    pub field_name: String,
+    /// Passage of the field text, with runs of whitespace collapsed
+    pub text: String,
+    /// Byte ranges of the highlighted terms in `text`
+    pub highlights: Vec<Range<usize>>,
+    pub is_start_truncated: bool,
+    pub is_end_truncated: bool,
+}
+
+struct TermMatch {
+    range: Range<usize>,
+    group: usize,
+}
+
+struct FieldMatches<'a> {
+    field_name: &'a str,
+    text: &'a str,
+    /// Byte ranges of the words of `text`
+    words: Vec<Range<usize>>,
+    matches: Vec<TermMatch>,
+    /// Index range into `matches` of the best window
+    window: Range<usize>,
+    num_groups_matched: usize,
+    span: usize,
+}
+
+/// Terms searched for by each (non-subtracted, non-inverted) query part, as `getSearchedTerms` does.
+///
+/// Terms of a part are grouped together, as matching any of them counts as matching the part.
+pub fn get_searched_terms(query_parts: &[QueryPart]) -> Vec<Vec<String>> {
+    let mut searched_terms = Vec::new();
+    add_searched_terms(query_parts, &mut searched_terms, true);
+    searched_terms
+}
+
+fn add_searched_terms(query_parts: &[QueryPart], searched_terms: &mut Vec<Vec<String>>, not_context: bool) {
+    for query_part in query_parts {
+        let not_context = if query_part.is_subtracted || query_part.is_inverted {
+            !not_context
+        } else {
+            not_context
+        };
+
+        if let Some(terms_searched) = &query_part.terms_searched {
+            if not_context && !terms_searched.is_empty() {
+                searched_terms.push(terms_searched.clone());
+            }
+        } else if let Some(children) = &query_part.children {
+            add_searched_terms(children, searched_terms, not_context);
+        }
+    }
+}
+
+fn find_matches(words: &[(Range<usize>, String)], searched_terms: &[Vec<String>]) -> Vec<TermMatch> {
+    words
+        .iter()
+        .filter_map(|(range, term)| {
+            searched_terms
+                .iter()
+                .position(|terms| terms.contains(term))
+                .map(|group| TermMatch { range: range.clone(), group })
+        })
+        .collect()
+}
+
+/// Finds the smallest window of matches containing every group that was matched.
+///
+/// Returns the number of groups matched, and the index range into `matches`.
+fn find_best_window(matches: &[TermMatch], num_groups: usize) -> (usize, Range<usize>) {
+    let mut group_counts = vec![0; num_groups];
+    for term_match in matches {
+        group_counts[term_match.group] += 1;
+    }
+    let num_groups_matched = group_counts.iter().filter(|&&count| count > 0).count();
+
+    let mut window_counts = vec![0; num_groups];
+    let mut num_window_groups = 0;
+    let mut best = 0..matches.len();
+    let mut lo = 0;
+    for (hi, term_match) in matches.iter().enumerate() {
+        window_counts[term_match.group] += 1;
+        if window_counts[term_match.group] == 1 {
+            num_window_groups += 1;
+        }
+
+        while num_window_groups == num_groups_matched {
+            let span = term_match.range.end - matches[lo].range.start;
+            if span < matches[best.end - 1].range.end - matches[best.start].range.start {
+                best = lo..hi + 1;
+            }
+
+            window_counts[matches[lo].group] -= 1;
+            if window_counts[matches[lo].group] == 0 {
+                num_window_groups -= 1;
+            }
+            lo += 1;
+        }
+    }
+
+    (num_groups_matched, best)
+}
+
+/// Extends the window of matches in both directions to max_len characters,
+/// dropping words cut off at either end.
+fn get_passage_range(text: &str, words: &[Range<usize>], window: Range<usize>, max_len: usize) -> Range<usize> {
+    if max_len == 0 {
+        return 0..text.len();
+    }
+
+    let window_len = text[window.clone()].chars().count();
+    let context_len = max_len.saturating_sub(window_len);
+
+    // Half the remaining length before the window, any leftovers after
+    let mut start = window.start;
+    let mut num_before = 0;
+    for (idx, _c) in text[..window.start].char_indices().rev() {
+        if num_before == context_len / 2 {
+            break;
+        }
+        start = idx;
+        num_before += 1;
+    }
+
+    let mut end = text[window.end..]
+        .char_indices()
+        .nth(context_len - num_before)
+        .map_or(text.len(), |(idx, _c)| window.end + idx);
+
+    if let Some(word) = words.iter().find(|word| word.start < start && start < word.end) {
+        start = word.end.min(window.start);
+    }
+
+    if let Some(word) = words.iter().find(|word| word.start < end && end < word.end) {
+        end = word.start.max(window.end);
+    }
+
+    start..end
+}
+
+/// Copies the passage, collapsing runs of whitespace and remapping highlights into it.
+fn build_snippet(field_name: &str, text: &str, passage: Range<usize>, matches: &[TermMatch]) -> Snippet {
+    let mut snippet_text = String::with_capacity(passage.end - passage.start);
+    let mut highlights: Vec<Range<usize>> = Vec::new();
+
+    let mut passage_matches = matches
+        .iter()
+        .filter(|term_match| term_match.range.start >= passage.start && term_match.range.end <= passage.end)
+        .peekable();
+    let mut highlight_start = None;
+    let mut highlight_end = 0;
+
+    let mut prev_whitespace = true;
+    for (idx, c) in text[passage.clone()].char_indices().map(|(idx, c)| (passage.start + idx, c)) {
+        if idx == highlight_end {
+            if let Some(start) = highlight_start.take() {
+                highlights.push(start..snippet_text.len());
+            }
+        }
+
+        if let Some(term_match) = passage_matches.next_if(|term_match| term_match.range.start == idx) {
+            highlight_start = Some(snippet_text.len());
+            highlight_end = term_match.range.end;
+        }
+
+        if c.is_whitespace() {
+            if !prev_whitespace {
+                snippet_text.push(' ');
+            }
+            prev_whitespace = true;
+        } else {
+            snippet_text.push(c);
+            prev_whitespace = false;
+        }
+    }
+
+    if let Some(start) = highlight_start {
+        highlights.push(start..snippet_text.len());
+    }
+
+    let trimmed_len = snippet_text.trim_end().len();
+    snippet_text.truncate(trimmed_len);
+
+    Snippet {
+        field_name: field_name.to_owned(),
+        text: snippet_text,
+        highlights,
+        is_start_truncated: passage.start > 0,
+        is_end_truncated: passage.end < text.len(),
+    }
+}
+
+/// Returns the best passage amongst the given field texts, or `None` if no searched term occurs in any.
+///
+/// Fields matching more query parts are preferred, then the ones with the closest window of terms.
+pub fn get_snippet(
+    fields: &[(&str, &str)],
+    searched_terms: &[Vec<String>],
+    tokenizer: &dyn SearchTokenizer,
+    options: &SnippetOptions,
+) -> Option<Snippet> {
+    let mut best: Option<FieldMatches> = None;
+
+    for &(field_name, text) in fields {
+        let words = tokenizer.split_text(field_name, text);
+        let matches = find_matches(&words, searched_terms);
+        if matches.is_empty() {
+            continue;
+        }
+        let words = words.into_iter().map(|(range, _term)| range).collect();
+
+        let (num_groups_matched, window) = find_best_window(&matches, searched_terms.len());
+        let span = matches[window.end - 1].range.end - matches[window.start].range.start;
+
+        let is_better = match &best {
+            Some(best) => {
+                num_groups_matched > best.num_groups_matched
+                    || (num_groups_matched == best.num_groups_matched && span < best.span)
+            }
+            None => true,
+        };
+        if is_better {
+            best = Some(FieldMatches { field_name, text, words, matches, window, num_groups_matched, span });
+        }
+    }
+
+    let FieldMatches { field_name, text, words, matches, window, .. } = best?;
+
+    // Fall back to the first match if the window doesn't fit
+    let mut window_range = matches[window.start].range.start..matches[window.end - 1].range.end;
+    if options.max_len != 0 && text[window_range.clone()].chars().count() > options.max_len {
+        window_range = matches[window.start].range.clone();
+    }
+
+    let passage = get_passage_range(text, &words, window_range, options.max_len);
+    Some(build_snippet(field_name, text, passage, &matches))
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use infisearch_common::language::InfiLanguageConfig;
+    use infisearch_common::tokenize::SearchTokenizer;
+
+    use super::{get_snippet, Snippet, SnippetOptions};
+
+    fn snippet_with(
+        tokenizer: &dyn SearchTokenizer,
+        fields: &[(&str, &str)],
+        searched_terms: &[&[&str]],
+        max_len: usize,
+    ) -> Option<Snippet> {
+        let searched_terms: Vec<Vec<String>> = searched_terms
+            .iter()
+            .map(|terms| terms.iter().map(|term| term.to_string()).collect())
+            .collect();
+
+        get_snippet(fields, &searched_terms, tokenizer, &SnippetOptions { max_len })
+    }
+
+    fn snippet(fields: &[(&str, &str)], searched_terms: &[&[&str]], max_len: usize) -> Option<Snippet> {
+        let tokenizer = infisearch_lang_ascii::ascii::new_with_options(&InfiLanguageConfig::default());
+        snippet_with(&tokenizer, fields, searched_terms, max_len)
+    }
+
+    fn highlighted(snippet: &Snippet) -> Vec<&str> {
+        snippet.highlights.iter().map(|range| &snippet.text[range.clone()]).collect()
+    }
+
+    #[test]
+    fn test_no_matches() {
+        assert!(snippet(&[("body", "lorem ipsum")], &[&["dolor"]], 0).is_none());
+        // Partial words don't match
+        assert!(snippet(&[("body", "lorem ipsum")], &[&["lore"]], 0).is_none());
+    }
+
+    #[test]
+    fn test_whole_text() {
+        let s = snippet(&[("body", "  Lorem\n\n ipsum,  lorem ")], &[&["lorem"]], 0).unwrap();
+        assert_eq!(s.text, "Lorem ipsum, lorem");
+        assert_eq!(s.highlights, vec![0..5, 13..18]);
+        assert!(!s.is_start_truncated && !s.is_end_truncated);
+    }
+
+    #[test]
+    fn test_best_window() {
+        let text = "ipsum aaa bbb ccc ddd eee fff lorem ggg hhh iii jjj kkk lorem ipsum lll mmm";
+        let s = snippet(&[("body", text)], &[&["lorem"], &["ipsum"]], 20).unwrap();
+        assert_eq!(highlighted(&s), vec!["lorem", "ipsum"]);
+        assert!(s.text.chars().count() <= 20);
+        assert!(s.is_start_truncated && s.is_end_truncated);
+        // Cut off words are dropped
+        assert!(s.text.split(' ').all(|word| word.len() == 3 || word.len() == 5));
+    }
+
+    #[test]
+    fn test_field_preference() {
+        let s = snippet(
+            &[("title", "lorem"), ("body", "lorem dolor ipsum"), ("heading", "ipsum lorem")],
+            &[&["lorem"], &["ipsum"]],
+            0,
+        ).unwrap();
+        assert_eq!(s.field_name, "heading");
+        assert_eq!(highlighted(&s), vec!["ipsum", "lorem"]);
+    }
+
+    #[test]
+    fn test_folding() {
+        // Text is folded as it is indexed, so both forms match the searched term
+        let s = snippet(&[("body", "Éclair über café, cafe")], &[&["cafe"]], 0).unwrap();
+        assert_eq!(highlighted(&s), vec!["café", "cafe"]);
+
+        let s = snippet(&[("body", "Éclair über café, cafe")], &[&["uber"]], 0).unwrap();
+        assert_eq!(highlighted(&s), vec!["über"]);
+
+        // As with stemmed terms
+        let tokenizer = infisearch_lang_ascii_stemmer::ascii_stemmer::new_with_options(&InfiLanguageConfig::default());
+        let s = snippet_with(&tokenizer, &[("body", "she runs, he running, the runner")], &[&["run"]], 0).unwrap();
+        assert_eq!(highlighted(&s), vec!["runs", "running"]);
+    }
+
+    #[test]
+    fn test_unsegmented() {
+        let tokenizer = infisearch_lang_chinese::chinese::new_with_options(&InfiLanguageConfig::default());
+        let s = snippet_with(&tokenizer, &[("body", "我是他的朋友，他很好")], &[&["他"]], 0).unwrap();
+        assert_eq!(highlighted(&s), vec!["他", "他"]);
+        assert_eq!(s.highlights, vec![6..9, 21..24]);
+
+        // Passages are cut between characters, as each is a word
+        let text = "我是他的朋友我是他的朋友我是他的朋友";
+        let s = snippet_with(&tokenizer, &[("body", text)], &[&["朋"]], 5).unwrap();
+        assert_eq!(s.text.chars().count(), 5);
+        assert_eq!(highlighted(&s), vec!["朋"]);
+    }
+}
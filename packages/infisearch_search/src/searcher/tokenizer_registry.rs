@@ -0,0 +1,37 @@
+//! Custom search tokenizers, keyed by the `lang_config.lang` name they are used for.
+//!
+//! These take precedence over the built-in tokenizers, and are given to each searcher as it is created,
+//! see `NativeSearcherOptions::register_tokenizer` and `get_new_searcher_with_tokenizers`.
+//! They should match the `IndexerTokenizer` registered with the indexer under the same name.
+
+use std::sync::Arc;
+
+use infisearch_common::language::InfiLanguageConfig;
+use infisearch_common::tokenize::SearchTokenizer;
+
+pub type SearchTokenizerFactory = Arc<dyn Fn(&InfiLanguageConfig) -> Box<dyn SearchTokenizer> + Send + Sync>;
+
+#[derive(Clone, Default)]
+pub struct TokenizerRegistry {
+    factories: Vec<(String, SearchTokenizerFactory)>,
+}
+
+impl TokenizerRegistry {
+    /// Registers a custom tokenizer for indexes with this `lang_config.lang`,
+    /// replacing any previously registered under the same name.
+    pub fn register_tokenizer(
+        &mut self,
+        lang: &str,
+        factory: impl Fn(&InfiLanguageConfig) -> Box<dyn SearchTokenizer> + Send + Sync + 'static,
+    ) {
+        self.factories.retain(|(name, _factory)| name != lang);
+        self.factories.push((lang.to_owned(), Arc::new(factory)));
+    }
+
+    pub(crate) fn create_tokenizer(&self, lang_config: &InfiLanguageConfig) -> Option<Box<dyn SearchTokenizer>> {
+        self.factories
+            .iter()
+            .find(|(name, _factory)| *name == lang_config.lang)
+            .map(|(_name, factory)| factory(lang_config))
+    }
+}
@@ -61,15 +61,23 @@ fn main() {
 
         let config = setup_config_file(&ctx.root, ctx.config.get(CONFIG_KEY));
 
+        let config = InfiConfig::new(config).unwrap_or_else(|e| {
+            eprintln!("mdbook-infisearch: {}", e);
+            std::process::exit(1);
+        });
+
         let mut indexer = Indexer::new(
             &input_folder_path,
             &output_folder_path,
-            InfiConfig::new(config),
+            config,
             is_incremental,
             false,
             false,
             false,
-        );
+        ).unwrap_or_else(|e| {
+            eprintln!("mdbook-infisearch: {}", e);
+            std::process::exit(1);
+        });
 
         for entry in WalkDir::new(input_folder_path.clone()) {
             match entry {
@@ -81,7 +89,9 @@ fn main() {
                     let path = dir_entry.path();
                     let relative_path = path.strip_prefix(&input_folder_path).unwrap();
     
-                    indexer.index_file(path, relative_path);
+                    if let Err(e) = indexer.index_file(path, relative_path) {
+                        eprintln!("mdbook-infisearch: Skipping file. {}", e);
+                    }
                 }
                 Err(e) => {
                     panic!("Error processing entry. {}", e)
@@ -89,7 +99,10 @@ fn main() {
             }
         }
 
-        indexer.finish_writing_docs();
+        if let Err(e) = indexer.finish_writing_docs() {
+            eprintln!("mdbook-infisearch: {}", e);
+            std::process::exit(1);
+        }
 
         assets::write_infisearch_assets(&assets_output_dir);
     } else {
@@ -16,6 +16,7 @@ export interface QueryPart {
   partType: QueryPartType;
   term?: string;
   termsSearched?: string[];
+  slop?: number;
   children?: QueryPart[];
   weight?: number;
 }
@@ -6,6 +6,7 @@ export interface SearcherOptions {
   cacheAllFieldStores?: boolean,
   plLazyCacheThreshold: number,
   resultLimit?: number,
+  spellingSuggestionsOnly?: boolean,
 }
 
 export function prepareSearcherOptions(searcherOptions: SearcherOptions) {
@@ -38,10 +39,15 @@ export function prepareSearcherOptions(searcherOptions: SearcherOptions) {
   if (!('resultLimit' in searcherOptions)) {
     searcherOptions.resultLimit = null;
   }
+
+  if (!('spellingSuggestionsOnly' in searcherOptions)) {
+    searcherOptions.spellingSuggestionsOnly = false;
+  }
 }
 
 export interface InfiConfig {
   ver: string,
+  formatVer?: number,
   indexVer: string,
   lastDocId: number,
   indexingConfig: {
@@ -53,6 +59,12 @@ export interface InfiConfig {
   langConfig: {
     lang: string,
     options: any,
+    analyzers?: { [name: string]: any },
+    synonyms?: {
+      equivalent?: string[][],
+      mappings?: { [key: string]: string[] },
+      expand_at_index_time?: boolean,
+    },
   },
   cacheAllFieldStores: boolean,
   fieldInfos: FieldInfo[],
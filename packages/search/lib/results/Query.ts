@@ -52,6 +52,25 @@ export function getRegexes(queryParts: QueryPart[], config: InfiConfig) {
   return [termRegexes, JSON.stringify(searchedTermsFlat)];
 }
 
+export interface Facets {
+  /**
+   * Counts of each enum value with at least one result. A null value counts documents without a value.
+   */
+  enumFacets: { [enumFieldName: string]: { value: string | null, count: number }[] },
+  /**
+   * Counts of each bucket, see `QueryOpts.i64Facets`.
+   */
+  i64Facets: { [numFieldName: string]: number[] },
+}
+
+export interface Suggestion {
+  /**
+   * The original query with its misspelled terms replaced by their corrections.
+   */
+  query: string,
+  corrections: { originalTerm: string, correctedTerm: string, docFreq: number }[],
+}
+
 export default class Query {
   _mrlRegexes: RegExp[];
 
@@ -69,6 +88,15 @@ export default class Query {
      * Syntactic tree of query parsed by InfiSearch.
      */
     public readonly queryParts: QueryPart[],
+    /**
+     * Counts of enum values and i64 buckets over all results, as requested in `QueryOpts`.
+     */
+    public readonly facets: Facets,
+    /**
+     * Spelling corrections made, or suggested if `spellingSuggestionsOnly` is enabled.
+     * Null if no terms were corrected.
+     */
+    public readonly suggestion: Suggestion | null,
     /**
      * Returns the next N results.
      */
@@ -1,13 +1,17 @@
-import Query, { getRegexes } from './Query';
+import Query, { Facets, Suggestion, getRegexes } from './Query';
 import { SearcherOptions, InfiConfig, prepareSearcherOptions, FieldInfo } from './Config';
 import { Result } from './Result';
 import { QueryPart } from '../parser/queryParser';
 import PersistentCache from './Cache';
 import { getFieldUrl } from '../utils/FieldStore';
 import { QueryOpts } from './Searcher/QueryOpts';
+import { Completions } from './Searcher/Completions';
 
 declare const INFISEARCH_VER;
 
+// FORMAT_VER of infisearch_common's metadata.rs
+const INDEX_FORMAT_VER = 1;
+
 // Code from
 /* webpack/runtime/publicPath */
 // manually handled since the WebWorker url is dynamic (based on language)
@@ -42,6 +46,10 @@ class Searcher {
     }
   } = Object.create(null);
 
+  private _mrlCompletions: {
+    [completionsId: number]: (completions: Completions) => void,
+  } = Object.create(null);
+
   // Use an auto-incrementing id to resolve queries to-fro the Worker
   private id = 0;
 
@@ -76,6 +84,8 @@ class Searcher {
               nextResults,
               resultsTotal,
               queryParts,
+              facets,
+              suggestion,
             } = ev.data;
 
             const q = this._mrlQueries[queryId];
@@ -85,8 +95,17 @@ class Searcher {
                 nextResults,
                 resultsTotal,
                 queryParts,
+                facets,
+                suggestion,
               });
             }
+          } else if (ev.data.completions) {
+            const { completionsId, completions } = ev.data;
+            const resolveCompletions = this._mrlCompletions[completionsId];
+            if (resolveCompletions) {
+              delete this._mrlCompletions[completionsId];
+              resolveCompletions(completions);
+            }
           } else if (ev.data === '') {
             configSetupPromise.then(() => this._mrlWorker.postMessage(this.cfg));
             URL.revokeObjectURL(objectUrl);
@@ -161,6 +180,10 @@ class Searcher {
       throw new Error('InfiSearch: search !== indexer version!');
     }
 
+    if ((this.cfg.formatVer || 0) !== INDEX_FORMAT_VER) {
+      throw new Error('InfiSearch: index format changed, the index should be rebuilt!');
+    }
+
     if (!('cacheAllFieldStores' in searcherOpts)) {
       searcherOpts.cacheAllFieldStores = !!this.cfg.cacheAllFieldStores;
     }
@@ -200,6 +223,9 @@ class Searcher {
     opts.i64Filters = opts.i64Filters || {};
     opts.sort = opts.sort || null;
     opts.sortAscending = opts.sortAscending || false;
+    opts.sortBy = opts.sortBy || [];
+    opts.enumFacets = opts.enumFacets || [];
+    opts.i64Facets = opts.i64Facets || {};
 
     const queryId = this.id;
     this.id += 1;
@@ -221,6 +247,8 @@ class Searcher {
     const result: {
       resultsTotal: number,
       queryParts: QueryPart[],
+      facets: Facets,
+      suggestion: Suggestion | null,
     } = await queries[queryId].promise;
 
     const [termRegexes, searchedTermsFlat] = getRegexes(result.queryParts, this.cfg);
@@ -281,12 +309,32 @@ class Searcher {
       query,
       result.resultsTotal,
       result.queryParts,
+      result.facets,
+      result.suggestion,
       getNextN,
       free,
       searchedTermsFlat as string,
     );
   }
 
+  /**
+   * Completes a partially typed query, without retrieving any postings lists.
+   * Returns at most n completions of each kind.
+   */
+  async getCompletions(query: string, n: number = 5): Promise<Completions> {
+    await this.setupPromise;
+
+    const completionsId = this.id;
+    this.id += 1;
+
+    return new Promise((resolve) => {
+      this._mrlCompletions[completionsId] = resolve;
+      this._mrlWorker.postMessage({
+        completionsQuery: query, completionsId, n, isGetCompletions: true,
+      });
+    });
+  }
+
   free() {
     this._mrlWorker.terminate();
   }
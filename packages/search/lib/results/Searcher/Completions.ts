@@ -0,0 +1,11 @@
+export interface Completions {
+  /**
+   * Completions of the query's last term, by the number of documents containing the term.
+   * Empty if the query ends with a separator.
+   */
+  terms: { query: string, term: string, docFreq: number }[],
+  /**
+   * Texts of fields with completions enabled containing the query, ranked by the field's weight.
+   */
+  phrases: { phrase: string, fieldName: string, numDocs: number }[],
+}
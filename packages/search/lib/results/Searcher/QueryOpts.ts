@@ -3,4 +3,19 @@ export interface QueryOpts {
   i64Filters?: { [numFieldName: string]: { gte?: number | bigint, lte?: number | bigint, } },
   sort?: string | null,
   sortAscending?: boolean,
+  /**
+   * Sort keys in order of precedence, overriding `sort` and `sortAscending` if non-empty.
+   * Keys are i64 or enum field names, or '_score' to sort by relevance.
+   */
+  sortBy?: { field: string, ascending?: boolean }[],
+  /**
+   * Enum fields to count the enum values of, over all results.
+   */
+  enumFacets?: string[],
+  /**
+   * I64 fields to count into buckets, over all results.
+   * Boundaries are sorted and deduplicated,
+   * then n bucket boundaries produce n + 1 buckets: (-inf, b1), [b1, b2), ..., [bn, inf)
+   */
+  i64Facets?: { [numFieldName: string]: (number | bigint)[] },
 }
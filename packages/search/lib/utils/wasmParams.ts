@@ -6,7 +6,9 @@ import { QueryOpts } from '../results/Searcher/QueryOpts';
 let encoder = new TextEncoder();
 
 export function serializeGetQueryParams(query: string, queryOpts: QueryOpts, config: InfiConfig): Uint8Array {
-  const { enumFilters, i64Filters, sort, sortAscending } = queryOpts;
+  const {
+    enumFilters, i64Filters, sort, sortAscending, sortBy, enumFacets, i64Facets,
+  } = queryOpts;
   const { fieldInfos } = config;
 
   /*
@@ -14,8 +16,9 @@ export function serializeGetQueryParams(query: string, queryOpts: QueryOpts, con
    4 (query length)
    1 (enum filter count)
    1 (i64 filter count)
-   1 + 1 (sortParam - i8 i8, first byte is a boolean indicator)
-   1 (sortAscending)
+   1 (sort key count)
+   1 (enum facet count)
+   1 (i64 facet count)
 
    Dynamic parts:
    query's encoded length
@@ -31,21 +34,60 @@ export function serializeGetQueryParams(query: string, queryOpts: QueryOpts, con
     1 (boolean - is there a upper bound?)
     8 (upper bound)
    )
+   sortKeys.length * (
+    1 (sort key type - 0 for score, 1 for i64 fields, 2 for enum fields)
+    1 (i64Id / enumId)
+    1 (boolean - is ascending?)
+   )
+   enumFacets.length * 1 (enumId)
+   i64Facets.length * (
+    1 (i64Id)
+    1 (number of bucket boundaries)
+    N (number of bucket boundaries * 8 for each boundary)
+   )
   */
 
 
   const enumFiltersArr = Object.entries(enumFilters);
   const i64FiltersArr = Object.entries(i64Filters);
+  const sortKeys = (sortBy.length ? sortBy : [{ field: sort, ascending: sortAscending }])
+    .map(({ field, ascending }) => {
+      if (field === '_score') {
+        return [0, 0, ascending] as const;
+      }
+
+      const fieldInfo = fieldInfos.find((fi) => fi.name === field);
+      if (fieldInfo && fieldInfo.i64Info) {
+        return [1, fieldInfo.i64Info.id, ascending] as const;
+      } else if (fieldInfo && fieldInfo.enumInfo) {
+        return [2, fieldInfo.enumInfo.enumId, ascending] as const;
+      }
+      return undefined;
+    })
+    .filter((sortKey) => sortKey);
+
+  const i64FacetsArr = Object.entries(i64Facets)
+    .map(([fieldName, boundaries]) => {
+      const sorted = Array.from(new Set(boundaries.map((boundary) => BigInt(boundary))))
+        .sort((a, b) => (a < b ? -1 : (a > b ? 1 : 0)));
+      return [fieldName, sorted.slice(0, 255)] as const;
+    });
 
   const encodedQuery = encoder.encode(query);
   const queryLength = encodedQuery.length;
   const enumFilterEnumValuesParamLength = Object.values(enumFilters).reduce((acc, b) => acc + b.length, 0);
 
+  const i64FacetBoundariesParamLength = i64FacetsArr.reduce((acc, [, b]) => acc + b.length, 0);
+
   const view = new DataView(new ArrayBuffer(
     9 + queryLength
       + enumFiltersArr.length * 2
       + enumFilterEnumValuesParamLength
-      + i64FiltersArr.length * 20,
+      + i64FiltersArr.length * 20
+      + sortKeys.length * 3
+      + enumFacets.length
+      + i64FacetsArr.length * 2
+      + i64FacetBoundariesParamLength * 8,
   ));
 
   let viewIdx = 4;
@@ -119,15 +161,42 @@ export function serializeGetQueryParams(query: string, queryOpts: QueryOpts, con
   // ------------------------------------
   // Sort parameters
 
-  const sortField = fieldInfos.find((fi) => fi.name === sort);
-  if (sortField) {
-    pushByte(1);
-    pushByte(sortField.i64Info.id);
-  } else {
-    pushByte(0);
-  }
+  pushByte(sortKeys.length);
+  sortKeys.forEach(([sortKeyType, id, ascending]) => {
+    pushByte(sortKeyType);
+    pushByte(id);
+    pushByte(ascending ? 1 : 0);
+  });
 
-  pushByte(sortAscending ? 1 : 0);
+  // ------------------------------------
+  // Facets
+
+  const enumFacetCountIdx = viewIdx;
+  view.setUint8(enumFacetCountIdx, 0);
+  viewIdx += 1;
+
+  enumFacets.forEach((fieldName) => {
+    const fieldInfo = fieldInfos.find((fi) => fi.name === fieldName);
+    if (fieldInfo && fieldInfo.enumInfo) {
+      pushByte(fieldInfo.enumInfo.enumId);
+      view.setUint8(enumFacetCountIdx, view.getUint8(enumFacetCountIdx) + 1);
+    }
+  });
+
+  const i64FacetCountIdx = viewIdx;
+  view.setUint8(i64FacetCountIdx, 0);
+  viewIdx += 1;
+
+  i64FacetsArr.forEach(([fieldName, boundaries]) => {
+    const fieldInfo = fieldInfos.find((fi) => fi.name === fieldName);
+    if (fieldInfo && fieldInfo.i64Info) {
+      pushByte(fieldInfo.i64Info.id);
+      pushByte(boundaries.length);
+      boundaries.forEach(pushBigInt);
+
+      view.setUint8(i64FacetCountIdx, view.getUint8(i64FacetCountIdx) + 1);
+    }
+  });
 
   return new Uint8Array(view.buffer);
 }
@@ -1,5 +1,7 @@
 import './publicPath';
-import { setupWasm, processQuery, getQueryNextN, freeQuery } from './workerSearcher';
+import {
+  setupWasm, processQuery, getQueryNextN, freeQuery, getCompletions,
+} from './workerSearcher';
 
 
 export default function setupWithWasmModule(wasmModule: Promise<any>) {
@@ -8,6 +10,10 @@ export default function setupWithWasmModule(wasmModule: Promise<any>) {
     if (data.searcherOptions) {
       await setupWasm(data, wasmModule);
       postMessage({ isSetupDone: true });
+    } else if (data.isGetCompletions) {
+      const { completionsQuery, completionsId, n } = data;
+      const completions = await getCompletions(completionsQuery, n);
+      postMessage({ completionsId, completions });
     } else if (data.query) {
       const {
         query, opts, queryId, n, isFree, isGetNextN,
@@ -27,6 +33,8 @@ export default function setupWithWasmModule(wasmModule: Promise<any>) {
           query,
           queryId,
           resultsTotal: workerQuery._mrlResultsTotal,
+          facets: workerQuery._mrlFacets,
+          suggestion: workerQuery._mrlSuggestion,
           queryParts: workerQuery._mrlQueryParts,
         });
       }
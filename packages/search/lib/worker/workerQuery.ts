@@ -1,9 +1,12 @@
 import { QueryPart } from '../parser/queryParser';
+import { Facets, Suggestion } from '../results/Query';
 
 export default class WorkerQuery {
   constructor(
     public _mrlQueryParts: QueryPart[],
     public _mrlResultsTotal: number,
+    public _mrlFacets: Facets,
+    public _mrlSuggestion: Suggestion | null,
     private _mrlQuery: any,
   ) {}
 
@@ -1,4 +1,6 @@
 import { InfiConfig } from '../results/Config';
+import { Facets, Suggestion } from '../results/Query';
+import { Completions } from '../results/Searcher/Completions';
 import { QueryOpts } from '../results/Searcher/QueryOpts';
 import { serializeGetQueryParams } from '../utils/wasmParams';
 import WorkerQuery from './workerQuery';
@@ -13,6 +15,48 @@ let wasmSearcher: any;
 
 let config: InfiConfig;
 
+let completionsUrl: string;
+
+let completionsPromise: Promise<void>;
+
+
+function getFacets(facetCountsRaw: Uint32Array, opts: QueryOpts): Facets {
+  const { fieldInfos } = config;
+  const facets: Facets = { enumFacets: {}, i64Facets: {} };
+
+  // See Query.get_facet_counts for the format
+  let pos = 0;
+  function nextCounts(): number[] {
+    const len = facetCountsRaw[pos];
+    const counts = Array.from(facetCountsRaw.subarray(pos + 1, pos + 1 + len));
+    pos += 1 + len;
+    return counts;
+  }
+
+  // Same order and field validation as serializeGetQueryParams
+  opts.enumFacets.forEach((fieldName) => {
+    const fieldInfo = fieldInfos.find((fi) => fi.name === fieldName);
+    if (fieldInfo && fieldInfo.enumInfo) {
+      const { enumValues } = fieldInfo.enumInfo;
+      facets.enumFacets[fieldName] = nextCounts()
+        .map((count, evId) => ({
+          // -1 as 0 is the "default" enum value
+          value: evId === 0 ? null : enumValues[evId - 1],
+          count,
+        }))
+        .filter(({ count }) => count > 0);
+    }
+  });
+
+  Object.keys(opts.i64Facets).forEach((fieldName) => {
+    const fieldInfo = fieldInfos.find((fi) => fi.name === fieldName);
+    if (fieldInfo && fieldInfo.i64Info) {
+      facets.i64Facets[fieldName] = nextCounts();
+    }
+  });
+
+  return facets;
+}
 
 export async function processQuery(
   query: string,
@@ -31,9 +75,19 @@ export async function processQuery(
     console.error(`Error deserializing query parts:\n${queryPartsRaw}\n${ex}`);
   }
 
+  const suggestionRaw = wasmQuery.get_suggestion() as string;
+  let suggestion: Suggestion | null = null;
+  try {
+    suggestion = JSON.parse(suggestionRaw);
+  } catch (ex) {
+    console.error(`Error deserializing suggestion:\n${suggestionRaw}\n${ex}`);
+  }
+
   workerQueries[queryId] = new WorkerQuery(
     queryParts,
     wasmQuery.results_total,
+    getFacets(wasmQuery.get_facet_counts(), opts),
+    suggestion,
     wasmQuery,
   );
 
@@ -52,6 +106,26 @@ export function freeQuery(queryId: number) {
 }
 
 
+export async function getCompletions(query: string, n: number): Promise<Completions> {
+  // Only fetched once completions are first requested
+  if (!completionsPromise) {
+    completionsPromise = fetch(completionsUrl)
+      .then((resp) => (resp.ok ? resp.arrayBuffer() : new ArrayBuffer(0)))
+      .catch(() => new ArrayBuffer(0))
+      .then((buf) => wasmSearcher.set_completion_phrases(new Uint8Array(buf)));
+  }
+  await completionsPromise;
+
+  const completionsRaw = wasmSearcher.get_completions(query, n) as string;
+  try {
+    return JSON.parse(completionsRaw);
+  } catch (ex) {
+    console.error(`Error deserializing completions:\n${completionsRaw}\n${ex}`);
+    return { terms: [], phrases: [] };
+  }
+}
+
+
 async function setupMetadata(baseUrl: string, innerUrl: string): Promise<ArrayBuffer> {
   let cache: Cache;
   try {
@@ -72,6 +146,76 @@ async function setupMetadata(baseUrl: string, innerUrl: string): Promise<ArrayBu
   ).then((resp) => resp.arrayBuffer());
 }
 
+function encodeStrings(strings: string[], encoder: TextEncoder): Uint8Array {
+  const encodedStrings = strings.map((s) => encoder.encode(s));
+  const serialized = new Uint8Array(encodedStrings.reduce((acc, next) => acc + 4 + next.length, 0));
+  const view = new DataView(serialized.buffer);
+
+  let writePos = 0;
+  encodedStrings.forEach((encoded) => {
+    view.setUint32(writePos, encoded.length, true);
+    serialized.set(encoded, writePos + 4);
+    writePos += 4 + encoded.length;
+  });
+
+  return serialized;
+}
+
+/**
+ * Serializes lang_config.analyzers as u32 length prefixed strings, see decode_analyzers in searcher.rs.
+ */
+function serializeAnalyzers(
+  analyzers: { [name: string]: any } | undefined,
+  encoder: TextEncoder,
+): Uint8Array | undefined {
+  if (!analyzers) {
+    return undefined;
+  }
+
+  const strings: string[] = [];
+  const pushFilters = (filters: any[] | undefined) => {
+    strings.push(String((filters || []).length));
+    (filters || []).forEach((filter) => {
+      const params = Object.entries(filter)
+        .filter(([key, value]) => key !== 'type' && value !== null && value !== undefined)
+        .map(([key, value]) => [key, Array.isArray(value) ? value.join('\0') : String(value)]);
+      strings.push(filter.type, String(params.length));
+      params.forEach(([key, value]) => strings.push(key, value));
+    });
+  };
+
+  Object.entries(analyzers).forEach(([name, analyzer]) => {
+    const fields: string[] = analyzer.fields || [];
+    strings.push(name, analyzer.tokenizer || 'standard', String(fields.length), ...fields);
+    pushFilters(analyzer.char_filters);
+    pushFilters(analyzer.token_filters);
+  });
+
+  return encodeStrings(strings, encoder);
+}
+
+/**
+ * Serializes lang_config.synonyms as u32 length prefixed strings, see decode_synonyms in searcher.rs.
+ * Synonyms expanded at index time are not needed by the searcher.
+ */
+function serializeSynonyms(
+  synonyms: InfiConfig['langConfig']['synonyms'],
+  encoder: TextEncoder,
+): Uint8Array | undefined {
+  if (!synonyms || synonyms.expand_at_index_time) {
+    return undefined;
+  }
+
+  const equivalent = synonyms.equivalent || [];
+  const strings: string[] = [String(equivalent.length)];
+  equivalent.forEach((set) => strings.push(String(set.length), ...set));
+  Object.entries(synonyms.mappings || {}).forEach(([from, to]) => {
+    strings.push(from, String(to.length), ...to);
+  });
+
+  return encodeStrings(strings, encoder);
+}
+
 export async function setupWasm(
   cfg: InfiConfig,
   wasmModulePromise: Promise<any>,
@@ -81,7 +225,7 @@ export async function setupWasm(
   const {
     indexVer,
     indexingConfig,
-    langConfig: { lang, options },
+    langConfig: { lang, options, analyzers, synonyms },
     fieldInfos,
     numScoredFields,
     searcherOptions,
@@ -89,6 +233,7 @@ export async function setupWasm(
 
   const innerUrl = `${searcherOptions.url}${indexVer}/`;
   const metadataPromise = setupMetadata(searcherOptions.url, innerUrl);
+  completionsUrl = `${innerUrl}completions.json`;
 
   const encoder = new TextEncoder();
 
@@ -156,6 +301,8 @@ export async function setupWasm(
     options.ignore_stop_words,
     options.stemmer,
     options.max_term_len,
+    serializeAnalyzers(analyzers, encoder),
+    serializeSynonyms(synonyms, encoder),
     fieldInfosSerialized,
     numScoredFields,
     searcherOptions.url,
@@ -165,5 +312,6 @@ export async function setupWasm(
     searcherOptions.useQueryTermProximity,
     searcherOptions.plLazyCacheThreshold,
     searcherOptions.resultLimit,
+    searcherOptions.spellingSuggestionsOnly,
   );
 }